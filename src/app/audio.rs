@@ -0,0 +1,200 @@
+//! Background audio that plays during slideshows.
+//!
+//! `rodio`'s output stream can't be sent between threads, so playback happens on its own thread, which is controlled over a channel.
+
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+const AUDIO_EXTENSIONS: &[&str] = &["mp3", "ogg", "flac", "wav"];
+/// How often the player checks whether the next track needs to be queued.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, PartialEq)]
+struct Playback {
+	source: PathBuf,
+	volume: f32,
+}
+
+fn is_playlist(path: &Path) -> bool {
+	path
+		.extension()
+		.is_some_and(|extension| extension == "m3u" || extension == "m3u8")
+}
+
+/// The tracks of `source`, which is a folder of audio files, an M3U playlist, or a single audio file.
+fn tracks(source: &Path) -> io::Result<Vec<PathBuf>> {
+	if source.is_dir() {
+		let mut tracks = Vec::new();
+		for entry in std::fs::read_dir(source)? {
+			let path = entry?.path();
+			let is_audio = path.extension().is_some_and(|extension| {
+				AUDIO_EXTENSIONS
+					.iter()
+					.any(|audio| extension.eq_ignore_ascii_case(audio))
+			});
+			if is_audio {
+				tracks.push(path);
+			}
+		}
+		tracks.sort_unstable_by(|a, b| natord::compare(&a.to_string_lossy(), &b.to_string_lossy()));
+		Ok(tracks)
+	} else if is_playlist(source) {
+		// paths in the playlist are relative to it
+		let base = source.parent().unwrap_or(Path::new("."));
+		Ok(
+			std::fs::read_to_string(source)?
+				.lines()
+				.map(str::trim)
+				.filter(|line| !line.is_empty() && !line.starts_with('#'))
+				.map(|line| base.join(line))
+				.collect(),
+		)
+	} else {
+		Ok(vec![source.to_owned()])
+	}
+}
+
+struct Output {
+	_stream: rodio::OutputStream,
+	handle: rodio::OutputStreamHandle,
+	sink: rodio::Sink,
+}
+
+impl Output {
+	fn new() -> Result<Self, String> {
+		let (stream, handle) = rodio::OutputStream::try_default().map_err(|error| error.to_string())?;
+		let sink = rodio::Sink::try_new(&handle).map_err(|error| error.to_string())?;
+		Ok(Self {
+			_stream: stream,
+			handle,
+			sink,
+		})
+	}
+
+	/// Drop anything that is queued.
+	fn clear(&mut self) -> Result<(), String> {
+		self.sink = rodio::Sink::try_new(&self.handle).map_err(|error| error.to_string())?;
+		Ok(())
+	}
+}
+
+struct Worker {
+	receiver: mpsc::Receiver<Option<Playback>>,
+	error: Arc<Mutex<Option<String>>>,
+	output: Option<Output>,
+	source: Option<PathBuf>,
+	tracks: Vec<PathBuf>,
+	next_track: usize,
+}
+
+impl Worker {
+	fn run(mut self) {
+		loop {
+			let result = match self.receiver.recv_timeout(POLL_INTERVAL) {
+				Ok(Some(playback)) => self.play(playback),
+				Ok(None) => {
+					if let Some(output) = &self.output {
+						output.sink.pause();
+					}
+					Ok(())
+				}
+				Err(mpsc::RecvTimeoutError::Timeout) => self.queue_next(),
+				Err(mpsc::RecvTimeoutError::Disconnected) => return,
+			};
+			if let Err(error) = result {
+				*self.error.lock().unwrap() = Some(error);
+			}
+		}
+	}
+
+	fn play(&mut self, Playback { source, volume }: Playback) -> Result<(), String> {
+		let output = match &mut self.output {
+			Some(output) => output,
+			output @ None => output.insert(Output::new()?),
+		};
+		if self.source.as_ref() != Some(&source) {
+			output.clear()?;
+			self.tracks = tracks(&source).map_err(|error| format!("{}: {error}", source.display()))?;
+			self.next_track = 0;
+			self.source = Some(source);
+		}
+		output.sink.set_volume(volume);
+		output.sink.play();
+		*self.error.lock().unwrap() = None;
+		self.queue_next()
+	}
+
+	/// Queue the next track once the current one is done, looping around the playlist.
+	fn queue_next(&mut self) -> Result<(), String> {
+		let Some(output) = &self.output else {
+			return Ok(());
+		};
+		if output.sink.is_paused() || !output.sink.empty() || self.tracks.is_empty() {
+			return Ok(());
+		}
+
+		let track = &self.tracks[self.next_track];
+		self.next_track = (self.next_track + 1) % self.tracks.len();
+		let decoder = File::open(track)
+			.map_err(|error| error.to_string())
+			.and_then(|file| rodio::Decoder::new(BufReader::new(file)).map_err(|error| error.to_string()))
+			.map_err(|error| format!("{}: {error}", track.display()))?;
+		output.sink.append(decoder);
+		Ok(())
+	}
+}
+
+pub struct Player {
+	sender: mpsc::Sender<Option<Playback>>,
+	current: Option<Playback>,
+	error: Arc<Mutex<Option<String>>>,
+}
+
+impl Player {
+	pub fn spawn() -> Self {
+		let (sender, receiver) = mpsc::channel();
+		let error = Arc::new(Mutex::new(None));
+		let worker_error = Arc::clone(&error);
+		thread::spawn(move || {
+			let worker = Worker {
+				receiver,
+				error: worker_error,
+				output: None,
+				source: None,
+				tracks: Vec::new(),
+				next_track: 0,
+			};
+			worker.run();
+		});
+		Self {
+			sender,
+			current: None,
+			error,
+		}
+	}
+
+	/// Play `source` (a folder of audio files, an M3U playlist, or a single audio file) on repeat, or pause if it is `None`.
+	pub fn play(&mut self, source: Option<&Path>, volume: f32) {
+		let playback = source.map(|source| Playback {
+			source: source.to_owned(),
+			volume,
+		});
+		if playback == self.current {
+			return;
+		}
+		self.current = playback;
+		self
+			.sender
+			.send(self.current.clone())
+			.expect("audio thread disconnected");
+	}
+
+	/// The last error that happened while playing, if any.
+	pub fn error(&self) -> Option<String> {
+		self.error.lock().unwrap().clone()
+	}
+}
@@ -0,0 +1,87 @@
+//! Hiding the panels and the mouse cursor when they aren't being used.
+
+use egui::{Context, CursorIcon, Order, Rect, Vec2};
+
+use crate::config;
+
+/// Moving the mouse within this distance (in points) of the edge of the window reveals the panels.
+const EDGE_SIZE: f32 = 32.0;
+
+#[derive(Debug)]
+pub struct AutoHide {
+	/// When the mouse was last moved or a key was pressed, in seconds of `egui::InputState::time`.
+	last_activity: f64,
+	/// When the mouse was last moved near the edge of the window or over the panels.
+	last_panel_activity: f64,
+	was_fullscreen: bool,
+	/// The area not covered by the panels in the last frame.
+	image_area: Rect,
+}
+
+impl Default for AutoHide {
+	fn default() -> Self {
+		Self {
+			last_activity: 0.0,
+			last_panel_activity: 0.0,
+			was_fullscreen: false,
+			image_area: Rect::EVERYTHING,
+		}
+	}
+}
+
+impl AutoHide {
+	/// Track activity, hide the cursor if it has been idle, and return whether the panels should be shown.
+	/// The panels are only hidden in fullscreen, and the cursor in fullscreen and during slideshows.
+	pub fn update(&mut self, ctx: &Context, config: config::AutoHide, slideshow: bool) -> bool {
+		let (now, fullscreen, pointer, moved, typed, screen) = ctx.input(|input| {
+			(
+				input.time,
+				input.viewport().fullscreen.unwrap_or(false),
+				input.pointer.latest_pos(),
+				input.pointer.delta() != Vec2::ZERO || input.pointer.any_down(),
+				input
+					.events
+					.iter()
+					.any(|event| matches!(event, egui::Event::Key { .. })),
+				input.screen_rect(),
+			)
+		});
+
+		if moved || typed {
+			self.last_activity = now;
+		}
+		let near_panels = pointer.is_some_and(|pointer| {
+			!screen.shrink(EDGE_SIZE).contains(pointer) || !self.image_area.contains(pointer)
+		});
+		// entering fullscreen shows the panels for a moment before hiding them
+		if (moved && near_panels) || fullscreen != self.was_fullscreen {
+			self.last_panel_activity = now;
+		}
+		self.was_fullscreen = fullscreen;
+
+		let hide_panels_at = self.last_panel_activity + f64::from(config.panels_after.as_secs_f32());
+		let show_panels = !(fullscreen && config.panels) || now < hide_panels_at;
+		if fullscreen && config.panels && show_panels {
+			ctx.request_repaint_after(std::time::Duration::from_secs_f64(hide_panels_at - now));
+		}
+
+		let over_window = pointer
+			.and_then(|pointer| ctx.layer_id_at(pointer))
+			.is_some_and(|layer| layer.order != Order::Background);
+		if config.cursor && (fullscreen || slideshow) && !near_panels && !over_window {
+			let hide_cursor_at = self.last_activity + f64::from(config.cursor_after.as_secs_f32());
+			if now >= hide_cursor_at {
+				ctx.set_cursor_icon(CursorIcon::None);
+			} else {
+				ctx.request_repaint_after(std::time::Duration::from_secs_f64(hide_cursor_at - now));
+			}
+		}
+
+		show_panels
+	}
+
+	/// Set the area that is not covered by the panels, so that the panels aren't hidden while the mouse is over them.
+	pub fn set_image_area(&mut self, image_area: Rect) {
+		self.image_area = image_area;
+	}
+}
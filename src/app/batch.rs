@@ -0,0 +1,178 @@
+//! Acting on all of the marked images at once, for culling: moving or copying them to a folder, converting them to another format, or deleting them.
+//!
+//! Moving is recorded in the history as one operation, so the whole batch can be undone at once. Deleting moves the images to the trash of the system, where they can be restored from. Copies and conversions are new files, which are left alone by undoing.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use image::ImageFormat;
+
+use super::history::Operation;
+use super::image::{chunks, data_uri};
+
+/// The formats that images can be converted to.
+pub const CONVERT_FORMATS: &[ImageFormat] =
+	&[ImageFormat::Png, ImageFormat::Jpeg, ImageFormat::WebP];
+
+#[derive(Debug, Clone)]
+pub enum Action {
+	Move(PathBuf),
+	Copy {
+		dir: PathBuf,
+		/// Leave out the EXIF, XMP, and ICC data, e.g., to share photos without their location.
+		strip_metadata: bool,
+	},
+	/// Write a copy in another format next to each image. Metadata is not kept.
+	Convert(ImageFormat),
+	/// Move to the trash of the system.
+	Delete,
+}
+
+impl Action {
+	pub fn describe(&self) -> String {
+		match self {
+			Self::Move(dir) => format!("Move to {}", dir.display()),
+			Self::Copy {
+				dir,
+				strip_metadata: false,
+			} => format!("Copy to {}", dir.display()),
+			Self::Copy {
+				dir,
+				strip_metadata: true,
+			} => format!("Copy to {} without metadata", dir.display()),
+			Self::Convert(format) => format!("Convert to {}", format.extensions_str()[0].to_uppercase()),
+			Self::Delete => "Move to trash".to_owned(),
+		}
+	}
+
+	/// Whether the images are gone from where they were once this is done.
+	pub fn removes(&self) -> bool {
+		matches!(self, Self::Move(_) | Self::Delete)
+	}
+}
+
+/// The progress of a batch (or another long task, such as exporting an animation) shared between the actor and the UI, which also allows the UI to cancel it.
+#[derive(Debug, Default)]
+pub struct Progress {
+	done: AtomicUsize,
+	total: AtomicUsize,
+	cancelled: AtomicBool,
+}
+
+impl Progress {
+	pub fn reset(&self, total: usize) {
+		self.done.store(0, Ordering::Relaxed);
+		self.total.store(total, Ordering::Relaxed);
+		self.cancelled.store(false, Ordering::Relaxed);
+	}
+
+	/// How many images have been done, and how many there are.
+	pub fn get(&self) -> (usize, usize) {
+		(
+			self.done.load(Ordering::Relaxed),
+			self.total.load(Ordering::Relaxed),
+		)
+	}
+
+	pub fn advance(&self) {
+		self.done.fetch_add(1, Ordering::Relaxed);
+	}
+
+	pub fn cancel(&self) {
+		self.cancelled.store(true, Ordering::Relaxed);
+	}
+
+	pub fn is_cancelled(&self) -> bool {
+		self.cancelled.load(Ordering::Relaxed)
+	}
+}
+
+#[derive(Debug)]
+pub struct Outcome {
+	pub action: Action,
+	/// The images that the action was done to.
+	pub done: Vec<Arc<Path>>,
+	/// The images that the action failed for, with the reason.
+	pub failed: Vec<(Arc<Path>, String)>,
+	pub cancelled: bool,
+}
+
+/// Where the image at `path` goes in `dir`, which must not exist yet.
+fn target(path: &Path, dir: &Path) -> io::Result<PathBuf> {
+	let target = dir.join(path.file_name().unwrap_or_default());
+	if target.exists() {
+		return Err(io::Error::new(
+			io::ErrorKind::AlreadyExists,
+			format!("{} already exists", target.display()),
+		));
+	}
+	Ok(target)
+}
+
+fn convert(path: &Path, format: ImageFormat) -> io::Result<()> {
+	let target = path.with_extension(format.extensions_str()[0]);
+	if target.exists() {
+		return Err(io::Error::new(
+			io::ErrorKind::AlreadyExists,
+			format!("{} already exists", target.display()),
+		));
+	}
+	let image = image::io::Reader::open(path)?
+		.with_guessed_format()?
+		.decode()
+		.map_err(io::Error::other)?;
+	let encoded = data_uri::encode(&image, format).map_err(io::Error::other)?;
+	std::fs::write(target, encoded)
+}
+
+/// Do `action` to the images at `paths`, stopping early if it is cancelled.
+/// Returns the operation to record in the history, if any files were moved.
+pub fn run(
+	action: Action,
+	paths: &[Arc<Path>],
+	progress: &Progress,
+) -> (Outcome, Option<Operation>) {
+	progress.reset(paths.len());
+	let mut operations = Vec::new();
+	let mut outcome = Outcome {
+		action,
+		done: Vec::new(),
+		failed: Vec::new(),
+		cancelled: false,
+	};
+
+	for path in paths {
+		if progress.is_cancelled() {
+			outcome.cancelled = true;
+			break;
+		}
+		let result = match &outcome.action {
+			Action::Move(dir) => target(path, dir)
+				.and_then(|target| Operation::rename(Arc::clone(path), target.into()))
+				.map(|operation| operations.push(operation)),
+			Action::Copy {
+				dir,
+				strip_metadata,
+			} => target(path, dir).and_then(|target| {
+				if *strip_metadata {
+					chunks::copy_stripped(path, &target)
+				} else {
+					std::fs::copy(path, target).map(drop)
+				}
+			}),
+			Action::Convert(format) => convert(path, *format),
+			Action::Delete => trash::delete(path).map_err(io::Error::other),
+		};
+		match result {
+			Ok(()) => outcome.done.push(Arc::clone(path)),
+			Err(error) => outcome.failed.push((Arc::clone(path), error.to_string())),
+		}
+		progress.advance();
+	}
+
+	let operation =
+		(!operations.is_empty()).then(|| Operation::batch(outcome.action.describe(), operations));
+	(outcome, operation)
+}
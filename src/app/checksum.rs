@@ -0,0 +1,185 @@
+//! Verifying files against checksums in sidecar files (`photo.jpg.sha256`) or manifests (`SHA256SUMS`), in the format used by `sha256sum`.
+
+use std::fmt::Write as _;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use md5::Md5;
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+	Sha256,
+	Md5,
+}
+
+impl Algorithm {
+	pub fn repr(self) -> &'static str {
+		match self {
+			Self::Sha256 => "SHA-256",
+			Self::Md5 => "MD5",
+		}
+	}
+
+	fn sidecar_extension(self) -> &'static str {
+		match self {
+			Self::Sha256 => "sha256",
+			Self::Md5 => "md5",
+		}
+	}
+
+	fn manifest_names(self) -> &'static [&'static str] {
+		match self {
+			Self::Sha256 => &["SHA256SUMS", "SHA256SUMS.txt", "checksums.sha256"],
+			Self::Md5 => &["MD5SUMS", "MD5SUMS.txt", "checksums.md5"],
+		}
+	}
+
+	const VARIANTS: &'static [Self] = &[Self::Sha256, Self::Md5];
+}
+
+#[derive(Debug, Clone)]
+pub enum Status {
+	/// There is no sidecar or manifest entry for the file.
+	Unknown,
+	Verified {
+		algorithm: Algorithm,
+		source: PathBuf,
+	},
+	Modified {
+		algorithm: Algorithm,
+		source: PathBuf,
+	},
+}
+
+fn hash_file<D: Digest + io::Write>(path: &Path) -> io::Result<String> {
+	let mut hasher = D::new();
+	io::copy(&mut std::fs::File::open(path)?, &mut hasher)?;
+	let mut hex = String::new();
+	for byte in hasher.finalize() {
+		write!(hex, "{byte:02x}").unwrap();
+	}
+	Ok(hex)
+}
+
+fn hash(path: &Path, algorithm: Algorithm) -> io::Result<String> {
+	match algorithm {
+		Algorithm::Sha256 => hash_file::<Sha256>(path),
+		Algorithm::Md5 => hash_file::<Md5>(path),
+	}
+}
+
+/// Parse a line of the form `<hash>  <file name>` or `<hash> *<file name>`. The file name may be omitted in sidecars.
+fn parse_line(line: &str) -> Option<(&str, Option<&str>)> {
+	let line = line.trim_end();
+	if line.is_empty() || line.starts_with('#') {
+		return None;
+	}
+	match line.split_once(' ') {
+		Some((hash, name)) => {
+			let name = name.trim_start_matches(' ');
+			let name = name.strip_prefix('*').unwrap_or(name);
+			Some((hash, Some(name)))
+		}
+		None => Some((line, None)),
+	}
+}
+
+/// Find the expected hash for `path`, and the file it came from.
+fn find_expected(path: &Path, algorithm: Algorithm) -> io::Result<Option<(String, PathBuf)>> {
+	let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+
+	let mut sidecar = path.as_os_str().to_owned();
+	sidecar.push(".");
+	sidecar.push(algorithm.sidecar_extension());
+	let sidecar = PathBuf::from(sidecar);
+	let manifests = algorithm
+		.manifest_names()
+		.iter()
+		.map(|name| path.with_file_name(name));
+
+	for source in std::iter::once(sidecar).chain(manifests) {
+		let raw = match std::fs::read_to_string(&source) {
+			Ok(raw) => raw,
+			Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+			Err(error) => return Err(error),
+		};
+		let expected = raw
+			.lines()
+			.filter_map(parse_line)
+			.find(|(_hash, name)| match name {
+				// a manifest entry may be a relative path
+				Some(name) => Path::new(name).file_name() == Some(file_name.as_ref().as_ref()),
+				// sidecars may omit the file name
+				None => true,
+			});
+		if let Some((hash, _name)) = expected {
+			return Ok(Some((hash.to_ascii_lowercase(), source)));
+		}
+	}
+	Ok(None)
+}
+
+/// Check `path` against the first checksum found for it.
+pub fn verify(path: &Path) -> io::Result<Status> {
+	for &algorithm in Algorithm::VARIANTS {
+		let Some((expected, source)) = find_expected(path, algorithm)? else {
+			continue;
+		};
+		let actual = hash(path, algorithm)?;
+		return Ok(if actual == expected {
+			Status::Verified { algorithm, source }
+		} else {
+			Status::Modified { algorithm, source }
+		});
+	}
+	Ok(Status::Unknown)
+}
+
+/// Write a SHA-256 manifest of all the files in `dir`. An existing manifest is not replaced.
+pub fn generate(dir: &Path) -> io::Result<PathBuf> {
+	let manifest_path = dir.join(Algorithm::Sha256.manifest_names()[0]);
+	let already_exists = || {
+		io::Error::new(
+			io::ErrorKind::AlreadyExists,
+			format!("{} already exists", manifest_path.display()),
+		)
+	};
+	// checked before hashing so that the work isn't wasted, and again when creating the file in case it was made meanwhile
+	if manifest_path.try_exists()? {
+		return Err(already_exists());
+	}
+
+	let mut names = Vec::new();
+	for entry in std::fs::read_dir(dir)? {
+		let entry = entry?;
+		if !entry.file_type()?.is_file() {
+			continue;
+		}
+		let name = entry.file_name();
+		let is_checksum_file = Algorithm::VARIANTS.iter().any(|algorithm| {
+			algorithm
+				.manifest_names()
+				.iter()
+				.any(|manifest| name == *manifest)
+				|| Path::new(&name).extension() == Some(algorithm.sidecar_extension().as_ref())
+		});
+		if !is_checksum_file {
+			names.push(name);
+		}
+	}
+	names.sort_unstable_by(|a, b| natord::compare(&a.to_string_lossy(), &b.to_string_lossy()));
+
+	let mut manifest = String::new();
+	for name in names {
+		let hash = hash(&dir.join(&name), Algorithm::Sha256)?;
+		writeln!(manifest, "{hash}  {}", name.to_string_lossy()).unwrap();
+	}
+	let mut file = match std::fs::File::create_new(&manifest_path) {
+		Ok(file) => file,
+		Err(error) if error.kind() == io::ErrorKind::AlreadyExists => return Err(already_exists()),
+		Err(error) => return Err(error),
+	};
+	io::Write::write_all(&mut file, manifest.as_bytes())?;
+	Ok(manifest_path)
+}
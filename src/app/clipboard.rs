@@ -0,0 +1,63 @@
+use std::borrow::Cow;
+
+use egui::{Color32, Rect};
+
+use crate::app::image::Pixels;
+
+/// Lazily connects to the system clipboard.
+/// The connection is kept alive since on some platforms the clipboard contents are lost when it is dropped.
+#[derive(Default)]
+pub struct Clipboard(Option<arboard::Clipboard>);
+
+impl Clipboard {
+	fn get(&mut self) -> Result<&mut arboard::Clipboard, arboard::Error> {
+		if self.0.is_none() {
+			self.0 = Some(arboard::Clipboard::new()?);
+		}
+		Ok(self.0.as_mut().unwrap())
+	}
+
+	/// Copy the pixels of `frame_idx` within `region` (in pixel coordinates), or the whole frame if `region` is `None`.
+	pub fn copy_pixels(
+		&mut self,
+		pixels: &Pixels,
+		frame_idx: usize,
+		region: Option<Rect>,
+	) -> Result<(), arboard::Error> {
+		let frame = pixels.frame(frame_idx);
+		let width: usize = az::cast(pixels.width);
+		let height: usize = az::cast(pixels.height);
+		let (x, y, crop_width, crop_height) = match region {
+			Some(region) => (
+				az::cast(region.min.x),
+				az::cast(region.min.y),
+				az::cast(region.width()),
+				az::cast(region.height()),
+			),
+			None => (0, 0, width, height),
+		};
+
+		let bytes = crop(frame, width, x..x + crop_width, y..y + crop_height);
+		self.get()?.set_image(arboard::ImageData {
+			width: crop_width,
+			height: crop_height,
+			bytes: Cow::Owned(bytes),
+		})?;
+		Ok(())
+	}
+}
+
+fn crop(
+	frame: &[Color32],
+	width: usize,
+	xs: std::ops::Range<usize>,
+	ys: std::ops::Range<usize>,
+) -> Vec<u8> {
+	frame
+		.chunks_exact(width)
+		.skip(ys.start)
+		.take(ys.len())
+		.flat_map(|row| &row[xs.clone()])
+		.flat_map(|pixel: &Color32| pixel.to_array())
+		.collect()
+}
@@ -0,0 +1,53 @@
+//! Noticing when the configuration file is edited by hand, so that the changes can be applied without restarting.
+//!
+//! Like the folder statistics, the modification time of the file is polled rather than relying on notifications from the platform.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+/// How often the file is checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Watches the configuration file in the background until this is dropped.
+pub struct Watcher {
+	changed: Arc<AtomicBool>,
+	/// The watcher stops once this is dropped.
+	_stop: mpsc::Sender<()>,
+}
+
+impl Watcher {
+	pub fn spawn(egui_ctx: egui::Context, path: PathBuf) -> Self {
+		let changed = Arc::new(AtomicBool::new(false));
+		let watcher_changed = Arc::clone(&changed);
+		let (stop, stopped) = mpsc::channel();
+		thread::spawn(move || {
+			let modified = || {
+				std::fs::metadata(&path)
+					.and_then(|metadata| metadata.modified())
+					.ok()
+			};
+			let mut last = modified();
+			while let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(POLL_INTERVAL) {
+				let current = modified();
+				// a missing file is likely being replaced by an editor, so it is read once it is back
+				if current.is_some() && current != last {
+					watcher_changed.store(true, Ordering::Relaxed);
+					egui_ctx.request_repaint();
+				}
+				last = current;
+			}
+		});
+		Self {
+			changed,
+			_stop: stop,
+		}
+	}
+
+	/// Whether the file changed since this was last called.
+	pub fn take_changed(&self) -> bool {
+		self.changed.swap(false, Ordering::Relaxed)
+	}
+}
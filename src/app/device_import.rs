@@ -0,0 +1,190 @@
+//! Importing images from cameras and phones attached over MTP/PTP.
+//!
+//! Devices are accessed through the mounts that `GVfs` creates for them (`mtp://` and `gphoto2://`), so the desktop must have mounted the device already.
+
+use std::fs::File;
+use std::io::{self, BufRead as _};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use image::ImageFormat;
+
+#[derive(Debug)]
+pub struct Entry {
+	pub path: PathBuf,
+	pub size: u64,
+}
+
+#[derive(Debug)]
+pub struct Device {
+	pub name: String,
+	pub images: Vec<Entry>,
+	/// Folders and files that couldn't be read, with the errors. The rest of the device is still listed.
+	pub unreadable: Vec<(PathBuf, String)>,
+}
+
+fn gvfs_dir() -> Option<PathBuf> {
+	std::env::var_os("XDG_RUNTIME_DIR").map(|runtime| Path::new(&runtime).join("gvfs"))
+}
+
+/// `mtp:host=Canon_EOS_R6` -> `Canon EOS R6`
+fn device_name(mount: &str) -> Option<String> {
+	let (scheme, rest) = mount.split_once(':')?;
+	if !matches!(scheme, "mtp" | "gphoto2") {
+		return None;
+	}
+	let host = rest
+		.split(',')
+		.find_map(|param| param.strip_prefix("host="))
+		.unwrap_or(rest);
+	Some(host.replace('_', " "))
+}
+
+fn find_images(dir: &Path, device: &mut Device) {
+	let entries = match std::fs::read_dir(dir) {
+		Ok(entries) => entries,
+		Err(error) => {
+			device.unreadable.push((dir.to_owned(), error.to_string()));
+			return;
+		}
+	};
+	for entry in entries {
+		let entry = match entry {
+			Ok(entry) => entry,
+			Err(error) => {
+				device.unreadable.push((dir.to_owned(), error.to_string()));
+				continue;
+			}
+		};
+		if entry.file_name().to_string_lossy().starts_with('.') {
+			continue;
+		}
+		let path = entry.path();
+		let ty = match entry.file_type() {
+			Ok(ty) => ty,
+			Err(error) => {
+				device.unreadable.push((path, error.to_string()));
+				continue;
+			}
+		};
+		if ty.is_dir() {
+			find_images(&path, device);
+		} else if ImageFormat::from_path(&path).is_ok() {
+			match entry.metadata() {
+				Ok(metadata) => device.images.push(Entry {
+					path,
+					size: metadata.len(),
+				}),
+				Err(error) => device.unreadable.push((path, error.to_string())),
+			}
+		}
+	}
+}
+
+/// List the attached devices and the images on them.
+pub fn devices() -> io::Result<Vec<Device>> {
+	let Some(gvfs) = gvfs_dir() else {
+		return Ok(Vec::new());
+	};
+	let mounts = match std::fs::read_dir(gvfs) {
+		Ok(mounts) => mounts,
+		Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+		Err(error) => return Err(error),
+	};
+
+	let mut devices = Vec::new();
+	for mount in mounts.flatten() {
+		let Some(name) = device_name(&mount.file_name().to_string_lossy()) else {
+			continue;
+		};
+		let mut device = Device {
+			name,
+			images: Vec::new(),
+			unreadable: Vec::new(),
+		};
+		find_images(&mount.path(), &mut device);
+		device.images.sort_unstable_by(|a, b| {
+			natord::compare(&a.path.to_string_lossy(), &b.path.to_string_lossy())
+		});
+		devices.push(device);
+	}
+	Ok(devices)
+}
+
+fn same_contents(a: &Path, b: &Path) -> io::Result<bool> {
+	if a.metadata()?.len() != b.metadata()?.len() {
+		return Ok(false);
+	}
+	let mut a = io::BufReader::new(File::open(a)?);
+	let mut b = io::BufReader::new(File::open(b)?);
+	loop {
+		let a_buf = a.fill_buf()?;
+		let b_buf = b.fill_buf()?;
+		if a_buf.is_empty() || b_buf.is_empty() {
+			return Ok(a_buf.is_empty() && b_buf.is_empty());
+		}
+		let len = a_buf.len().min(b_buf.len());
+		if a_buf[..len] != b_buf[..len] {
+			return Ok(false);
+		}
+		a.consume(len);
+		b.consume(len);
+	}
+}
+
+/// `IMG_0001.JPG` -> `IMG_0001 (2).JPG`
+fn numbered_name(name: &Path, number: u32) -> PathBuf {
+	let stem = name.file_stem().unwrap_or_default().to_string_lossy();
+	match name.extension() {
+		Some(extension) => format!("{stem} ({number}).{}", extension.to_string_lossy()).into(),
+		None => format!("{stem} ({number})").into(),
+	}
+}
+
+fn import_file(file: &Path, target: &Path) -> io::Result<PathBuf> {
+	let name = Path::new(file.file_name().unwrap_or_default());
+	let mut source = File::open(file)?;
+	let mut destination = target.join(name);
+	let mut number = 1;
+	loop {
+		match File::options()
+			.write(true)
+			.create_new(true)
+			.open(&destination)
+		{
+			Ok(mut copy) => {
+				if let Err(error) = io::copy(&mut source, &mut copy) {
+					_ = std::fs::remove_file(&destination);
+					return Err(error);
+				}
+				return Ok(destination);
+			}
+			Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {
+				if same_contents(file, &destination)? {
+					return Ok(destination);
+				}
+			}
+			Err(error) => return Err(error),
+		}
+		number += 1;
+		destination = target.join(numbered_name(name, number));
+	}
+}
+
+/// Copy `files` into `target`, returning the paths of the copies.
+///
+/// Existing files are never overwritten. A file that already exists in `target` with the same contents is assumed to have been imported before and is not copied again, and a different file with the same name gets a number added to the name of the copy.
+pub fn import(files: &[PathBuf], target: &Path) -> io::Result<Vec<Arc<Path>>> {
+	std::fs::create_dir_all(target)?;
+	files
+		.iter()
+		.map(|file| import_file(file, target).map(Arc::from))
+		.collect()
+}
+
+/// Where images are imported to by default.
+pub fn default_target() -> PathBuf {
+	directories_next::UserDirs::new()
+		.and_then(|dirs| dirs.picture_dir().map(Path::to_owned))
+		.unwrap_or_default()
+}
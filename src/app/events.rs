@@ -0,0 +1,78 @@
+//! Events that happen in the app, which interested modules subscribe to rather than being called from wherever the event happens.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use super::rating::Rating;
+
+#[derive(Debug, Clone)]
+pub enum Event {
+	/// Another image was opened, even if it failed to load.
+	ImageOpened(Arc<Path>),
+	/// The image was replaced by another one.
+	ImageClosed(Arc<Path>),
+	FileDeleted(Arc<Path>),
+	/// The app wrote the file, possibly over an existing one.
+	FileWritten(Arc<Path>),
+	RatingChanged(Arc<Path>, Rating),
+	/// The slideshow moved on to the next image.
+	SlideshowTick,
+	/// Moving on would have wrapped around to the first image.
+	EndReached,
+}
+
+impl std::fmt::Display for Event {
+	fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::ImageOpened(path) => write!(formatter, "Opened {}", path.display()),
+			Self::ImageClosed(path) => write!(formatter, "Closed {}", path.display()),
+			Self::FileDeleted(path) => write!(formatter, "Deleted {}", path.display()),
+			Self::FileWritten(path) => write!(formatter, "Wrote {}", path.display()),
+			Self::RatingChanged(path, rating) => {
+				write!(
+					formatter,
+					"Rated {} {}/{}",
+					path.display(),
+					rating.stars,
+					super::rating::MAX_STARS
+				)?;
+				if rating.favorite {
+					formatter.write_str(" (favorite)")?;
+				}
+				Ok(())
+			}
+			Self::SlideshowTick => formatter.write_str("Slideshow moved on"),
+			Self::EndReached => formatter.write_str("Reached the end"),
+		}
+	}
+}
+
+type Subscriber = Box<dyn FnMut(&Event)>;
+
+/// Events are queued as they are emitted and delivered together once per frame, so that emitting one never re-enters the code that handles it.
+#[derive(Default)]
+pub struct Bus {
+	pending: Vec<Event>,
+	subscribers: Vec<Subscriber>,
+}
+
+impl Bus {
+	pub fn emit(&mut self, event: Event) {
+		self.pending.push(event);
+	}
+
+	pub fn subscribe(&mut self, subscriber: impl FnMut(&Event) + 'static) {
+		self.subscribers.push(Box::new(subscriber));
+	}
+
+	/// Deliver the events emitted since the last call to the subscribers, and return them so that the caller can handle them too.
+	pub fn dispatch(&mut self) -> Vec<Event> {
+		let events = std::mem::take(&mut self.pending);
+		for event in &events {
+			for subscriber in &mut self.subscribers {
+				subscriber(event);
+			}
+		}
+		events
+	}
+}
@@ -0,0 +1,276 @@
+//! Statistics about the images in the current folder, kept up to date by a background indexer.
+//!
+//! Only the headers of the images are read. The indexer polls the modification time of the folder to notice added, removed, and renamed files, and only re-reads files whose size or modification time changed.
+
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use image::ImageFormat;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+/// How often the folder is checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// How many newly indexed files are processed between updates of the statistics.
+const PUBLISH_EVERY: usize = 32;
+
+/// The upper bounds of the buckets of the dimension distribution, in megapixels.
+pub const MEGAPIXEL_BUCKETS: [(f64, &str); 5] = [
+	(1.0, "Under 1 MP"),
+	(4.0, "1–4 MP"),
+	(12.0, "4–12 MP"),
+	(24.0, "12–24 MP"),
+	(f64::INFINITY, "24 MP and over"),
+];
+
+#[derive(Debug, Default, Clone)]
+pub struct Stats {
+	pub images: usize,
+	pub total_size: u64,
+	/// Sorted by count, most common first.
+	pub formats: Vec<(ImageFormat, usize)>,
+	/// Indexes correspond to `MEGAPIXEL_BUCKETS`.
+	pub dimensions: [usize; MEGAPIXEL_BUCKETS.len()],
+	/// Images whose dimensions couldn't be read.
+	pub unreadable: usize,
+	pub oldest: Option<SystemTime>,
+	pub newest: Option<SystemTime>,
+	/// Whether the folder is still being indexed, so the statistics are incomplete.
+	pub indexing: bool,
+}
+
+struct FileInfo {
+	modified: Option<SystemTime>,
+	size: u64,
+	format: ImageFormat,
+	dimensions: Option<(u32, u32)>,
+}
+
+fn megapixel_bucket((width, height): (u32, u32)) -> usize {
+	let megapixels = f64::from(width) * f64::from(height) / 1_000_000.0;
+	MEGAPIXEL_BUCKETS
+		.iter()
+		.position(|&(max, _label)| megapixels < max)
+		.unwrap_or(MEGAPIXEL_BUCKETS.len() - 1)
+}
+
+enum Message {
+	/// Index the folder, or pause indexing if `None`.
+	Watch(Option<PathBuf>),
+	/// The app changed or removed the file, so it should be re-read even if the folder looks unchanged.
+	Changed(PathBuf),
+}
+
+struct Indexer {
+	egui_ctx: egui::Context,
+	receiver: mpsc::Receiver<Message>,
+	shared: Arc<Mutex<Stats>>,
+	/// The folder that was indexed last. It is kept while paused so that indexing can resume from where it left off.
+	dir: Option<PathBuf>,
+	watching: bool,
+	/// The modification time of `dir` when it was last indexed.
+	dir_modified: Option<SystemTime>,
+	files: FxHashMap<PathBuf, FileInfo>,
+}
+
+impl Indexer {
+	fn run(mut self) {
+		let mut next = None;
+		loop {
+			let command = match next.take() {
+				Some(message) => Ok(message),
+				None if !self.watching => self
+					.receiver
+					.recv()
+					.map_err(|mpsc::RecvError| mpsc::RecvTimeoutError::Disconnected),
+				None => self.receiver.recv_timeout(POLL_INTERVAL),
+			};
+			match command {
+				Ok(Message::Watch(None)) => {
+					self.watching = false;
+					continue;
+				}
+				Ok(Message::Changed(path)) => {
+					self.files.remove(&path);
+					self.dir_modified = None;
+					if !self.watching {
+						continue;
+					}
+				}
+				Ok(Message::Watch(Some(dir))) => {
+					if self.dir.as_ref() != Some(&dir) {
+						self.files.clear();
+						self.dir_modified = None;
+						self.publish(true);
+					}
+					self.dir = Some(dir);
+					self.watching = true;
+				}
+				Err(mpsc::RecvTimeoutError::Timeout) => {}
+				Err(mpsc::RecvTimeoutError::Disconnected) => return,
+			}
+
+			if let ControlFlow::Break(message) = self.update() {
+				next = Some(message);
+			}
+		}
+	}
+
+	/// Re-index the folder if it changed. Breaks if another message arrived in the meantime.
+	fn update(&mut self) -> ControlFlow<Message> {
+		let Some(dir) = self.dir.clone() else {
+			self.publish(false);
+			return ControlFlow::Continue(());
+		};
+		let modified = std::fs::metadata(&dir)
+			.and_then(|metadata| metadata.modified())
+			.ok();
+		if modified.is_some() && modified == self.dir_modified {
+			return ControlFlow::Continue(());
+		}
+
+		let Ok(entries) = std::fs::read_dir(&dir) else {
+			self.files.clear();
+			self.publish(false);
+			return ControlFlow::Continue(());
+		};
+		let mut seen = FxHashSet::default();
+		let mut since_publish = 0;
+		for entry in entries.flatten() {
+			if let Ok(message) = self.receiver.try_recv() {
+				return ControlFlow::Break(message);
+			}
+
+			let path = entry.path();
+			let Ok(format) = ImageFormat::from_path(&path) else {
+				continue;
+			};
+			let Ok(metadata) = entry.metadata() else {
+				continue;
+			};
+			if !metadata.is_file() {
+				continue;
+			}
+			let modified = metadata.modified().ok();
+			let size = metadata.len();
+			seen.insert(path.clone());
+
+			let unchanged = self
+				.files
+				.get(&path)
+				.is_some_and(|info| info.modified == modified && info.size == size);
+			if unchanged {
+				continue;
+			}
+			let dimensions = image::image_dimensions(&path).ok();
+			self.files.insert(
+				path,
+				FileInfo {
+					modified,
+					size,
+					format,
+					dimensions,
+				},
+			);
+			since_publish += 1;
+			if since_publish >= PUBLISH_EVERY {
+				since_publish = 0;
+				self.publish(true);
+			}
+		}
+
+		self.files.retain(|path, _info| seen.contains(path));
+		self.dir_modified = modified;
+		self.publish(false);
+		ControlFlow::Continue(())
+	}
+
+	fn publish(&self, indexing: bool) {
+		let mut stats = Stats {
+			indexing,
+			..Stats::default()
+		};
+		let mut formats = FxHashMap::<ImageFormat, usize>::default();
+		for info in self.files.values() {
+			stats.images += 1;
+			stats.total_size += info.size;
+			*formats.entry(info.format).or_default() += 1;
+			match info.dimensions {
+				Some(dimensions) => stats.dimensions[megapixel_bucket(dimensions)] += 1,
+				None => stats.unreadable += 1,
+			}
+			if let Some(modified) = info.modified {
+				stats.oldest = Some(stats.oldest.map_or(modified, |oldest| oldest.min(modified)));
+				stats.newest = Some(stats.newest.map_or(modified, |newest| newest.max(modified)));
+			}
+		}
+		stats.formats = formats.into_iter().collect();
+		stats
+			.formats
+			.sort_unstable_by_key(|&(_format, count)| std::cmp::Reverse(count));
+
+		*self.shared.lock().unwrap() = stats;
+		self.egui_ctx.request_repaint();
+	}
+}
+
+/// Tells the indexer about changes that the app made, which can be sent from anywhere.
+#[derive(Clone)]
+pub struct Notifier(mpsc::Sender<Message>);
+
+impl Notifier {
+	pub fn changed(&self, path: &Path) {
+		// the indexer only stops when the app does
+		_ = self.0.send(Message::Changed(path.to_owned()));
+	}
+}
+
+pub struct FolderStats {
+	sender: mpsc::Sender<Message>,
+	dir: Option<PathBuf>,
+	stats: Arc<Mutex<Stats>>,
+}
+
+impl FolderStats {
+	pub fn spawn(egui_ctx: egui::Context) -> Self {
+		let (sender, receiver) = mpsc::channel();
+		let stats = Arc::new(Mutex::new(Stats::default()));
+		let indexer = Indexer {
+			egui_ctx,
+			receiver,
+			shared: Arc::clone(&stats),
+			dir: None,
+			watching: false,
+			dir_modified: None,
+			files: FxHashMap::default(),
+		};
+		thread::spawn(move || indexer.run());
+		Self {
+			sender,
+			dir: None,
+			stats,
+		}
+	}
+
+	/// Index `dir` and keep the statistics up to date as it changes, or pause indexing if it is `None`.
+	pub fn watch(&mut self, dir: Option<&Path>) {
+		if self.dir.as_deref() == dir {
+			return;
+		}
+		self.dir = dir.map(Path::to_owned);
+		self
+			.sender
+			.send(Message::Watch(self.dir.clone()))
+			.expect("indexer disconnected");
+	}
+
+	pub fn notifier(&self) -> Notifier {
+		Notifier(self.sender.clone())
+	}
+
+	pub fn stats(&self) -> MutexGuard<'_, Stats> {
+		self.stats.lock().unwrap()
+	}
+}
@@ -0,0 +1,426 @@
+//! Undoing and redoing the actions that change files.
+//!
+//! Every such action is an `Operation` that knows how to reverse itself. Deleted files are moved to a trash folder rather than being removed, so that deleting can be undone.
+//! Files are removed from the trash for good once their operation falls off the end of the history or the app exits.
+//! Each running instance has its own trash, which it keeps locked, so that the trash of instances that have exited can be emptied without touching that of the others.
+
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use std::{io, thread};
+
+use super::rating::{self, Rating};
+
+/// How many operations can be undone.
+const MAX_UNDO: usize = 50;
+/// Locked by the instance that the trash belongs to while it runs.
+const LOCK_NAME: &str = ".lock";
+
+fn trash_root() -> PathBuf {
+	directories_next::ProjectDirs::from("nz", "felle", "eo2")
+		.expect("getting data path")
+		.data_dir()
+		.join("trash")
+}
+
+/// Remove the trash of instances that are no longer running, which is no longer locked.
+fn empty_abandoned_trash(root: &Path, own: &Path) {
+	let Ok(entries) = std::fs::read_dir(root) else {
+		return;
+	};
+	for entry in entries.flatten() {
+		let path = entry.path();
+		if path == own {
+			continue;
+		}
+		if !entry.file_type().is_ok_and(|ty| ty.is_dir()) {
+			// from before each instance had its own trash
+			_ = std::fs::remove_file(&path);
+			continue;
+		}
+		// without a lock, the instance may not have locked it yet
+		let Ok(lock) = File::open(path.join(LOCK_NAME)) else {
+			continue;
+		};
+		if lock.try_lock().is_ok() {
+			drop(lock);
+			_ = std::fs::remove_dir_all(&path);
+		}
+	}
+}
+
+#[derive(Debug)]
+struct Trash {
+	dir: PathBuf,
+	_lock: File,
+}
+
+impl Trash {
+	fn new() -> io::Result<Self> {
+		let root = trash_root();
+		let dir = root.join(std::process::id().to_string());
+		// left behind by an instance that had the same process ID, which must have exited
+		_ = std::fs::remove_dir_all(&dir);
+		std::fs::create_dir_all(&dir)?;
+		let lock = File::create(dir.join(LOCK_NAME))?;
+		lock.lock()?;
+		empty_abandoned_trash(&root, &dir);
+		Ok(Self { dir, _lock: lock })
+	}
+}
+
+impl Drop for Trash {
+	fn drop(&mut self) {
+		_ = std::fs::remove_dir_all(&self.dir);
+	}
+}
+
+/// Renaming only works within a filesystem, so fall back to copying.
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+	if std::fs::rename(from, to).is_ok() {
+		return Ok(());
+	}
+	copy_and_remove(from, to)
+}
+
+fn copy_and_remove(from: &Path, to: &Path) -> io::Result<()> {
+	if let Err(error) = std::fs::copy(from, to) {
+		_ = std::fs::remove_file(to);
+		return Err(error);
+	}
+	std::fs::remove_file(from)
+}
+
+/// A file being moved to or from the trash.
+/// Moving between filesystems copies the file, which can take a while, so that is done in the background to avoid blocking the viewer.
+#[derive(Debug, Default)]
+pub struct Move(Mutex<Option<thread::JoinHandle<io::Result<()>>>>);
+
+impl Move {
+	fn start(&self, from: &Path, to: &Path) -> io::Result<()> {
+		self.finish()?;
+		if std::fs::rename(from, to).is_ok() {
+			return Ok(());
+		}
+		let (from, to) = (from.to_owned(), to.to_owned());
+		*self.0.lock().unwrap() = Some(thread::spawn(move || copy_and_remove(&from, &to)));
+		Ok(())
+	}
+
+	/// Wait for the file to be moved.
+	fn finish(&self) -> io::Result<()> {
+		match self.0.lock().unwrap().take() {
+			Some(handle) => handle
+				.join()
+				.unwrap_or_else(|_| Err(io::Error::other("moving the file panicked"))),
+			None => Ok(()),
+		}
+	}
+}
+
+/// Moving a file over an existing one replaces it silently, which would lose it.
+fn ensure_missing(path: &Path) -> io::Result<()> {
+	if path.exists() {
+		return Err(io::Error::new(
+			io::ErrorKind::AlreadyExists,
+			format!("{} already exists", path.display()),
+		));
+	}
+	Ok(())
+}
+
+fn rename_new(from: &Path, to: &Path) -> io::Result<()> {
+	ensure_missing(to)?;
+	move_file(from, to)?;
+	// the file has moved either way
+	_ = rating::moved(from, to);
+	Ok(())
+}
+
+#[derive(Debug)]
+pub enum Operation {
+	Delete {
+		path: Arc<Path>,
+		/// Where the file is kept while it is deleted.
+		trashed: PathBuf,
+		moving: Move,
+	},
+	Rename {
+		from: Arc<Path>,
+		to: Arc<Path>,
+	},
+	/// The rating of the image was changed from `before` to `after`.
+	Rate {
+		path: Arc<Path>,
+		before: Rating,
+		after: Rating,
+	},
+	/// Operations that were done together, which are undone and redone together. Never empty.
+	Batch {
+		description: String,
+		operations: Vec<Operation>,
+	},
+}
+
+impl Operation {
+	/// Delete the file at `path` in a way that can be undone, by moving it into `trash`.
+	pub fn delete(path: Arc<Path>, trash: &Path) -> io::Result<Self> {
+		// the time keeps files with the same name apart
+		let nanos = SystemTime::now()
+			.duration_since(SystemTime::UNIX_EPOCH)
+			.unwrap_or_default()
+			.as_nanos();
+		let name = path.file_name().unwrap_or_default().to_string_lossy();
+		let trashed = trash.join(format!("{nanos}-{name}"));
+		let moving = Move::default();
+		moving.start(&path, &trashed)?;
+		Ok(Self::Delete {
+			path,
+			trashed,
+			moving,
+		})
+	}
+
+	/// Set the rating of the image at `path` in a way that can be undone.
+	pub fn rate(path: Arc<Path>, after: Rating) -> io::Result<Self> {
+		let before = rating::get(&path)?;
+		rating::set(&path, after)?;
+		Ok(Self::Rate {
+			path,
+			before,
+			after,
+		})
+	}
+
+	/// Rename the file at `from` to `to`, which must not exist yet.
+	pub fn rename(from: Arc<Path>, to: Arc<Path>) -> io::Result<Self> {
+		rename_new(&from, &to)?;
+		Ok(Self::Rename { from, to })
+	}
+
+	/// Group operations that were done together. `operations` must not be empty.
+	pub fn batch(description: String, operations: Vec<Self>) -> Self {
+		debug_assert!(!operations.is_empty());
+		Self::Batch {
+			description,
+			operations,
+		}
+	}
+
+	fn undo(&self) -> io::Result<()> {
+		match self {
+			Self::Delete {
+				path,
+				trashed,
+				moving,
+			} => {
+				// the file never made it to the trash
+				if moving.finish().is_err() && !trashed.exists() {
+					return Ok(());
+				}
+				ensure_missing(path)?;
+				moving.start(trashed, path)
+			}
+			Self::Rename { from, to } => rename_new(to, from),
+			Self::Rate { path, before, .. } => rating::set(path, *before),
+			Self::Batch { operations, .. } => {
+				for (idx, operation) in operations.iter().enumerate().rev() {
+					if let Err(error) = operation.undo() {
+						// put back the ones that were undone, so the batch stays whole
+						for operation in &operations[idx + 1..] {
+							_ = operation.redo();
+						}
+						return Err(error);
+					}
+				}
+				Ok(())
+			}
+		}
+	}
+
+	fn redo(&self) -> io::Result<()> {
+		match self {
+			Self::Delete {
+				path,
+				trashed,
+				moving,
+			} => moving.start(path, trashed),
+			Self::Rename { from, to } => rename_new(from, to),
+			Self::Rate { path, after, .. } => rating::set(path, *after),
+			Self::Batch { operations, .. } => {
+				for (idx, operation) in operations.iter().enumerate() {
+					if let Err(error) = operation.redo() {
+						for operation in operations[..idx].iter().rev() {
+							_ = operation.undo();
+						}
+						return Err(error);
+					}
+				}
+				Ok(())
+			}
+		}
+	}
+
+	/// The operations on single files that make up this one.
+	pub fn parts(&self) -> &[Self] {
+		match self {
+			Self::Batch { operations, .. } => operations,
+			Self::Delete { .. } | Self::Rename { .. } | Self::Rate { .. } => std::slice::from_ref(self),
+		}
+	}
+
+	/// The file that the operation changed, as it is after doing the operation.
+	/// For a batch, this is the first file.
+	pub fn path(&self) -> &Arc<Path> {
+		match self {
+			Self::Delete { path, .. } | Self::Rate { path, .. } => path,
+			Self::Rename { to, .. } => to,
+			Self::Batch { operations, .. } => operations[0].path(),
+		}
+	}
+
+	/// The file that the operation changed, as it is after undoing the operation.
+	/// For a batch, this is the first file.
+	pub fn undone_path(&self) -> &Arc<Path> {
+		match self {
+			Self::Delete { path, .. } | Self::Rate { path, .. } => path,
+			Self::Rename { from, .. } => from,
+			Self::Batch { operations, .. } => operations[0].undone_path(),
+		}
+	}
+
+	/// The rating that the operation leaves the image with, if it rates an image.
+	pub fn rating(&self) -> Option<Rating> {
+		match self {
+			Self::Rate { after, .. } => Some(*after),
+			_ => None,
+		}
+	}
+
+	/// The rating that undoing the operation leaves the image with, if it rates an image.
+	pub fn undone_rating(&self) -> Option<Rating> {
+		match self {
+			Self::Rate { before, .. } => Some(*before),
+			_ => None,
+		}
+	}
+
+	fn describe(&self) -> String {
+		match self {
+			Self::Delete { path, .. } => format!("Delete {}", path.display()),
+			Self::Rename { from, to } => format!(
+				"Rename {} to {}",
+				from.display(),
+				to.file_name().unwrap_or_default().to_string_lossy(),
+			),
+			Self::Rate { path, after, .. } => {
+				let favorite = if after.favorite { ", favorite" } else { "" };
+				format!(
+					"Rate {} {}/{}{favorite}",
+					path.display(),
+					after.stars,
+					rating::MAX_STARS,
+				)
+			}
+			Self::Batch {
+				description,
+				operations,
+			} => match operations.len() {
+				1 => format!("{description} (1 file)"),
+				len => format!("{description} ({len} files)"),
+			},
+		}
+	}
+
+	/// Permanently apply the operation once it can no longer be undone.
+	fn discard(self) {
+		match self {
+			Self::Delete {
+				trashed, moving, ..
+			} => {
+				_ = moving.finish();
+				_ = std::fs::remove_file(trashed);
+			}
+			Self::Rename { .. } | Self::Rate { .. } => {}
+			Self::Batch { operations, .. } => operations.into_iter().for_each(Self::discard),
+		}
+	}
+}
+
+/// The descriptions of the operations that can be undone and redone, most recent last.
+#[derive(Debug, Default, Clone)]
+pub struct Info {
+	pub undo: Vec<String>,
+	pub redo: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct History {
+	undo: Vec<Operation>,
+	redo: Vec<Operation>,
+	/// Failing to set up the trash only stops files from being deleted.
+	trash: Result<Trash, String>,
+}
+
+impl History {
+	/// Empties the trash of previous runs, since their history is gone.
+	pub fn new() -> Self {
+		Self {
+			undo: Vec::new(),
+			redo: Vec::new(),
+			trash: Trash::new().map_err(|error| format!("setting up the trash: {error}")),
+		}
+	}
+
+	/// Where deleted files are kept.
+	pub fn trash(&self) -> io::Result<&Path> {
+		match &self.trash {
+			Ok(trash) => Ok(&trash.dir),
+			Err(error) => Err(io::Error::other(error.clone())),
+		}
+	}
+
+	/// Record an operation that was just done.
+	pub fn push(&mut self, operation: Operation) {
+		// redoing applies to the state before the new operation
+		self.redo.clear();
+		self.undo.push(operation);
+		if self.undo.len() > MAX_UNDO {
+			self.undo.remove(0).discard();
+		}
+	}
+
+	/// Undo the most recent operation, returning it if there was one.
+	pub fn undo(&mut self) -> io::Result<Option<&Operation>> {
+		let Some(operation) = self.undo.pop() else {
+			return Ok(None);
+		};
+		if let Err(error) = operation.undo() {
+			self.undo.push(operation);
+			return Err(error);
+		}
+		self.redo.push(operation);
+		Ok(self.redo.last())
+	}
+
+	/// Redo the most recently undone operation, returning it if there was one.
+	pub fn redo(&mut self) -> io::Result<Option<&Operation>> {
+		let Some(operation) = self.redo.pop() else {
+			return Ok(None);
+		};
+		if let Err(error) = operation.redo() {
+			self.redo.push(operation);
+			return Err(error);
+		}
+		self.undo.push(operation);
+		Ok(self.undo.last())
+	}
+
+	pub fn info(&self) -> Info {
+		Info {
+			undo: self.undo.iter().map(Operation::describe).collect(),
+			redo: self.redo.iter().map(Operation::describe).collect(),
+		}
+	}
+}
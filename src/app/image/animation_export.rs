@@ -0,0 +1,265 @@
+//! Exporting animations to GIF or animated WebP, e.g., after trimming them to a range of frames.
+//!
+//! The `image` crate can only encode still WebP images, so each frame is encoded on its own and the frames are put together in the container of animated WebP.
+//! Animated PNG is not supported, since the `image` crate can't encode it.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use egui::Color32;
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::codecs::webp::WebPEncoder;
+use image::{Delay, ExtendedColorType, ImageResult};
+
+use super::loop_count::LoopCount;
+use super::{to_rgba, Pixels};
+use crate::app::batch::Progress;
+use crate::config::AnimationFormat;
+use crate::duration::Duration;
+
+#[derive(Debug, Clone)]
+pub struct Options {
+	pub format: AnimationFormat,
+	/// Indices of the frames to include.
+	pub frames: RangeInclusive<usize>,
+	/// For GIF, from 1, which is the slowest but has the most accurate colors, to 30.
+	pub speed: u8,
+	/// `None` keeps the loop count of the animation.
+	pub loops: Option<LoopCount>,
+}
+
+#[derive(Debug)]
+pub enum Outcome {
+	Saved(PathBuf),
+	Cancelled,
+	Failed(String),
+}
+
+pub fn output_path(path: &Path, format: AnimationFormat) -> PathBuf {
+	path.with_extension(format!("export.{}", format.extension()))
+}
+
+fn repeat(loops: LoopCount) -> Repeat {
+	match loops {
+		LoopCount::Forever => Repeat::Infinite,
+		// GIFs store how many times to repeat after the first time
+		LoopCount::Times(times) => Repeat::Finite(az::saturating_cast(times.saturating_sub(1))),
+	}
+}
+
+/// Write `frames` of `image` to `file` in `options.format`, reporting each frame to `progress`.
+/// Returns whether it finished, or `false` if it was cancelled.
+fn encode(
+	image: &Pixels,
+	frames: &[(Arc<[Color32]>, Duration)],
+	file: &File,
+	options: &Options,
+	progress: &Progress,
+) -> ImageResult<bool> {
+	progress.reset(frames.len());
+	let loops = options
+		.loops
+		.or(image.loop_count)
+		.unwrap_or(LoopCount::Forever);
+	match options.format {
+		AnimationFormat::Gif => encode_gif(image, frames, file, options.speed, loops, progress),
+		AnimationFormat::WebP => encode_webp(image, frames, file, loops, progress),
+	}
+}
+
+fn encode_gif(
+	image: &Pixels,
+	frames: &[(Arc<[Color32]>, Duration)],
+	file: &File,
+	speed: u8,
+	loops: LoopCount,
+	progress: &Progress,
+) -> ImageResult<bool> {
+	let speed = speed.clamp(1, 30).into();
+	let mut encoder = GifEncoder::new_with_speed(BufWriter::new(file), speed);
+	encoder.set_repeat(repeat(loops))?;
+
+	for (frame, delay) in frames {
+		if progress.is_cancelled() {
+			return Ok(false);
+		}
+		let buffer = to_rgba(image.width, image.height, frame);
+		let delay = Delay::from_saturating_duration((*delay).into());
+		encoder.encode_frame(image::Frame::from_parts(buffer, 0, 0, delay))?;
+		progress.advance();
+	}
+	Ok(true)
+}
+
+/// The flags of the `VP8X` chunk for an animation that may have transparent pixels.
+const VP8X_ALPHA_AND_ANIMATION: u8 = 0b0001_0010;
+/// The flags of an `ANMF` chunk for a frame that replaces the canvas rather than being drawn over it.
+const ANMF_NO_BLEND: u8 = 0b0000_0010;
+/// The largest value of the 24-bit fields of the container, such as the duration of a frame in milliseconds.
+const MAX_U24: u32 = 0xff_ffff;
+
+fn u24(value: u32) -> [u8; 3] {
+	let [a, b, c, _] = value.min(MAX_U24).to_le_bytes();
+	[a, b, c]
+}
+
+fn write_chunk(out: &mut Vec<u8>, name: [u8; 4], data: &[u8]) -> io::Result<()> {
+	let size = u32::try_from(data.len())
+		.map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "the WebP would be too large"))?;
+	out.extend_from_slice(&name);
+	out.extend_from_slice(&size.to_le_bytes());
+	out.extend_from_slice(data);
+	// chunks start at even offsets
+	if data.len() % 2 == 1 {
+		out.push(0);
+	}
+	Ok(())
+}
+
+/// The `VP8L` chunk of a still WebP file, with its header and padding.
+fn vp8l_chunk(file: &[u8]) -> Option<&[u8]> {
+	// after the RIFF header
+	let mut rest = file.get(12..)?;
+	while rest.len() >= 8 {
+		let size: usize = az::checked_cast(u32::from_le_bytes(rest[4..8].try_into().ok()?))?;
+		let len = 8 + size + size % 2;
+		if &rest[..4] == b"VP8L" {
+			return rest.get(..len);
+		}
+		rest = rest.get(len..)?;
+	}
+	None
+}
+
+fn encode_webp(
+	image: &Pixels,
+	frames: &[(Arc<[Color32]>, Duration)],
+	file: &File,
+	loops: LoopCount,
+	progress: &Progress,
+) -> ImageResult<bool> {
+	let mut body = b"WEBP".to_vec();
+	let mut canvas = vec![VP8X_ALPHA_AND_ANIMATION, 0, 0, 0];
+	canvas.extend(u24(image.width - 1));
+	canvas.extend(u24(image.height - 1));
+	write_chunk(&mut body, *b"VP8X", &canvas)?;
+	// unlike GIFs, the count includes the first time
+	let loops: u16 = match loops {
+		LoopCount::Forever => 0,
+		LoopCount::Times(times) => az::saturating_cast(times.max(1)),
+	};
+	let mut animation = vec![0; 4];
+	animation.extend(loops.to_le_bytes());
+	write_chunk(&mut body, *b"ANIM", &animation)?;
+
+	for (frame, delay) in frames {
+		if progress.is_cancelled() {
+			return Ok(false);
+		}
+		let mut still = Vec::new();
+		WebPEncoder::new_lossless(&mut still).encode(
+			bytemuck::cast_slice(frame),
+			image.width,
+			image.height,
+			ExtendedColorType::Rgba8,
+		)?;
+		let bitstream =
+			vp8l_chunk(&still).ok_or_else(|| io::Error::other("the encoded frame has no VP8L chunk"))?;
+		// at the top left, covering the whole canvas
+		let mut placed = [u24(0), u24(0), u24(image.width - 1), u24(image.height - 1)].concat();
+		placed.extend(u24(az::saturating_cast(delay.as_millis_f32().round())));
+		placed.push(ANMF_NO_BLEND);
+		placed.extend_from_slice(bitstream);
+		write_chunk(&mut body, *b"ANMF", &placed)?;
+		progress.advance();
+	}
+
+	// the body is the data of the RIFF chunk
+	let mut riff = Vec::with_capacity(body.len() + 8);
+	write_chunk(&mut riff, *b"RIFF", &body)?;
+	let mut file = file;
+	file.write_all(&riff)?;
+	Ok(true)
+}
+
+/// Export `image`, the animation at `path`, as described by `options`.
+/// The animation is written to a temporary file that only takes its place once it is finished, and an existing file is never replaced.
+pub fn export(path: &Path, image: &Pixels, options: &Options, progress: &Progress) -> Outcome {
+	// the animation may have changed since the range was chosen
+	let Some(frames) = image
+		.frames
+		.get(options.frames.clone())
+		.filter(|frames| !frames.is_empty())
+	else {
+		return Outcome::Failed("the frames to export are not in the animation".into());
+	};
+
+	let output = output_path(path, options.format);
+	let already_exists = || Outcome::Failed(format!("{} already exists", output.display()));
+	if output.exists() {
+		return already_exists();
+	}
+
+	let dir = output.parent().unwrap_or(Path::new("."));
+	let temp = match tempfile::Builder::new()
+		.prefix(".eo2-export-")
+		.suffix(&format!(".{}", options.format.extension()))
+		.tempfile_in(dir)
+	{
+		Ok(temp) => temp,
+		Err(error) => return Outcome::Failed(error.to_string()),
+	};
+	// the temporary file is removed when it is dropped unless it was persisted
+	match encode(image, frames, temp.as_file(), options, progress) {
+		Ok(true) => match temp.persist_noclobber(&output) {
+			Ok(_) => Outcome::Saved(output),
+			Err(error) if error.error.kind() == io::ErrorKind::AlreadyExists => already_exists(),
+			Err(error) => Outcome::Failed(error.to_string()),
+		},
+		Ok(false) => Outcome::Cancelled,
+		Err(error) => Outcome::Failed(error.to_string()),
+	}
+}
+
+#[test]
+fn test_webp() {
+	use std::io::{BufReader, Seek as _};
+
+	use image::AnimationDecoder as _;
+
+	let frame = |color: Color32| -> Arc<[Color32]> { vec![color, Color32::TRANSPARENT].into() };
+	let delay = Duration::new_millis_f32(120.0).unwrap();
+	let image = Pixels {
+		format: image::ImageFormat::Gif,
+		width: 2,
+		height: 1,
+		loop_count: None,
+		frames: vec![(frame(Color32::RED), delay), (frame(Color32::BLUE), delay)],
+	};
+	let mut file = tempfile::tempfile().unwrap();
+	let finished = encode_webp(
+		&image,
+		&image.frames,
+		&file,
+		LoopCount::Forever,
+		&Progress::default(),
+	)
+	.unwrap();
+	assert!(finished);
+
+	file.rewind().unwrap();
+	let decoder = image::codecs::webp::WebPDecoder::new(BufReader::new(file)).unwrap();
+	// some versions report an error rather than the end after the last frame
+	let frames: Vec<_> = decoder.into_frames().map_while(Result::ok).collect();
+	assert_eq!(frames.len(), 2);
+	for (decoded, (pixels, _)) in frames.iter().zip(&image.frames) {
+		assert_eq!(
+			decoded.buffer().as_raw().as_slice(),
+			bytemuck::cast_slice::<_, u8>(pixels)
+		);
+		assert_eq!(decoded.delay().numer_denom_ms(), (120, 1));
+	}
+}
@@ -0,0 +1,392 @@
+//! Reading the raw metadata chunks of an image, such as EXIF, XMP, and ICC profiles, for inspection, and removing them, e.g., before sharing the image.
+
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const PNG_MAGIC: &[u8] = b"\x89PNG\r\n\x1a\n";
+const JPEG_MAGIC: &[u8] = b"\xff\xd8";
+
+const JPEG_SOS: u8 = 0xda;
+const JPEG_EOI: u8 = 0xd9;
+const JPEG_COM: u8 = 0xfe;
+const JPEG_APP0: u8 = 0xe0;
+const JPEG_APP1: u8 = 0xe1;
+const JPEG_APP13: u8 = 0xed;
+const JPEG_APP15: u8 = 0xef;
+
+fn is_webp(magic: &[u8]) -> bool {
+	magic.starts_with(b"RIFF") && magic.get(8..12) == Some(b"WEBP")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+	Exif,
+	Xmp,
+	Icc,
+	Other,
+}
+
+impl Kind {
+	fn extension(self) -> &'static str {
+		match self {
+			Self::Exif => "exif",
+			Self::Xmp => "xmp",
+			Self::Icc => "icc",
+			Self::Other => "bin",
+		}
+	}
+}
+
+#[derive(Debug)]
+pub struct Chunk {
+	/// The name of the chunk in the container, e.g., `APP1` for JPEG or `iTXt` for PNG.
+	pub name: String,
+	pub kind: Kind,
+	/// The position of the data in the file.
+	pub offset: u64,
+	pub data: Arc<[u8]>,
+}
+
+/// Where to export the chunk at `idx`, next to the image.
+pub fn export_path(image_path: &Path, idx: usize, chunk: &Chunk) -> PathBuf {
+	let mut name = image_path.file_name().unwrap_or_default().to_owned();
+	name.push(format!(".{idx}.{}", chunk.kind.extension()));
+	image_path.with_file_name(name)
+}
+
+/// Write the data of a chunk to `path`. An earlier export is never replaced.
+pub fn export(path: &Path, data: &[u8]) -> io::Result<()> {
+	let mut file = std::fs::File::create_new(path).map_err(|error| {
+		if error.kind() == io::ErrorKind::AlreadyExists {
+			io::Error::new(
+				io::ErrorKind::AlreadyExists,
+				format!("{} already exists", path.display()),
+			)
+		} else {
+			error
+		}
+	})?;
+	file.write_all(data)
+}
+
+/// Read the metadata chunks of a PNG, JPEG, or WebP file. Other formats have no chunks.
+pub fn read(path: &Path) -> io::Result<Vec<Chunk>> {
+	let mut reader = BufReader::new(std::fs::File::open(path)?);
+	let mut magic = [0u8; 12];
+	let magic_len = read_up_to(&mut reader, &mut magic)?;
+	let magic = &magic[..magic_len];
+	reader.rewind()?;
+
+	if magic.starts_with(PNG_MAGIC) {
+		read_png(&mut reader)
+	} else if magic.starts_with(JPEG_MAGIC) {
+		read_jpeg(&mut reader)
+	} else if is_webp(magic) {
+		read_webp(&mut reader)
+	} else {
+		Ok(Vec::new())
+	}
+}
+
+fn read_up_to(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+	let mut len = 0;
+	while len < buf.len() {
+		match reader.read(&mut buf[len..])? {
+			0 => break,
+			amount => len += amount,
+		}
+	}
+	Ok(len)
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+	let mut buf = [0; N];
+	reader.read_exact(&mut buf)?;
+	Ok(buf)
+}
+
+fn read_data(reader: &mut (impl Read + Seek), len: usize) -> io::Result<(u64, Arc<[u8]>)> {
+	let offset = reader.stream_position()?;
+	// the length comes from the file, so the buffer only grows as much as there actually is to read
+	let mut data = Vec::new();
+	reader.by_ref().take(az::cast(len)).read_to_end(&mut data)?;
+	if data.len() < len {
+		return Err(io::ErrorKind::UnexpectedEof.into());
+	}
+	Ok((offset, data.into()))
+}
+
+/// Like `read_array`, but returns `None` at the end of the file.
+fn read_header<const N: usize>(reader: &mut impl Read) -> io::Result<Option<[u8; N]>> {
+	let mut buf = [0; N];
+	match read_up_to(reader, &mut buf)? {
+		0 => Ok(None),
+		len if len == N => Ok(Some(buf)),
+		_ => Err(io::ErrorKind::UnexpectedEof.into()),
+	}
+}
+
+fn read_png(reader: &mut (impl Read + Seek)) -> io::Result<Vec<Chunk>> {
+	// the signature
+	reader.seek(SeekFrom::Start(8))?;
+
+	let mut chunks = Vec::new();
+	while let Some(header) = read_header::<8>(reader)? {
+		let len = u32::from_be_bytes(header[..4].try_into().unwrap());
+		let name = &header[4..];
+		// chunks with a lowercase first letter are ancillary, i.e., not needed to decode the image
+		if name[0].is_ascii_lowercase() {
+			let kind = match name {
+				b"eXIf" => Kind::Exif,
+				b"iCCP" => Kind::Icc,
+				_ => Kind::Other,
+			};
+			let (offset, data) = read_data(reader, az::cast(len))?;
+			// XMP is stored in an iTXt chunk with this keyword
+			let kind = if name == b"iTXt" && data.starts_with(b"XML:com.adobe.xmp\0") {
+				Kind::Xmp
+			} else {
+				kind
+			};
+			chunks.push(Chunk {
+				name: String::from_utf8_lossy(name).into_owned(),
+				kind,
+				offset,
+				data,
+			});
+		} else {
+			reader.seek(SeekFrom::Current(len.into()))?;
+		}
+		if name == b"IEND" {
+			break;
+		}
+		// the CRC
+		reader.seek(SeekFrom::Current(4))?;
+	}
+	Ok(chunks)
+}
+
+fn read_jpeg(reader: &mut (impl Read + Seek)) -> io::Result<Vec<Chunk>> {
+	// SOI
+	reader.seek(SeekFrom::Start(2))?;
+
+	let mut chunks = Vec::new();
+	loop {
+		let [marker_start, marker] = read_array(reader)?;
+		if marker_start != 0xff {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidData,
+				"invalid JPEG marker",
+			));
+		}
+		// metadata always comes before the image data
+		if marker == JPEG_SOS || marker == JPEG_EOI {
+			break;
+		}
+		// the length includes itself
+		let len = u16::from_be_bytes(read_array(reader)?).saturating_sub(2);
+		if !matches!(marker, JPEG_APP0..=JPEG_APP15 | JPEG_COM) {
+			reader.seek(SeekFrom::Current(len.into()))?;
+			continue;
+		}
+
+		let (offset, data) = read_data(reader, len.into())?;
+		let kind = if data.starts_with(b"Exif\0\0") {
+			Kind::Exif
+		} else if data.starts_with(b"http://ns.adobe.com/xap/1.0/\0") {
+			Kind::Xmp
+		} else if data.starts_with(b"ICC_PROFILE\0") {
+			Kind::Icc
+		} else {
+			Kind::Other
+		};
+		let name = if marker == JPEG_COM {
+			"COM".to_owned()
+		} else {
+			format!("APP{}", marker - JPEG_APP0)
+		};
+		chunks.push(Chunk {
+			name,
+			kind,
+			offset,
+			data,
+		});
+	}
+	Ok(chunks)
+}
+
+fn read_webp(reader: &mut (impl Read + Seek)) -> io::Result<Vec<Chunk>> {
+	// the RIFF header
+	reader.seek(SeekFrom::Start(12))?;
+
+	let mut chunks = Vec::new();
+	while let Some(header) = read_header::<8>(reader)? {
+		let name = &header[..4];
+		let len = u32::from_le_bytes(header[4..].try_into().unwrap());
+		// chunks are padded to an even size
+		let padded_len = u64::from(len) + u64::from(len % 2);
+		let kind = match name {
+			b"EXIF" => Kind::Exif,
+			b"XMP " => Kind::Xmp,
+			b"ICCP" => Kind::Icc,
+			_ => {
+				reader.seek(SeekFrom::Current(az::cast(padded_len)))?;
+				continue;
+			}
+		};
+		let (offset, data) = read_data(reader, az::cast(len))?;
+		reader.seek(SeekFrom::Current(az::cast(padded_len - u64::from(len))))?;
+		chunks.push(Chunk {
+			name: String::from_utf8_lossy(name).trim_end().to_owned(),
+			kind,
+			offset,
+			data,
+		});
+	}
+	Ok(chunks)
+}
+
+fn strip_png(data: &[u8]) -> Option<Vec<u8>> {
+	let mut stripped = data.get(..8)?.to_vec();
+	let mut pos = 8;
+	while pos < data.len() {
+		let len: usize = az::cast(u32::from_be_bytes(data.get(pos..pos + 4)?.try_into().ok()?));
+		// the length, name, and CRC
+		let end = pos.checked_add(len)?.checked_add(12)?;
+		let chunk = data.get(pos..end)?;
+		let name = &chunk[4..8];
+		// text chunks hold XMP, but also other information such as the software and author
+		if !matches!(
+			name,
+			b"eXIf" | b"iCCP" | b"iTXt" | b"tEXt" | b"zTXt" | b"tIME"
+		) {
+			stripped.extend_from_slice(chunk);
+		}
+		pos = end;
+		if name == b"IEND" {
+			break;
+		}
+	}
+	Some(stripped)
+}
+
+fn strip_jpeg(data: &[u8]) -> Option<Vec<u8>> {
+	let mut stripped = data.get(..2)?.to_vec();
+	let mut pos = 2;
+	loop {
+		let &[0xff, marker] = data.get(pos..pos + 2)? else {
+			return None;
+		};
+		// the image data follows, which is copied as is
+		if marker == JPEG_SOS || marker == JPEG_EOI {
+			stripped.extend_from_slice(&data[pos..]);
+			return Some(stripped);
+		}
+		// the length includes itself but not the marker
+		let len = usize::from(u16::from_be_bytes(
+			data.get(pos + 2..pos + 4)?.try_into().ok()?,
+		));
+		let end = pos + 2 + len;
+		let segment = data.get(pos..end)?;
+		// APP0 is JFIF and APP14 is Adobe's, which says how to decode the colors; the others are metadata
+		if !matches!(marker, JPEG_APP1..=JPEG_APP13 | JPEG_APP15 | JPEG_COM) {
+			stripped.extend_from_slice(segment);
+		}
+		pos = end;
+	}
+}
+
+fn strip_webp(data: &[u8]) -> Option<Vec<u8>> {
+	// in the flags of the VP8X chunk
+	const HAS_ICC: u8 = 0x20;
+	const HAS_EXIF: u8 = 0x08;
+	const HAS_XMP: u8 = 0x04;
+
+	let mut stripped = data.get(..12)?.to_vec();
+	let mut pos = 12;
+	while pos < data.len() {
+		let name = data.get(pos..pos + 4)?;
+		let len: usize = az::cast(u32::from_le_bytes(
+			data.get(pos + 4..pos + 8)?.try_into().ok()?,
+		));
+		let end = pos.checked_add(8)?.checked_add(len)?;
+		if end > data.len() {
+			return None;
+		}
+		// chunks are padded to an even size, except sometimes at the end of the file
+		let padded_end = (end + len % 2).min(data.len());
+		let chunk = &data[pos..padded_end];
+		match name {
+			b"EXIF" | b"XMP " | b"ICCP" => {}
+			b"VP8X" => {
+				let mut chunk = chunk.to_vec();
+				*chunk.get_mut(8)? &= !(HAS_ICC | HAS_EXIF | HAS_XMP);
+				stripped.extend(chunk);
+			}
+			_ => stripped.extend_from_slice(chunk),
+		}
+		pos = padded_end;
+	}
+	// the size of the RIFF container excludes its header
+	let size = u32::try_from(stripped.len() - 8).ok()?;
+	stripped[4..8].copy_from_slice(&size.to_le_bytes());
+	Some(stripped)
+}
+
+/// The PNG, JPEG, or WebP file `data` without its EXIF, XMP, and ICC chunks, or other metadata such as comments.
+/// The image data is copied as is, so nothing is re-encoded.
+pub fn strip(data: &[u8]) -> io::Result<Vec<u8>> {
+	let stripped = if data.starts_with(PNG_MAGIC) {
+		strip_png(data)
+	} else if data.starts_with(JPEG_MAGIC) {
+		strip_jpeg(data)
+	} else if is_webp(data) {
+		strip_webp(data)
+	} else {
+		return Err(io::Error::new(
+			io::ErrorKind::Unsupported,
+			"metadata can only be removed from PNG, JPEG, and WebP files",
+		));
+	};
+	stripped.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "the file is malformed"))
+}
+
+/// Copy the image at `from` to `to` without its metadata.
+pub fn copy_stripped(from: &Path, to: &Path) -> io::Result<()> {
+	std::fs::write(to, strip(&std::fs::read(from)?)?)
+}
+
+#[test]
+fn test_strip_png() {
+	let chunk = |name: &[u8], data: &[u8]| {
+		let len = u32::try_from(data.len()).unwrap().to_be_bytes();
+		// the CRC isn't checked
+		[&len[..], name, data, &[0; 4]].concat()
+	};
+	let ihdr = chunk(b"IHDR", &[0; 13]);
+	let png = [
+		PNG_MAGIC,
+		&ihdr,
+		&chunk(b"eXIf", b"MM\0\x2a"),
+		&chunk(b"tEXt", b"Author\0me"),
+		&chunk(b"IDAT", &[1, 2, 3]),
+		&chunk(b"IEND", &[]),
+	]
+	.concat();
+
+	let stripped = strip(&png).unwrap();
+	assert_eq!(
+		stripped,
+		[
+			PNG_MAGIC,
+			&ihdr,
+			&chunk(b"IDAT", &[1, 2, 3]),
+			&chunk(b"IEND", &[])
+		]
+		.concat()
+	);
+	let chunks = read_png(&mut io::Cursor::new(stripped)).unwrap();
+	assert!(chunks.iter().all(|chunk| chunk.kind == Kind::Other));
+	assert!(strip(b"GIF89a").is_err());
+}
@@ -0,0 +1,38 @@
+//! Detection of uniform borders around the content of an image, such as the margins of scans.
+
+use egui::{Color32, Pos2, Rect};
+
+/// How much each channel may differ from the border color while still being considered part of the border.
+const TOLERANCE: u8 = 8;
+
+fn similar(a: Color32, b: Color32) -> bool {
+	a.to_array()
+		.into_iter()
+		.zip(b.to_array())
+		.all(|(a, b)| a.abs_diff(b) <= TOLERANCE)
+}
+
+/// Returns the bounds of the content of the image in pixels, excluding any border that has the same color as the top left pixel.
+/// Returns `None` if there is no such border or the whole image is uniform.
+pub fn bounds(pixels: &[Color32], width: u32, height: u32) -> Option<Rect> {
+	let width: usize = az::cast(width);
+	let height: usize = az::cast(height);
+	let border = *pixels.first()?;
+	let pixel = |x: usize, y: usize| pixels[y * width + x];
+	let row_is_border = |y: usize| (0..width).all(|x| similar(pixel(x, y), border));
+
+	let top = (0..height).find(|&y| !row_is_border(y))?;
+	let bottom = (top..height).rev().find(|&y| !row_is_border(y))? + 1;
+	let column_is_border = |x: usize| (top..bottom).all(|y| similar(pixel(x, y), border));
+	let left = (0..width).find(|&x| !column_is_border(x))?;
+	let right = (left..width).rev().find(|&x| !column_is_border(x))? + 1;
+
+	if (left, top, right, bottom) == (0, 0, width, height) {
+		return None;
+	}
+
+	Some(Rect::from_min_max(
+		Pos2::new(az::cast(left), az::cast(top)),
+		Pos2::new(az::cast(right), az::cast(bottom)),
+	))
+}
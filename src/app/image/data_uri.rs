@@ -0,0 +1,83 @@
+use std::io::Cursor;
+use std::path::Path;
+
+use base64::Engine as _;
+use image::codecs::jpeg::JpegEncoder;
+use image::{DynamicImage, ImageFormat, ImageResult};
+
+#[derive(Debug, Clone, Copy)]
+pub enum Markup {
+	/// Just the `data:` URI.
+	Uri,
+	/// An HTML `<img>` element with the `data:` URI as its source.
+	Html,
+}
+
+/// Formats that browsers can display, and therefore don't need to be re-encoded.
+const WEB_FORMATS: &[ImageFormat] = &[
+	ImageFormat::Png,
+	ImageFormat::Jpeg,
+	ImageFormat::Gif,
+	ImageFormat::WebP,
+];
+
+const JPEG_QUALITY: u8 = 85;
+
+/// Encode `image` in `format`, dropping the alpha channel for JPEG.
+pub fn encode(image: &DynamicImage, format: ImageFormat) -> ImageResult<Vec<u8>> {
+	let mut buf = Vec::new();
+	match format {
+		// JPEG doesn't support alpha so we must strip it first.
+		ImageFormat::Jpeg => {
+			let encoder = JpegEncoder::new_with_quality(&mut buf, JPEG_QUALITY);
+			image.to_rgb8().write_with_encoder(encoder)?;
+		}
+		_ => image.write_to(&mut Cursor::new(&mut buf), format)?,
+	}
+	Ok(buf)
+}
+
+/// Uses the file as-is if browsers can display it and it is under `max_size`.
+/// Otherwise it is re-encoded to PNG, falling back to JPEG if the PNG is still too large.
+fn encoded_for_web(raw: Vec<u8>, max_size: usize) -> ImageResult<(ImageFormat, Vec<u8>)> {
+	let format = image::guess_format(&raw)?;
+	if WEB_FORMATS.contains(&format) && raw.len() <= max_size {
+		return Ok((format, raw));
+	}
+
+	let image = image::load_from_memory_with_format(&raw, format)?;
+	let png = encode(&image, ImageFormat::Png)?;
+	if png.len() <= max_size {
+		return Ok((ImageFormat::Png, png));
+	}
+	Ok((ImageFormat::Jpeg, encode(&image, ImageFormat::Jpeg)?))
+}
+
+fn escape_html_attribute(raw: &str) -> String {
+	raw
+		.replace('&', "&amp;")
+		.replace('"', "&quot;")
+		.replace('<', "&lt;")
+		.replace('>', "&gt;")
+}
+
+pub fn make(path: &Path, max_size: usize, markup: Markup) -> ImageResult<String> {
+	let raw = std::fs::read(path)?;
+	let (format, data) = encoded_for_web(raw, max_size)?;
+	let uri = format!(
+		"data:{};base64,{}",
+		format.to_mime_type(),
+		base64::engine::general_purpose::STANDARD.encode(data),
+	);
+
+	Ok(match markup {
+		Markup::Uri => uri,
+		Markup::Html => {
+			let alt = path
+				.file_name()
+				.map(|name| escape_html_attribute(&name.to_string_lossy()))
+				.unwrap_or_default();
+			format!(r#"<img src="{uri}" alt="{alt}">"#)
+		}
+	})
+}
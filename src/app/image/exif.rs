@@ -0,0 +1,234 @@
+//! Reading fields from EXIF metadata, which is a TIFF structure of directories of tagged fields.
+
+use super::chunks::{Chunk, Kind};
+
+/// The directory of GPS fields, pointed to from the first directory.
+const GPS_DIRECTORY: u16 = 0x8825;
+const GPS_LATITUDE_REF: u16 = 1;
+const GPS_LATITUDE: u16 = 2;
+const GPS_LONGITUDE_REF: u16 = 3;
+const GPS_LONGITUDE: u16 = 4;
+const GPS_ALTITUDE_REF: u16 = 5;
+const GPS_ALTITUDE: u16 = 6;
+const GPS_H_POSITIONING_ERROR: u16 = 31;
+
+const TYPE_LONG: u16 = 4;
+const TYPE_RATIONAL: u16 = 5;
+
+/// The location where a photo was taken.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gps {
+	/// In degrees, north of the equator.
+	pub latitude: f64,
+	/// In degrees, east of the prime meridian.
+	pub longitude: f64,
+	/// In meters above sea level.
+	pub altitude: Option<f64>,
+	/// How far off the location may be, in meters.
+	pub accuracy: Option<f64>,
+}
+
+impl Gps {
+	/// The coordinates, e.g., `48.858370° N, 2.294481° E`.
+	pub fn repr(&self) -> String {
+		let north_south = if self.latitude < 0.0 { 'S' } else { 'N' };
+		let east_west = if self.longitude < 0.0 { 'W' } else { 'E' };
+		format!(
+			"{:.6}° {north_south}, {:.6}° {east_west}",
+			self.latitude.abs(),
+			self.longitude.abs(),
+		)
+	}
+
+	pub fn map_url(&self) -> String {
+		format!(
+			"https://www.openstreetmap.org/?mlat={lat:.6}&mlon={lon:.6}#map=16/{lat:.6}/{lon:.6}",
+			lat = self.latitude,
+			lon = self.longitude,
+		)
+	}
+}
+
+struct Entry {
+	tag: u16,
+	ty: u16,
+	count: u32,
+	/// The position of the value, or of the offset of the value if it doesn't fit in four bytes.
+	value_pos: usize,
+}
+
+struct Tiff<'a> {
+	data: &'a [u8],
+	big_endian: bool,
+}
+
+impl<'a> Tiff<'a> {
+	fn new(data: &'a [u8]) -> Option<Self> {
+		// JPEG files, and some others, put this before the TIFF header
+		let data = data.strip_prefix(b"Exif\0\0").unwrap_or(data);
+		let big_endian = match data.get(..2)? {
+			b"II" => false,
+			b"MM" => true,
+			_ => return None,
+		};
+		let tiff = Self { data, big_endian };
+		(tiff.u16(2)? == 42).then_some(tiff)
+	}
+
+	fn bytes<const N: usize>(&self, pos: usize) -> Option<[u8; N]> {
+		self.data.get(pos..pos.checked_add(N)?)?.try_into().ok()
+	}
+
+	fn u16(&self, pos: usize) -> Option<u16> {
+		let bytes = self.bytes(pos)?;
+		Some(if self.big_endian {
+			u16::from_be_bytes(bytes)
+		} else {
+			u16::from_le_bytes(bytes)
+		})
+	}
+
+	fn u32(&self, pos: usize) -> Option<u32> {
+		let bytes = self.bytes(pos)?;
+		Some(if self.big_endian {
+			u32::from_be_bytes(bytes)
+		} else {
+			u32::from_le_bytes(bytes)
+		})
+	}
+
+	fn first_directory(&self) -> Option<Vec<Entry>> {
+		self.directory(az::cast(self.u32(4)?))
+	}
+
+	fn directory(&self, pos: usize) -> Option<Vec<Entry>> {
+		let len = self.u16(pos)?;
+		(0..usize::from(len))
+			.map(|idx| {
+				let pos = pos + 2 + idx * 12;
+				Some(Entry {
+					tag: self.u16(pos)?,
+					ty: self.u16(pos + 2)?,
+					count: self.u32(pos + 4)?,
+					value_pos: pos + 8,
+				})
+			})
+			.collect()
+	}
+
+	/// The position of the value of `entry`, which takes up `size` bytes.
+	fn value_pos(&self, entry: &Entry, size: usize) -> Option<usize> {
+		if size <= 4 {
+			Some(entry.value_pos)
+		} else {
+			Some(az::cast(self.u32(entry.value_pos)?))
+		}
+	}
+
+	fn rationals(&self, entry: &Entry) -> Option<Vec<f64>> {
+		if entry.ty != TYPE_RATIONAL {
+			return None;
+		}
+		let count: usize = az::cast(entry.count);
+		let pos = self.value_pos(entry, count.checked_mul(8)?)?;
+		(0..count)
+			.map(|idx| {
+				let numerator = self.u32(pos + idx * 8)?;
+				let denominator = self.u32(pos + idx * 8 + 4)?;
+				(denominator != 0).then(|| f64::from(numerator) / f64::from(denominator))
+			})
+			.collect()
+	}
+
+	/// The first byte of the value of `entry`, e.g., for a one-letter ASCII field.
+	fn first_byte(&self, entry: &Entry) -> Option<u8> {
+		self
+			.data
+			.get(self.value_pos(entry, az::cast(entry.count))?)
+			.copied()
+	}
+}
+
+/// Degrees, minutes, and seconds to degrees, negated if `reference` is south or west.
+fn degrees(tiff: &Tiff<'_>, value: &Entry, reference: Option<&Entry>) -> Option<f64> {
+	let [degrees, minutes, seconds] = tiff.rationals(value)?[..] else {
+		return None;
+	};
+	let degrees = degrees + minutes / 60.0 + seconds / 3600.0;
+	let negative = matches!(
+		reference.and_then(|reference| tiff.first_byte(reference)),
+		Some(b'S' | b'W'),
+	);
+	Some(if negative { -degrees } else { degrees })
+}
+
+/// Read the GPS location from EXIF data, if it has one.
+pub fn gps(data: &[u8]) -> Option<Gps> {
+	let tiff = Tiff::new(data)?;
+	let pointer = tiff
+		.first_directory()?
+		.into_iter()
+		.find(|entry| entry.tag == GPS_DIRECTORY && entry.ty == TYPE_LONG)?;
+	let entries = tiff.directory(az::cast(tiff.u32(pointer.value_pos)?))?;
+	let find = |tag| entries.iter().find(|entry| entry.tag == tag);
+	let single = |tag| Some(*tiff.rationals(find(tag)?)?.first()?);
+
+	let latitude = degrees(&tiff, find(GPS_LATITUDE)?, find(GPS_LATITUDE_REF))?;
+	let longitude = degrees(&tiff, find(GPS_LONGITUDE)?, find(GPS_LONGITUDE_REF))?;
+	// the reference is 1 for below sea level
+	let below_sea_level = find(GPS_ALTITUDE_REF).and_then(|entry| tiff.first_byte(entry)) == Some(1);
+	let sign = if below_sea_level { -1.0 } else { 1.0 };
+	let altitude = single(GPS_ALTITUDE).map(|altitude| sign * altitude);
+	Some(Gps {
+		latitude,
+		longitude,
+		altitude,
+		accuracy: single(GPS_H_POSITIONING_ERROR),
+	})
+}
+
+/// The GPS location from the first EXIF chunk that has one.
+pub fn gps_from_chunks(chunks: &[Chunk]) -> Option<Gps> {
+	chunks
+		.iter()
+		.filter(|chunk| chunk.kind == Kind::Exif)
+		.find_map(|chunk| gps(&chunk.data))
+}
+
+#[test]
+fn test_gps() {
+	// a little-endian TIFF header, then the first directory at 8 with the pointer to the GPS directory at 26
+	let mut data = b"II\x2a\x00\x08\x00\x00\x00".to_vec();
+	data.extend([1, 0]);
+	data.extend([0x25, 0x88, 4, 0, 1, 0, 0, 0, 26, 0, 0, 0]);
+	data.extend([0, 0, 0, 0]);
+	// the GPS directory: the references are inline, and the rationals follow at 26 + 2 + 5 * 12 + 4 = 92
+	data.extend([5, 0]);
+	data.extend([1, 0, 2, 0, 2, 0, 0, 0, b'S', 0, 0, 0]);
+	data.extend([2, 0, 5, 0, 3, 0, 0, 0, 92, 0, 0, 0]);
+	data.extend([3, 0, 2, 0, 2, 0, 0, 0, b'E', 0, 0, 0]);
+	data.extend([4, 0, 5, 0, 3, 0, 0, 0, 116, 0, 0, 0]);
+	data.extend([6, 0, 5, 0, 1, 0, 0, 0, 140, 0, 0, 0]);
+	data.extend([0, 0, 0, 0]);
+	let rational = |numerator: u32, denominator: u32| {
+		[numerator.to_le_bytes(), denominator.to_le_bytes()].concat()
+	};
+	for (numerator, denominator) in [
+		(33, 1),
+		(51, 1),
+		(3150, 100),
+		(151, 1),
+		(12, 1),
+		(36, 1),
+		(58, 2),
+	] {
+		data.extend(rational(numerator, denominator));
+	}
+
+	let gps = gps(&data).unwrap();
+	assert!((gps.latitude - -(33.0 + 51.0 / 60.0 + 31.5 / 3600.0)).abs() < 1e-9);
+	assert!((gps.longitude - (151.0 + 12.0 / 60.0 + 36.0 / 3600.0)).abs() < 1e-9);
+	assert_eq!(gps.altitude, Some(29.0));
+	assert_eq!(gps.accuracy, None);
+	assert_eq!(gps.repr(), "33.858750° S, 151.210000° E");
+}
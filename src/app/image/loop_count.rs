@@ -0,0 +1,140 @@
+//! Reading how many times an animation plays, which the decoders don't expose.
+
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use image::ImageFormat;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoopCount {
+	Forever,
+	/// The number of times the animation plays in total, at least one.
+	Times(u32),
+}
+
+impl LoopCount {
+	pub fn repr(self) -> String {
+		match self {
+			Self::Forever => "Forever".to_owned(),
+			Self::Times(1) => "Once".to_owned(),
+			Self::Times(times) => format!("{times} times"),
+		}
+	}
+}
+
+/// Read the loop count of the animation at `path`, or `None` if `format` doesn't store one or the file isn't animated.
+pub fn read(path: &Path, format: ImageFormat) -> io::Result<Option<LoopCount>> {
+	let mut reader = BufReader::new(std::fs::File::open(path)?);
+	match format {
+		ImageFormat::Gif => read_gif(&mut reader).map(Some),
+		ImageFormat::Png => read_png(&mut reader),
+		ImageFormat::WebP => read_webp(&mut reader),
+		_ => Ok(None),
+	}
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+	let mut buf = [0; N];
+	reader.read_exact(&mut buf)?;
+	Ok(buf)
+}
+
+/// Zero means forever in every format.
+fn from_raw(raw: u32, extra_plays: u32) -> LoopCount {
+	match raw {
+		0 => LoopCount::Forever,
+		raw => LoopCount::Times(raw.saturating_add(extra_plays)),
+	}
+}
+
+/// The loop count is in the NETSCAPE2.0 application extension, which comes before the first image.
+fn read_gif(reader: &mut (impl Read + Seek)) -> io::Result<LoopCount> {
+	const EXTENSION: u8 = 0x21;
+	const APPLICATION: u8 = 0xff;
+
+	// the header, then the logical screen descriptor
+	reader.seek(SeekFrom::Start(6 + 4))?;
+	let [flags, _background, _aspect] = read_array(reader)?;
+	if flags & 0x80 != 0 {
+		let global_color_table_len = 3 << ((flags & 0x07) + 1);
+		reader.seek(SeekFrom::Current(global_color_table_len))?;
+	}
+
+	// without the extension, browsers play the animation once
+	while let [EXTENSION, label] = read_array(reader)? {
+		let mut first_block = true;
+		let mut netscape = false;
+		loop {
+			let [len] = read_array(reader)?;
+			if len == 0 {
+				break;
+			}
+			let mut block = vec![0; len.into()];
+			reader.read_exact(&mut block)?;
+			if label == APPLICATION && first_block {
+				netscape = block == b"NETSCAPE2.0" || block == b"ANIMEXTS1.0";
+			} else if netscape && block.len() == 3 && block[0] == 1 {
+				// the count is of the repeats after playing once
+				return Ok(from_raw(u16::from_le_bytes([block[1], block[2]]).into(), 1));
+			}
+			first_block = false;
+		}
+	}
+	Ok(LoopCount::Times(1))
+}
+
+/// The loop count is in the `acTL` chunk, which comes before the image data.
+fn read_png(reader: &mut (impl Read + Seek)) -> io::Result<Option<LoopCount>> {
+	// the signature
+	reader.seek(SeekFrom::Start(8))?;
+	loop {
+		let header: [u8; 8] = read_array(reader)?;
+		let len = u32::from_be_bytes(header[..4].try_into().unwrap());
+		match &header[4..] {
+			b"acTL" => {
+				let data: [u8; 8] = read_array(reader)?;
+				let plays = u32::from_be_bytes(data[4..].try_into().unwrap());
+				return Ok(Some(from_raw(plays, 0)));
+			}
+			b"IDAT" | b"IEND" => return Ok(None),
+			// the data, then the CRC
+			_ => {
+				reader.seek(SeekFrom::Current(i64::from(len) + 4))?;
+			}
+		}
+	}
+}
+
+/// The loop count is in the `ANIM` chunk, which comes before the frames.
+fn read_webp(reader: &mut (impl Read + Seek)) -> io::Result<Option<LoopCount>> {
+	// the RIFF header
+	reader.seek(SeekFrom::Start(12))?;
+	loop {
+		let header: [u8; 8] = read_array(reader)?;
+		let len = u32::from_le_bytes(header[4..].try_into().unwrap());
+		match &header[..4] {
+			b"ANIM" => {
+				let [_, _, _, _, low, high] = read_array(reader)?;
+				return Ok(Some(from_raw(u16::from_le_bytes([low, high]).into(), 0)));
+			}
+			b"ANMF" | b"VP8 " | b"VP8L" => return Ok(None),
+			// chunks are padded to an even size
+			_ => {
+				reader.seek(SeekFrom::Current(i64::from(len) + i64::from(len % 2)))?;
+			}
+		}
+	}
+}
+
+#[test]
+fn test_read_gif() {
+	let header = b"GIF89a\x01\x00\x01\x00\x00\x00\x00";
+	let netscape = b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x05\x00\x00";
+	let image = b"\x2c\x00";
+
+	let read = |parts: &[&[u8]]| read_gif(&mut io::Cursor::new(parts.concat())).unwrap();
+	assert_eq!(read(&[header, netscape, image]), LoopCount::Times(6));
+	assert_eq!(read(&[header, image]), LoopCount::Times(1));
+	let forever = b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00";
+	assert_eq!(read(&[header, forever, image]), LoopCount::Forever);
+}
@@ -1,12 +1,35 @@
-use std::path::Path;
+use std::ops::{ControlFlow, Range};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
-use egui::{Context, TextureFilter, TextureHandle, TextureOptions, TextureWrapMode};
-use image::{ImageFormat, ImageResult};
+use egui::{Color32, Context, TextureFilter, TextureOptions, TextureWrapMode};
+use image::{ImageFormat, ImageResult, RgbaImage};
 use once_cell::sync::Lazy;
 
+pub use self::progress::Progress;
+pub use self::read::{info, Frame, Info};
 use crate::duration::Duration;
+use crate::widgets::TiledTexture;
 
+pub mod animation_export;
+pub mod chunks;
+mod content;
+pub mod data_uri;
+pub mod exif;
+pub mod loop_count;
+mod progress;
 mod read;
+pub mod save_as;
+pub mod sprite_sheet;
+pub mod white_balance;
+
+/// The size of the thumbnails in the frames strip, in points.
+pub const THUMBNAIL_SIZE: f32 = 100.0; // XXX 100 is arbitrary; make it configurable?
+
+/// The size of thumbnails in physical pixels, so that they are sharp on the current display.
+fn thumbnail_side(ctx: &Context) -> usize {
+	az::saturating_cast((THUMBNAIL_SIZE * ctx.pixels_per_point()).ceil())
+}
 
 static TIMEZONE: Lazy<time::UtcOffset> =
 	Lazy::new(|| time::UtcOffset::current_local_offset().unwrap());
@@ -15,6 +38,16 @@ pub fn init_timezone() {
 	Lazy::force(&TIMEZONE);
 }
 
+/// Format a file time in the local timezone.
+pub fn format_time(time: std::time::SystemTime) -> String {
+	time::OffsetDateTime::from(time)
+		.to_offset(*TIMEZONE)
+		.format(time::macros::format_description!(
+			"[year]-[month]-[day] [hour]:[minute]:[second]"
+		))
+		.unwrap()
+}
+
 #[derive(Debug)]
 pub struct Metadata {
 	pub file_size: u64,
@@ -26,25 +59,113 @@ impl Metadata {
 		let metadata = std::fs::metadata(path)?;
 		Ok(Self {
 			file_size: metadata.len(),
-			mtime: metadata.modified().ok().map(|sys| {
-				time::OffsetDateTime::from(sys)
-					.to_offset(*TIMEZONE)
-					.format(time::macros::format_description!(
-						"[year]-[month]-[day] [hour]:[minute]:[second]"
-					))
-					.unwrap()
-			}),
+			mtime: metadata.modified().ok().map(format_time),
 		})
 	}
 }
 
+/// If the extension of `path` is not one for `format`, the format that the file is actually in, then the path with the right extension.
+pub fn corrected_path(path: &Path, format: ImageFormat) -> Option<PathBuf> {
+	if ImageFormat::from_path(path).ok() == Some(format) {
+		return None;
+	}
+	let extension = format.extensions_str().first()?;
+	Some(path.with_extension(extension))
+}
+
+#[derive(Debug, Clone)]
+pub enum FramesStatus {
+	/// More frames are still being decoded.
+	Loading,
+	Complete,
+	/// Decoding was abandoned before all the frames were decoded.
+	Cancelled,
+	/// Decoding failed partway through; the frames decoded up to that point are kept.
+	Failed(String),
+}
+
+#[derive(Debug)]
+pub struct FrameList<FrameType> {
+	pub list: Vec<(FrameType, Duration)>,
+	pub status: FramesStatus,
+	/// How many frames were decoded, including the ones that repeat the previous frame and were merged into it.
+	pub encoded_len: usize,
+	/// The total of the delays of the frames.
+	pub duration: Duration,
+}
+
+impl<FrameType> FrameList<FrameType> {
+	fn push(&mut self, frame: FrameType, delay: Duration) {
+		self.list.push((frame, delay));
+		self.encoded_len += 1;
+		self.duration = self.duration.saturating_add(delay);
+	}
+
+	/// Show the last frame for `delay` longer, for a frame that repeats it.
+	fn repeat(&mut self, delay: Duration) {
+		if let Some((_, last_delay)) = self.list.last_mut() {
+			*last_delay = last_delay.saturating_add(delay);
+		}
+		self.encoded_len += 1;
+		self.duration = self.duration.saturating_add(delay);
+	}
+
+	/// The average number of frames per second, counting repeated frames, unless the frames have no delay.
+	pub fn average_fps(&self) -> Option<f32> {
+		let secs = self.duration.as_secs_f32();
+		(secs > 0.0).then(|| az::cast::<_, f32>(self.encoded_len) / secs)
+	}
+}
+
+/// The frames of an image, which may still be streaming in from the decoder.
 #[derive(Debug)]
-pub struct Image<FrameType = TextureHandle> {
+pub struct Frames<FrameType>(RwLock<FrameList<FrameType>>);
+
+impl<FrameType> Frames<FrameType> {
+	fn new(list: Vec<(FrameType, Duration)>, status: FramesStatus) -> Self {
+		let duration = list
+			.iter()
+			.fold(Duration::new_micros(0), |total, (_, delay)| {
+				total.saturating_add(*delay)
+			});
+		Self(RwLock::new(FrameList {
+			encoded_len: list.len(),
+			duration,
+			list,
+			status,
+		}))
+	}
+
+	pub fn read(&self) -> RwLockReadGuard<'_, FrameList<FrameType>> {
+		self.0.read().unwrap()
+	}
+
+	fn write(&self) -> RwLockWriteGuard<'_, FrameList<FrameType>> {
+		self.0.write().unwrap()
+	}
+
+	pub fn is_loading(&self) -> bool {
+		matches!(self.read().status, FramesStatus::Loading)
+	}
+
+	pub fn is_complete(&self) -> bool {
+		matches!(self.read().status, FramesStatus::Complete)
+	}
+}
+
+#[derive(Debug)]
+pub struct Image<FrameType = TiledTexture> {
 	pub format: ImageFormat,
 	pub width: u32,
 	pub height: u32,
-	pub frames: Vec<(FrameType, Duration)>,
+	pub frames: Frames<FrameType>,
 	pub metadata: Metadata,
+	/// How many times the animation plays, if it is animated and the format stores it.
+	pub loop_count: Option<loop_count::LoopCount>,
+	/// The color type of the file, before its pixels were converted to 8-bit RGBA.
+	pub color_type: ::image::ExtendedColorType,
+	/// The bounds of the first frame without any uniform border, in pixels, if it has such a border.
+	pub content: Option<egui::Rect>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -64,7 +185,7 @@ impl Kind {
 
 impl Image {
 	pub fn is_animated(&self) -> bool {
-		self.frames.len() > 1
+		self.frames.read().list.len() > 1
 	}
 
 	pub fn kind(&self) -> Kind {
@@ -75,33 +196,198 @@ impl Image {
 		}
 	}
 
-	pub fn load(ctx: &Context, path: &Path) -> ImageResult<Self> {
-		let image = read::read(path, |width, height, frame| {
-			ctx.load_texture(
-				"", // has no importance
-				egui::ColorImage {
-					size: [width.try_into().unwrap(), height.try_into().unwrap()],
-					pixels: frame.into(),
-				},
+	/// `on_progress` is called every time a frame is added, and can stop the decoding early.
+	/// The returned image is only partially loaded if `on_progress` stops the decoding or decoding fails after the first frame.
+	/// `progress` is updated as the file is read, and cancelling it aborts loading.
+	/// If `keep_pixels` is set, the textures can be restored after they are evicted from the GPU.
+	/// If `thumbnails` is not set, the thumbnails of the frames are left to `show_thumbnails`.
+	pub fn load(
+		ctx: &Context,
+		path: &Path,
+		progress: &Progress,
+		keep_pixels: bool,
+		thumbnails: bool,
+		on_progress: impl FnMut(&Arc<Self>) -> ControlFlow<()>,
+	) -> ImageResult<Arc<Self>> {
+		let thumbnail_side = thumbnail_side(ctx);
+		let load_frame = |width: u32, height: u32, frame: Frame| {
+			TiledTexture::load(
+				ctx,
+				[width.try_into().unwrap(), height.try_into().unwrap()],
+				frame,
 				TextureOptions {
 					magnification: TextureFilter::Nearest,
 					minification: TextureFilter::Linear,
 					wrap_mode: TextureWrapMode::default(),
 				},
+				keep_pixels,
+				thumbnails.then_some(thumbnail_side),
 			)
-		})?;
-		Ok(image)
+		};
+		read::read(path, progress, load_frame, on_progress)
 	}
 
+	/// The memory used by the kept pixels of the frames.
+	/// Repeated frames of animations are merged when decoding, so every frame counted here takes up its own memory.
 	pub fn size_in_memory(&self) -> usize {
 		self
 			.frames
+			.read()
+			.list
 			.iter()
-			.map(|(frame, _delay)| {
-				let [width, height] = frame.size();
-				let pixel_size = std::mem::size_of::<egui::Color32>();
-				width.saturating_mul(height).saturating_mul(pixel_size)
-			})
+			.map(|(frame, _delay)| frame.size_in_memory())
 			.sum()
 	}
+
+	pub fn size_on_gpu(&self) -> usize {
+		self
+			.frames
+			.read()
+			.list
+			.iter()
+			.map(|(frame, _delay)| frame.size_on_gpu())
+			.sum()
+	}
+
+	pub fn is_resident(&self) -> bool {
+		self
+			.frames
+			.read()
+			.list
+			.iter()
+			.all(|(frame, _delay)| frame.is_resident())
+	}
+
+	/// Whether the pixels of all the frames are kept, so that their textures can be restored after being evicted.
+	pub fn has_pixels(&self) -> bool {
+		self
+			.frames
+			.read()
+			.list
+			.iter()
+			.all(|(frame, _delay)| frame.has_pixels())
+	}
+
+	/// Free the kept pixels of all the frames.
+	pub fn drop_pixels(&self) {
+		for (frame, _delay) in &mut self.frames.write().list {
+			frame.drop_pixels();
+		}
+	}
+
+	/// Free the textures of all the frames on the GPU.
+	pub fn evict_textures(&self) {
+		for (frame, _delay) in &mut self.frames.write().list {
+			frame.evict();
+		}
+	}
+
+	/// Re-upload any evicted textures. Returns whether all the frames are now resident.
+	pub fn restore_textures(&self, ctx: &Context) -> bool {
+		self
+			.frames
+			.write()
+			.list
+			.iter_mut()
+			.all(|(frame, _delay)| frame.restore(ctx))
+	}
+
+	/// Upload the thumbnails of the frames in `visible` and free the rest, so that only the thumbnails that are shown take up GPU memory.
+	/// The thumbnails are made first if they are missing or the scale of the display changed since they were made, which is only possible if the pixels were kept.
+	pub fn show_thumbnails(&self, ctx: &Context, visible: Range<usize>) {
+		let side = thumbnail_side(ctx);
+		for (idx, (frame, _delay)) in self.frames.write().list.iter_mut().enumerate() {
+			if visible.contains(&idx) {
+				frame.update_thumbnail(side);
+				frame.load_thumbnail(ctx);
+			} else {
+				frame.unload_thumbnail();
+			}
+		}
+	}
+}
+
+impl Image<Frame> {
+	/// Decode the first frame of the image into CPU-side pixels, without uploading it as a texture.
+	pub fn load_first_frame(path: &Path) -> ImageResult<Arc<Self>> {
+		read::read(
+			path,
+			&Progress::default(),
+			|_width, _height, frame| frame,
+			|_| ControlFlow::Break(()),
+		)
+	}
+}
+
+/// Convert a frame of a `width` by `height` image to an image buffer, e.g., to encode it.
+pub fn to_rgba(width: u32, height: u32, frame: &[Color32]) -> RgbaImage {
+	RgbaImage::from_raw(width, height, bytemuck::cast_slice(frame).to_vec())
+		.unwrap(/* frames always have the size of the image */)
+}
+
+/// The CPU-side pixels of the frames of an image, for copying and exporting it.
+#[derive(Debug)]
+pub struct Pixels {
+	pub format: ImageFormat,
+	pub width: u32,
+	pub height: u32,
+	pub loop_count: Option<loop_count::LoopCount>,
+	/// Never empty.
+	pub frames: Vec<(Arc<[Color32]>, Duration)>,
+}
+
+impl Pixels {
+	/// The pixels that `image` kept, without copying them, if it kept all of them.
+	pub fn kept(image: &Image) -> Option<Self> {
+		let frames = image
+			.frames
+			.read()
+			.list
+			.iter()
+			.map(|(frame, delay)| Some((Arc::clone(frame.pixels()?), *delay)))
+			.collect::<Option<Vec<_>>>()?;
+		(!frames.is_empty()).then_some(Self {
+			format: image.format,
+			width: image.width,
+			height: image.height,
+			loop_count: image.loop_count,
+			frames,
+		})
+	}
+
+	/// Decode the image at `path` again.
+	pub fn decode(path: &Path) -> ImageResult<Self> {
+		let image = read::read(
+			path,
+			&Progress::default(),
+			|_width, _height, frame| Arc::<[Color32]>::from(frame),
+			|_| ControlFlow::Continue(()),
+		)?;
+		let frames = image.frames.read().list.clone();
+		Ok(Self {
+			format: image.format,
+			width: image.width,
+			height: image.height,
+			loop_count: image.loop_count,
+			frames,
+		})
+	}
+
+	/// The pixels that `cached` kept, or else those of the image at `path` decoded again.
+	pub fn get(path: &Path, cached: Option<&Image>) -> ImageResult<Self> {
+		match cached.and_then(Self::kept) {
+			Some(pixels) => Ok(pixels),
+			None => Self::decode(path),
+		}
+	}
+
+	/// The pixels of frame `idx`, or of the first frame if there is no such frame.
+	pub fn frame(&self, idx: usize) -> &[Color32] {
+		&self.frames.get(idx).unwrap_or(&self.frames[0]).0
+	}
+
+	/// Like [`Self::frame`], as an image buffer.
+	pub fn frame_rgba(&self, idx: usize) -> RgbaImage {
+		to_rgba(self.width, self.height, self.frame(idx))
+	}
 }
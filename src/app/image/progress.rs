@@ -0,0 +1,103 @@
+use std::io::{self, BufRead, Read, Seek, SeekFrom};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+
+/// Loading progress shared between the actor and the UI, which also allows the UI to cancel loading.
+#[derive(Debug, Default)]
+pub struct Progress {
+	bytes_read: AtomicU64,
+	total_bytes: AtomicU64,
+	frames: AtomicUsize,
+	cancelled: AtomicBool,
+}
+
+impl Progress {
+	pub fn reset(&self) {
+		self.bytes_read.store(0, Ordering::Relaxed);
+		self.total_bytes.store(0, Ordering::Relaxed);
+		self.frames.store(0, Ordering::Relaxed);
+		self.cancelled.store(false, Ordering::Relaxed);
+	}
+
+	/// The fraction of the file that has been read, if known.
+	pub fn fraction(&self) -> Option<f32> {
+		let total = self.total_bytes.load(Ordering::Relaxed);
+		let read = self.bytes_read.load(Ordering::Relaxed);
+		(total > 0).then(|| az::cast::<_, f32>(read.min(total)) / az::cast::<_, f32>(total))
+	}
+
+	pub fn frames(&self) -> usize {
+		self.frames.load(Ordering::Relaxed)
+	}
+
+	pub(super) fn set_total_bytes(&self, total: u64) {
+		self.total_bytes.store(total, Ordering::Relaxed);
+	}
+
+	pub(super) fn add_frame(&self) {
+		self.frames.fetch_add(1, Ordering::Relaxed);
+	}
+
+	pub fn cancel(&self) {
+		self.cancelled.store(true, Ordering::Relaxed);
+	}
+
+	pub fn is_cancelled(&self) -> bool {
+		self.cancelled.load(Ordering::Relaxed)
+	}
+}
+
+/// Reports the position in the file to a `Progress`, and fails reads once loading has been cancelled.
+pub(super) struct ProgressReader<'a, R> {
+	inner: R,
+	progress: &'a Progress,
+}
+
+impl<'a, R> ProgressReader<'a, R> {
+	pub(super) fn new(inner: R, progress: &'a Progress) -> Self {
+		Self { inner, progress }
+	}
+
+	fn check_cancelled(&self) -> io::Result<()> {
+		if self.progress.is_cancelled() {
+			Err(io::Error::other("loading was cancelled"))
+		} else {
+			Ok(())
+		}
+	}
+
+	fn advance(&self, amount: usize) {
+		self
+			.progress
+			.bytes_read
+			.fetch_add(az::cast(amount), Ordering::Relaxed);
+	}
+}
+
+impl<R: Read> Read for ProgressReader<'_, R> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		self.check_cancelled()?;
+		let amount = self.inner.read(buf)?;
+		self.advance(amount);
+		Ok(amount)
+	}
+}
+
+impl<R: BufRead> BufRead for ProgressReader<'_, R> {
+	fn fill_buf(&mut self) -> io::Result<&[u8]> {
+		self.check_cancelled()?;
+		self.inner.fill_buf()
+	}
+
+	fn consume(&mut self, amount: usize) {
+		self.inner.consume(amount);
+		self.advance(amount);
+	}
+}
+
+impl<R: Seek> Seek for ProgressReader<'_, R> {
+	fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+		let position = self.inner.seek(pos)?;
+		self.progress.bytes_read.store(position, Ordering::Relaxed);
+		Ok(position)
+	}
+}
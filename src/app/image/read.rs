@@ -1,24 +1,46 @@
-use std::io::{BufRead, Seek};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Seek};
+use std::num::NonZeroUsize;
+use std::ops::ControlFlow;
 use std::path::Path;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
 
 use egui::Color32;
 use image::error::{DecodingError, ImageError, ImageFormatHint, ImageResult};
 use image::io::Limits;
-use image::{AnimationDecoder, DynamicImage, ImageDecoder, ImageFormat};
+use image::{
+	AnimationDecoder, DynamicImage, ExtendedColorType, ImageDecoder, ImageFormat, RgbaImage,
+};
 
-use super::{Image, Metadata};
+use super::progress::ProgressReader;
+use super::{content, loop_count, Frames, FramesStatus, Image, Metadata, Progress};
 use crate::duration::Duration;
 
-type Frame = Box<[Color32]>;
+pub type Frame = Box<[Color32]>;
+
+/// How many decoded frames can be waiting to be converted before the decoder blocks.
+const PIPELINE_DEPTH: usize = 4;
+/// The most workers that convert and upload the frames of an animation at the same time.
+const MAX_WORKERS: usize = 4;
+
+/// A frame that was decoded, as an `RgbaImage` to be converted by a worker, then as the converted frame to be added to the image.
+enum Decoded<F> {
+	Frame(F, Duration),
+	/// The frame is the same as the previous one, which is shown for longer instead of keeping another copy.
+	Repeat(Duration),
+}
 
 trait DecoderVisitor {
 	type Return;
 
 	fn visit<D: ImageDecoder>(self, decoder: D, format: ImageFormat) -> ImageResult<Self::Return>;
+	/// `color_type` is the color type of the file, since animation decoders only produce 8-bit RGBA.
 	fn visit_animated<'a, D: AnimationDecoder<'a>>(
 		self,
 		decoder: D,
 		format: ImageFormat,
+		color_type: ExtendedColorType,
 	) -> ImageResult<Self::Return>;
 }
 
@@ -31,14 +53,17 @@ fn load_decoder<V: DecoderVisitor>(
 		(@arm @png $($decoder:ident)::*) => {{
 			let decoder = image::codecs:: $($decoder)::* ::new(reader)?;
 			if decoder.is_apng()? {
-				visitor.visit_animated(decoder.apng()?, format)
+				let color_type = decoder.original_color_type();
+				visitor.visit_animated(decoder.apng()?, format, color_type)
 			} else {
 				visitor.visit(decoder, format)
 			}
 		}};
-		(@arm @animated $($decoder:ident)::*) => {
-			visitor.visit_animated(image::codecs:: $($decoder)::* ::new(reader)?, format)
-		};
+		(@arm @animated $($decoder:ident)::*) => {{
+			let decoder = image::codecs:: $($decoder)::* ::new(reader)?;
+			let color_type = decoder.original_color_type();
+			visitor.visit_animated(decoder, format, color_type)
+		}};
 		(@arm $($decoder:ident)::*) => {
 			visitor.visit(image::codecs:: $($decoder)::* ::new(reader)?, format)
 		};
@@ -71,13 +96,21 @@ fn load_decoder<V: DecoderVisitor>(
 	}
 }
 
-struct Visitor<F> {
+struct Visitor<'a, F, P> {
+	path: &'a Path,
 	frame_mapper: F,
+	on_progress: P,
+	progress: &'a Progress,
 	metadata: Metadata,
 }
 
-impl<OutFrameType, F: FnMut(u32, u32, Frame) -> OutFrameType> DecoderVisitor for Visitor<F> {
-	type Return = Image<OutFrameType>;
+impl<
+		OutFrameType: Send + Sync,
+		F: Fn(u32, u32, Frame) -> OutFrameType + Sync,
+		P: FnMut(&Arc<Image<OutFrameType>>) -> ControlFlow<()>,
+	> DecoderVisitor for Visitor<'_, F, P>
+{
+	type Return = Arc<Image<OutFrameType>>;
 
 	fn visit<D: ImageDecoder>(
 		mut self,
@@ -90,97 +123,311 @@ impl<OutFrameType, F: FnMut(u32, u32, Frame) -> OutFrameType> DecoderVisitor for
 		limits.max_alloc = Some(1024 * 1024 * 1024); // 1 GB
 		limits.reserve(decoder.total_bytes())?;
 		decoder.set_limits(limits)?;
+		let color_type = decoder.original_color_type();
 		let image = DynamicImage::from_decoder(decoder)?.into_rgba8();
+		self.progress.add_frame();
 		let (width, height) = image.dimensions();
 		// `egui::Color32` and `image::Rgba<u8>` have the same size (4) and align (1) so `cast_vec` will never fail
-		let frame = bytemuck::allocation::cast_vec(image.into_raw());
-		Ok(Image {
+		let frame: Vec<Color32> = bytemuck::allocation::cast_vec(image.into_raw());
+		let content = content::bounds(&frame, width, height);
+		let image = Arc::new(Image {
 			format,
 			width,
 			height,
-			frames: vec![(
-				(self.frame_mapper)(width, height, frame.into()),
-				Duration::new_secs(1).unwrap(), // this value is ignored
-			)],
+			frames: Frames::new(
+				vec![(
+					(self.frame_mapper)(width, height, frame.into()),
+					Duration::new_secs(1).unwrap(), // this value is ignored
+				)],
+				FramesStatus::Complete,
+			),
 			metadata: self.metadata,
-		})
+			loop_count: None,
+			color_type,
+			content,
+		});
+		_ = (self.on_progress)(&image);
+		Ok(image)
 	}
 
 	fn visit_animated<'a, D: AnimationDecoder<'a>>(
-		mut self,
+		self,
 		decoder: D,
 		format: ImageFormat,
+		color_type: ExtendedColorType,
 	) -> ImageResult<Self::Return> {
+		let Self {
+			path,
+			frame_mapper,
+			mut on_progress,
+			progress,
+			metadata,
+		} = self;
+
 		let error = |error| ImageError::Decoding(DecodingError::new(format.into(), error));
 		let partial_frame_error = || error("partial frames are unimplemented");
 
 		let mut size = None;
-		let frames = decoder
-			.into_frames()
-			.map(|frame| {
-				let frame = frame?;
-
-				let this_size = frame.buffer().dimensions();
-				match size {
-					None => {
-						size = Some(this_size);
-					}
-					Some(old_size) => {
-						if old_size != this_size {
-							return Err(partial_frame_error());
-						}
+		let mut decode_frame = |frame: ImageResult<image::Frame>| {
+			let frame = frame?;
+
+			let this_size = frame.buffer().dimensions();
+			match size {
+				None => {
+					size = Some(this_size);
+				}
+				Some(old_size) => {
+					if old_size != this_size {
+						return Err(partial_frame_error());
 					}
 				}
-				let (width, height) = this_size;
+			}
 
-				if frame.top() != 0 || frame.left() != 0 {
-					return Err(partial_frame_error());
-				}
+			if frame.top() != 0 || frame.left() != 0 {
+				return Err(partial_frame_error());
+			}
 
-				let delay = frame.delay();
-				let frame = bytemuck::allocation::cast_vec(frame.into_buffer().into_raw());
-				Ok((
-					(self.frame_mapper)(width, height, frame.into()),
-					delay.try_into().map_err(|_| error("delay out of range"))?,
-				))
-			})
-			.collect::<Result<Vec<_>, _>>()?;
+			let delay = frame
+				.delay()
+				.try_into()
+				.map_err(|_| error("delay out of range"))?;
+			progress.add_frame();
+			Ok((frame.into_buffer(), delay))
+		};
+		// converting the pixels and uploading the texture is kept separate from decoding so it can happen on other threads.
+		let map_frame = |(buffer, delay): (RgbaImage, Duration)| {
+			let (width, height) = buffer.dimensions();
+			let frame = bytemuck::allocation::cast_vec(buffer.into_raw());
+			(frame_mapper(width, height, frame.into()), delay)
+		};
 
-		let (width, height) = size.ok_or_else(|| {
+		let mut frames = decoder.into_frames();
+
+		let first_frame = frames.next().ok_or_else(|| {
 			ImageError::Decoding(DecodingError::new(
 				ImageFormatHint::Exact(format),
 				"no frames",
 			))
 		})?;
+		let first_frame = decode_frame(first_frame)?;
+		let mut previous = first_frame.0.clone();
+		let (width, height) = first_frame.0.dimensions();
+		let content = content::bounds(bytemuck::cast_slice(first_frame.0.as_raw()), width, height);
 
-		Ok(Image {
+		let image = Arc::new(Image {
 			format,
 			width,
 			height,
-			frames,
-			metadata: self.metadata,
-		})
+			frames: Frames::new(vec![map_frame(first_frame)], FramesStatus::Loading),
+			metadata,
+			// only informational, so it doesn't fail the image
+			loop_count: loop_count::read(path, format).ok().flatten(),
+			color_type,
+			content,
+		});
+
+		// errors after the first frame don't fail the whole image; the frames decoded so far are kept.
+		let status = thread::scope(|scope| {
+			let decoded_sender = spawn_converters(scope, &image, &map_frame);
+			let mut idx: usize = 0;
+			let mut send = |decoded| {
+				decoded_sender.send((idx, decoded)).unwrap();
+				idx += 1;
+			};
+			loop {
+				if progress.is_cancelled() || on_progress(&image).is_break() {
+					break FramesStatus::Cancelled;
+				}
+
+				let Some(frame) = frames.next() else {
+					break FramesStatus::Complete;
+				};
+
+				match decode_frame(frame) {
+					// many animations hold a frame by repeating it, which would take up memory for every copy
+					Ok((buffer, delay)) if buffer == previous => {
+						send(Decoded::Repeat(delay));
+					}
+					Ok((buffer, delay)) => {
+						previous.clone_from(&buffer);
+						send(Decoded::Frame(buffer, delay));
+					}
+					// cancelling makes reads fail, so the decoder may notice before `on_progress` does
+					Err(_) if progress.is_cancelled() => break FramesStatus::Cancelled,
+					Err(error) => break FramesStatus::Failed(error.to_string()),
+				}
+			}
+			// dropping `decoded_sender` lets the workers finish, then the scope waits for them.
+		});
+		image.frames.write().status = status;
+		// report the final status
+		_ = on_progress(&image);
+
+		Ok(image)
 	}
 }
 
-pub fn read<OutFrameType>(
-	path: &Path,
-	load_frame: impl FnMut(u32, u32, Frame) -> OutFrameType,
-) -> ImageResult<Image<OutFrameType>> {
-	let metadata = Metadata::from_path(path)?;
-	let reader = image::io::Reader::open(path)?;
-	let reader = reader.with_guessed_format()?;
+/// Start workers in `scope` that convert the frames sent to the returned sender with `map_frame`, then add them to `image` in the order of their indices.
+/// The workers finish once the sender is dropped.
+fn spawn_converters<'scope, T: Send + Sync>(
+	scope: &'scope thread::Scope<'scope, '_>,
+	image: &'scope Image<T>,
+	map_frame: &'scope (impl Fn((RgbaImage, Duration)) -> (T, Duration) + Sync),
+) -> mpsc::SyncSender<(usize, Decoded<RgbaImage>)> {
+	let (decoded_sender, decoded_receiver) = mpsc::sync_channel(PIPELINE_DEPTH);
+	let decoded_receiver = Arc::new(Mutex::new(decoded_receiver));
+	let (converted_sender, converted_receiver) = mpsc::channel();
+	for _ in 0..num_workers() {
+		let decoded_receiver = Arc::clone(&decoded_receiver);
+		let converted_sender = converted_sender.clone();
+		scope.spawn(move || loop {
+			// the lock is only held while waiting, so that the workers convert frames at the same time
+			let Ok((idx, job)) = decoded_receiver.lock().unwrap().recv() else {
+				break;
+			};
+			let converted = match job {
+				Decoded::Frame(buffer, delay) => {
+					let (frame, delay) = map_frame((buffer, delay));
+					Decoded::Frame(frame, delay)
+				}
+				Decoded::Repeat(delay) => Decoded::Repeat(delay),
+			};
+			_ = converted_sender.send((idx, converted));
+		});
+	}
+	// only the workers keep senders, so the frames end once they are done
+	drop(converted_sender);
+
+	scope.spawn(move || {
+		// the workers may finish out of order, so frames wait until the ones before them are added
+		let mut waiting = BTreeMap::new();
+		let mut next: usize = 0;
+		for (idx, converted) in converted_receiver {
+			waiting.insert(idx, converted);
+			while let Some(converted) = waiting.remove(&next) {
+				match converted {
+					Decoded::Frame(frame, delay) => image.frames.write().push(frame, delay),
+					Decoded::Repeat(delay) => image.frames.write().repeat(delay),
+				}
+				next += 1;
+			}
+		}
+	});
+
+	decoded_sender
+}
+
+/// Enough workers to keep up with the decoder on most machines, without taking all the cores.
+fn num_workers() -> usize {
+	thread::available_parallelism()
+		.map_or(1, NonZeroUsize::get)
+		.min(MAX_WORKERS)
+}
+
+/// Guess the format of the image from its contents, returning the reader rewound to the start.
+fn guess_format<R: BufRead + Seek>(reader: R, path: &Path) -> ImageResult<(R, ImageFormat)> {
+	let reader = image::io::Reader::new(reader).with_guessed_format()?;
 	let format = reader.format().ok_or_else(|| {
 		ImageError::Unsupported(ImageFormatHint::PathExtension(path.to_owned()).into())
 	})?;
 	let mut reader = reader.into_inner();
 	reader.rewind()?;
+	Ok((reader, format))
+}
+
+pub fn read<OutFrameType: Send + Sync>(
+	path: &Path,
+	progress: &Progress,
+	load_frame: impl Fn(u32, u32, Frame) -> OutFrameType + Sync,
+	on_progress: impl FnMut(&Arc<Image<OutFrameType>>) -> ControlFlow<()>,
+) -> ImageResult<Arc<Image<OutFrameType>>> {
+	let metadata = Metadata::from_path(path)?;
+	progress.set_total_bytes(metadata.file_size);
+	let file = std::fs::File::open(path)?;
+	let reader = ProgressReader::new(BufReader::new(file), progress);
+	let (reader, format) = guess_format(reader, path)?;
 	load_decoder(
 		reader,
 		format,
 		Visitor {
+			path,
 			frame_mapper: load_frame,
+			on_progress,
+			progress,
 			metadata,
 		},
 	)
 }
+
+/// A summary of an image that is read without keeping its pixels.
+#[derive(Debug)]
+pub struct Info {
+	pub format: ImageFormat,
+	pub width: u32,
+	pub height: u32,
+	pub frames: usize,
+	/// The total of the delays of the frames, if the image is animated.
+	pub duration: Option<Duration>,
+	pub metadata: Metadata,
+}
+
+struct InfoVisitor {
+	metadata: Metadata,
+}
+
+impl DecoderVisitor for InfoVisitor {
+	type Return = Info;
+
+	fn visit<D: ImageDecoder>(self, decoder: D, format: ImageFormat) -> ImageResult<Info> {
+		let (width, height) = decoder.dimensions();
+		Ok(Info {
+			format,
+			width,
+			height,
+			frames: 1,
+			duration: None,
+			metadata: self.metadata,
+		})
+	}
+
+	/// The frames of animations have to be decoded to count them, but each is dropped right away.
+	fn visit_animated<'a, D: AnimationDecoder<'a>>(
+		self,
+		decoder: D,
+		format: ImageFormat,
+		_color_type: ExtendedColorType,
+	) -> ImageResult<Info> {
+		let error = |error| ImageError::Decoding(DecodingError::new(format.into(), error));
+
+		let mut size = None;
+		let mut frames = 0;
+		let mut duration = Duration::new_micros(0);
+		for frame in decoder.into_frames() {
+			let frame = frame?;
+			size.get_or_insert(frame.buffer().dimensions());
+			let delay: Duration = frame
+				.delay()
+				.try_into()
+				.map_err(|_| error("delay out of range"))?;
+			frames += 1;
+			duration = duration.saturating_add(delay);
+		}
+		let (width, height) = size.ok_or_else(|| error("no frames"))?;
+		Ok(Info {
+			format,
+			width,
+			height,
+			frames,
+			duration: Some(duration),
+			metadata: self.metadata,
+		})
+	}
+}
+
+/// Read the format, dimensions, and frames of the image at `path`, without keeping any pixels.
+pub fn info(path: &Path) -> ImageResult<Info> {
+	let metadata = Metadata::from_path(path)?;
+	let file = std::fs::File::open(path)?;
+	let (reader, format) = guess_format(BufReader::new(file), path)?;
+	load_decoder(reader, format, InfoVisitor { metadata })
+}
@@ -0,0 +1,202 @@
+//! Saving a copy of an image, optionally in another format or resized, e.g., to make web-sized copies.
+
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, ImageError, ImageFormat, ImageResult};
+
+use super::{chunks, data_uri, Pixels};
+
+/// The filters that images can be resized with, from the fastest to the sharpest.
+pub const FILTERS: &[FilterType] = &[
+	FilterType::Nearest,
+	FilterType::Triangle,
+	FilterType::CatmullRom,
+	FilterType::Lanczos3,
+];
+
+pub fn filter_to_string(filter: FilterType) -> &'static str {
+	match filter {
+		FilterType::Nearest => "Nearest (pixel art)",
+		FilterType::Triangle => "Bilinear",
+		FilterType::CatmullRom => "Bicubic",
+		FilterType::Gaussian => "Gaussian",
+		FilterType::Lanczos3 => "Lanczos",
+	}
+}
+
+/// The most pixels that a generated image (a resized copy or a sprite sheet) can have, so that it cannot use up all the memory.
+pub const MAX_PIXELS: u64 = 250_000_000;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SaveError {
+	#[error(transparent)]
+	Image(#[from] ImageError),
+	#[error(transparent)]
+	Io(#[from] io::Error),
+	#[error("{} already exists", .0.display())]
+	Exists(PathBuf),
+	#[error("a copy of {0}×{1} pixels would be too large")]
+	TooLarge(u32, u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resize {
+	Original,
+	/// Scale both sides by this percentage.
+	Percent(f32),
+	/// Scale down to fit within this size, keeping the aspect ratio. Images that already fit are left alone.
+	Fit {
+		width: u32,
+		height: u32,
+	},
+}
+
+impl Resize {
+	pub fn repr(self) -> &'static str {
+		match self {
+			Self::Original => "Original Size",
+			Self::Percent(_) => "Percentage",
+			Self::Fit { .. } => "Fit Within",
+		}
+	}
+
+	pub const VARIANTS: &'static [Self] = &[
+		Self::Original,
+		Self::Percent(50.0),
+		Self::Fit {
+			width: 1920,
+			height: 1080,
+		},
+	];
+
+	/// The size of an image of `width` by `height` once it is resized.
+	pub fn size(self, width: u32, height: u32) -> (u32, u32) {
+		let scale = match self {
+			Self::Original => return (width, height),
+			Self::Percent(percent) => f64::from(percent) / 100.0,
+			Self::Fit {
+				width: max_width,
+				height: max_height,
+			} => {
+				if width <= max_width && height <= max_height {
+					return (width, height);
+				}
+				(f64::from(max_width) / f64::from(width)).min(f64::from(max_height) / f64::from(height))
+			}
+		};
+		let scaled =
+			|side: u32| az::saturating_cast::<f64, u32>((f64::from(side) * scale).round()).max(1);
+		(scaled(width), scaled(height))
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct Options {
+	pub target: PathBuf,
+	/// `None` keeps the format of the image. If it isn't resized either, the file is copied as is.
+	pub format: Option<ImageFormat>,
+	pub resize: Resize,
+	pub filter: FilterType,
+	/// Only applies to copies, since re-encoded images never keep their metadata.
+	pub strip_metadata: bool,
+	/// The frame of animations to save when re-encoding.
+	pub frame_idx: usize,
+}
+
+impl Options {
+	/// Whether the image is decoded and encoded again, rather than copied.
+	pub fn re_encodes(&self) -> bool {
+		self.format.is_some() || self.resize != Resize::Original
+	}
+}
+
+/// Where to save a copy of the image at `path` by default, next to it.
+pub fn default_target(path: &Path) -> PathBuf {
+	let mut name = path.file_stem().unwrap_or_default().to_owned();
+	name.push("-copy");
+	let mut target = path.with_file_name(name);
+	if let Some(extension) = path.extension() {
+		target.set_extension(extension);
+	}
+	target
+}
+
+/// Save a copy of the image at `path` as described by `options`.
+/// `pixels` is only called if the image has to be re-encoded.
+/// The copy is written to a temporary file that only takes its place once it is finished, and an existing file is never replaced.
+pub fn save(
+	path: &Path,
+	options: &Options,
+	pixels: impl FnOnce() -> ImageResult<Pixels>,
+) -> Result<(), SaveError> {
+	if options.target.try_exists()? {
+		return Err(SaveError::Exists(options.target.clone()));
+	}
+
+	let dir = options.target.parent().unwrap_or(Path::new("."));
+	// the temporary file is removed when it is dropped unless it was persisted
+	let mut temp = tempfile::Builder::new()
+		.prefix(".eo2-save-")
+		.tempfile_in(dir)?;
+	// like `std::fs::copy`, rather than the private permissions of temporary files
+	temp
+		.as_file()
+		.set_permissions(std::fs::metadata(path)?.permissions())?;
+	if options.re_encodes() {
+		temp.write_all(&encode(options, pixels)?)?;
+	} else if options.strip_metadata {
+		temp.write_all(&chunks::strip(&std::fs::read(path)?)?)?;
+	} else {
+		io::copy(&mut File::open(path)?, &mut temp)?;
+	}
+
+	match temp.persist_noclobber(&options.target) {
+		Ok(_) => Ok(()),
+		Err(error) if error.error.kind() == io::ErrorKind::AlreadyExists => {
+			Err(SaveError::Exists(options.target.clone()))
+		}
+		Err(error) => Err(error.error.into()),
+	}
+}
+
+/// Resize and encode the image as described by `options`.
+fn encode(
+	options: &Options,
+	pixels: impl FnOnce() -> ImageResult<Pixels>,
+) -> Result<Vec<u8>, SaveError> {
+	let image = pixels()?;
+	let (width, height) = options.resize.size(image.width, image.height);
+	if u64::from(width) * u64::from(height) > MAX_PIXELS {
+		return Err(SaveError::TooLarge(width, height));
+	}
+	let source = image.frame_rgba(options.frame_idx);
+
+	let resized = if (width, height) == source.dimensions() {
+		source
+	} else {
+		imageops::resize(&source, width, height, options.filter)
+	};
+	let format = options.format.unwrap_or(image.format);
+	Ok(data_uri::encode(
+		&DynamicImage::ImageRgba8(resized),
+		format,
+	)?)
+}
+
+#[test]
+fn test_resize_size() {
+	assert_eq!(Resize::Original.size(640, 480), (640, 480));
+	assert_eq!(Resize::Percent(50.0).size(640, 480), (320, 240));
+	assert_eq!(Resize::Percent(0.01).size(640, 480), (1, 1));
+	let fit = Resize::Fit {
+		width: 256,
+		height: 256,
+	};
+	assert_eq!(fit.size(1024, 512), (256, 128));
+	assert_eq!(fit.size(300, 1200), (64, 256));
+	assert_eq!(fit.size(100, 50), (100, 50));
+	assert_eq!(fit.size(4000, 1), (256, 1));
+}
@@ -0,0 +1,159 @@
+use std::io::{self, BufWriter, Write as _};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+use image::{ImageError, ImageFormat, RgbaImage};
+use serde::Serialize;
+use tempfile::NamedTempFile;
+
+use super::{to_rgba, Pixels};
+use crate::config;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+	#[error(transparent)]
+	Image(#[from] ImageError),
+	#[error(transparent)]
+	Io(#[from] io::Error),
+	#[error("writing descriptor: {0}")]
+	Json(#[from] serde_json::Error),
+	#[error("no frames in range")]
+	NoFrames,
+	#[error("the sprite sheet would be too large")]
+	TooLarge,
+}
+
+#[derive(Debug, Clone)]
+pub struct Options {
+	pub layout: config::SpriteSheet,
+	/// Indices of the frames to include.
+	pub frames: RangeInclusive<usize>,
+}
+
+#[derive(Serialize)]
+struct FrameRect {
+	x: u32,
+	y: u32,
+	width: u32,
+	height: u32,
+	delay_ms: f32,
+}
+
+#[derive(Serialize)]
+struct Descriptor {
+	image: String,
+	width: u32,
+	height: u32,
+	frames: Vec<FrameRect>,
+}
+
+pub fn image_path(path: &Path) -> PathBuf {
+	path.with_extension("sheet.png")
+}
+
+pub fn descriptor_path(path: &Path) -> PathBuf {
+	path.with_extension("sheet.json")
+}
+
+fn already_exists(path: &Path) -> io::Error {
+	io::Error::new(
+		io::ErrorKind::AlreadyExists,
+		format!("{} already exists", path.display()),
+	)
+}
+
+/// A temporary file next to `path`, which is moved into place once it is written.
+fn temp_for(path: &Path) -> io::Result<NamedTempFile> {
+	tempfile::Builder::new()
+		.prefix(".eo2-export-")
+		.tempfile_in(path.parent().unwrap_or(Path::new(".")))
+}
+
+/// Move `temp` to `path`, unless a file was created there in the meantime.
+fn persist(temp: NamedTempFile, path: &Path) -> io::Result<()> {
+	match temp.persist_noclobber(path) {
+		Ok(_) => Ok(()),
+		Err(error) if error.error.kind() == io::ErrorKind::AlreadyExists => Err(already_exists(path)),
+		Err(error) => Err(error.error),
+	}
+}
+
+/// Write the frames of `image` to a sprite sheet and descriptor next to it at `path`.
+/// Both files are written to temporary files first, and existing files are never replaced.
+pub fn export(path: &Path, image: &Pixels, options: Options) -> Result<(), ExportError> {
+	let sheet_path = image_path(path);
+	let descriptor_path = descriptor_path(path);
+	for target in [&sheet_path, &descriptor_path] {
+		if target.try_exists()? {
+			return Err(already_exists(target).into());
+		}
+	}
+
+	let frames = image
+		.frames
+		.get(options.frames)
+		.ok_or(ExportError::NoFrames)?;
+	if frames.is_empty() {
+		return Err(ExportError::NoFrames);
+	}
+
+	let num_frames: u32 = az::cast(frames.len());
+	let columns = options.layout.columns.get().min(num_frames);
+	let rows = num_frames.div_ceil(columns);
+	let padding = options.layout.padding;
+	let size = |len: u32, count: u32| {
+		let cell = len.checked_add(padding)?;
+		Some((cell, cell.checked_mul(count)?.checked_add(padding)?))
+	};
+	let ((cell_width, sheet_width), (cell_height, sheet_height)) = size(image.width, columns)
+		.zip(size(image.height, rows))
+		.ok_or(ExportError::TooLarge)?;
+	if u64::from(sheet_width) * u64::from(sheet_height) > super::save_as::MAX_PIXELS {
+		return Err(ExportError::TooLarge);
+	}
+
+	let mut sheet = RgbaImage::new(sheet_width, sheet_height);
+	let mut rects = Vec::with_capacity(frames.len());
+	for (idx, (frame, delay)) in (0..).zip(frames) {
+		let x = padding + (idx % columns) * cell_width;
+		let y = padding + (idx / columns) * cell_height;
+		let frame = to_rgba(image.width, image.height, frame);
+		image::imageops::replace(&mut sheet, &frame, x.into(), y.into());
+		rects.push(FrameRect {
+			x,
+			y,
+			width: image.width,
+			height: image.height,
+			delay_ms: delay.as_millis_f32(),
+		});
+	}
+
+	let descriptor = Descriptor {
+		image: sheet_path
+			.file_name()
+			.unwrap_or_default()
+			.to_string_lossy()
+			.into_owned(),
+		width: sheet.width(),
+		height: sheet.height(),
+		frames: rects,
+	};
+	let descriptor = serde_json::to_string_pretty(&descriptor)?;
+
+	// the temporary files are removed when they are dropped unless they were persisted
+	let sheet_temp = temp_for(&sheet_path)?;
+	let mut writer = BufWriter::new(sheet_temp.as_file());
+	sheet.write_to(&mut writer, ImageFormat::Png)?;
+	writer.flush()?;
+	drop(writer);
+	let mut descriptor_temp = temp_for(&descriptor_path)?;
+	descriptor_temp.write_all(descriptor.as_bytes())?;
+
+	persist(sheet_temp, &sheet_path)?;
+	if let Err(error) = persist(descriptor_temp, &descriptor_path) {
+		// a sheet without its descriptor is of no use
+		_ = std::fs::remove_file(&sheet_path);
+		return Err(error.into());
+	}
+	Ok(())
+}
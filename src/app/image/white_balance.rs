@@ -0,0 +1,129 @@
+//! Correcting the white balance of an image, e.g., a scan or a phone photo with a color cast.
+//!
+//! The correction is shown by tinting the textures, so the image itself is never changed; it is only applied to the pixels when exporting.
+
+use std::path::{Path, PathBuf};
+
+use egui::{Color32, Pos2, Rgba};
+use image::RgbaImage;
+
+use super::save_as::SaveError;
+use super::Pixels;
+
+/// How far the sliders go, in stops of change to each channel.
+pub const RANGE: f32 = 2.0;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct WhiteBalance {
+	/// Positive is warmer (more red, less blue).
+	pub temperature: f32,
+	/// Positive is more magenta, negative is more green.
+	pub tint: f32,
+}
+
+impl WhiteBalance {
+	pub fn is_neutral(self) -> bool {
+		self.temperature == 0.0 && self.tint == 0.0
+	}
+
+	/// The linear factors for the red, green, and blue channels.
+	/// They are scaled so that the largest is 1, since tinting textures can only darken them.
+	fn gains(self) -> [f32; 3] {
+		let stops = [self.temperature, -self.tint, -self.temperature];
+		let max = stops.into_iter().fold(f32::NEG_INFINITY, f32::max);
+		stops.map(|stop| (stop - max).exp2())
+	}
+
+	/// The color to tint the textures with to show the correction.
+	pub fn tint_color(self) -> Color32 {
+		let [red, green, blue] = self.gains();
+		Rgba::from_rgb(red, green, blue).into()
+	}
+
+	/// The correction that makes `color`, which should be gray, neutral.
+	pub fn from_gray_point(color: Color32) -> Self {
+		let [red, green, blue, _] = color.to_array();
+		let linear = Rgba::from_srgba_unmultiplied(red, green, blue, u8::MAX);
+		// black carries no color information
+		if linear.r().min(linear.g()).min(linear.b()) <= 0.0 {
+			return Self::default();
+		}
+		let [red, green, blue] = [linear.r(), linear.g(), linear.b()].map(|channel| -channel.log2());
+		let temperature = (red - blue) / 2.0;
+		// the green gain is relative to the average of the red and blue gains
+		let tint = red - temperature - green;
+		Self {
+			temperature: temperature.clamp(-RANGE, RANGE),
+			tint: tint.clamp(-RANGE, RANGE),
+		}
+	}
+
+	/// Frames hold unmultiplied sRGBA bytes, which is what this returns too.
+	fn apply(self, pixel: Color32) -> [u8; 4] {
+		let [red, green, blue, alpha] = pixel.to_array();
+		let linear = Rgba::from_srgba_unmultiplied(red, green, blue, alpha);
+		let [red_gain, green_gain, blue_gain] = self.gains();
+		let corrected = Rgba::from_rgba_premultiplied(
+			linear.r() * red_gain,
+			linear.g() * green_gain,
+			linear.b() * blue_gain,
+			linear.a(),
+		);
+		corrected.to_srgba_unmultiplied()
+	}
+}
+
+/// The average color of the pixels within `RADIUS` of `pixel` in frame `frame_idx` of `image`.
+pub fn sample(image: &Pixels, frame_idx: usize, pixel: Pos2) -> Color32 {
+	const RADIUS: u32 = 2;
+
+	let frame = image.frame(frame_idx);
+
+	let x: u32 = az::saturating_cast(pixel.x.max(0.0));
+	let y: u32 = az::saturating_cast(pixel.y.max(0.0));
+	let xs = x.saturating_sub(RADIUS)..(x + RADIUS + 1).min(image.width);
+	let ys = y.saturating_sub(RADIUS)..(y + RADIUS + 1).min(image.height);
+	let mut sum = [0u32; 3];
+	let mut count = 0;
+	for y in ys {
+		for x in xs.clone() {
+			let idx: usize = az::cast(y * image.width + x);
+			let [red, green, blue, _] = frame[idx].to_array();
+			for (sum, channel) in sum.iter_mut().zip([red, green, blue]) {
+				*sum += u32::from(channel);
+			}
+			count += 1;
+		}
+	}
+	let [red, green, blue] = sum.map(|sum| az::cast(sum / count.max(1)));
+	Color32::from_rgb(red, green, blue)
+}
+
+/// Where to export the corrected image, next to the original.
+pub fn export_path(path: &Path) -> PathBuf {
+	path.with_extension("adjusted.png")
+}
+
+/// Save frame `frame_idx` of `image`, which is at `path`, with the white balance applied.
+/// An earlier export is only replaced if `replace` is set.
+pub fn export(
+	path: &Path,
+	image: &Pixels,
+	frame_idx: usize,
+	white_balance: WhiteBalance,
+	replace: bool,
+) -> Result<(), SaveError> {
+	let target = export_path(path);
+	if !replace && target.exists() {
+		return Err(SaveError::Exists(target));
+	}
+	let bytes = image
+		.frame(frame_idx)
+		.iter()
+		.flat_map(|&pixel| white_balance.apply(pixel))
+		.collect();
+	let corrected = RgbaImage::from_raw(image.width, image.height, bytes)
+		.unwrap(/* frames always have the size of the image */);
+	corrected.save(target)?;
+	Ok(())
+}
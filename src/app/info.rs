@@ -0,0 +1,163 @@
+//! Printing information about images without opening a window, for `--info`, and copying the properties of the current image.
+
+use std::fmt::Write as _;
+use std::path::Path;
+use std::sync::Arc;
+
+use super::image::{self, exif, Image, Info};
+use super::{bit_depth_to_string, checksum, color_type_to_string, format_to_string, rating};
+
+fn print_text(path: &Path, info: &Info) {
+	println!("File: {}", path.display());
+	println!("Format: {}", format_to_string(info.format));
+	println!("Dimensions: {}×{}", info.width, info.height);
+	println!("Frames: {}", info.frames);
+	if let Some(duration) = info.duration {
+		println!("Duration: {duration}");
+	}
+	println!(
+		"File Size: {}",
+		humansize::format_size(info.metadata.file_size, humansize::DECIMAL)
+	);
+	if let Some(mtime) = &info.metadata.mtime {
+		println!("Modified: {mtime}");
+	}
+}
+
+fn print_json(path: &Path, info: &Info) {
+	let json = serde_json::json!({
+		"path": path,
+		"format": format_to_string(info.format),
+		"width": info.width,
+		"height": info.height,
+		"frames": info.frames,
+		"duration_secs": info
+			.duration
+			.map(|duration| std::time::Duration::from(duration).as_secs_f64()),
+		"file_size": info.metadata.file_size,
+		"modified": info.metadata.mtime,
+	});
+	println!("{json}");
+}
+
+/// Print the information of each image, as text or as one line of JSON per image.
+/// Images that can't be read are reported on stderr, and make this return an error once the rest are printed.
+pub fn print(paths: &[Arc<Path>], json: bool) -> Result<(), ()> {
+	if paths.is_empty() {
+		eprintln!("no images given");
+		return Err(());
+	}
+
+	let mut result = Ok(());
+	for (idx, path) in paths.iter().enumerate() {
+		let info = match image::info(path) {
+			Ok(info) => info,
+			Err(error) => {
+				eprintln!("{}: {error}", path.display());
+				result = Err(());
+				continue;
+			}
+		};
+		if json {
+			print_json(path, &info);
+		} else {
+			if idx > 0 {
+				println!();
+			}
+			print_text(path, &info);
+		}
+	}
+	result
+}
+
+/// The properties of an image shown in the sidebar, as pairs of the label and the value, to be copied.
+/// The checksum and rating are left out if they aren't known yet.
+pub fn properties(
+	path: &Path,
+	image: &Image,
+	checksum: Option<&checksum::Status>,
+	rating: Option<rating::Rating>,
+	gps: Option<&exif::Gps>,
+) -> Vec<(&'static str, String)> {
+	let mut properties = vec![
+		("File", path.display().to_string()),
+		("Width", image.width.to_string()),
+		("Height", image.height.to_string()),
+		("Format", format_to_string(image.format).to_owned()),
+		(
+			"Color Type",
+			color_type_to_string(image.color_type).to_owned(),
+		),
+		("Bit Depth", bit_depth_to_string(image.color_type)),
+		("Kind", image.kind().repr().to_owned()),
+	];
+	if image.is_animated() {
+		let frames = image.frames.read();
+		properties.push(("Frames", frames.encoded_len.to_string()));
+		properties.push(("Duration", frames.duration.to_string()));
+		if let Some(fps) = frames.average_fps() {
+			properties.push(("Average FPS", format!("{fps:.1}")));
+		}
+		if let Some(loop_count) = image.loop_count {
+			properties.push(("Loops", loop_count.repr()));
+		}
+	}
+	properties.push((
+		"File Size",
+		humansize::format_size(image.metadata.file_size, humansize::DECIMAL),
+	));
+	if let Some(mtime) = &image.metadata.mtime {
+		properties.push(("Modified", mtime.clone()));
+	}
+	if let Some(checksum) = checksum {
+		let checksum = match checksum {
+			checksum::Status::Unknown => "None".to_owned(),
+			checksum::Status::Verified { algorithm, source } => {
+				format!("Verified ({} from {})", algorithm.repr(), source.display())
+			}
+			checksum::Status::Modified { algorithm, source } => format!(
+				"Modified (does not match the {} from {})",
+				algorithm.repr(),
+				source.display()
+			),
+		};
+		properties.push(("Checksum", checksum));
+	}
+	if let Some(rating) = rating {
+		let favorite = if rating.favorite { ", favorite" } else { "" };
+		properties.push((
+			"Rating",
+			format!("{}/{}{favorite}", rating.stars, rating::MAX_STARS),
+		));
+	}
+	if let Some(gps) = gps {
+		properties.push(("Location", gps.repr()));
+		if let Some(altitude) = gps.altitude {
+			properties.push(("Altitude", format!("{altitude:.0} m")));
+		}
+	}
+	properties
+}
+
+/// One line per property, e.g., `Width: 800`.
+pub fn properties_to_text(properties: &[(&str, String)]) -> String {
+	let mut text = String::new();
+	for (label, value) in properties {
+		writeln!(text, "{label}: {value}").unwrap();
+	}
+	text
+}
+
+/// A JSON object with a key per property, e.g., `"color_type": "RGB"`.
+pub fn properties_to_json(properties: &[(&str, String)]) -> String {
+	let object: serde_json::Map<_, _> = properties
+		.iter()
+		.map(|(label, value)| {
+			(
+				label.to_lowercase().replace(' ', "_"),
+				serde_json::Value::from(value.as_str()),
+			)
+		})
+		.collect();
+	serde_json::to_string_pretty(&object).expect("serializing properties")
+}
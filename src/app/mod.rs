@@ -1,54 +1,123 @@
-use std::path::Path;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::hash::{Hash as _, Hasher as _};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 use std::sync::Arc;
 
-use ::image::ImageFormat;
+use ::image::imageops::FilterType;
+use ::image::{ExtendedColorType, ImageFormat};
 use eframe::CreationContext;
 use egui::{
-	Color32, Context, Frame, Margin, Modifiers, Painter, Rect, Rounding, Vec2, ViewportCommand,
+	Color32, ComboBox, Context, CursorIcon, Frame, Margin, Painter, Rect, Rounding, Vec2,
+	ViewportCommand,
 };
 
 pub use self::image::init_timezone;
-use self::state::actor::{NavigationMode, NextPath, NextPathMode};
+use self::image::white_balance::{self, WhiteBalance};
+use self::image::{data_uri, exif, save_as, FramesStatus, THUMBNAIL_SIZE};
+pub use self::info::print as print_info;
+use self::state::actor::{NavigationMode, NextPath, NextPathMode, SavedDiagnostics};
 use self::state::play::State as PlayState;
 use self::state::State as ImageState;
+pub use self::stdin::Piped;
+pub use self::thumbnailer::run as make_thumbnail;
 use crate::app::next_path::Direction;
-use crate::args::Args;
-use crate::config::Config;
+use crate::args::{self, Args};
+use crate::bindings::Action;
+use crate::config::{Config, SettingsTab};
 use crate::duration::Duration;
-use crate::widgets::ShowColumnsExt as _;
+use crate::widgets::image::View;
+use crate::widgets::{AccessibleExt as _, ShowColumnsExt as _};
 use crate::{config, error, widgets};
 
+#[cfg(feature = "audio")]
+mod audio;
+mod auto_hide;
+mod batch;
+mod checksum;
+mod clipboard;
+mod config_watch;
+#[cfg(feature = "device-import")]
+mod device_import;
+mod events;
+mod folder_stats;
+mod history;
 mod image;
+mod info;
 mod next_path;
+mod open_with;
+mod power;
+mod rating;
+mod shuffle;
 mod state;
+mod stdin;
+mod swipe;
+mod theme;
+mod thumbnailer;
+mod video_export;
+mod workspace;
 
 #[derive(Default, Clone, Copy, Debug)]
 enum SlideshowState {
 	Active {
 		remaining: Duration,
+		/// The time for the current image, including any extra time it was given.
+		total: Duration,
 	},
 	#[default]
 	Inactive,
+	/// Stopped at the end, which is shown until it is dismissed.
+	Finished,
+}
+
+/// How much of an interval of `total` is left, from 1 to 0. An interval of zero is always over.
+fn remaining_fraction(remaining: Duration, total: Duration) -> f32 {
+	if total.is_over() {
+		0.0
+	} else {
+		remaining.as_secs_f32() / total.as_secs_f32()
+	}
 }
 
 impl SlideshowState {
 	fn is_active(self) -> bool {
 		match self {
 			Self::Active { .. } => true,
-			Self::Inactive => false,
+			Self::Inactive | Self::Finished => false,
 		}
 	}
 
 	fn start(&mut self, config: &Config) {
 		*self = Self::Active {
 			remaining: config.slideshow.interval,
+			total: config.slideshow.interval,
 		};
 	}
 
+	/// How far through the current interval the slideshow is, from 0 to 1.
+	fn progress(self) -> Option<f32> {
+		match self {
+			Self::Active { remaining, total } => Some(1.0 - remaining_fraction(remaining, total)),
+			Self::Inactive | Self::Finished => None,
+		}
+	}
+
 	fn advance(&mut self, secs: Duration) -> bool {
 		match self {
-			Self::Active { remaining } => remaining.advance(secs),
-			Self::Inactive => false,
+			Self::Active { remaining, .. } => remaining.advance(secs),
+			Self::Inactive | Self::Finished => false,
+		}
+	}
+
+	/// Give the current image `extra` time without changing the interval for the others.
+	fn extend(&mut self, extra: Duration) {
+		match self {
+			Self::Active { remaining, total } => {
+				*remaining = remaining.saturating_add(extra);
+				*total = total.saturating_add(extra);
+			}
+			Self::Inactive | Self::Finished => {}
 		}
 	}
 
@@ -73,7 +142,7 @@ impl SlideshowState {
 		let icon = if slideshow_active { "⏸" } else { "▶" };
 		let changed = ui
 			.toggle_value(&mut slideshow_active, icon)
-			.on_hover_text("Toggle slideshow (s)")
+			.on_hover_label("Toggle slideshow (s)")
 			.changed();
 
 		if changed {
@@ -83,49 +152,322 @@ impl SlideshowState {
 
 	fn reset(&mut self, config: &Config) {
 		match self {
-			Self::Active { remaining } => {
+			Self::Active { remaining, total } => {
 				*remaining = config.slideshow.interval;
+				*total = config.slideshow.interval;
 			}
-			Self::Inactive => {}
+			Self::Inactive | Self::Finished => {}
 		}
 	}
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum SidebarTab {
+	#[default]
+	Properties,
+	RawMetadata,
+	Folder,
+	Adjustments,
+	Marked,
+}
+
+/// What dragging over the image does, other than selecting with Shift.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum DragMode {
+	#[default]
+	Pan,
+	Measure,
+}
+
+/// How many events are kept for the internal state window.
+const EVENT_LOG_SIZE: usize = 32;
+
 pub struct App {
 	config: Config,
 	image_state: ImageState,
-	fullscreen: bool,
 	settings_open: bool,
+	settings_tab: SettingsTab,
 	internal_open: bool,
+	history_open: bool,
+	drag_mode: DragMode,
 	asking_to_delete: Option<Arc<Path>>,
+	/// The range of frames (inclusive, 1-based) to export, if the sprite sheet export window is open.
+	sprite_sheet_frames: Option<(usize, usize)>,
+	/// The range of frames (inclusive, 1-based) to export, if the animation export window is open.
+	animation_frames: Option<(usize, usize)>,
 	slideshow: SlideshowState,
+	sidebar_tab: SidebarTab,
+	/// The index of the chunk shown in the raw metadata tab.
+	selected_chunk: usize,
+	folder_stats: folder_stats::FolderStats,
+	config_watch: config_watch::Watcher,
+	/// Scrolling that hasn't added up to moving to another image yet.
+	scroll_navigation: f32,
+	swipe: swipe::Swipe,
+	auto_hide: auto_hide::AutoHide,
+	events: events::Bus,
+	/// The most recent events, newest last, for the internal state window.
+	event_log: Rc<RefCell<VecDeque<events::Event>>>,
+	/// The name typed into the workspaces menu.
+	workspace_name: String,
+	/// The program typed into the "Open with" menu of the error screen.
+	open_with: String,
+	/// The image that was piped in, if any.
+	piped: Option<Piped>,
+	/// Set if the Save As window is open.
+	save_as: Option<SaveAsDialog>,
+	batch_settings: BatchSettings,
+	/// The configuration that command-line flags replaced, to put back before saving it.
+	overridden: args::Overridden,
+	power: power::Monitor,
+	#[cfg(feature = "audio")]
+	audio: audio::Player,
+	/// Set if the video export window is open.
+	video_export: Option<VideoExport>,
+	/// The title of the window as last set, to only set it when it changes.
+	title: String,
+	/// Set if the device import window is open.
+	#[cfg(feature = "device-import")]
+	device_import: Option<DeviceImport>,
+}
+
+/// The settings of the Save As window.
+struct SaveAsDialog {
+	target: String,
+	/// `None` keeps the format of the image.
+	format: Option<ImageFormat>,
+	resize: save_as::Resize,
+	filter: FilterType,
+	strip_metadata: bool,
+}
+
+impl SaveAsDialog {
+	fn new(path: &Path, piped: Option<&Piped>) -> Self {
+		let target = match piped {
+			// the piped image is in a temporary folder
+			Some(piped) if *piped.path == *path => piped.default_target(),
+			_ => save_as::default_target(path),
+		};
+		Self {
+			target: target.display().to_string(),
+			format: None,
+			resize: save_as::Resize::Original,
+			filter: FilterType::CatmullRom,
+			strip_metadata: false,
+		}
+	}
+
+	fn options(&self, frame_idx: usize) -> save_as::Options {
+		save_as::Options {
+			target: PathBuf::from(self.target.trim()),
+			format: self.format,
+			resize: self.resize,
+			filter: self.filter,
+			strip_metadata: self.strip_metadata,
+			frame_idx,
+		}
+	}
+
+	fn format_ui(&mut self, ui: &mut egui::Ui, original: ImageFormat) {
+		let repr = |format: Option<ImageFormat>| match format {
+			Some(format) => format_to_string(format).to_owned(),
+			None => format!("Original ({})", format_to_string(original)),
+		};
+		let previous = self.format;
+		ComboBox::from_id_source("save-as-format-combo")
+			.selected_text(repr(self.format))
+			.show_ui(ui, |ui| {
+				ui.selectable_value(&mut self.format, None, repr(None));
+				for &format in batch::CONVERT_FORMATS {
+					ui.selectable_value(&mut self.format, Some(format), repr(Some(format)));
+				}
+			});
+		// keep the extension in line with the format
+		if self.format != previous {
+			let format = self.format.unwrap_or(original);
+			let target = Path::new(self.target.trim()).with_extension(format.extensions_str()[0]);
+			self.target = target.display().to_string();
+		}
+	}
+
+	fn resize_ui(&mut self, ui: &mut egui::Ui) {
+		ui.horizontal(|ui| {
+			ComboBox::from_id_source("save-as-resize-combo")
+				.selected_text(self.resize.repr())
+				.show_ui(ui, |ui| {
+					for &resize in save_as::Resize::VARIANTS {
+						let selected = std::mem::discriminant(&self.resize) == std::mem::discriminant(&resize);
+						if ui.selectable_label(selected, resize.repr()).clicked() && !selected {
+							self.resize = resize;
+						}
+					}
+				});
+			match &mut self.resize {
+				save_as::Resize::Original => {}
+				save_as::Resize::Percent(percent) => {
+					ui.add(
+						egui::DragValue::new(percent)
+							.clamp_range(1.0..=1000.0)
+							.suffix("%"),
+					);
+				}
+				save_as::Resize::Fit { width, height } => {
+					ui.add(egui::DragValue::new(width).clamp_range(1..=u32::MAX));
+					ui.label("×");
+					ui.add(egui::DragValue::new(height).clamp_range(1..=u32::MAX));
+				}
+			}
+		});
+	}
+}
+
+struct VideoExport {
+	output: String,
+	/// Set once the slideshow order was requested, to start exporting when it arrives.
+	starting: bool,
+	export: Option<video_export::Export>,
+}
+
+#[cfg(feature = "device-import")]
+struct DeviceImport {
+	selected: rustc_hash::FxHashSet<PathBuf>,
+	target: String,
 }
 
 impl App {
 	#[allow(clippy::needless_pass_by_value)] // consistency
-	pub fn new(Args { paths }: Args, config: Config, cc: &CreationContext<'_>) -> Self {
-		let navigation_mode = match paths.len() {
-			0 => NavigationMode::Empty,
-			1 => NavigationMode::InDirectory {
-				current: paths.into_iter().next().unwrap(),
-			},
-			_ => NavigationMode::specified(paths),
+	pub fn new(
+		args: Args,
+		mut config: Config,
+		piped: Option<Piped>,
+		cc: &CreationContext<'_>,
+	) -> Self {
+		let overridden = args.override_config(&mut config);
+		let Args {
+			paths,
+			slideshow: start_slideshow,
+			..
+		} = args;
+
+		let mut slideshow = SlideshowState::default();
+		slideshow.set_active(start_slideshow, &config);
+
+		let sort = sort_order(&config);
+		let filter = navigation_filter(&config);
+		let navigation_mode = match (&piped, paths.len()) {
+			// the image has no directory to move around in
+			(Some(piped), _) => NavigationMode::specified(vec![Arc::clone(&piped.path)]),
+			(None, 0) => NavigationMode::Empty,
+			(None, 1) => {
+				let path = paths.into_iter().next().unwrap();
+				if path.is_dir() {
+					NavigationMode::directory(path, sort, &filter)
+				} else {
+					NavigationMode::InDirectory { current: path }
+				}
+			}
+			(None, _) => NavigationMode::specified(paths),
 		};
 
-		let cache_size = config.cache_size;
+		let cache_limits = cache_limits(&config);
+		// the scale is set in the settings instead, and some of the keys of egui's zooming are bound to other actions
+		cc.egui_ctx
+			.options_mut(|options| options.zoom_with_keyboard = false);
+		update_ui_scale(&cc.egui_ctx, config.ui_scale);
+		let config_watch = config_watch::Watcher::spawn(cc.egui_ctx.clone(), config.path());
+
+		let mut events = events::Bus::default();
+		let event_log = Rc::new(RefCell::new(VecDeque::new()));
+		events.subscribe({
+			let event_log = Rc::clone(&event_log);
+			move |event| {
+				let mut event_log = event_log.borrow_mut();
+				if event_log.len() == EVENT_LOG_SIZE {
+					event_log.pop_front();
+				}
+				event_log.push_back(event.clone());
+			}
+		});
+		// so that `--config` moves them along with the configuration
+		let workspace_dir = workspace::dir(&config.path());
+		let folder_stats = folder_stats::FolderStats::spawn(cc.egui_ctx.clone());
+		events.subscribe({
+			let notifier = folder_stats.notifier();
+			move |event| {
+				// a file that is rewritten in place doesn't change the modification time of the folder
+				if let events::Event::FileDeleted(path) | events::Event::FileWritten(path) = event {
+					notifier.changed(path);
+				}
+			}
+		});
 
 		Self {
 			config,
-			image_state: ImageState::new(cc.egui_ctx.clone(), cache_size, navigation_mode),
-			fullscreen: false,
+			image_state: ImageState::new(
+				cc.egui_ctx.clone(),
+				cache_limits,
+				navigation_mode,
+				sort,
+				filter,
+				workspace_dir,
+			),
 			settings_open: false,
+			settings_tab: SettingsTab::default(),
 			internal_open: false,
+			history_open: false,
+			drag_mode: DragMode::default(),
 			asking_to_delete: None,
-			slideshow: SlideshowState::default(),
+			sprite_sheet_frames: None,
+			animation_frames: None,
+			slideshow,
+			sidebar_tab: SidebarTab::default(),
+			selected_chunk: 0,
+			folder_stats,
+			config_watch,
+			scroll_navigation: 0.0,
+			swipe: swipe::Swipe::default(),
+			auto_hide: auto_hide::AutoHide::default(),
+			events,
+			event_log,
+			workspace_name: String::new(),
+			open_with: String::new(),
+			piped,
+			save_as: None,
+			batch_settings: BatchSettings::default(),
+			overridden,
+			power: power::Monitor::default(),
+			#[cfg(feature = "audio")]
+			audio: audio::Player::spawn(),
+			video_export: None,
+			title: String::new(),
+			#[cfg(feature = "device-import")]
+			device_import: None,
 		}
 	}
 }
 
+fn cache_limits(config: &Config) -> state::actor::CacheLimits {
+	state::actor::CacheLimits {
+		size: config.cache_size,
+		gpu_budget: config.gpu_budget,
+		keep_pixels: config.keep_evicted_pixels,
+	}
+}
+
+/// Which images to move between in a directory, by name and by rating.
+fn sort_order(config: &Config) -> next_path::Order {
+	next_path::Order {
+		sort: config.sort,
+		names: config.name_order,
+	}
+}
+
+fn navigation_filter(config: &Config) -> next_path::Filter {
+	next_path::Filter::parse(&config.filter)
+		.with_rating(config.rating_filter)
+		.with_hidden_files(config.hidden_files)
+}
+
 fn format_to_string(format: ImageFormat) -> &'static str {
 	match format {
 		ImageFormat::Png => "PNG",
@@ -146,35 +488,76 @@ fn format_to_string(format: ImageFormat) -> &'static str {
 	}
 }
 
+/// The channels of `color_type`, e.g., "RGB with alpha".
+fn color_type_to_string(color_type: ExtendedColorType) -> &'static str {
+	match color_type {
+		ExtendedColorType::A8 => "Alpha",
+		ExtendedColorType::L1
+		| ExtendedColorType::L2
+		| ExtendedColorType::L4
+		| ExtendedColorType::L8
+		| ExtendedColorType::L16 => "Grayscale",
+		ExtendedColorType::La1
+		| ExtendedColorType::La2
+		| ExtendedColorType::La4
+		| ExtendedColorType::La8
+		| ExtendedColorType::La16 => "Grayscale with alpha",
+		ExtendedColorType::Rgb1
+		| ExtendedColorType::Rgb2
+		| ExtendedColorType::Rgb4
+		| ExtendedColorType::Rgb8
+		| ExtendedColorType::Rgb16
+		| ExtendedColorType::Rgb32F => "RGB",
+		ExtendedColorType::Rgba1
+		| ExtendedColorType::Rgba2
+		| ExtendedColorType::Rgba4
+		| ExtendedColorType::Rgba8
+		| ExtendedColorType::Rgba16
+		| ExtendedColorType::Rgba32F => "RGB with alpha",
+		ExtendedColorType::Bgr8 => "BGR",
+		ExtendedColorType::Bgra8 => "BGR with alpha",
+		ExtendedColorType::Cmyk8 => "CMYK",
+		_ => "unknown",
+	}
+}
+
+/// The size of each channel of `color_type`, e.g., "16 bits per channel".
+fn bit_depth_to_string(color_type: ExtendedColorType) -> String {
+	let bits = color_type.bits_per_pixel() / u16::from(color_type.channel_count());
+	match color_type {
+		// e.g., indices into a palette
+		ExtendedColorType::Unknown(bits) => format!("{bits} bits per pixel"),
+		ExtendedColorType::Rgb32F | ExtendedColorType::Rgba32F => {
+			format!("{bits}-bit float per channel")
+		}
+		_ if bits == 1 => "1 bit per channel".to_owned(),
+		_ => format!("{bits} bits per channel"),
+	}
+}
+
 impl config::Background {
 	fn draw(self, painter: &Painter, rect: Rect) {
 		fn draw_solid(painter: &Painter, rect: Rect, color: Color32) {
 			painter.rect_filled(rect, Rounding::ZERO, color);
 		}
 
-		fn draw_checker(painter: &Painter, rect: Rect, color1: Color32, color2: Color32) {
-			const CHECKER_SIZE: u32 = 20;
-			#[allow(clippy::cast_precision_loss)]
-			const CHECKER_VEC: Vec2 = Vec2::splat(CHECKER_SIZE as f32);
-			const STEP: usize = (CHECKER_SIZE * 2) as usize;
+		fn draw_checker(painter: &Painter, rect: Rect, size: f32, color1: Color32, color2: Color32) {
+			let size = size.max(config::Checker::MIN_SIZE);
+			let square = Vec2::splat(size);
 
 			painter.rect_filled(rect, Rounding::ZERO, color1);
 
 			let base_pos = rect.left_top();
 			// only add rects for color2
 			let painter = painter.with_clip_rect(rect);
-			// draw two rows at a time; one offset by CHECKER_SIZE
-			for y in (0..az::cast(rect.height())).step_by(STEP) {
-				for x in (0..az::cast(rect.width())).step_by(STEP) {
-					painter.rect_filled(
-						Rect::from_min_size(base_pos + Vec2::new(az::cast(x), az::cast(y)), CHECKER_VEC),
-						Rounding::ZERO,
-						color2,
-					);
+			let columns: u32 = az::saturating_cast((rect.width() / size).ceil());
+			let rows: u32 = az::saturating_cast((rect.height() / size).ceil());
+			for y in 0..rows {
+				for x in (y % 2..columns).step_by(2) {
 					painter.rect_filled(
 						Rect::from_min_size(
-							base_pos + Vec2::new(az::cast(x), az::cast(y)) + CHECKER_VEC,
-							CHECKER_VEC,
+							base_pos + Vec2::new(az::cast(x), az::cast(y)) * size,
+							square,
 						),
 						Rounding::ZERO,
 						color2,
@@ -183,44 +566,91 @@ impl config::Background {
 			}
 		}
 
-		let dark = match self.color {
-			config::BackgroundColor::Default => painter.ctx().style().visuals.dark_mode,
-			config::BackgroundColor::Light => false,
-			config::BackgroundColor::Dark => true,
+		let (primary_color, secondary_color) = self.colors(painter.ctx().style().visuals.dark_mode);
+		let primary_color = primary_color.gamma_multiply(self.opacity);
+		let secondary_color = secondary_color.gamma_multiply(self.opacity);
+
+		if self.checkered {
+			draw_checker(
+				painter,
+				rect,
+				self.checker.size,
+				primary_color,
+				secondary_color,
+			);
+		} else {
+			draw_solid(painter, rect, primary_color);
+		}
+	}
+
+	/// The color of the background, and the other color of the checker pattern.
+	fn colors(self, dark_mode: bool) -> (Color32, Color32) {
+		const CONTRAST: u8 = 12;
+
+		let base = match self.color {
+			config::BackgroundColor::Default if dark_mode => Color32::from_gray(27),
+			config::BackgroundColor::Dark => Color32::from_gray(27),
+			config::BackgroundColor::Default | config::BackgroundColor::Light => Color32::from_gray(248),
+			config::BackgroundColor::Custom => self.custom_color,
 		};
 
-		let (primary_color, secondary_color) = {
-			const CONTRAST: u8 = 12;
-			if dark {
-				const BASE: u8 = 27;
-				(
-					Color32::from_gray(BASE),
-					Color32::from_gray(BASE + CONTRAST),
-				)
+		if let (true, Some([primary, secondary])) = (self.checkered, self.checker.colors) {
+			return (primary, secondary);
+		}
+
+		// a lighter shade on dark backgrounds and a darker one on light backgrounds, as with the defaults
+		let shade = |channel: u8| {
+			if egui::Rgba::from(base).intensity() < 0.5 {
+				channel.saturating_add(CONTRAST)
 			} else {
-				const BASE: u8 = 248;
-				(
-					Color32::from_gray(BASE),
-					Color32::from_gray(BASE - CONTRAST),
-				)
+				channel.saturating_sub(CONTRAST)
 			}
 		};
+		let secondary = Color32::from_rgb(shade(base.r()), shade(base.g()), shade(base.b()));
+		(base, secondary)
+	}
+}
 
-		if self.checkered {
-			draw_checker(painter, rect, primary_color, secondary_color);
-		} else {
-			draw_solid(painter, rect, primary_color);
-		}
+/// Without a title bar, the empty part of the toolbar moves the window instead, and maximizes it when double-clicked.
+/// Must be called before the buttons are added, so that they take priority.
+fn drag_window(ui: &egui::Ui) {
+	let response = ui.interact(
+		ui.max_rect(),
+		ui.id().with("drag-window"),
+		egui::Sense::click_and_drag(),
+	);
+	if response.drag_started_by(egui::PointerButton::Primary) {
+		ui.ctx().send_viewport_cmd(ViewportCommand::StartDrag);
+	}
+	if response.double_clicked() {
+		let maximized = ui.input(|input| input.viewport().maximized.unwrap_or(false));
+		ui.ctx()
+			.send_viewport_cmd(ViewportCommand::Maximized(!maximized));
+	}
+}
+
+/// Show or hide the title bar and borders of the window when the setting changes. They are set when the window is opened by the native options.
+fn update_decorations(ctx: &Context, borderless: bool) {
+	let id = egui::Id::new("window-borderless");
+	let applied =
+		ctx.data_mut(|data| std::mem::replace(data.get_temp_mut_or(id, borderless), borderless));
+	if applied != borderless {
+		ctx.send_viewport_cmd(ViewportCommand::Decorations(!borderless));
 	}
 }
 
+fn toggle_fullscreen(ctx: &Context) {
+	let fullscreen = ctx.input(|input| input.viewport().fullscreen.unwrap_or(false));
+	ctx.send_viewport_cmd(ViewportCommand::Fullscreen(!fullscreen));
+}
+
 fn show_fullscreen_toggle(ui: &mut egui::Ui) {
 	let Some(mut fullscreen) = ui.input(|input| input.viewport().fullscreen) else {
 		return;
 	};
 	if ui
 		.toggle_value(&mut fullscreen, "⛶")
-		.on_hover_text("Toggle fullscreen (f)")
+		.on_hover_label("Toggle fullscreen (f)")
 		.changed()
 	{
 		let cmd = ViewportCommand::Fullscreen(!fullscreen);
@@ -228,190 +658,2294 @@ fn show_fullscreen_toggle(ui: &mut egui::Ui) {
 	}
 }
 
-#[derive(Debug, Clone, Copy)]
-enum MoveMode {
-	IgnoreSlideshow,
-	RespectSlideshow,
+fn show_sprite_sheet_cell(
+	ui: &mut egui::Ui,
+	image: &image::Image,
+	sprite_sheet: &mut state::play::SpriteSheet,
+	zoom: &mut widgets::image::Zoom,
+	view: &mut Option<View>,
+	scroll_zoom: widgets::image::ScrollZoom,
+	tint: Color32,
+) -> egui::Response {
+	let image_size = [image.width, image.height];
+	let frames = image.frames.read();
+	let cell = sprite_sheet.cell_rect(sprite_sheet.current_frame.idx, image_size);
+	let response = ui.add(
+		widgets::Image::for_texture(&frames.list[0].0)
+			.region(cell)
+			.clickable(true)
+			.zoom(*zoom)
+			.tint(tint),
+	);
+	if response.clicked() {
+		sprite_sheet.playing ^= true;
+	}
+	if sprite_sheet.playing {
+		let elapsed = ui.input(|input| input.unstable_dt);
+		sprite_sheet.advance(Duration::new_secs_f32_saturating(elapsed), image_size);
+		power::request_animation_repaint(ui.ctx(), sprite_sheet.current_frame.remaining.into());
+	}
+	zoom.update_from_response(&response, cell.size(), scroll_zoom);
+	*view = Some(View::from_response(&response, cell.size()));
+	response
 }
 
-impl App {
-	fn move_in(&mut self, direction: Direction, mode: MoveMode) {
-		let respect_slideshow = match mode {
-			MoveMode::IgnoreSlideshow => false,
-			MoveMode::RespectSlideshow => true,
-		};
-		let mode = if respect_slideshow && self.slideshow.is_active() && self.config.slideshow.shuffle {
-			NextPathMode::Random
-		} else {
-			NextPathMode::Simple
-		};
-		let direction = NextPath { direction, mode };
-		self.image_state.next_path(direction);
-		self.slideshow.reset(&self.config);
+/// egui's own animations, such as sliding panels and opening collapsing sections, are turned off by making them instant.
+fn update_animation_time(ctx: &Context, reduce_motion: bool) {
+	let animation_time = if reduce_motion {
+		0.0
+	} else {
+		egui::Style::default().animation_time
+	};
+	if (ctx.style().animation_time - animation_time).abs() > f32::EPSILON {
+		ctx.style_mut(|style| style.animation_time = animation_time);
 	}
 }
 
-impl App {
-	fn show_actions_left(&mut self, ui: &mut egui::Ui) {
-		if let Some(current_path) = self.image_state.current_path() {
-			let response =
-				ui.add(egui::Label::new(current_path.display().to_string()).sense(egui::Sense::click()));
-			let clicked = response.clicked();
-			let show_copied = ui.ctx().animate_bool_with_time(
-				response.id,
-				clicked,
-				ui.ctx().style().animation_time * 2.0,
-			) > 0.0;
-			response.on_hover_text(if show_copied {
-				"Copied!"
-			} else {
-				"Click to copy"
-			});
-			if clicked {
-				let copied_text = current_path.display().to_string();
-				ui.output_mut(|output| output.copied_text = copied_text);
-			}
-		}
+fn update_ui_scale(ctx: &Context, scale: f32) {
+	let scale = scale.clamp(
+		*config::UI_SCALE_RANGE.start(),
+		*config::UI_SCALE_RANGE.end(),
+	);
+	if (ctx.zoom_factor() - scale).abs() > f32::EPSILON {
+		ctx.set_zoom_factor(scale);
 	}
+}
 
-	fn show_actions_right(&mut self, ui: &mut egui::Ui) {
-		let mut to_delete = None;
+/// The zoom to show the image at `path` with, which follows the Ken Burns effect on static images during slideshows unless the image was zoomed.
+fn slideshow_zoom(
+	ctx: &Context,
+	path: &Path,
+	zoom: widgets::image::Zoom,
+	single: bool,
+	config: &Config,
+	slideshow: SlideshowState,
+) -> widgets::image::Zoom {
+	let ken_burns = config.slideshow.ken_burns;
+	match slideshow.progress() {
+		Some(t)
+			if single && ken_burns.enabled && !config.accessibility.reduce_motion && !zoom.modified() =>
+		{
+			ctx.request_repaint();
+			ken_burns_zoom(path, ken_burns, t)
+		}
+		_ => zoom,
+	}
+}
 
-		ui.toggle_value(&mut self.settings_open, "⛭")
-			.on_hover_text("Toggle settings window");
+/// The zoom for a Ken Burns effect `t` (from 0 to 1) of the way through showing the image at `path`.
+fn ken_burns_zoom(path: &Path, config: config::KenBurns, t: f32) -> widgets::image::Zoom {
+	// vary the movement between images, but keep it the same for each image
+	let hash = {
+		let mut hasher = rustc_hash::FxHasher::default();
+		path.hash(&mut hasher);
+		hasher.finish()
+	};
+	let angle = az::cast::<_, f32>(hash % 360).to_radians();
+	let zoom_in = (hash >> 32) & 1 == 0;
+
+	let t = if zoom_in { t } else { 1.0 - t };
+	// ease in and out
+	let t = t * t * (3.0 - 2.0 * t);
+	let factor = 1.0 + (config.max_zoom() - 1.0) * t;
+	widgets::image::Zoom::ken_burns(factor, Vec2::angled(angle))
+}
 
-		show_fullscreen_toggle(ui);
+fn show_zoom_controls(ui: &mut egui::Ui, inner: &mut state::OpenImageInner) {
+	if ui
+		.add_enabled(inner.zoom.modified(), egui::Button::new("="))
+		.on_hover_label("Fit to window (0)")
+		.clicked()
+	{
+		inner.zoom = widgets::image::Zoom::fit();
+	}
+	if ui.button("⬌").on_hover_label("Fill window (w)").clicked() {
+		inner.zoom = widgets::image::Zoom::fill();
+	}
+	if ui.button("1:1").on_hover_label("Actual size (1)").clicked() {
+		inner.zoom = widgets::image::Zoom::actual_size();
+	}
+	if let Some(view) = inner.view {
+		let mut percent = inner.zoom.scale(view) * 100.0;
+		let response = ui
+			.add(
+				egui::DragValue::new(&mut percent)
+					.suffix("%")
+					.max_decimals(0),
+			)
+			.on_hover_text("Zoom, relative to the actual size (click to type)");
+		if response.changed() && percent > 0.0 {
+			inner.zoom.set_scale(view, percent / 100.0);
+		}
+	}
 
-		self.config.light_dark_toggle_button(ui);
+	if let (Some(content), None) = (inner.image.content, &inner.sprite_sheet) {
+		if ui
+			.button("▣")
+			.on_hover_label("Fit to content, ignoring borders (b)")
+			.clicked()
+		{
+			inner.zoom = widgets::image::Zoom::fit_to(content);
+		}
+	}
+}
 
-		if let Some(current) = &mut self.image_state.current {
-			let delete_button = ui.button("🗑");
-			to_delete = delete_button.clicked().then(|| current.path.clone());
-			delete_button.on_hover_text("Delete File");
+/// The pixel that was clicked to pick the gray point, if any. Shows a crosshair over the image while picking.
+fn gray_point_clicked(
+	response: &egui::Response,
+	mapping: widgets::selection::PixelMapping,
+) -> Option<egui::Pos2> {
+	let response = response.clone().on_hover_cursor(CursorIcon::Crosshair);
+	let pos = response
+		.interact_pointer_pos()
+		.filter(|_| response.clicked())?;
+	Some(mapping.to_pixel(pos))
+}
 
-			self.slideshow.show_toggle(ui, &self.config);
+/// What to do about an image that failed to load, as chosen on the error screen.
+#[derive(Debug, Clone, Copy)]
+enum LoadErrorAction {
+	Retry,
+	OpenWith,
+	SaveDiagnostics,
+}
 
-			if let Ok(inner) = &mut current.inner {
-				if ui
-					.add_enabled(inner.zoom.modified(), egui::Button::new("="))
-					.on_hover_text("Reset zoom")
-					.clicked()
-				{
-					inner.zoom = widgets::image::Zoom::default();
+/// `program` is the program typed into the "Open with" menu, and `bundle` is the last diagnostic bundle that was saved.
+fn show_load_error(
+	ui: &mut egui::Ui,
+	path: &Path,
+	error: &::image::ImageError,
+	program: &mut String,
+	bundle: Option<&SavedDiagnostics>,
+) -> Option<LoadErrorAction> {
+	let mut action = None;
+	ui.heading(format!("error: {error}"));
+	ui.horizontal(|ui| {
+		if ui
+			.button("Retry")
+			.on_hover_text("Load the file again, e.g., if it was still being copied")
+			.clicked()
+		{
+			action = Some(LoadErrorAction::Retry);
+		}
+		ui.menu_button("Open with…", |ui| {
+			ui.horizontal(|ui| {
+				let response = ui.add(
+					egui::TextEdit::singleline(program)
+						.hint_text("Default application")
+						.desired_width(160.0),
+				);
+				let entered =
+					response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter));
+				if ui.button("Open").clicked() || entered {
+					action = Some(LoadErrorAction::OpenWith);
+					ui.close_menu();
 				}
+			});
+		})
+		.response
+		.on_hover_text("Open the file in another program, or the default application if none is given");
+		if ui
+			.button("Save diagnostic bundle")
+			.on_hover_text(format!(
+				"Save the error and the start of the file to a zip file in {} to attach to a bug report",
+				error::diagnostic::bundle_dir().display(),
+			))
+			.clicked()
+		{
+			action = Some(LoadErrorAction::SaveDiagnostics);
+		}
+	});
+	if let Some(bundle) = bundle.filter(|bundle| *bundle.path == *path) {
+		ui.label(format!(
+			"Saved the diagnostic bundle to {}",
+			bundle.bundle.display()
+		));
+	}
+	action
+}
 
-				ui.toggle_value(&mut self.config.show_sidebar, "ℹ")
-					.on_hover_text("Toggle sidebar");
+fn show_sprite_sheet_toggle(ui: &mut egui::Ui, inner: &mut state::OpenImageInner) {
+	let mut viewing = inner.sprite_sheet.is_some();
+	if ui
+		.toggle_value(&mut viewing, "▤")
+		.on_hover_label("View as sprite sheet")
+		.changed()
+	{
+		inner.sprite_sheet = viewing.then(state::play::SpriteSheet::new);
+		// they are over the whole image, not the cells
+		inner.selection = None;
+		inner.measurement = None;
+	}
+}
 
-				if inner.image.is_animated() {
-					ui.toggle_value(&mut self.config.show_frames, "🎞")
-						.on_hover_text("Toggle frames");
-				}
-			}
-		}
+/// The toggles for the loupe and measuring, and the last measurement, which is copied when clicked.
+fn show_inspection_tools(
+	ui: &mut egui::Ui,
+	loupe: &mut bool,
+	drag_mode: &mut DragMode,
+	measurement: Option<widgets::Measurement>,
+) {
+	ui.toggle_value(loupe, "🔍")
+		.on_hover_label("Toggle loupe (o)");
+	let mut measuring = *drag_mode == DragMode::Measure;
+	if ui
+		.toggle_value(&mut measuring, "📏")
+		.on_hover_label("Measure by dragging over the image (r)")
+		.changed()
+	{
+		*drag_mode = if measuring {
+			DragMode::Measure
+		} else {
+			DragMode::Pan
+		};
+	}
+	let Some(measurement) = measurement.filter(|_| measuring) else {
+		return;
+	};
+	let text = measurement.repr();
+	let response = ui
+		.add(egui::Label::new(&text).sense(egui::Sense::click()))
+		.on_hover_text("Click to copy");
+	if response.clicked() {
+		ui.output_mut(|output| output.copied_text = text);
+	}
+}
 
-		if self.image_state.waiting() {
-			ui.spinner().on_hover_text("Loading");
-		}
+/// Paint the pixel grid, the selection, and the measurement over the image.
+fn paint_overlays(
+	ui: &egui::Ui,
+	config: &Config,
+	mapping: widgets::selection::PixelMapping,
+	selection: Option<widgets::Selection>,
+	measurement: Option<widgets::Measurement>,
+) {
+	let pixel_size = mapping.scale().min_elem() * ui.ctx().pixels_per_point();
+	if config.pixel_grid.visible_at(pixel_size) {
+		widgets::pixel_grid::paint(ui.painter(), mapping, config.pixel_grid.show_coordinates);
+	}
+	if let Some(selection) = selection {
+		selection.paint(ui.painter(), mapping);
+	}
+	if let Some(measurement) = measurement {
+		measurement.paint(ui.painter(), mapping);
+	}
+}
 
-		if let SlideshowState::Active { remaining } = self.slideshow {
-			ui.label(format!("\u{2398} {} s", remaining.ceil_secs()));
-			ui.ctx()
-				.request_repaint_after(std::time::Duration::from_secs(1));
-		}
+/// Paint the loupe over the pixels under the cursor, if it is enabled and the cursor is over the image.
+fn paint_loupe(
+	ui: &egui::Ui,
+	response: &egui::Response,
+	image: &image::Image,
+	play_state: &PlayState,
+	mapping: widgets::selection::PixelMapping,
+	loupe: &config::Loupe,
+	tint: Color32,
+) {
+	let Some(cursor) = response.hover_pos().filter(|_| loupe.enabled) else {
+		return;
+	};
+	let frame_idx = match play_state {
+		PlayState::Single => 0,
+		PlayState::Animated { current_frame, .. } => current_frame.idx,
+	};
+	let frames = image.frames.read();
+	let Some((texture, _)) = frames.list.get(frame_idx) else {
+		return;
+	};
+	let options = widgets::loupe::Options {
+		size: loupe.size,
+		// the magnification is relative to the actual size, where one image pixel is one screen pixel
+		scale: loupe.magnification / ui.ctx().pixels_per_point(),
+		circle: loupe.shape == config::LoupeShape::Circle,
+		tint,
+	};
+	widgets::loupe::paint(ui.painter(), texture, mapping, cursor, options);
+}
 
-		if let Some(to_delete) = to_delete {
-			self.delete_file(ui, to_delete);
+/// Show the current frame of a single or animated image, playing the animation if needed.
+fn show_current_frame(
+	ui: &mut egui::Ui,
+	image: &image::Image,
+	play_state: &mut PlayState,
+	zoom: widgets::image::Zoom,
+	tint: Color32,
+) -> (egui::Response, Rect) {
+	match play_state {
+		PlayState::Single => {
+			let frames = image.frames.read();
+			// clickable so that it can be double-clicked
+			widgets::Image::for_texture(&frames.list[0].0)
+				.clickable(true)
+				.zoom(zoom)
+				.tint(tint)
+				.show(ui)
+		}
+		PlayState::Animated {
+			current_frame,
+			playing,
+		} => {
+			let frames = image.frames.read();
+			let (current_texture, _) = &frames.list[current_frame.idx];
+			let (response, image_rect) = widgets::Image::for_texture(current_texture)
+				.clickable(true)
+				.zoom(zoom)
+				.tint(tint)
+				.show(ui);
+			if response.clicked() {
+				*playing = !*playing;
+			}
+			if *playing {
+				let elapsed = ui.input(|input| input.unstable_dt);
+				current_frame.advance(
+					Duration::new_secs_f32_saturating(elapsed),
+					frames.list.len(),
+					matches!(frames.status, FramesStatus::Loading),
+					|idx| frames.list[idx].1,
+				);
+				power::request_animation_repaint(ui.ctx(), current_frame.remaining.into());
+			}
+			(response, image_rect)
 		}
 	}
+}
 
-	fn delete_file(&mut self, ui: &egui::Ui, path: Arc<Path>) {
-		if ui.input(|input| input.modifiers.shift) {
-			self.asking_to_delete = None;
-			self.image_state.delete_file(path);
-		} else {
-			self.asking_to_delete = Some(path);
+/// Returns `Some(replace)` if the corrected image of the file at `path` should be exported, where `replace` is whether the user agreed to replace an earlier export.
+fn show_white_balance(
+	ui: &mut egui::Ui,
+	path: &Path,
+	white_balance: &mut WhiteBalance,
+	picking_gray_point: &mut bool,
+) -> Option<bool> {
+	let mut export = None;
+	// the export that the user is being asked to replace, so that the question is not shown for other images
+	let confirm_id = egui::Id::new("white-balance-confirm-replace");
+	let target = white_balance::export_path(path);
+	ui.heading("White Balance");
+	widgets::KeyValue::new("white-balance-kv").show(ui, |mut rows| {
+		let range = -white_balance::RANGE..=white_balance::RANGE;
+		rows.row("Temperature", |ui| {
+			ui.add(egui::Slider::new(
+				&mut white_balance.temperature,
+				range.clone(),
+			))
+			.on_hover_text("Positive is warmer")
+		});
+		rows.row("Tint", |ui| {
+			ui.add(egui::Slider::new(&mut white_balance.tint, range))
+				.on_hover_text("Positive is more magenta, negative is more green")
+		});
+	});
+	ui.horizontal(|ui| {
+		ui.toggle_value(picking_gray_point, "Pick Gray Point")
+			.on_hover_text("Click a part of the image that should be gray");
+		let neutral = white_balance.is_neutral();
+		if ui
+			.add_enabled(!neutral, egui::Button::new("Reset"))
+			.clicked()
+		{
+			*white_balance = WhiteBalance::default();
+		}
+		if ui
+			.add_enabled(!neutral, egui::Button::new("Export"))
+			.on_hover_text("Save the current frame with the correction applied, next to the image")
+			.clicked()
+		{
+			if target.exists() {
+				ui.data_mut(|data| data.insert_temp(confirm_id, target.clone()));
+			} else {
+				export = Some(false);
+			}
 		}
+	});
+
+	if ui
+		.data(|data| data.get_temp::<PathBuf>(confirm_id))
+		.as_ref()
+		== Some(&target)
+	{
+		let name = target.file_name().unwrap_or_default().to_string_lossy();
+		ui.horizontal_wrapped(|ui| {
+			ui.colored_label(
+				ui.visuals().warn_fg_color,
+				format!("⚠ {name} already exists."),
+			);
+			if ui.button("Replace").clicked() {
+				export = Some(true);
+			}
+			if export.is_some() || ui.button("Cancel").clicked() {
+				ui.data_mut(|data| data.remove::<PathBuf>(confirm_id));
+			}
+		});
 	}
+	export
+}
 
-	fn show_actions(&mut self, ctx: &Context) {
-		let panel = {
-			let style = ctx.style();
-			let frame = Frame {
-				inner_margin: Margin::symmetric(4.0, 2.0),
-				rounding: Rounding::ZERO,
-				fill: style.visuals.window_fill(),
-				stroke: style.visuals.window_stroke(),
-				..Default::default()
-			};
-			egui::TopBottomPanel::top("actions").frame(frame)
-		};
+/// Warn if the extension of the file at `path` does not match the `format` that it is in.
+/// Returns whether the file should be renamed to fix it.
+fn show_extension_mismatch(ui: &mut egui::Ui, path: &Path, format: ImageFormat) -> bool {
+	let Some(corrected) = image::corrected_path(path, format) else {
+		return false;
+	};
+	ui.button("⚠ Extension")
+		.on_hover_text(format!(
+			"This is a {} file, but its extension says otherwise. Click to rename it to {}",
+			format_to_string(format),
+			corrected.file_name().unwrap_or_default().to_string_lossy(),
+		))
+		.clicked()
+}
 
-		panel.show(ctx, |ui| {
+/// The buttons that open the sprite sheet and animation export windows, with all `num_frames` frames of the animation selected.
+/// The animation export button is labeled with the configured `format`.
+fn show_animation_export_buttons(
+	ui: &mut egui::Ui,
+	num_frames: usize,
+	format: config::AnimationFormat,
+	sprite_sheet_frames: &mut Option<(usize, usize)>,
+	animation_frames: &mut Option<(usize, usize)>,
+) {
+	if ui
+		.button("▦")
+		.on_hover_label("Export sprite sheet")
+		.clicked()
+	{
+		*sprite_sheet_frames = Some((1, num_frames));
+	}
+	let label = match format {
+		config::AnimationFormat::Gif => "GIF",
+		config::AnimationFormat::WebP => "WebP",
+	};
+	if ui
+		.button(label)
+		.on_hover_label(format!("Export as {}", format.repr()))
+		.clicked()
+	{
+		*animation_frames = Some((1, num_frames));
+	}
+}
+
+/// Opens the video export window, saving next to `path` by default.
+fn show_video_export_button(ui: &mut egui::Ui, path: &Path, dialog: &mut Option<VideoExport>) {
+	if ui
+		.button("🎬")
+		.on_hover_label("Export slideshow as video")
+		.clicked()
+	{
+		let dir = path.parent().unwrap_or(Path::new(""));
+		// suggest a name that isn't taken, since an existing file is not replaced
+		let name = |number| match number {
+			1 => dir.join("slideshow.mp4"),
+			_ => dir.join(format!("slideshow ({number}).mp4")),
+		};
+		let output = (1..=100)
+			.map(name)
+			.find(|output| !output.exists())
+			.unwrap_or_else(|| name(1));
+		*dialog = Some(VideoExport {
+			output: output.display().to_string(),
+			starting: false,
+			export: None,
+		});
+	}
+}
+
+fn show_rating(ui: &mut egui::Ui, rating: &mut rating::Rating) {
+	for stars in 1..=rating::MAX_STARS {
+		let star = if stars <= rating.stars { "★" } else { "☆" };
+		if ui
+			.selectable_label(false, star)
+			.on_hover_label(format!("{stars} stars (Ctrl-{stars})"))
+			.clicked()
+		{
+			// clicking the current rating clears it
+			rating.stars = if rating.stars == stars { 0 } else { stars };
+		}
+	}
+	let heart = if rating.favorite { "♥" } else { "♡" };
+	ui.toggle_value(&mut rating.favorite, heart)
+		.on_hover_label("Favorite (h)");
+}
+
+/// The counts grow while the frames are still loading.
+fn show_animation_properties(rows: &mut widgets::key_value::Rows<'_>, image: &image::Image) {
+	let frames = image.frames.read();
+	rows.row("Frames", |ui| {
+		let label = ui.label(frames.encoded_len.to_string());
+		if frames.list.len() < frames.encoded_len {
+			label.on_hover_text(format!(
+				"{} of them are distinct; the rest repeat the frame before them",
+				frames.list.len(),
+			));
+		}
+	});
+	rows.row("Duration", |ui| ui.label(frames.duration.to_string()));
+	if let Some(fps) = frames.average_fps() {
+		rows.row("Average FPS", |ui| ui.label(format!("{fps:.1}")));
+	}
+	if let Some(loop_count) = image.loop_count {
+		rows.row("Loops", |ui| ui.label(loop_count.repr()));
+	}
+}
+
+/// What the user asked for in the properties.
+#[derive(Default)]
+struct PropertiesRequest {
+	generate_checksums: bool,
+	/// Read the metadata chunks of the file, which contain the location.
+	read_chunks: bool,
+}
+
+/// The rating is changed in place once it is known.
+/// The location is only known once the chunks have been read, which is done when the user asks since it can be slow.
+fn show_properties(
+	ui: &mut egui::Ui,
+	path: &Path,
+	image: &image::Image,
+	checksum: Option<&checksum::Status>,
+	rating: Option<&mut rating::Rating>,
+	gps: Option<&exif::Gps>,
+	chunks_read: bool,
+) -> PropertiesRequest {
+	let copied_rating = rating.as_deref().copied();
+	let mut request = PropertiesRequest::default();
+	widgets::KeyValue::new("properties-kv").show(ui, |mut rows| {
+		rows.row("Width", |ui| ui.label(image.width.to_string()));
+		rows.row("Height", |ui| ui.label(image.height.to_string()));
+		rows.row("Format", |ui| ui.label(format_to_string(image.format)));
+		rows.row("Color Type", |ui| {
+			ui.label(color_type_to_string(image.color_type))
+		});
+		rows.row("Bit Depth", |ui| {
+			ui.label(bit_depth_to_string(image.color_type))
+		});
+		rows.row("Kind", |ui| ui.label(image.kind().repr()));
+		if image.is_animated() {
+			show_animation_properties(&mut rows, image);
+		}
+
+		rows.separator();
+		rows.row("File Size", |ui| {
+			ui.label(humansize::format_size(
+				image.metadata.file_size,
+				humansize::DECIMAL,
+			))
+		});
+		if let Some(mtime) = &image.metadata.mtime {
+			rows.row("Modified", |ui| ui.label(mtime));
+		}
+
+		rows.separator();
+		rows.row("Checksum", |ui| {
+			match checksum {
+				None => ui.spinner(),
+				Some(checksum::Status::Unknown) => ui.label("None"),
+				Some(checksum::Status::Verified { algorithm, source }) => ui
+					.colored_label(Color32::GREEN, "✔ Verified")
+					.on_hover_text(format!("{} from {}", algorithm.repr(), source.display())),
+				Some(checksum::Status::Modified { algorithm, source }) => ui
+					.colored_label(Color32::RED, "✖ Modified")
+					.on_hover_text(format!(
+						"Does not match the {} from {}",
+						algorithm.repr(),
+						source.display()
+					)),
+			};
+			request.generate_checksums = ui
+				.small_button("⟳")
+				.on_hover_label("Generate checksums for the folder")
+				.clicked();
+		});
+		rows.row("Rating", |ui| {
+			ui.spacing_mut().item_spacing.x = 0.0;
+			match rating {
+				Some(rating) => show_rating(ui, rating),
+				None => _ = ui.spinner(),
+			}
+		});
+	});
+
+	if let Some(gps) = gps {
+		ui.separator();
+		show_gps(ui, gps);
+	} else if !chunks_read {
+		ui.separator();
+		request.read_chunks = ui
+			.button("Find Location")
+			.on_hover_text("Read the GPS location from the metadata of the file")
+			.clicked();
+	}
+	ui.separator();
+	if let Some(copied) = show_copy_info(ui, path, image, checksum, copied_rating, gps) {
+		ui.ctx().output_mut(|output| output.copied_text = copied);
+	}
+	request
+}
+
+fn show_gps(ui: &mut egui::Ui, gps: &exif::Gps) {
+	widgets::KeyValue::new("gps-kv").show(ui, |mut rows| {
+		rows.row("Location", |ui| ui.label(gps.repr()));
+		if let Some(altitude) = gps.altitude {
+			rows.row("Altitude", |ui| ui.label(format!("{altitude:.0} m")));
+		}
+		if let Some(accuracy) = gps.accuracy {
+			rows.row("Accuracy", |ui| ui.label(format!("± {accuracy:.0} m")));
+		}
+	});
+	if ui
+		.button("Open in OpenStreetMap")
+		.on_hover_text("Show the location in the browser")
+		.clicked()
+	{
+		ui.ctx().open_url(egui::OpenUrl::new_tab(gps.map_url()));
+	}
+}
+
+/// Returns the properties to copy, as text or JSON.
+fn show_copy_info(
+	ui: &mut egui::Ui,
+	path: &Path,
+	image: &image::Image,
+	checksum: Option<&checksum::Status>,
+	rating: Option<rating::Rating>,
+	gps: Option<&exif::Gps>,
+) -> Option<String> {
+	let mut copied = None;
+	ui.menu_button("Copy Info", |ui| {
+		let properties = || info::properties(path, image, checksum, rating, gps);
+		if ui.button("As Text").clicked() {
+			copied = Some(info::properties_to_text(&properties()));
+			ui.close_menu();
+		}
+		if ui.button("As JSON").clicked() {
+			copied = Some(info::properties_to_json(&properties()));
+			ui.close_menu();
+		}
+	})
+	.response
+	.on_hover_text("Copy the properties, e.g., to paste into a bug report");
+	copied
+}
+
+fn show_sidebar_tabs(ui: &mut egui::Ui, tab: &mut SidebarTab, num_marked: usize) {
+	ui.horizontal_wrapped(|ui| {
+		ui.selectable_value(tab, SidebarTab::Properties, "Properties");
+		ui.selectable_value(tab, SidebarTab::RawMetadata, "Raw Metadata");
+		ui.selectable_value(tab, SidebarTab::Folder, "Folder");
+		ui.selectable_value(tab, SidebarTab::Adjustments, "Adjustments");
+		ui.selectable_value(tab, SidebarTab::Marked, format!("Marked ({num_marked})"));
+	});
+}
+
+#[derive(Debug, Clone)]
+enum MarkedAction {
+	ToggleCurrent,
+	/// Move between the marked images instead of the current ones.
+	Open,
+	CopyPaths,
+	Clear,
+	Unmark(usize),
+	Batch(batch::Action),
+	CancelBatch,
+}
+
+/// What the batch actions in the marked tab are set to.
+struct BatchSettings {
+	/// The folder to move or copy to.
+	target: String,
+	format: ImageFormat,
+	strip_metadata: bool,
+}
+
+impl Default for BatchSettings {
+	fn default() -> Self {
+		Self {
+			target: String::new(),
+			format: ImageFormat::Png,
+			strip_metadata: false,
+		}
+	}
+}
+
+/// `progress` is the number of images done and the total, if a batch is running.
+fn show_batch(
+	ui: &mut egui::Ui,
+	num_marked: usize,
+	settings: &mut BatchSettings,
+	progress: Option<(usize, usize)>,
+	outcome: Option<&batch::Outcome>,
+) -> Option<MarkedAction> {
+	let mut action = None;
+	if let Some((done, total)) = progress {
+		ui.horizontal(|ui| {
+			let fraction = az::cast::<_, f32>(done) / az::cast::<_, f32>(total.max(1));
+			ui.add(
+				egui::ProgressBar::new(fraction)
+					.desired_width(150.0)
+					.text(format!("{done} / {total}")),
+			);
+			if ui.small_button("✖").on_hover_label("Cancel").clicked() {
+				action = Some(MarkedAction::CancelBatch);
+			}
+		});
+		ui.ctx()
+			.request_repaint_after(std::time::Duration::from_millis(100));
+		return action;
+	}
+
+	ui.add_enabled_ui(num_marked > 0, |ui| {
+		ui.horizontal(|ui| {
+			ui.add(
+				egui::TextEdit::singleline(&mut settings.target)
+					.hint_text("Folder")
+					.desired_width(150.0),
+			);
+			let dir = PathBuf::from(settings.target.trim());
+			ui.add_enabled_ui(dir.is_dir(), |ui| {
+				if ui.button("Move").clicked() {
+					action = Some(MarkedAction::Batch(batch::Action::Move(dir.clone())));
+				}
+				if ui.button("Copy").clicked() {
+					action = Some(MarkedAction::Batch(batch::Action::Copy {
+						dir,
+						strip_metadata: settings.strip_metadata,
+					}));
+				}
+			});
+		});
+		ui.checkbox(&mut settings.strip_metadata, "Remove metadata from copies")
+			.on_hover_text("Leave out the EXIF, XMP, and ICC data of PNG, JPEG, and WebP files, e.g., the location and camera. Converted images never keep it");
+		ui.horizontal(|ui| {
+			ComboBox::from_id_source("batch-format-combo")
+				.selected_text(format_to_string(settings.format))
+				.show_ui(ui, |ui| {
+					for &format in batch::CONVERT_FORMATS {
+						ui.selectable_value(&mut settings.format, format, format_to_string(format));
+					}
+				});
+			if ui
+				.button("Convert")
+				.on_hover_text("Write a copy in this format next to each image")
+				.clicked()
+			{
+				action = Some(MarkedAction::Batch(batch::Action::Convert(settings.format)));
+			}
+			ui.menu_button("Delete", |ui| {
+				ui.label(format!("Move {num_marked} images to the trash?"));
+				if ui.button("Move to Trash").clicked() {
+					action = Some(MarkedAction::Batch(batch::Action::Delete));
+					ui.close_menu();
+				}
+			});
+		});
+	});
+
+	if let Some(outcome) = outcome {
+		let mut summary = format!("{}: {} done", outcome.action.describe(), outcome.done.len());
+		if outcome.cancelled {
+			summary.push_str(", cancelled");
+		}
+		if outcome.failed.is_empty() {
+			ui.label(summary);
+		} else {
+			let failures: Vec<_> = outcome
+				.failed
+				.iter()
+				.map(|(path, error)| format!("{}: {error}", path.display()))
+				.collect();
+			ui.colored_label(
+				Color32::RED,
+				format!("{summary}, {} failed", outcome.failed.len()),
+			)
+			.on_hover_text(failures.join("\n"));
+		}
+	}
+	action
+}
+
+fn show_marked(
+	ui: &mut egui::Ui,
+	marked: &[Arc<Path>],
+	batch: impl FnOnce(&mut egui::Ui) -> Option<MarkedAction>,
+) -> Option<MarkedAction> {
+	let mut action = None;
+	ui.horizontal_wrapped(|ui| {
+		if ui
+			.button("Mark/Unmark Current")
+			.on_hover_text("m")
+			.clicked()
+		{
+			action = Some(MarkedAction::ToggleCurrent);
+		}
+		ui.add_enabled_ui(!marked.is_empty(), |ui| {
+			if ui
+				.button("Open")
+				.on_hover_text("Move between the marked images only")
+				.clicked()
+			{
+				action = Some(MarkedAction::Open);
+			}
+			if ui.button("Copy Paths").clicked() {
+				action = Some(MarkedAction::CopyPaths);
+			}
+			if ui.button("Clear").clicked() {
+				action = Some(MarkedAction::Clear);
+			}
+		});
+	});
+	ui.separator();
+	action = batch(ui).or(action);
+	ui.separator();
+
+	if marked.is_empty() {
+		ui.label("Press m to mark the current image");
+	}
+	egui::ScrollArea::vertical().show(ui, |ui| {
+		for (idx, path) in marked.iter().enumerate() {
+			ui.horizontal(|ui| {
+				if ui.small_button("✖").on_hover_label("Unmark").clicked() {
+					action = Some(MarkedAction::Unmark(idx));
+				}
+				let name = path.file_name().unwrap_or_default().to_string_lossy();
+				ui.label(name).on_hover_text(path.display().to_string());
+			});
+		}
+	});
+	action
+}
+
+fn show_folder_stats(ui: &mut egui::Ui, stats: &folder_stats::Stats) {
+	widgets::KeyValue::new("folder-stats-kv").show(ui, |mut rows| {
+		rows.row("Images", |ui| {
+			if stats.indexing {
+				ui.spinner();
+			}
+			ui.label(stats.images.to_string())
+		});
+		rows.row("Total Size", |ui| {
+			ui.label(humansize::format_size(stats.total_size, humansize::DECIMAL))
+		});
+		if let (Some(oldest), Some(newest)) = (stats.oldest, stats.newest) {
+			rows.row("Oldest", |ui| ui.label(image::format_time(oldest)));
+			rows.row("Newest", |ui| ui.label(image::format_time(newest)));
+		}
+
+		rows.separator();
+		for &(format, count) in &stats.formats {
+			rows.row(format_to_string(format), |ui| ui.label(count.to_string()));
+		}
+
+		rows.separator();
+		for ((_max, label), count) in folder_stats::MEGAPIXEL_BUCKETS.iter().zip(stats.dimensions) {
+			rows.row(*label, |ui| ui.label(count.to_string()));
+		}
+		if stats.unreadable > 0 {
+			rows.row("Unreadable", |ui| ui.label(stats.unreadable.to_string()));
+		}
+	});
+}
+
+/// Returns the index of the chunk to export, if requested.
+fn show_chunks(
+	ui: &mut egui::Ui,
+	chunks: &Result<Vec<image::chunks::Chunk>, String>,
+	selected: &mut usize,
+) -> Option<usize> {
+	let chunks = match chunks {
+		Ok(chunks) => chunks,
+		Err(error) => {
+			ui.colored_label(ui.visuals().error_fg_color, error.as_str());
+			return None;
+		}
+	};
+	if chunks.is_empty() {
+		ui.label("No metadata chunks");
+		return None;
+	}
+	*selected = (*selected).min(chunks.len() - 1);
+
+	let mut export = None;
+	egui::Grid::new("raw-metadata-chunks")
+		.striped(true)
+		.show(ui, |ui| {
+			for (idx, chunk) in chunks.iter().enumerate() {
+				let kind = match chunk.kind {
+					image::chunks::Kind::Exif => "EXIF",
+					image::chunks::Kind::Xmp => "XMP",
+					image::chunks::Kind::Icc => "ICC",
+					image::chunks::Kind::Other => "",
+				};
+				ui.selectable_value(selected, idx, &chunk.name)
+					.on_hover_text(format!("At offset {:#x}", chunk.offset));
+				ui.label(kind);
+				ui.label(humansize::format_size(chunk.data.len(), humansize::DECIMAL));
+				if ui.small_button("💾").on_hover_label("Export").clicked() {
+					export = Some(idx);
+				}
+				ui.end_row();
+			}
+		});
+
+	ui.separator();
+	widgets::hex_dump::show(ui, "raw-metadata-hex", &chunks[*selected].data);
+
+	export
+}
+
+#[derive(Debug, Clone, Copy)]
+enum MoveMode {
+	IgnoreSlideshow,
+	RespectSlideshow,
+	/// The slideshow moved on by itself, so it may stop at the end.
+	Slideshow,
+}
+
+impl App {
+	fn move_in(&mut self, direction: Direction, mode: MoveMode) {
+		let respect_slideshow = match mode {
+			MoveMode::IgnoreSlideshow => false,
+			MoveMode::RespectSlideshow | MoveMode::Slideshow => true,
+		};
+		let wrap = !matches!(mode, MoveMode::Slideshow)
+			|| self.config.slideshow.end == config::SlideshowEnd::Wrap;
+		let mode = if respect_slideshow && self.slideshow.is_active() && self.config.slideshow.shuffle {
+			NextPathMode::Random
+		} else {
+			NextPathMode::Simple
+		};
+		let direction = NextPath {
+			direction,
+			mode,
+			sort: sort_order(&self.config),
+			filter: navigation_filter(&self.config),
+			wrap,
+		};
+		self.image_state.next_path(direction);
+		self.slideshow.reset(&self.config);
+	}
+}
+
+/// How long "Copied!" is shown after clicking the path, in seconds.
+const COPIED_TIME: f32 = 1.0 / 6.0;
+
+impl App {
+	fn show_actions_left(&mut self, ui: &mut egui::Ui) {
+		if let Some(current_path) = self.image_state.current_path() {
+			let response =
+				ui.add(egui::Label::new(current_path.display().to_string()).sense(egui::Sense::click()));
+			let clicked = response.clicked();
+			// not the style's animation time, which is zero when reducing motion
+			let show_copied = ui
+				.ctx()
+				.animate_bool_with_time(response.id, clicked, COPIED_TIME)
+				> 0.0;
+			response.on_hover_text(if show_copied {
+				"Copied!"
+			} else {
+				"Click to copy"
+			});
+			if clicked {
+				let copied_text = current_path.display().to_string();
+				ui.output_mut(|output| output.copied_text = copied_text);
+			}
+		}
+	}
+
+	fn show_actions_right(&mut self, ui: &mut egui::Ui) {
+		let mut to_delete = None;
+		let mut cancel_frames = false;
+		let mut fix_extension = false;
+
+		ui.toggle_value(&mut self.settings_open, "⛭")
+			.on_hover_label("Toggle settings window");
+
+		ui.toggle_value(&mut self.history_open, "🕓")
+			.on_hover_label("Toggle history (undo and redo)");
+
+		ui.menu_button("🗂", |ui| self.show_workspaces(ui))
+			.response
+			.on_hover_label("Workspaces");
+
+		show_fullscreen_toggle(ui);
+
+		self.config.light_dark_toggle_button(ui);
+
+		#[cfg(feature = "device-import")]
+		if ui
+			.button("📷")
+			.on_hover_label("Import from device")
+			.clicked()
+		{
+			self.image_state.list_devices();
+			self.device_import = Some(DeviceImport {
+				selected: rustc_hash::FxHashSet::default(),
+				target: device_import::default_target().display().to_string(),
+			});
+		}
+
+		if let Some(current) = &mut self.image_state.current {
+			let delete_button = ui.button("🗑");
+			to_delete = delete_button.clicked().then(|| current.path.clone());
+			delete_button.on_hover_label("Delete File");
+
+			self.slideshow.show_toggle(ui, &self.config);
+			show_video_export_button(ui, &current.path, &mut self.video_export);
+
+			if let Ok(inner) = &mut current.inner {
+				if ui.button("💾").on_hover_label("Save As…").clicked() {
+					self.save_as = Some(SaveAsDialog::new(&current.path, self.piped.as_ref()));
+				}
+
+				show_zoom_controls(ui, inner);
+				ui.toggle_value(&mut self.image_state.lock_view, "🔒")
+					.on_hover_label("Keep zoom and pan when changing images (l)");
+				show_inspection_tools(
+					ui,
+					&mut self.config.loupe.enabled,
+					&mut self.drag_mode,
+					inner.measurement,
+				);
+
+				ui.toggle_value(&mut self.config.show_sidebar, "ℹ")
+					.on_hover_label("Toggle sidebar");
+
+				if inner.image.is_animated() {
+					ui.toggle_value(&mut self.config.show_frames, "🎞")
+						.on_hover_label("Toggle frames");
+
+					let num_frames = inner.image.frames.read().list.len();
+					show_animation_export_buttons(
+						ui,
+						num_frames,
+						self.config.animation_export.format,
+						&mut self.sprite_sheet_frames,
+						&mut self.animation_frames,
+					);
+				} else {
+					show_sprite_sheet_toggle(ui, inner);
+				}
+
+				match &inner.image.frames.read().status {
+					FramesStatus::Loading => {
+						cancel_frames = ui
+							.small_button("✖")
+							.on_hover_label("Stop loading frames")
+							.clicked();
+						ui.spinner().on_hover_text("Loading frames");
+					}
+					FramesStatus::Failed(error) => {
+						ui.label("⚠")
+							.on_hover_label(format!("Some frames could not be loaded: {error}"));
+					}
+					FramesStatus::Complete | FramesStatus::Cancelled => {}
+				}
+
+				fix_extension = show_extension_mismatch(ui, &current.path, inner.image.format);
+			}
+		}
+
+		if cancel_frames {
+			self.image_state.progress().cancel();
+		}
+		if fix_extension {
+			self.image_state.fix_extension();
+		}
+
+		self.show_loading(ui);
+
+		#[cfg(feature = "audio")]
+		if let Some(error) = self.audio.error() {
+			ui.label("♫ ⚠")
+				.on_hover_label(format!("Background audio failed: {error}"));
+		}
+
+		if let SlideshowState::Active { remaining, .. } = self.slideshow {
+			ui.label(format!("\u{2398} {} s", remaining.ceil_secs()))
+				.on_hover_text("Time left for this image; press + to add more");
+			ui.ctx()
+				.request_repaint_after(std::time::Duration::from_secs(1));
+		}
+
+		if let Some(to_delete) = to_delete {
+			self.delete_file(ui, to_delete);
+		}
+	}
+
+	fn show_loading(&self, ui: &mut egui::Ui) {
+		/// Loads faster than this just show a spinner, to avoid flashing the progress bar.
+		const SLOW_LOAD: std::time::Duration = std::time::Duration::from_millis(250);
+
+		let Some(waiting_for) = self.image_state.waiting_for() else {
+			return;
+		};
+
+		let progress = self.image_state.progress();
+		let fraction = progress.fraction().filter(|_| waiting_for >= SLOW_LOAD);
+		let Some(fraction) = fraction else {
+			ui.spinner().on_hover_text("Loading");
+			return;
+		};
+
+		if ui
+			.small_button("✖")
+			.on_hover_label("Cancel loading")
+			.clicked()
+		{
+			progress.cancel();
+		}
+
+		let frames = progress.frames();
+		let text = if frames > 1 {
+			format!("{:.0}% ({frames} frames)", fraction * 100.0)
+		} else {
+			format!("{:.0}%", fraction * 100.0)
+		};
+		ui.add(
+			egui::ProgressBar::new(fraction)
+				.desired_width(ui.spacing().interact_size.x * 3.0)
+				.text(text),
+		)
+		.on_hover_text("Loading");
+		// progress is reported from another thread, so we have to poll it
+		ui.ctx().request_repaint();
+	}
+
+	fn delete_file(&mut self, ui: &egui::Ui, path: Arc<Path>) {
+		if ui.input(|input| input.modifiers.shift) {
+			self.asking_to_delete = None;
+			self.image_state.delete_file(path);
+		} else {
+			self.asking_to_delete = Some(path);
+		}
+	}
+
+	fn show_actions(&mut self, ctx: &Context) {
+		let panel = {
+			let style = ctx.style();
+			let frame = Frame {
+				inner_margin: Margin::symmetric(4.0, 2.0),
+				rounding: Rounding::ZERO,
+				fill: style.visuals.window_fill(),
+				stroke: style.visuals.window_stroke(),
+				..Default::default()
+			};
+			egui::TopBottomPanel::top("actions").frame(frame)
+		};
+
+		let borderless = self.config.window.borderless;
+		panel.show(ctx, |ui| {
+			if borderless {
+				drag_window(ui);
+			}
 			ui.horizontal(|ui| {
 				use egui::{Align, Layout};
 
-				ui.with_layout(Layout::left_to_right(Align::Center), |ui| {
-					self.show_actions_left(ui);
+				ui.with_layout(Layout::left_to_right(Align::Center), |ui| {
+					self.show_actions_left(ui);
+				});
+				ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+					if borderless && ui.button("✖").on_hover_label("Close").clicked() {
+						ui.ctx().send_viewport_cmd(ViewportCommand::Close);
+					}
+					self.show_actions_right(ui);
+				});
+			});
+		});
+	}
+
+	/// Only index the folder while its statistics are visible.
+	fn watch_folder_stats(&mut self) {
+		let folder = if self.config.show_sidebar && self.sidebar_tab == SidebarTab::Folder {
+			self
+				.image_state
+				.current_path()
+				.and_then(Path::parent)
+				.map(|parent| {
+					if parent.as_os_str().is_empty() {
+						".".as_ref()
+					} else {
+						parent
+					}
+				})
+		} else {
+			None
+		};
+		self.folder_stats.watch(folder);
+	}
+
+	fn show_sidebar(&mut self, ctx: &Context) {
+		self.watch_folder_stats();
+
+		if !self.config.show_sidebar {
+			return;
+		}
+
+		let batch_progress = self.image_state.batch_progress().map(batch::Progress::get);
+		let Some(state::OpenImage {
+			inner:
+				Ok(state::OpenImageInner {
+					image,
+					chunks,
+					gps,
+					checksum,
+					rating,
+					white_balance,
+					picking_gray_point,
+					..
+				}),
+			path,
+			..
+		}) = &mut self.image_state.current
+		else {
+			return;
+		};
+
+		let mut export_white_balance = None;
+		let mut request = PropertiesRequest::default();
+		let mut request_checksum = false;
+		let mut new_rating = None;
+		let mut export_chunk = None;
+		let mut marked_action = None;
+		egui::SidePanel::right("properties").show(ctx, |ui| {
+			show_sidebar_tabs(ui, &mut self.sidebar_tab, self.image_state.marked.len());
+			ui.separator();
+
+			match self.sidebar_tab {
+				SidebarTab::Properties => {
+					request_checksum = checksum.is_none();
+					let mut edited = *rating;
+					request = show_properties(
+						ui,
+						path,
+						image,
+						checksum.as_ref(),
+						edited.as_mut(),
+						gps.as_ref(),
+						chunks.is_some(),
+					);
+					if edited != *rating {
+						new_rating = edited;
+					}
+				}
+				SidebarTab::RawMetadata => {
+					if let Some(chunks) = chunks {
+						export_chunk = show_chunks(ui, chunks, &mut self.selected_chunk);
+					} else {
+						request.read_chunks = true;
+						ui.spinner();
+					}
+				}
+				SidebarTab::Folder => show_folder_stats(ui, &self.folder_stats.stats()),
+				SidebarTab::Adjustments => {
+					export_white_balance = show_white_balance(ui, path, white_balance, picking_gray_point);
+				}
+				SidebarTab::Marked => {
+					let batch = |ui: &mut egui::Ui| {
+						show_batch(
+							ui,
+							self.image_state.marked.len(),
+							&mut self.batch_settings,
+							batch_progress,
+							self.image_state.batch_outcome.as_ref(),
+						)
+					};
+					marked_action = show_marked(ui, &self.image_state.marked, batch);
+				}
+			}
+		});
+
+		if request.read_chunks {
+			self.image_state.request_chunks();
+		}
+		if request_checksum {
+			self.image_state.request_checksum();
+		}
+		if request.generate_checksums {
+			self.image_state.generate_checksums();
+		}
+		if let Some(new_rating) = new_rating {
+			self
+				.image_state
+				.update_rating(|rating| *rating = new_rating);
+		}
+		if let Some(idx) = export_chunk {
+			self.image_state.export_chunk(idx);
+		}
+		if let Some(replace) = export_white_balance {
+			self.image_state.export_white_balance(replace);
+		}
+		if let Some(action) = marked_action {
+			self.apply_marked_action(action);
+		}
+	}
+
+	fn apply_marked_action(&mut self, action: MarkedAction) {
+		match action {
+			MarkedAction::ToggleCurrent => self.image_state.toggle_mark(),
+			MarkedAction::Open => self.image_state.open_marked(),
+			MarkedAction::CopyPaths => self.image_state.copy_marked_paths(),
+			MarkedAction::Clear => self.image_state.marked.clear(),
+			MarkedAction::Unmark(idx) => _ = self.image_state.marked.remove(idx),
+			MarkedAction::Batch(action) => self.image_state.run_batch(action),
+			MarkedAction::CancelBatch => {
+				if let Some(progress) = self.image_state.batch_progress() {
+					progress.cancel();
+				}
+			}
+		}
+	}
+
+	fn show_status_bar(&self, ctx: &Context) {
+		if !self.config.status_bar.enabled {
+			return;
+		}
+		let Some(current) = &self.image_state.current else {
+			return;
+		};
+
+		egui::TopBottomPanel::bottom("status-bar").show(ctx, |ui| {
+			ui.horizontal(|ui| {
+				if let Some((idx, total)) = current.index {
+					ui.label(format!("{} / {total}", idx + 1));
+					ui.separator();
+				}
+				if self.image_state.is_marked() {
+					ui.label("☑ Marked");
+					ui.separator();
+				}
+				let Ok(inner) = &current.inner else {
+					ui.label("Failed to load");
+					return;
+				};
+				let image = &inner.image;
+				if let Some(view) = inner.view {
+					ui.label(format!("{:.0}%", inner.zoom.scale(view) * 100.0));
+					ui.separator();
+				}
+				ui.label(format!("{} × {}", image.width, image.height));
+				ui.separator();
+				ui.label(humansize::format_size(
+					image.metadata.file_size,
+					humansize::DECIMAL,
+				));
+
+				// the image is painted after this panel, so the mapping is from the previous frame
+				let hovered = inner
+					.mapping
+					.filter(|_| inner.sprite_sheet.is_none())
+					.zip(ctx.pointer_hover_pos())
+					.and_then(|(mapping, pos)| {
+						let pixel = mapping.to_pixel(pos).floor();
+						let size = mapping.image_size;
+						let inside = pixel.x >= 0.0 && pixel.y >= 0.0 && pixel.x < size.x && pixel.y < size.y;
+						inside.then_some(pixel)
+					});
+				if let Some(pixel) = hovered {
+					ui.separator();
+					ui.label(format!("{}, {}", pixel.x, pixel.y));
+				}
+			});
+		});
+	}
+
+	fn show_frames(&mut self, ctx: &Context) {
+		if !self.config.show_frames {
+			return;
+		}
+
+		let Some(state::OpenImage {
+			inner:
+				Ok(state::OpenImageInner {
+					play_state: PlayState::Animated {
+						current_frame,
+						playing,
+					},
+					image,
+					..
+				}),
+			..
+		}) = &mut self.image_state.current
+		else {
+			return;
+		};
+		let frame_count = image.frames.read().list.len();
+
+		let outer_frame_size = Vec2::splat(THUMBNAIL_SIZE);
+
+		let frame_style = {
+			let style = ctx.style();
+			Frame {
+				inner_margin: style.spacing.window_margin,
+				fill: style.visuals.window_fill(),
+				stroke: style.visuals.window_stroke(),
+				..Frame::default()
+			}
+		};
+		egui::TopBottomPanel::bottom("frames")
+			.resizable(false)
+			.frame(frame_style)
+			.default_height(outer_frame_size.y + frame_style.inner_margin.sum().y) // may not include the scroll bar, but that's fine. this is just a decent baseline
+			.show(ctx, |ui| {
+				egui::ScrollArea::horizontal().show_columns(
+					ui,
+					outer_frame_size.x,
+					frame_count,
+					|ui, visible_range| {
+						// only the thumbnails of visible frames are kept on the GPU, since there may be thousands of frames
+						image.show_thumbnails(ctx, visible_range.clone());
+						let frames = image.frames.read();
+						let frames = &frames.list;
+						// iterate over an enumerated subslice with correct indices
+						// XXX more elegant way to do that?
+						for (idx, (texture, frame_time)) in frames[visible_range.clone()]
+							.iter()
+							.enumerate()
+							.map(|(idx, v)| (idx + visible_range.start, v))
+						{
+							let button = widgets::ImageButton::new(texture, outer_frame_size)
+								.selected(idx == current_frame.idx);
+							let response = ui
+								.add(button)
+								.accessible_label(format!("Frame {}", idx + 1));
+							if response.clicked() {
+								// always stop playing if a user selects a frame
+								*playing = false;
+								current_frame.move_to(idx, *frame_time);
+							}
+							response.context_menu(|ui| {
+								let frame = idx + 1;
+								if ui.button("Export Animation from This Frame").clicked() {
+									let (first, last) = self.animation_frames.get_or_insert((1, frame_count));
+									*first = frame;
+									*last = (*last).max(frame);
+									ui.close_menu();
+								}
+								if ui.button("Export Animation up to This Frame").clicked() {
+									let (first, last) = self.animation_frames.get_or_insert((1, frame_count));
+									*first = (*first).min(frame);
+									*last = frame;
+									ui.close_menu();
+								}
+							});
+							// inline of on_hover_text that lazily evaluates `format!`
+							response.on_hover_ui(|ui| {
+								ui.label(format!("Frame {}, {}", idx + 1, frames[idx].1));
+							});
+						}
+					},
+				);
+			});
+	}
+
+	/// Stop the slideshow once it reached the end, as configured.
+	fn end_slideshow(&mut self, ctx: &Context) {
+		if !self.slideshow.is_active() {
+			return;
+		}
+		match self.config.slideshow.end {
+			config::SlideshowEnd::Wrap => {}
+			config::SlideshowEnd::Stop => self.slideshow = SlideshowState::Finished,
+			config::SlideshowEnd::ExitFullscreen => {
+				self.slideshow.stop();
+				ctx.send_viewport_cmd(ViewportCommand::Fullscreen(false));
+			}
+		}
+	}
+
+	fn show_slideshow_finished(&mut self, ctx: &Context) {
+		if !matches!(self.slideshow, SlideshowState::Finished) {
+			return;
+		}
+		let mut start_over = false;
+		let mut dismiss = false;
+		egui::Window::new("Slideshow Finished")
+			.collapsible(false)
+			.resizable(false)
+			.anchor(egui::Align2::CENTER_CENTER, Vec2::ZERO)
+			.show(ctx, |ui| {
+				ui.label("The slideshow reached the end.");
+				ui.horizontal(|ui| {
+					start_over = ui.button("Start Over").clicked();
+					dismiss = ui.button("Ok").clicked();
 				});
-				ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
-					self.show_actions_right(ui);
+			});
+
+		if start_over {
+			self.slideshow.start(&self.config);
+			self.move_in(Direction::Right, MoveMode::RespectSlideshow);
+		} else if dismiss {
+			self.slideshow.stop();
+		}
+	}
+
+	/// A thin bar along the bottom of the image that counts down the time left for the current image.
+	fn show_slideshow_progress(&self, ctx: &Context, image_area: Rect) {
+		const HEIGHT: f32 = 3.0;
+
+		let SlideshowState::Active { remaining, total } = self.slideshow else {
+			return;
+		};
+		if !self.config.slideshow.progress_bar {
+			return;
+		}
+
+		let mut bar = image_area;
+		bar.set_top(bar.bottom() - HEIGHT);
+		bar.set_width(image_area.width() * remaining_fraction(remaining, total));
+		// the panels are drawn on the background layer, so this goes over the image but under windows
+		let painter = ctx.layer_painter(egui::LayerId::background());
+		painter.rect_filled(bar, 0.0, ctx.style().visuals.selection.bg_fill);
+
+		if remaining.is_over() {
+			return;
+		}
+		// repaint often enough for the bar to shrink by about a pixel at a time
+		let per_pixel = total.as_secs_f32() / image_area.width().max(1.0);
+		ctx.request_repaint_after(std::time::Duration::from_secs_f32(
+			per_pixel.max(1.0 / 60.0),
+		));
+	}
+
+	/// Show the current image, its position, the zoom, and whether a slideshow is running in the title of the window.
+	fn update_title(&mut self, ctx: &Context) {
+		let mut parts = Vec::new();
+		if let Some(current) = &self.image_state.current {
+			let name = current.path.file_name().map_or_else(
+				|| current.path.display().to_string(),
+				|name| name.to_string_lossy().into_owned(),
+			);
+			parts.push(match current.index {
+				Some((idx, total)) => format!("{name} ({}/{total})", idx + 1),
+				None => name,
+			});
+			if let Ok(state::OpenImageInner {
+				zoom,
+				view: Some(view),
+				..
+			}) = &current.inner
+			{
+				parts.push(format!("{:.0}%", zoom.scale(*view) * 100.0));
+			}
+		}
+		if self.slideshow.is_active() {
+			parts.push("Slideshow".to_owned());
+		}
+		parts.push("eo2".to_owned());
+
+		let title = parts.join(" — ");
+		if title != self.title {
+			ctx.send_viewport_cmd(ViewportCommand::Title(title.clone()));
+			self.title = title;
+		}
+	}
+
+	/// Whether the slideshow should stay on the current image until its animation has played through once.
+	/// The time for the image stays at zero meanwhile, so it moves on as soon as the loop finishes.
+	fn waiting_for_animation(&self) -> bool {
+		self.config.slideshow.wait_for_animations
+			&& matches!(
+				&self.image_state.current,
+				Some(state::OpenImage {
+					inner: Ok(state::OpenImageInner { play_state, .. }),
+					..
+				}) if play_state.is_in_first_loop()
+			)
+	}
+
+	fn update_slideshow(&mut self, ctx: &Context) {
+		let elapsed = ctx.input(|input| input.unstable_dt);
+
+		let next_from_slideshow = self
+			.slideshow
+			.advance(Duration::new_secs_f32_saturating(elapsed))
+			&& !self.waiting_for_animation();
+
+		if next_from_slideshow {
+			self.events.emit(events::Event::SlideshowTick);
+			self.move_in(Direction::Right, MoveMode::Slideshow);
+		}
+
+		// once the time is over, only a playing animation holds the slideshow, and it repaints by itself
+		if let SlideshowState::Active { remaining, .. } = self.slideshow {
+			if !remaining.is_over() {
+				ctx.request_repaint_after(remaining.into());
+			}
+		}
+
+		#[cfg(feature = "audio")]
+		{
+			let audio = &self.config.slideshow_audio;
+			let source = audio.active_source().filter(|_| self.slideshow.is_active());
+			self.audio.play(source, audio.volume);
+		}
+	}
+
+	fn central_panel(&self, ctx: &Context) -> egui::CentralPanel {
+		// when an image is open, the background is drawn by `config::Background` which handles its opacity
+		let (margin, fill) = if matches!(
+			self.image_state.current,
+			Some(state::OpenImage { inner: Ok(..), .. })
+		) {
+			(0.0, Color32::TRANSPARENT)
+		} else {
+			(8.0, ctx.style().visuals.window_fill())
+		};
+		let frame = Frame::none().fill(fill).inner_margin(margin);
+		egui::CentralPanel::default().frame(frame)
+	}
+
+	fn show_central(&mut self, ctx: &Context) {
+		let panel = self.central_panel(ctx);
+
+		let mut image_response = None;
+		let mut swiped = None;
+		let mut gray_point = None;
+		let mut load_error_action = None;
+		panel.show(ctx, |ui| match &mut self.image_state.current {
+			Some(state::OpenImage {
+				inner:
+					Ok(state::OpenImageInner {
+						play_state,
+						image,
+						zoom,
+						view,
+						mapping: last_mapping,
+						selection,
+						measurement,
+						sprite_sheet,
+						white_balance,
+						picking_gray_point,
+						..
+					}),
+				path,
+				..
+			}) => {
+				ui.centered_and_justified(|ui| {
+					self.config.background.draw(ui.painter(), ui.max_rect());
+
+					let scroll_zoom = self
+						.config
+						.scroll
+						.zoom(self.config.accessibility.reduce_motion);
+					let tint = white_balance.tint_color();
+					if let Some(sprite_sheet) = sprite_sheet {
+						let response =
+							show_sprite_sheet_cell(ui, image, sprite_sheet, zoom, view, scroll_zoom, tint);
+						image_response = Some(response);
+						return;
+					}
+
+					let single = matches!(play_state, PlayState::Single);
+					let shown_zoom = slideshow_zoom(ctx, path, *zoom, single, &self.config, self.slideshow);
+
+					let (response, image_rect) = show_current_frame(ui, image, play_state, shown_zoom, tint);
+					image_response = Some(response.clone());
+
+					let mapping = widgets::selection::PixelMapping {
+						image_rect,
+						image_size: Vec2::new(az::cast(image.width), az::cast(image.height)),
+					};
+					*last_mapping = Some(mapping);
+					if *picking_gray_point {
+						gray_point = gray_point_clicked(&response, mapping);
+					}
+					let swiping;
+					(swiping, swiped) = self.swipe.drag(&response, zoom.modified());
+					// shift-dragging selects a region, and dragging while measuring measures, rather than panning
+					if ui.input(|input| input.modifiers.shift) {
+						widgets::Selection::update_from_response(selection, &response, mapping);
+					} else if self.drag_mode == DragMode::Measure {
+						widgets::Measurement::update_from_response(measurement, &response, mapping);
+					} else if !swiping {
+						// interacting during the Ken Burns effect takes over from where it is
+						let mut updated = shown_zoom;
+						updated.update_from_response(&response, mapping.image_size, scroll_zoom);
+						if updated != shown_zoom {
+							*zoom = updated;
+						}
+					}
+					*view = Some(View::from_response(&response, mapping.image_size));
+					paint_overlays(ui, &self.config, mapping, *selection, *measurement);
+					paint_loupe(
+						ui,
+						&response,
+						image,
+						play_state,
+						mapping,
+						&self.config.loupe,
+						tint,
+					);
+				});
+			}
+			Some(state::OpenImage {
+				inner: Err(error),
+				path,
+				..
+			}) => {
+				let bundle = self.image_state.diagnostic_bundle.as_ref();
+				load_error_action = show_load_error(ui, path, error, &mut self.open_with, bundle);
+			}
+			None => {
+				ui.heading("no image open");
+			}
+		});
+
+		if let Some(pixel) = gray_point {
+			self.image_state.pick_gray_point(pixel);
+		}
+		if let Some(action) = load_error_action {
+			self.do_load_error_action(action);
+		}
+		if let Some(response) = image_response {
+			if let Some(description) = self.image_description() {
+				let announce = self.config.accessibility.announce_images;
+				widgets::accessibility::describe_image(&response, description, announce);
+			}
+			self.handle_image_interaction(ctx, &response, swiped);
+		}
+	}
+
+	fn do_load_error_action(&mut self, action: LoadErrorAction) {
+		match action {
+			LoadErrorAction::Retry => self.image_state.reload(),
+			LoadErrorAction::OpenWith => self.open_current_with(),
+			LoadErrorAction::SaveDiagnostics => self.image_state.save_diagnostics(),
+		}
+	}
+
+	/// Open the current image with the program typed into the "Open with" menu.
+	fn open_current_with(&mut self) {
+		let Some(current) = &self.image_state.current else {
+			return;
+		};
+		let path = Arc::clone(&current.path);
+		if let Err(error) = open_with::open(&path, &self.open_with) {
+			let program = match self.open_with.trim() {
+				"" => "the default application",
+				program => program,
+			};
+			self.image_state.push_error(format!(
+				"Could not open {} with {program}: {error}",
+				path.display()
+			));
+		}
+	}
+
+	/// What screen readers say about the current image, e.g., "cat.png, 3 of 12, 800 × 600 PNG".
+	fn image_description(&self) -> Option<String> {
+		let current = self.image_state.current.as_ref()?;
+		let image = &current.inner.as_ref().ok()?.image;
+		let name = current
+			.path
+			.file_name()
+			.unwrap_or_default()
+			.to_string_lossy();
+		let position = current
+			.index
+			.map(|(idx, total)| format!(", {} of {total}", idx + 1))
+			.unwrap_or_default();
+		let description = format!(
+			"{name}{position}, {} × {} {}",
+			image.width,
+			image.height,
+			format_to_string(image.format)
+		);
+		Some(description)
+	}
+
+	/// Handle the interactions with the image that act on the whole app. `swiped` is the direction of a finished touch swipe, if any.
+	fn handle_image_interaction(
+		&mut self,
+		ctx: &Context,
+		response: &egui::Response,
+		mut swiped: Option<Direction>,
+	) {
+		if response.double_clicked() {
+			toggle_fullscreen(ctx);
+		}
+		if response.hovered() {
+			swiped = swiped.or_else(|| self.swipe.scroll(ctx));
+			if self.config.scroll.wheel == config::WheelAction::Navigate {
+				self.navigate_by_scroll(ctx);
+			}
+		}
+		if let Some(direction) = swiped {
+			self.move_in(direction, MoveMode::RespectSlideshow);
+		}
+	}
+
+	fn navigate_by_scroll(&mut self, ctx: &Context) {
+		/// Scrolling this far moves by one image. One notch of most mouse wheels is 50 points.
+		const SCROLL_PER_IMAGE: f32 = 50.0;
+
+		let (scroll, modifiers) = ctx.input(|input| (input.raw_scroll_delta.y, input.modifiers));
+		// Alt-scrolling zooms by whole pixels
+		if modifiers.alt {
+			return;
+		}
+		self.scroll_navigation += scroll;
+		if self.scroll_navigation.abs() >= SCROLL_PER_IMAGE {
+			let direction = if self.scroll_navigation > 0.0 {
+				Direction::Left
+			} else {
+				Direction::Right
+			};
+			self.scroll_navigation = 0.0;
+			self.move_in(direction, MoveMode::RespectSlideshow);
+		}
+	}
+
+	/// A banner explaining that the configuration file couldn't be loaded, until it is dismissed or the file is fixed.
+	fn show_config_error(&mut self, ctx: &Context) {
+		let Some(error) = self.config.load_error().map(str::to_owned) else {
+			return;
+		};
+		let dismissed_id = egui::Id::new("config-error-dismissed");
+		if ctx.data(|data| data.get_temp::<bool>(dismissed_id)) == Some(true) {
+			return;
+		}
+
+		let mut overwrite = false;
+		let mut dismiss = false;
+		egui::TopBottomPanel::top("config-error").show(ctx, |ui| {
+			ui.horizontal_wrapped(|ui| {
+				ui.colored_label(ui.visuals().warn_fg_color, "⚠");
+				ui.label(format!(
+					"The configuration file {} could not be loaded, so the defaults are used. The file is left as it is, and fixing it applies it right away.",
+					self.config.path().display(),
+				));
+				overwrite = ui
+					.button("Overwrite on Exit")
+					.on_hover_text("Save the current settings over the file when eo2 exits")
+					.clicked();
+				dismiss = ui.button("Dismiss").clicked();
+			});
+			ui.collapsing("Details", |ui| ui.monospace(error));
+		});
+
+		if overwrite {
+			self.config.confirm_overwrite();
+		}
+		if dismiss {
+			ctx.data_mut(|data| data.insert_temp(dismissed_id, true));
+		}
+	}
+
+	fn show_settings(&mut self, ctx: &Context) {
+		let window = egui::Window::new("Settings")
+			.open(&mut self.settings_open)
+			.resizable(false)
+			.collapsible(true);
+		window.show(ctx, |ui| {
+			self.config.ui(ui, &mut self.settings_tab);
+		});
+	}
+
+	fn show_save_as(&mut self, ctx: &Context) {
+		let Some(dialog) = &mut self.save_as else {
+			return;
+		};
+		let Some(state::OpenImage {
+			inner: Ok(inner), ..
+		}) = &self.image_state.current
+		else {
+			self.save_as = None;
+			return;
+		};
+		let (width, height) = (inner.image.width, inner.image.height);
+		let original = inner.image.format;
+		let frame_idx = inner.frame_idx();
+
+		let mut open = true;
+		let mut save = false;
+		let window = egui::Window::new("Save As")
+			.open(&mut open)
+			.resizable(false)
+			.collapsible(true);
+		window.show(ctx, |ui| {
+			widgets::KeyValue::new("save-as-kv").show(ui, |mut rows| {
+				rows.row("Output", |ui| {
+					ui.add(egui::TextEdit::singleline(&mut dialog.target).desired_width(240.0))
+				});
+				rows.row("Format", |ui| dialog.format_ui(ui, original));
+				rows.row("Size", |ui| dialog.resize_ui(ui));
+				if dialog.resize != save_as::Resize::Original {
+					rows.row("Filter", |ui| {
+						ComboBox::from_id_source("save-as-filter-combo")
+							.selected_text(save_as::filter_to_string(dialog.filter))
+							.show_ui(ui, |ui| {
+								for &filter in save_as::FILTERS {
+									ui.selectable_value(
+										&mut dialog.filter,
+										filter,
+										save_as::filter_to_string(filter),
+									);
+								}
+							});
+					});
+					let (new_width, new_height) = dialog.resize.size(width, height);
+					rows.row("Result", |ui| {
+						ui.label(format!("{new_width} × {new_height}"))
+					});
+				}
+				let re_encodes = dialog.options(frame_idx).re_encodes();
+				rows.row("Metadata", |ui| {
+					ui.add_enabled(
+						!re_encodes,
+						egui::Checkbox::new(&mut dialog.strip_metadata, "Remove"),
+					)
+					.on_hover_text("Leave out the EXIF, XMP, and ICC data, e.g., the location and camera")
+					.on_disabled_hover_text("Converted and resized images never keep metadata");
+				});
+			});
+			ui.vertical_centered(|ui| save = ui.button("Save").clicked());
+		});
+
+		if save {
+			self.image_state.save_as(dialog.options(frame_idx));
+			open = false;
+		}
+		if !open {
+			self.save_as = None;
+		}
+	}
+
+	fn show_animation_export(&mut self, ctx: &Context) {
+		let Some((first, last)) = &mut self.animation_frames else {
+			return;
+		};
+		let Some(state::OpenImage {
+			inner: Ok(inner),
+			path,
+			..
+		}) = &self.image_state.current
+		else {
+			self.animation_frames = None;
+			return;
+		};
+		let num_frames = inner.image.frames.read().list.len();
+		let output = image::animation_export::output_path(path, self.config.animation_export.format);
+		let progress = self
+			.image_state
+			.animation_export_progress()
+			.map(batch::Progress::get);
+
+		let mut open = true;
+		let mut export = false;
+		let mut cancel = false;
+		let window = egui::Window::new("Export Animation")
+			.open(&mut open)
+			.resizable(false)
+			.collapsible(true);
+		window.show(ctx, |ui| {
+			ui.add_enabled_ui(progress.is_none(), |ui| {
+				self.config.animation_export.ui(ui);
+				widgets::KeyValue::new("animation-export-frames-kv").show(ui, |mut rows| {
+					rows.row("First Frame", |ui| {
+						ui.add(egui::DragValue::new(first).clamp_range(1..=*last))
+					});
+					rows.row("Last Frame", |ui| {
+						ui.add(egui::DragValue::new(last).clamp_range(*first..=num_frames))
+					});
+				});
+			});
+			ui.label(format!("Saves to {}", output.display()));
+			ui.label("Animated PNG is not supported, since it cannot be encoded yet");
+
+			match &self.image_state.animation_export_outcome {
+				_ if progress.is_some() => {}
+				Some(image::animation_export::Outcome::Saved(output)) => {
+					ui.label(format!("Saved to {}", output.display()));
+				}
+				Some(image::animation_export::Outcome::Cancelled) => {
+					ui.label("Cancelled");
+				}
+				Some(image::animation_export::Outcome::Failed(error)) => {
+					ui.colored_label(ui.visuals().error_fg_color, error);
+				}
+				None => {}
+			}
+			ui.vertical_centered(|ui| {
+				if let Some((done, total)) = progress {
+					let fraction = az::cast::<usize, f32>(done) / az::cast::<usize, f32>(total.max(1));
+					ui.add(egui::ProgressBar::new(fraction).text(format!("{done} of {total} frames")));
+					cancel = ui.button("Cancel").clicked();
+					ui.ctx()
+						.request_repaint_after(std::time::Duration::from_millis(100));
+				} else {
+					export = ui.button("Export").clicked();
+				}
+			});
+		});
+
+		if export {
+			let loops = self.config.animation_export.loops.map(|loops| match loops {
+				0 => image::loop_count::LoopCount::Forever,
+				times => image::loop_count::LoopCount::Times(times),
+			});
+			let options = image::animation_export::Options {
+				format: self.config.animation_export.format,
+				frames: *first - 1..=*last - 1,
+				speed: self.config.animation_export.speed,
+				loops,
+			};
+			self.image_state.export_animation(options);
+		}
+		if cancel || (!open && progress.is_some()) {
+			if let Some(progress) = self.image_state.animation_export_progress() {
+				progress.cancel();
+			}
+		}
+		if !open {
+			self.animation_frames = None;
+		}
+	}
+
+	fn show_sprite_sheet_export(&mut self, ctx: &Context) {
+		let Some((first, last)) = &mut self.sprite_sheet_frames else {
+			return;
+		};
+		let Some(state::OpenImage {
+			inner: Ok(inner),
+			path,
+			..
+		}) = &self.image_state.current
+		else {
+			self.sprite_sheet_frames = None;
+			return;
+		};
+		let num_frames = inner.image.frames.read().list.len();
+
+		let mut open = true;
+		let mut export = false;
+		let window = egui::Window::new("Export Sprite Sheet")
+			.open(&mut open)
+			.resizable(false)
+			.collapsible(true);
+		window.show(ctx, |ui| {
+			self.config.sprite_sheet.ui(ui);
+			widgets::KeyValue::new("sprite-sheet-frames-kv").show(ui, |mut rows| {
+				rows.row("First Frame", |ui| {
+					ui.add(egui::DragValue::new(first).clamp_range(1..=*last))
+				});
+				rows.row("Last Frame", |ui| {
+					ui.add(egui::DragValue::new(last).clamp_range(*first..=num_frames))
 				});
 			});
+			ui.label(format!(
+				"Saves to {} and {}",
+				image::sprite_sheet::image_path(path).display(),
+				image::sprite_sheet::descriptor_path(path).display(),
+			));
+			ui.vertical_centered(|ui| export = ui.button("Export").clicked());
 		});
-	}
 
-	fn show_sidebar(&mut self, ctx: &Context) {
-		if !self.config.show_sidebar {
-			return;
+		if export {
+			let options = image::sprite_sheet::Options {
+				layout: self.config.sprite_sheet,
+				frames: *first - 1..=*last - 1,
+			};
+			self.image_state.export_sprite_sheet(options);
+			open = false;
+		}
+		if !open {
+			self.sprite_sheet_frames = None;
 		}
+	}
 
-		let Some(state::OpenImage {
-			inner: Ok(state::OpenImageInner { image, .. }),
-			..
-		}) = &self.image_state.current
-		else {
+	fn show_video_export(&mut self, ctx: &Context) {
+		let Some(dialog) = &mut self.video_export else {
 			return;
 		};
+		if dialog.starting {
+			if let Some(paths) = self.image_state.slideshow_order.take() {
+				dialog.starting = false;
+				let settings = video_export::Settings {
+					width: self.config.video_export.width,
+					height: self.config.video_export.height,
+					frame_rate: self.config.video_export.frame_rate,
+					interval: self.config.slideshow.interval,
+					wait_for_animations: self.config.slideshow.wait_for_animations,
+				};
+				let output = PathBuf::from(&dialog.output);
+				dialog.export = Some(video_export::Export::start(
+					ctx.clone(),
+					paths,
+					output,
+					settings,
+				));
+			}
+		}
+		let status = dialog.export.as_ref().map(video_export::Export::status);
+		let running = dialog.starting || matches!(status, Some(video_export::Status::Running { .. }));
 
-		egui::SidePanel::right("properties").show(ctx, |ui| {
-			ui.vertical_centered(|ui| {
-				ui.heading("Properties");
+		let mut open = true;
+		let mut export = false;
+		let mut cancel = false;
+		let window = egui::Window::new("Export Slideshow as Video")
+			.open(&mut open)
+			.resizable(false)
+			.collapsible(true);
+		window.show(ctx, |ui| {
+			ui.add_enabled_ui(!running, |ui| {
+				self.config.video_export.ui(ui);
+				widgets::KeyValue::new("video-export-kv").show(ui, |mut rows| {
+					rows.row("Output", |ui| ui.text_edit_singleline(&mut dialog.output));
+				});
 			});
+			ui.label("Shows the images in the order of the slideshow, each for the slideshow interval. Requires ffmpeg. An existing file is not replaced");
 
-			widgets::KeyValue::new("properties-kv").show(ui, |mut rows| {
-				rows.row("Width", |ui| ui.label(image.width.to_string()));
-				rows.row("Height", |ui| ui.label(image.height.to_string()));
-				rows.row("Format", |ui| ui.label(format_to_string(image.format)));
-				rows.row("Kind", |ui| ui.label(image.kind().repr()));
-
-				rows.separator();
-				rows.row("File Size", |ui| {
-					ui.label(humansize::format_size(
-						image.metadata.file_size,
-						humansize::DECIMAL,
-					))
-				});
-				if let Some(mtime) = &image.metadata.mtime {
-					rows.row("Modified", |ui| ui.label(mtime));
+			match &status {
+				Some(video_export::Status::Running { done, total }) => {
+					let fraction = az::cast::<usize, f32>(*done) / az::cast::<usize, f32>((*total).max(1));
+					ui.add(egui::ProgressBar::new(fraction).text(format!("{done} of {total} images")));
+				}
+				Some(video_export::Status::Finished { output, skipped }) => {
+					ui.label(format!("Saved to {}", output.display()));
+					if *skipped > 0 {
+						ui.label(format!("{skipped} images could not be loaded and were left out"));
+					}
+				}
+				Some(video_export::Status::Cancelled) => {
+					ui.label("Cancelled");
+				}
+				Some(video_export::Status::Failed(error)) => {
+					ui.colored_label(ui.visuals().error_fg_color, error);
+				}
+				None => {}
+			}
+			ui.vertical_centered(|ui| {
+				if running {
+					cancel = ui.button("Cancel").clicked();
+				} else {
+					export = ui.button("Export").clicked();
 				}
 			});
 		});
+
+		if export {
+			dialog.starting = self.image_state.list_slideshow_order(
+				sort_order(&self.config),
+				navigation_filter(&self.config),
+				self.config.slideshow.shuffle,
+			);
+			dialog.export = None;
+		}
+		if cancel {
+			dialog.starting = false;
+			if let Some(export) = &dialog.export {
+				export.cancel();
+			}
+		}
+		if !open {
+			// dropping the export cancels it
+			self.video_export = None;
+		}
 	}
 
-	fn show_frames(&mut self, ctx: &Context) {
-		if !self.config.show_frames {
+	#[cfg(feature = "device-import")]
+	fn show_device_import(&mut self, ctx: &Context) {
+		let Some(dialog) = &mut self.device_import else {
 			return;
+		};
+
+		let mut open = true;
+		let mut refresh = false;
+		let mut import = false;
+		let window = egui::Window::new("Import from Device")
+			.open(&mut open)
+			.collapsible(true);
+		window.show(ctx, |ui| {
+			match &self.image_state.devices {
+				None => {
+					ui.spinner();
+				}
+				Some(devices) if devices.is_empty() => {
+					ui.label("No cameras or phones are mounted");
+				}
+				Some(devices) => {
+					egui::ScrollArea::vertical()
+						.max_height(400.0)
+						.show(ui, |ui| {
+							for device in devices {
+								ui.heading(&device.name);
+								if device.images.is_empty() {
+									ui.label("No images");
+								}
+								if !device.unreadable.is_empty() {
+									let details = device
+										.unreadable
+										.iter()
+										.map(|(path, error)| format!("{}: {error}", path.display()))
+										.collect::<Vec<_>>()
+										.join("\n");
+									ui.colored_label(
+										ui.visuals().warn_fg_color,
+										format!("⚠ {} entries couldn't be read", device.unreadable.len()),
+									)
+									.on_hover_text(details);
+								}
+								for entry in &device.images {
+									let mut selected = dialog.selected.contains(&entry.path);
+									let name = entry.path.file_name().unwrap_or_default().to_string_lossy();
+									let label = format!(
+										"{name} ({})",
+										humansize::format_size(entry.size, humansize::DECIMAL)
+									);
+									if ui
+										.checkbox(&mut selected, label)
+										.on_hover_text(entry.path.display().to_string())
+										.changed()
+									{
+										if selected {
+											dialog.selected.insert(entry.path.clone());
+										} else {
+											dialog.selected.remove(&entry.path);
+										}
+									}
+								}
+							}
+						});
+				}
+			}
+			ui.separator();
+			widgets::KeyValue::new("device-import-kv").show(ui, |mut rows| {
+				rows.row("Target Folder", |ui| {
+					ui.text_edit_singleline(&mut dialog.target)
+				});
+			});
+			ui.horizontal(|ui| {
+				refresh = ui.button("Refresh").clicked();
+				import = ui
+					.add_enabled(
+						!dialog.selected.is_empty(),
+						egui::Button::new(format!("Import {}", dialog.selected.len())),
+					)
+					.clicked();
+			});
+		});
+
+		if refresh {
+			self.image_state.list_devices();
+		}
+		if import {
+			let mut files: Vec<_> = dialog.selected.drain().collect();
+			files.sort_unstable_by(|a, b| natord::compare(&a.to_string_lossy(), &b.to_string_lossy()));
+			let target = PathBuf::from(&dialog.target);
+			self.image_state.import_from_device(files, target);
+			open = false;
+		}
+		if !open {
+			self.device_import = None;
 		}
+	}
 
+	fn show_sprite_sheet_view(&mut self, ctx: &Context) {
 		let Some(state::OpenImage {
 			inner:
 				Ok(state::OpenImageInner {
-					play_state: PlayState::Animated {
-						current_frame,
-						playing,
-					},
 					image,
+					sprite_sheet: sprite_sheet @ Some(..),
 					..
 				}),
 			..
@@ -419,150 +2953,244 @@ impl App {
 		else {
 			return;
 		};
-		let frames = &image.frames;
-
-		let outer_frame_size = Vec2::splat(100.0); // XXX 100 is arbitrary; make it configurable?
+		let image_size = [image.width, image.height];
 
-		let frame_style = {
-			let style = ctx.style();
-			Frame {
-				inner_margin: style.spacing.window_margin,
-				fill: style.visuals.window_fill(),
-				stroke: style.visuals.window_stroke(),
-				..Frame::default()
-			}
-		};
-		egui::TopBottomPanel::bottom("frames")
+		let mut open = true;
+		let window = egui::Window::new("Sprite Sheet")
+			.open(&mut open)
 			.resizable(false)
-			.frame(frame_style)
-			.default_height(outer_frame_size.y + frame_style.inner_margin.sum().y) // may not include the scroll bar, but that's fine. this is just a decent baseline
-			.show(ctx, |ui| {
-				egui::ScrollArea::horizontal().show_columns(
-					ui,
-					outer_frame_size.x,
-					frames.len(),
-					|ui, visible_range| {
-						// iterate over an enumerated subslice with correct indices
-						// XXX more elegant way to do that?
-						for (idx, (texture, frame_time)) in frames[visible_range.clone()]
-							.iter()
-							.enumerate()
-							.map(|(idx, v)| (idx + visible_range.start, v))
-						{
-							let button = widgets::ImageButton::new(texture, outer_frame_size)
-								.selected(idx == current_frame.idx);
-							let response = ui.add(button);
-							if response.clicked() {
-								// always stop playing if a user selects a frame
-								*playing = false;
-								current_frame.move_to(idx, *frame_time);
-							}
-							// inline of on_hover_text that lazily evaluates `format!`
-							response.on_hover_ui(|ui| {
-								ui.label(format!("Frame {}, {}", idx + 1, frames[idx].1));
-							});
-						}
-					},
-				);
+			.collapsible(true);
+		window.show(ctx, |ui| {
+			let view = sprite_sheet.as_mut().unwrap();
+			let previous_grid = view.grid;
+			let [columns, rows] = view.dimensions(image_size);
+			let cell = view.cell_rect(0, image_size);
+			let [cell_width, cell_height] = [cell.width(), cell.height()].map(az::cast::<f32, u32>);
+
+			// switching modes keeps the current layout
+			ui.horizontal(|ui| {
+				let is_count = matches!(view.grid, state::play::Grid::Count { .. });
+				if ui
+					.radio(is_count, "Grid")
+					.on_hover_text("Split into a number of columns and rows")
+					.clicked()
+				{
+					view.grid = state::play::Grid::Count { columns, rows };
+				}
+				if ui
+					.radio(!is_count, "Cell Size")
+					.on_hover_text("Split into cells of a fixed size")
+					.clicked()
+				{
+					view.grid = state::play::Grid::CellSize {
+						width: cell_width,
+						height: cell_height,
+					};
+				}
 			});
-	}
 
-	fn update_slideshow(&mut self, ctx: &Context) {
-		let elapsed = ctx.input(|input| input.unstable_dt);
+			widgets::KeyValue::new("sprite-sheet-view-kv").show(ui, |mut rows_ui| {
+				match &mut view.grid {
+					state::play::Grid::Count { columns, rows } => {
+						rows_ui.row("Columns", |ui| {
+							ui.add(egui::DragValue::new(columns).clamp_range(1..=image.width))
+						});
+						rows_ui.row("Rows", |ui| {
+							ui.add(egui::DragValue::new(rows).clamp_range(1..=image.height))
+						});
+					}
+					state::play::Grid::CellSize { width, height } => {
+						rows_ui.row("Width", |ui| {
+							ui.add(
+								egui::DragValue::new(width)
+									.clamp_range(1..=image.width)
+									.suffix(" px"),
+							)
+						});
+						rows_ui.row("Height", |ui| {
+							ui.add(
+								egui::DragValue::new(height)
+									.clamp_range(1..=image.height)
+									.suffix(" px"),
+							)
+						});
+					}
+				}
+				rows_ui.row("FPS", |ui| {
+					ui.add(egui::Slider::new(&mut view.fps, 1.0..=60.0).logarithmic(true))
+				});
+				rows_ui.row("Cell", |ui| {
+					let num_cells = view.num_cells(image_size);
+					ui.label(format!(
+						"{} of {num_cells}",
+						view.current_frame.idx.min(num_cells - 1) + 1
+					))
+				});
+			});
 
-		let next_from_slideshow = self
-			.slideshow
-			.advance(Duration::new_secs_f32_saturating(elapsed));
+			if view.grid != previous_grid {
+				view.current_frame.move_to(0, view.frame_time());
+			}
 
-		if next_from_slideshow {
-			self.move_in(Direction::Right, MoveMode::RespectSlideshow);
-		}
+			ui.vertical_centered(|ui| {
+				let label = if view.playing { "Pause" } else { "Play" };
+				if ui.button(label).clicked() {
+					view.playing ^= true;
+				}
+			});
+		});
 
-		if let SlideshowState::Active { remaining } = self.slideshow {
-			ctx.request_repaint_after(remaining.into());
+		if !open {
+			*sprite_sheet = None;
 		}
 	}
 
-	fn show_central(&mut self, ctx: &Context) {
-		let panel = {
-			let margin = if matches!(
-				self.image_state.current,
-				Some(state::OpenImage { inner: Ok(..), .. })
-			) {
-				0.0
-			} else {
-				8.0
-			};
-			let frame = Frame::none()
-				.fill(ctx.style().visuals.window_fill())
-				.inner_margin(margin);
-			egui::CentralPanel::default().frame(frame)
-		};
+	fn show_internal(&mut self, ctx: &Context) {
+		let mut clear_cache = false;
+		let window = egui::Window::new("Internal")
+			.open(&mut self.internal_open)
+			.collapsible(true);
+		window.show(ctx, |ui| {
+			let info = self.image_state.cache_info();
+			let size = |size| humansize::format_size(size, humansize::BINARY);
 
-		panel.show(ctx, |ui| match &mut self.image_state.current {
-			Some(state::OpenImage {
-				inner: Ok(state::OpenImageInner {
-					play_state,
-					image,
-					zoom,
-					..
-				}),
-				..
-			}) => {
-				ui.centered_and_justified(|ui| {
-					self.config.background.draw(ui.painter(), ui.max_rect());
-					let response = match play_state {
-						PlayState::Single => {
-							ui.add(widgets::Image::for_texture(&image.frames[0].0).zoom(*zoom))
+			ui.heading("Image Cache");
+			widgets::KeyValue::new("internal-cache-kv").show(ui, |mut rows| {
+				rows.row("Memory Usage", |ui| {
+					ui.label(format!("{} of {}", size(info.weight), size(info.capacity)))
+				});
+				rows.row("GPU Usage", |ui| {
+					ui.label(format!(
+						"{} of {}",
+						size(info.gpu_weight),
+						size(info.gpu_capacity)
+					))
+				});
+				rows.row("Entries", |ui| ui.label(info.entries.len().to_string()));
+				rows.row("Hits", |ui| ui.label(info.hits.to_string()));
+				rows.row("Misses", |ui| ui.label(info.misses.to_string()));
+			});
+			clear_cache = ui.button("Clear Cache").clicked();
+
+			ui.heading("Power");
+			widgets::KeyValue::new("internal-power-kv").show(ui, |mut rows| {
+				let yes_no = |value| if value { "Yes" } else { "No" };
+				rows.row("On Battery", |ui| ui.label(yes_no(self.power.on_battery())));
+				rows.row("Saving Power", |ui| ui.label(yes_no(power::saving())));
+			});
+
+			ui.separator();
+			egui::ScrollArea::vertical().show(ui, |ui| {
+				egui::Grid::new("internal-cache-entries")
+					.striped(true)
+					.show(ui, |ui| {
+						for heading in ["Path", "Dimensions", "Frames", "In Memory", "On GPU"] {
+							ui.strong(heading);
 						}
-						PlayState::Animated {
-							current_frame,
-							playing,
-						} => {
-							let (current_texture, _) = &image.frames[current_frame.idx];
-							let response = ui.add(
-								widgets::Image::for_texture(current_texture)
-									.clickable(true)
-									.zoom(*zoom),
-							);
-							if response.clicked() {
-								*playing = !*playing;
-							}
-							if *playing {
-								let elapsed = ctx.input(|input| input.unstable_dt);
-								current_frame.advance(
-									Duration::new_secs_f32_saturating(elapsed),
-									image.frames.len(),
-									|idx| image.frames[idx].1,
-								);
-								ctx.request_repaint_after(current_frame.remaining.into());
-							}
-							response
+						ui.end_row();
+						for entry in &info.entries {
+							ui.label(entry.path.display().to_string());
+							ui.label(format!("{}×{}", entry.width, entry.height));
+							ui.label(entry.frames.to_string());
+							ui.label(size(entry.weight));
+							ui.label(if entry.resident { "Yes" } else { "No" });
+							ui.end_row();
 						}
-					};
+					});
+			});
 
-					zoom.update_from_response(&response);
-				});
-			}
-			Some(state::OpenImage {
-				inner: Err(error), ..
-			}) => {
-				ui.heading(format!("error: {error}"));
-			}
-			None => {
-				ui.heading("no image open");
+			ui.separator();
+			ui.collapsing("Recent Events", |ui| {
+				for event in self.event_log.borrow().iter().rev() {
+					ui.label(event.to_string());
+				}
+			});
+		});
+
+		if clear_cache {
+			self.image_state.clear_cache();
+		}
+	}
+
+	fn show_workspaces(&mut self, ui: &mut egui::Ui) {
+		ui.horizontal(|ui| {
+			ui.add(egui::TextEdit::singleline(&mut self.workspace_name).hint_text("Name"));
+			let name = self.workspace_name.trim();
+			if ui
+				.add_enabled(!name.is_empty(), egui::Button::new("Save"))
+				.on_hover_text("Save the images being viewed, the zoom and whether it is locked, the marked images, and the sort order and filter")
+				.clicked()
+			{
+				let listing = workspace::Listing::from_config(&self.config);
+				self.image_state.save_workspace(name.to_owned(), listing);
+				ui.close_menu();
 			}
 		});
+		ui.separator();
+
+		let names = match self.image_state.workspace_names() {
+			Ok(names) => names.clone(),
+			Err(error) => {
+				ui.label(format!("Could not list the workspaces: {error}"));
+				return;
+			}
+		};
+		if names.is_empty() {
+			ui.label("No saved workspaces");
+		}
+		for name in names {
+			ui.horizontal(|ui| {
+				if ui.button(&name).on_hover_text("Load").clicked() {
+					self.image_state.load_workspace(name.clone());
+					self.workspace_name.clone_from(&name);
+					ui.close_menu();
+				}
+				if ui.small_button("🗑").on_hover_label("Delete").clicked() {
+					self.image_state.delete_workspace(name);
+				}
+			});
+		}
 	}
 
-	fn show_settings(&mut self, ctx: &Context) {
-		let window = egui::Window::new("Settings")
-			.open(&mut self.settings_open)
-			.resizable(false)
+	fn show_history(&mut self, ctx: &Context) {
+		let mut undo = false;
+		let mut redo = false;
+		let window = egui::Window::new("History")
+			.open(&mut self.history_open)
 			.collapsible(true);
 		window.show(ctx, |ui| {
-			self.config.ui(ui);
+			let history = &self.image_state.history;
+			ui.horizontal(|ui| {
+				undo = ui
+					.add_enabled(!history.undo.is_empty(), egui::Button::new("⟲ Undo"))
+					.on_hover_text("Ctrl-Z")
+					.clicked();
+				redo = ui
+					.add_enabled(!history.redo.is_empty(), egui::Button::new("⟳ Redo"))
+					.on_hover_text("Ctrl-Shift-Z")
+					.clicked();
+			});
+			ui.separator();
+			if history.undo.is_empty() && history.redo.is_empty() {
+				ui.label("Nothing has been changed yet");
+			}
+			egui::ScrollArea::vertical().show(ui, |ui| {
+				// newest first, with the undone operations above the current point
+				for description in &history.redo {
+					ui.weak(description);
+				}
+				for description in history.undo.iter().rev() {
+					ui.label(description);
+				}
+			});
 		});
+
+		if undo {
+			self.image_state.undo();
+		}
+		if redo {
+			self.image_state.redo();
+		}
 	}
 
 	fn show_asking_to_delete(&mut self, ctx: &Context) {
@@ -599,86 +3227,232 @@ impl App {
 		}
 	}
 
-	fn handle_global_keys(&mut self, ctx: &Context) {
-		use egui::Key;
-
-		const KEYS: &[(Key, Modifiers, Direction)] = &[
-			(Key::ArrowLeft, Modifiers::NONE, Direction::Left),
-			(Key::ArrowRight, Modifiers::NONE, Direction::Right),
-			(Key::P, Modifiers::NONE, Direction::Left),
-			(Key::N, Modifiers::NONE, Direction::Right),
-			(Key::N, Modifiers::SHIFT, Direction::Left),
-		];
-
-		for &(key, modifiers, direction) in KEYS {
-			debug_assert!(!modifiers.contains(Modifiers::ALT));
-			let mode = ctx.input_mut(|input| {
-				Some(if input.consume_key(modifiers, key) {
-					MoveMode::RespectSlideshow
-				} else if input.consume_key(modifiers | Modifiers::ALT, key) {
-					MoveMode::IgnoreSlideshow
-				} else {
-					return None;
-				})
-			});
-			if let Some(mode) = mode {
-				self.move_in(direction, mode);
+	/// Apply the configuration file again if it was edited while running. Changes made in the settings since the start are replaced by the file.
+	fn reload_config(&mut self) {
+		if !self.config_watch.take_changed() {
+			return;
+		}
+		match self.config.reload() {
+			Ok(mut config) => {
+				self.overridden.carry_over(&self.config, &mut config);
+				self.config = config;
 			}
+			Err(error) => self
+				.image_state
+				.push_error(format!("Reloading the configuration: {}", error.0)),
 		}
+	}
 
-		if ctx.input_mut(|input| input.consume_key(Modifiers::CTRL | Modifiers::SHIFT, Key::I)) {
-			self.internal_open = !self.internal_open;
+	fn handle_global_keys(&mut self, ctx: &Context) {
+		let actions = ctx.input_mut(|input| self.config.bindings.take_pressed(input));
+		for action in actions {
+			self.do_action(ctx, action);
 		}
 
-		let key = |key| ctx.input_mut(|input| input.consume_key(Modifiers::NONE, key));
+		self.handle_mouse_buttons(ctx);
+	}
 
-		if key(Key::S) {
-			self.slideshow.toggle(&self.config);
+	fn do_action(&mut self, ctx: &Context, action: Action) {
+		if let Some(stars) = action.stars() {
+			self
+				.image_state
+				.update_rating(|rating| rating.stars = stars);
+			return;
 		}
 
-		if key(Key::F) {
-			ctx.send_viewport_cmd(ViewportCommand::Fullscreen(!self.fullscreen));
+		match action {
+			Action::Next => self.move_in(Direction::Right, MoveMode::RespectSlideshow),
+			Action::Previous => self.move_in(Direction::Left, MoveMode::RespectSlideshow),
+			Action::NextIgnoringSlideshow => self.move_in(Direction::Right, MoveMode::IgnoreSlideshow),
+			Action::PreviousIgnoringSlideshow => {
+				self.move_in(Direction::Left, MoveMode::IgnoreSlideshow);
+			}
+			Action::ZoomFit => self.set_zoom(widgets::image::Zoom::fit()),
+			Action::ZoomFill => self.set_zoom(widgets::image::Zoom::fill()),
+			Action::ZoomActualSize => self.set_zoom(widgets::image::Zoom::actual_size()),
+			Action::ZoomContent => self.zoom_to_content(),
+			Action::ToggleSlideshow => self.slideshow.toggle(&self.config),
+			Action::ExtendSlideshow => self.slideshow.extend(self.config.slideshow.extra_time),
+			Action::ToggleFullscreen => toggle_fullscreen(ctx),
+			Action::ToggleSidebar => self.config.show_sidebar ^= true,
+			Action::ToggleSettings => self.settings_open ^= true,
+			Action::ToggleInternal => self.internal_open ^= true,
+			Action::ToggleLockView => self.image_state.lock_view ^= true,
+			Action::ToggleLoupe => self.config.loupe.enabled ^= true,
+			Action::ToggleMeasure => {
+				self.drag_mode = match self.drag_mode {
+					DragMode::Pan => DragMode::Measure,
+					DragMode::Measure => DragMode::Pan,
+				};
+			}
+			Action::ToggleMark => self.image_state.toggle_mark(),
+			Action::ToggleFavorite => self
+				.image_state
+				.update_rating(|rating| rating.favorite ^= true),
+			Action::CopyPixels => self.image_state.copy_pixels(),
+			Action::CopyDataUri => self
+				.image_state
+				.copy_data_uri(self.config.data_uri_max_size.get(), data_uri::Markup::Uri),
+			Action::CopyHtml => self
+				.image_state
+				.copy_data_uri(self.config.data_uri_max_size.get(), data_uri::Markup::Html),
+			Action::ClearSelection => {
+				if let Some(state::OpenImage {
+					inner: Ok(inner), ..
+				}) = &mut self.image_state.current
+				{
+					inner.selection = None;
+					inner.measurement = None;
+				}
+			}
+			Action::Undo => self.image_state.undo(),
+			Action::Redo => self.image_state.redo(),
+			Action::Quit => ctx.send_viewport_cmd(ViewportCommand::Close),
+			Action::Rate1
+			| Action::Rate2
+			| Action::Rate3
+			| Action::Rate4
+			| Action::Rate5
+			| Action::ClearRating => unreachable!("handled above"),
 		}
+	}
 
-		if key(Key::I) {
-			self.config.show_sidebar ^= true;
+	fn set_zoom(&mut self, zoom: widgets::image::Zoom) {
+		if let Some(state::OpenImage {
+			inner: Ok(inner), ..
+		}) = &mut self.image_state.current
+		{
+			inner.zoom = zoom;
 		}
+	}
 
-		if key(Key::C) {
-			self.settings_open ^= true;
+	/// Zoom to fit the content of the image, ignoring uniform borders.
+	fn zoom_to_content(&mut self) {
+		if let Some(state::OpenImage {
+			inner: Ok(inner), ..
+		}) = &mut self.image_state.current
+		{
+			if let (Some(content), None) = (inner.image.content, &inner.sprite_sheet) {
+				inner.zoom = widgets::image::Zoom::fit_to(content);
+			}
 		}
+	}
+
+	/// The back and forward buttons that some mice have move between images, like the arrow keys.
+	fn handle_mouse_buttons(&mut self, ctx: &Context) {
+		use egui::PointerButton;
+
+		const BUTTONS: &[(PointerButton, Direction)] = &[
+			(PointerButton::Extra1, Direction::Left),
+			(PointerButton::Extra2, Direction::Right),
+		];
 
-		if key(Key::Q) {
-			ctx.send_viewport_cmd(ViewportCommand::Close);
+		for &(button, direction) in BUTTONS {
+			let (pressed, alt) =
+				ctx.input(|input| (input.pointer.button_pressed(button), input.modifiers.alt));
+			if pressed {
+				let mode = if alt {
+					MoveMode::IgnoreSlideshow
+				} else {
+					MoveMode::RespectSlideshow
+				};
+				self.move_in(direction, mode);
+			}
 		}
 	}
 
 	fn handle_actor_responses(&mut self) {
-		self.image_state.handle_actor_responses();
+		self.image_state.handle_actor_responses(&mut self.events);
+		if let Some(listing) = self.image_state.take_workspace_listing() {
+			listing.apply(&mut self.config);
+		}
+	}
+
+	fn handle_events(&mut self, ctx: &Context) {
+		for event in self.events.dispatch() {
+			match event {
+				// scrolling or swiping partway towards another image shouldn't carry over to the next one
+				events::Event::ImageOpened(_) => {
+					self.scroll_navigation = 0.0;
+					self.swipe = swipe::Swipe::default();
+				}
+				events::Event::FileDeleted(path) => {
+					if self.asking_to_delete.as_ref() == Some(&path) {
+						self.asking_to_delete = None;
+					}
+				}
+				events::Event::EndReached => self.end_slideshow(ctx),
+				events::Event::ImageClosed(_)
+				| events::Event::FileWritten(_)
+				| events::Event::RatingChanged(..)
+				| events::Event::SlideshowTick => {}
+			}
+		}
 	}
 }
 
 impl eframe::App for App {
-	fn update(&mut self, ctx: &Context, _frame: &mut eframe::Frame) {
+	// everything is covered by panels anyway, and this allows the window to be transparent if configured.
+	fn clear_color(&self, _visuals: &egui::Visuals) -> [f32; 4] {
+		Color32::TRANSPARENT.to_normalized_gamma_f32()
+	}
+
+	fn update(&mut self, ctx: &Context, frame: &mut eframe::Frame) {
+		self.reload_config();
+		self
+			.image_state
+			.set_cache_limits(cache_limits(&self.config));
+		theme::update(ctx, &self.config, frame.info().system_theme);
+		update_animation_time(ctx, self.config.accessibility.reduce_motion);
+		update_ui_scale(ctx, self.config.ui_scale);
+		update_decorations(ctx, self.config.window.borderless);
+		self.power.update(self.config.power_saving);
+
 		if !ctx.wants_keyboard_input() {
 			self.handle_global_keys(ctx);
 		}
 
 		self.update_slideshow(ctx);
 		self.handle_actor_responses();
+		self.handle_events(ctx);
+		self.image_state.request_rating();
 		self.image_state.show_errors(ctx);
 
 		self.show_settings(ctx);
+		self.show_internal(ctx);
+		self.show_history(ctx);
+		self.show_slideshow_finished(ctx);
 		self.show_asking_to_delete(ctx);
-
-		self.show_actions(ctx);
-		self.show_sidebar(ctx);
-		self.show_frames(ctx);
+		self.show_sprite_sheet_export(ctx);
+		self.show_animation_export(ctx);
+		self.show_save_as(ctx);
+		self.show_sprite_sheet_view(ctx);
+		self.show_video_export(ctx);
+		#[cfg(feature = "device-import")]
+		self.show_device_import(ctx);
+
+		self.show_config_error(ctx);
+		let show_panels = self
+			.auto_hide
+			.update(ctx, self.config.auto_hide, self.slideshow.is_active());
+		if show_panels {
+			self.show_actions(ctx);
+			self.show_sidebar(ctx);
+			self.show_status_bar(ctx);
+			self.show_frames(ctx);
+		}
+		let image_area = ctx.available_rect();
+		self.auto_hide.set_image_area(image_area);
 		self.show_central(ctx);
+		self.show_slideshow_progress(ctx, image_area);
+		self.update_title(ctx);
 	}
 
 	// NB save is not called without the persistence feature, so on_exit is a better option
 	fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+		// removes the temporary file
+		self.piped = None;
+		self.overridden.restore(&mut self.config);
 		if let Err(error) = self.config.save() {
 			error::show(error.to_string());
 		}
@@ -1,7 +1,13 @@
 use std::cmp::Ordering;
-use std::hash::{Hash, Hasher as _};
 use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+use icu_collator::{Collator, CollatorOptions, Numeric, Strength};
+
+use super::rating;
+use crate::config::{HiddenFiles, NameOrder, RatingFilter, Sort};
 
 #[derive(Clone, Copy, Debug)]
 pub enum Direction {
@@ -9,18 +15,6 @@ pub enum Direction {
 	Right,
 }
 
-#[derive(Clone, Copy, Debug)]
-pub enum Mode {
-	Simple,
-	Random { seed: u64 },
-}
-
-#[derive(Clone, Copy, Debug)]
-pub struct NextPath {
-	pub direction: Direction,
-	pub mode: Mode,
-}
-
 impl Direction {
 	fn for_ordering(self, ordering: Ordering) -> Ordering {
 		match self {
@@ -39,35 +33,218 @@ impl Direction {
 }
 
 trait MakeFindNextKey {
-	type Key: Ord + Eq + Clone + Copy + std::fmt::Debug;
+	type Key: Ord + Eq + Clone + std::fmt::Debug;
 
 	fn for_name(&self, s: &str) -> Self::Key;
 }
 
+#[cfg(test)]
 struct NoKey;
 
+#[cfg(test)]
 impl MakeFindNextKey for NoKey {
 	type Key = ();
 
 	fn for_name(&self, _: &str) -> Self::Key {}
 }
 
-struct WithHash {
-	seed: u64,
+/// The order of the images when moving between them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Order {
+	pub sort: Sort,
+	pub names: NameOrder,
+}
+
+/// Orders the files in `dir` by `order.sort`, then by their names as compared by `order.names`.
+struct SortKey<'a> {
+	order: Order,
+	dir: &'a Path,
+}
+
+impl MakeFindNextKey for SortKey<'_> {
+	/// The folded name is `None` when names are compared as they are.
+	type Key = (u128, Option<FoldedName>);
+
+	fn for_name(&self, name: &str) -> Self::Key {
+		// files that cannot be read sort first
+		let metadata = || std::fs::metadata(self.dir.join(name)).ok();
+		let key = match self.order.sort {
+			Sort::Name => 0,
+			Sort::Modified => metadata()
+				.and_then(|metadata| metadata.modified().ok())
+				.and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+				.map_or(0, |since_epoch| since_epoch.as_nanos()),
+			Sort::Size => metadata().map_or(0, |metadata| metadata.len().into()),
+		};
+		(key, fold(name, self.order.names))
+	}
+}
+
+/// `name` as it is compared by `names`, or `None` if names are compared as they are.
+fn fold(name: &str, names: NameOrder) -> Option<FoldedName> {
+	match names {
+		NameOrder::CaseSensitive => None,
+		NameOrder::IgnoreCase => Some(FoldedName::Lowercase(HumanCompare(name.to_lowercase()))),
+		NameOrder::IgnoreCaseAndAccents => Some(FoldedName::Collated(Collated(name.to_owned()))),
+	}
+}
+
+/// Names in one listing are all folded the same way, so the variants are never compared with each other.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum FoldedName {
+	Lowercase(HumanCompare<String>),
+	Collated(Collated),
+}
+
+thread_local! {
+	/// Compares only the base letters, in the root collation order of Unicode since the locale is not known, and numbers by their value.
+	#[allow(clippy::default_trait_access)] // the root locale, whose type is not re-exported
+	static COLLATOR: Collator = {
+		let mut options = CollatorOptions::new();
+		options.strength = Some(Strength::Primary);
+		options.numeric = Some(Numeric::On);
+		Collator::try_new(&Default::default(), options).expect("the collation data is built in")
+	};
+}
+
+/// A name compared ignoring case and the accents of any script, e.g., `é` is equal to `e` and `ё` to `е`.
+#[derive(Debug, Clone)]
+struct Collated(String);
+
+impl PartialEq for Collated {
+	fn eq(&self, other: &Self) -> bool {
+		self.cmp(other).is_eq()
+	}
+}
+
+impl Eq for Collated {}
+
+impl PartialOrd for Collated {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
 }
 
-pub fn fxhash(v: &(impl Hash + ?Sized)) -> u64 {
-	let mut hasher = rustc_hash::FxHasher::default();
-	v.hash(&mut hasher);
-	hasher.finish()
+impl Ord for Collated {
+	fn cmp(&self, other: &Self) -> Ordering {
+		COLLATOR.with(|collator| collator.compare(&self.0, &other.0))
+	}
+}
+
+/// Which files are shown when moving between the images in a directory, from a comma-separated list of globs or extensions, e.g., `*.png,*.webp` or `png, webp`.
+/// Names are matched case-insensitively, and an empty list matches every image.
+/// Images can also be left out by their rating, and hidden files are left out unless they are included.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Filter {
+	/// Lowercase globs, where `*` matches any run of characters and `?` matches one.
+	patterns: Vec<String>,
+	rating: RatingFilter,
+	hidden_files: HiddenFiles,
+}
+
+impl Filter {
+	pub fn parse(raw: &str) -> Self {
+		let patterns = raw
+			.split(',')
+			.map(str::trim)
+			.filter(|pattern| !pattern.is_empty())
+			.map(|pattern| {
+				let pattern = pattern.to_lowercase();
+				if pattern.contains(['*', '?']) {
+					pattern
+				} else {
+					// a bare extension, with or without the dot
+					format!("*.{}", pattern.trim_start_matches('.'))
+				}
+			})
+			.collect();
+		Self {
+			patterns,
+			rating: RatingFilter::default(),
+			hidden_files: HiddenFiles::default(),
+		}
+	}
+
+	#[must_use]
+	pub fn with_rating(self, rating: RatingFilter) -> Self {
+		Self { rating, ..self }
+	}
+
+	#[must_use]
+	pub fn with_hidden_files(self, hidden_files: HiddenFiles) -> Self {
+		Self {
+			hidden_files,
+			..self
+		}
+	}
+
+	pub fn matches(&self, name: &str) -> bool {
+		if self.hidden_files == HiddenFiles::Skip && name.starts_with('.') {
+			return false;
+		}
+		let name = name.to_lowercase();
+		self.patterns.is_empty()
+			|| self
+				.patterns
+				.iter()
+				.any(|pattern| glob_matches(pattern, &name))
+	}
 }
 
-impl MakeFindNextKey for WithHash {
-	type Key = u64;
+fn glob_matches(pattern: &str, name: &str) -> bool {
+	let pattern: Vec<char> = pattern.chars().collect();
+	let name: Vec<char> = name.chars().collect();
+	let (mut pattern_idx, mut name_idx) = (0, 0);
+	// the last `*` and the position in the name that it matches up to, to backtrack to if the rest doesn't match
+	let mut star = None;
+	while name_idx < name.len() {
+		match pattern.get(pattern_idx) {
+			Some('*') => {
+				star = Some((pattern_idx, name_idx));
+				pattern_idx += 1;
+			}
+			Some(&ch) if ch == '?' || ch == name[name_idx] => {
+				pattern_idx += 1;
+				name_idx += 1;
+			}
+			_ => {
+				let Some((star_idx, matched_to)) = star else {
+					return false;
+				};
+				// let the `*` match one more character
+				star = Some((star_idx, matched_to + 1));
+				pattern_idx = star_idx + 1;
+				name_idx = matched_to + 1;
+			}
+		}
+	}
+	pattern[pattern_idx..].iter().all(|&ch| ch == '*')
+}
 
-	fn for_name(&self, s: &str) -> Self::Key {
-		fxhash(&(self.seed, s))
+#[test]
+fn test_filter() {
+	let filter = Filter::parse("*.png, webp,.JPG");
+	for name in ["a.png", "B.PNG", "c.webp", "d.jpg"] {
+		assert!(filter.matches(name), "{name} matches");
 	}
+	assert!(!filter.matches(".png"));
+	assert!(filter
+		.clone()
+		.with_hidden_files(HiddenFiles::Include)
+		.matches(".png"));
+	for name in ["a.gif", "png", "a.png.bak", "webp.gif"] {
+		assert!(!filter.matches(name), "{name} does not match");
+	}
+
+	let filter = Filter::parse("img_??.*");
+	assert!(filter.matches("img_01.png"));
+	assert!(!filter.matches("img_001.png"));
+
+	assert!(Filter::parse(" , ").matches("anything.gif"));
+	assert!(!Filter::parse("").matches(".thumbnail.png"));
+	assert!(Filter::parse("")
+		.with_hidden_files(HiddenFiles::Include)
+		.matches(".thumbnail.png"));
 }
 
 #[derive(Debug, Clone)]
@@ -147,7 +324,7 @@ fn find_next_impl<K: MakeFindNextKey + ?Sized>(
 
 		if direction.after(
 			&FindNextItem {
-				key: this_name.key,
+				key: this_name.key.clone(),
 				name: this_name.name.as_ref(),
 			},
 			&current_name,
@@ -168,8 +345,6 @@ fn find_next_impl<K: MakeFindNextKey + ?Sized>(
 
 #[test]
 fn test_find_next_impl() {
-	use std::collections::HashSet;
-
 	const FILES: &[&str] = &["a", "b", "c", "d"];
 
 	fn files() -> impl Iterator<Item = String> {
@@ -204,73 +379,107 @@ fn test_find_next_impl() {
 		Some((FILES.last().copied().unwrap().into(), FILES.len() - 1)),
 	);
 
-	// fuzz with various seeds
-	for _ in 0..20 {
-		let random_seed = rand::random();
-		let mut current = FILES.first().copied().unwrap().to_owned();
-		let mut seen = HashSet::from([current.clone()]);
-		let mut seen_idxs = HashSet::from([0]);
-		loop {
-			let (next, next_idx) = find_next_impl(
-				Direction::Right,
-				&current,
-				files(),
-				&WithHash { seed: random_seed },
-			)
-			.unwrap();
-			if next == FILES.first().copied().unwrap() {
-				break;
-			}
-			assert!(seen_idxs.insert(next_idx), "no indexes are repeated");
-			assert!(seen.insert(next.clone()), "no files are repeated");
-			current = next;
-		}
-		assert_eq!(seen, files().collect(), "all files are seen");
-		assert_eq!(
-			seen_idxs,
-			(0..FILES.len()).collect(),
-			"all indexes are seen"
-		);
-	}
+	// without wrapping, the ends are final
+	assert_eq!(
+		find_next(Direction::Right, "b", files(), &NoKey, false),
+		Some(("c".into(), 2)),
+	);
+	assert_eq!(
+		find_next(
+			Direction::Right,
+			FILES.last().unwrap(),
+			files(),
+			&NoKey,
+			false
+		),
+		None,
+	);
+	assert_eq!(
+		find_next(
+			Direction::Left,
+			FILES.first().unwrap(),
+			files(),
+			&NoKey,
+			false
+		),
+		None,
+	);
 }
 
-pub fn read_dir_to_find_next_iterator(dir: std::fs::ReadDir) -> impl Iterator<Item = String> {
-	dir
-		.filter_map(Result::ok)
-		.filter(|entry| entry.file_type().map_or(false, |ty| !ty.is_dir()))
-		.map(|entry| entry.file_name())
-		.filter(|name| image::ImageFormat::from_path(name).is_ok())
-		.map(|name| name.to_string_lossy().into_owned())
-}
-
-impl NextPath {
-	fn find_next(
-		self,
-		current_name: &str,
-		items: impl Iterator<Item = String>,
-	) -> Option<(String, usize)> {
-		match self.mode {
-			Mode::Simple => find_next_impl(self.direction, current_name, items, &NoKey),
-			Mode::Random { seed } => {
-				find_next_impl(self.direction, current_name, items, &WithHash { seed })
-			}
-		}
-	}
+/// The names of the images in `dir` that match `filter`.
+pub fn read_dir_to_find_next_iterator<'a>(
+	dir: &Path,
+	filter: &'a Filter,
+) -> io::Result<impl Iterator<Item = String> + 'a> {
+	// the ratings are only read if they can leave images out
+	let ratings = if filter.rating.is_active() {
+		rating::read_dir(dir)?
+	} else {
+		std::collections::BTreeMap::new()
+	};
+	Ok(
+		dir
+			.read_dir()?
+			.filter_map(Result::ok)
+			.filter(|entry| entry.file_type().map_or(false, |ty| !ty.is_dir()))
+			.map(|entry| entry.file_name())
+			.filter(|name| image::ImageFormat::from_path(name).is_ok())
+			.map(|name| name.to_string_lossy().into_owned())
+			.filter(|name| filter.matches(name))
+			.filter(move |name| {
+				let rating = ratings.get(name).copied().unwrap_or_default();
+				rating.passes(filter.rating)
+			}),
+	)
 }
 
-pub fn next_in_directory(current_path: &Path, direction: NextPath) -> io::Result<Option<PathBuf>> {
-	let parent = current_path.parent().unwrap(/* path must have a parent because it must be a file, though it may be empty. */);
-	let current_name = current_path.file_name().unwrap(/* ditto */).to_string_lossy();
+/// Find the next item like `find_next_impl`, but return `None` rather than wrapping around to the start if `wrap` is false.
+fn find_next<K: MakeFindNextKey + ?Sized>(
+	direction: Direction,
+	current_name: &str,
+	items: impl Iterator<Item = String>,
+	make_key: &K,
+	wrap: bool,
+) -> Option<(String, usize)> {
+	find_next_impl(direction, current_name, items, make_key).filter(|(next_name, _)| {
+		let item = |name| FindNextItem {
+			key: make_key.for_name(name),
+			name: HumanCompare(name),
+		};
+		wrap || direction.after(&item(next_name), &item(current_name))
+	})
+}
 
+/// The directory containing `path`, and the same directory in a form that can be read.
+fn parent(path: &Path) -> (&Path, &Path) {
+	let parent = path.parent().unwrap(/* path must have a parent because it must be a file, though it may be empty. */);
 	let readable_parent = if parent.as_os_str().is_empty() {
 		".".as_ref()
 	} else {
 		parent
 	};
+	(parent, readable_parent)
+}
 
-	let next_name = direction.find_next(
+pub fn next_in_directory(
+	current_path: &Path,
+	direction: Direction,
+	order: Order,
+	filter: &Filter,
+	wrap: bool,
+) -> io::Result<Option<PathBuf>> {
+	let (parent, readable_parent) = parent(current_path);
+	let current_name = current_path.file_name().unwrap(/* ditto, see `parent` */).to_string_lossy();
+
+	let next_name = find_next(
+		direction,
 		&current_name,
-		read_dir_to_find_next_iterator(readable_parent.read_dir()?),
+		read_dir_to_find_next_iterator(readable_parent, filter)?,
+		&SortKey {
+			order,
+			dir: readable_parent,
+		},
+		wrap,
 	);
 
 	Ok(next_name.map(|(next_name, _idx)| parent.join(next_name)))
@@ -279,14 +488,98 @@ pub fn next_in_directory(current_path: &Path, direction: NextPath) -> io::Result
 pub fn next_in_list<'a>(
 	list: impl Iterator<Item = &'a Path>,
 	current_path: &Path,
-	direction: NextPath,
+	direction: Direction,
+	order: Order,
+	wrap: bool,
 ) -> Option<usize> {
 	let current_name = current_path.to_string_lossy();
 
-	let next_name = direction.find_next(
+	let next_name = find_next(
+		direction,
 		&current_name,
 		list.map(|path| path.to_string_lossy().into_owned()),
+		// the names are whole paths
+		&SortKey {
+			order,
+			dir: Path::new(""),
+		},
+		wrap,
 	);
 
 	next_name.map(|(_, idx)| idx)
 }
+
+/// The first image in `dir` that matches `filter`, in `order`.
+pub fn first_in_directory(
+	dir: &Path,
+	order: Order,
+	filter: &Filter,
+) -> io::Result<Option<PathBuf>> {
+	let make_key = SortKey { order, dir };
+	let first = read_dir_to_find_next_iterator(dir, filter)?
+		.map(|name| FindNextItem {
+			key: make_key.for_name(&name),
+			name: HumanCompare(name),
+		})
+		.min();
+	Ok(first.map(|item| dir.join(item.name.0)))
+}
+
+/// Sort whole paths in `order`, as `next_in_list` moves between them.
+pub fn sort_paths(paths: &mut [Arc<Path>], order: Order) {
+	let make_key = SortKey {
+		order,
+		dir: Path::new(""),
+	};
+	paths.sort_by_cached_key(|path| {
+		let name = path.to_string_lossy().into_owned();
+		FindNextItem {
+			key: make_key.for_name(&name),
+			name: HumanCompare(name),
+		}
+	});
+}
+
+/// All the images in the directory containing `path` that match `filter`.
+pub fn images_in_directory(path: &Path, filter: &Filter) -> io::Result<Vec<PathBuf>> {
+	let (parent, readable_parent) = parent(path);
+	Ok(
+		read_dir_to_find_next_iterator(readable_parent, filter)?
+			.map(|name| parent.join(name))
+			.collect(),
+	)
+}
+
+#[test]
+fn test_name_order() {
+	let sorted = |names: NameOrder| {
+		let mut paths: Vec<Arc<Path>> = [
+			"zebra", "école", "Banana", "apple", "Apple", "img10", "img9",
+		]
+		.into_iter()
+		.map(|name| Path::new(name).into())
+		.collect();
+		let order = Order {
+			sort: Sort::Name,
+			names,
+		};
+		sort_paths(&mut paths, order);
+		paths
+			.iter()
+			.map(|path| path.to_string_lossy().into_owned())
+			.collect::<Vec<_>>()
+	};
+
+	assert_eq!(
+		sorted(NameOrder::CaseSensitive),
+		["Apple", "Banana", "apple", "img9", "img10", "zebra", "école"],
+	);
+	assert_eq!(
+		sorted(NameOrder::IgnoreCase),
+		["Apple", "apple", "Banana", "img9", "img10", "zebra", "école"],
+	);
+	assert_eq!(
+		sorted(NameOrder::IgnoreCaseAndAccents),
+		["Apple", "apple", "Banana", "école", "img9", "img10", "zebra"],
+	);
+}
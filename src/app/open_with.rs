@@ -0,0 +1,47 @@
+//! Opening images in other programs, e.g., ones that can read files that eo2 can't.
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::{io, thread};
+
+/// The command that opens files with the default application of the system.
+#[cfg(target_os = "macos")]
+fn default_command() -> Command {
+	Command::new("open")
+}
+
+#[cfg(windows)]
+fn default_command() -> Command {
+	let mut command = Command::new("cmd");
+	// the empty argument is the title of the window, which `start` would otherwise take from the path
+	command.args(["/C", "start", ""]);
+	command
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn default_command() -> Command {
+	Command::new("xdg-open")
+}
+
+/// Open `path` with `program`, which can include arguments before the path (e.g., `gimp -n`), or with the default application if it is empty.
+/// Returns once the program is started.
+pub fn open(path: &Path, program: &str) -> io::Result<()> {
+	let mut words = program.split_whitespace();
+	let mut command = match words.next() {
+		Some(program) => {
+			let mut command = Command::new(program);
+			command.args(words);
+			command
+		}
+		None => default_command(),
+	};
+	let mut child = command
+		.arg(path)
+		.stdin(Stdio::null())
+		.stdout(Stdio::null())
+		.stderr(Stdio::null())
+		.spawn()?;
+	// waited for so that it doesn't linger as a zombie process once it exits
+	thread::spawn(move || child.wait());
+	Ok(())
+}
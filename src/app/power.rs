@@ -0,0 +1,99 @@
+//! Saving power on battery, so that laptops don't spin their fans while showing an image.
+//!
+//! While saving power, animations are repainted less often, and the thumbnails of frames are not made while decoding; they are made when shown if the pixels are kept, and the frames themselves are shown scaled down otherwise.
+//! The state is detected on the UI thread and shared with the actor through a global, since both need it.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+use egui::Context;
+
+use crate::config::PowerSaving;
+
+/// How often to check the power supply, which rarely changes.
+const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// The shortest time between repaints of animations while saving power.
+const MIN_FRAME_TIME: std::time::Duration = std::time::Duration::from_millis(50);
+
+static SAVING: AtomicBool = AtomicBool::new(false);
+
+/// Whether power is being saved, as of the last update.
+pub fn saving() -> bool {
+	SAVING.load(Ordering::Relaxed)
+}
+
+/// Request a repaint for the next frame of an animation, which is delayed while saving power.
+pub fn request_animation_repaint(ctx: &Context, after: std::time::Duration) {
+	let after = if saving() {
+		after.max(MIN_FRAME_TIME)
+	} else {
+		after
+	};
+	ctx.request_repaint_after(after);
+}
+
+#[derive(Debug, Default)]
+pub struct Monitor {
+	checked_at: Option<Instant>,
+	on_battery: bool,
+}
+
+impl Monitor {
+	pub fn on_battery(&self) -> bool {
+		self.on_battery
+	}
+
+	/// Decide whether to save power according to `setting`, checking the power supply if it is automatic.
+	pub fn update(&mut self, setting: PowerSaving) {
+		let saving = match setting {
+			PowerSaving::Auto => {
+				let checked_recently = self
+					.checked_at
+					.is_some_and(|checked_at| checked_at.elapsed() < CHECK_INTERVAL);
+				if !checked_recently {
+					self.on_battery = detect();
+					self.checked_at = Some(Instant::now());
+				}
+				self.on_battery
+			}
+			PowerSaving::Always => true,
+			PowerSaving::Never => false,
+		};
+		SAVING.store(saving, Ordering::Relaxed);
+	}
+}
+
+/// Whether the system is running on battery or has power saving turned on.
+#[cfg(target_os = "linux")]
+fn detect() -> bool {
+	use std::path::Path;
+
+	fn read_trimmed(path: &Path) -> Option<String> {
+		std::fs::read_to_string(path)
+			.ok()
+			.map(|raw| raw.trim().to_owned())
+	}
+
+	if read_trimmed(Path::new("/sys/firmware/acpi/platform_profile")).as_deref() == Some("low-power")
+	{
+		return true;
+	}
+
+	let Ok(supplies) = std::fs::read_dir("/sys/class/power_supply") else {
+		return false;
+	};
+	supplies.flatten().any(|supply| {
+		let path = supply.path();
+		// the batteries of devices such as wireless mice don't power the system
+		read_trimmed(&path.join("type")).as_deref() == Some("Battery")
+			&& read_trimmed(&path.join("scope")).as_deref() != Some("Device")
+			&& read_trimmed(&path.join("status")).as_deref() == Some("Discharging")
+	})
+}
+
+/// The power supply is only detected on Linux; elsewhere, power saving has to be turned on in the settings.
+#[cfg(not(target_os = "linux"))]
+fn detect() -> bool {
+	false
+}
@@ -0,0 +1,113 @@
+//! Rating images from one to five stars and marking them as favorites.
+//!
+//! Ratings are stored in a hidden TOML file in the folder of the images, keyed by file name, so that they move along with the folder.
+
+use std::collections::BTreeMap;
+use std::io::{self, Write as _};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::RatingFilter;
+
+const SIDECAR_NAME: &str = ".eo2-ratings.toml";
+
+pub const MAX_STARS: u8 = 5;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rating {
+	/// From zero (unrated) to `MAX_STARS`.
+	#[serde(default)]
+	pub stars: u8,
+	#[serde(default)]
+	pub favorite: bool,
+}
+
+impl Rating {
+	pub fn passes(self, filter: RatingFilter) -> bool {
+		self.stars >= filter.min_stars && (self.favorite || !filter.favorites_only)
+	}
+}
+
+fn sidecar_path(dir: &Path) -> PathBuf {
+	dir.join(SIDECAR_NAME)
+}
+
+/// The directory containing `path` and the name of the file in it.
+fn split(path: &Path) -> (&Path, String) {
+	let dir = path.parent().unwrap_or(Path::new(""));
+	let dir = if dir.as_os_str().is_empty() {
+		Path::new(".")
+	} else {
+		dir
+	};
+	let name = path.file_name().unwrap_or_default().to_string_lossy();
+	(dir, name.into_owned())
+}
+
+/// The ratings of the images in `dir`, by file name. Images without an entry are unrated.
+pub fn read_dir(dir: &Path) -> io::Result<BTreeMap<String, Rating>> {
+	let raw = match std::fs::read_to_string(sidecar_path(dir)) {
+		Ok(raw) => raw,
+		Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+		Err(error) => return Err(error),
+	};
+	toml::from_str(&raw).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+pub fn get(path: &Path) -> io::Result<Rating> {
+	let (dir, name) = split(path);
+	Ok(read_dir(dir)?.get(&name).copied().unwrap_or_default())
+}
+
+/// Set the rating of the image at `path`, removing the sidecar once no image in the folder is rated.
+pub fn set(path: &Path, rating: Rating) -> io::Result<()> {
+	let (dir, name) = split(path);
+	let mut ratings = read_dir(dir)?;
+	if rating == Rating::default() {
+		ratings.remove(&name);
+	} else {
+		ratings.insert(name, rating);
+	}
+
+	if ratings.is_empty() {
+		return match std::fs::remove_file(sidecar_path(dir)) {
+			Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+			_ => Ok(()),
+		};
+	}
+	let raw =
+		toml::to_string(&ratings).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+	// written beside the sidecar and renamed over it, so that the ratings aren't lost if writing fails partway
+	let mut temp = tempfile::Builder::new()
+		.prefix(SIDECAR_NAME)
+		.tempfile_in(dir)?;
+	temp.write_all(raw.as_bytes())?;
+	temp.persist(sidecar_path(dir))?;
+	Ok(())
+}
+
+/// Move the rating of the image that was moved from `from` to `to`, since ratings are kept by file name.
+pub fn moved(from: &Path, to: &Path) -> io::Result<()> {
+	let rating = get(from)?;
+	if rating == Rating::default() {
+		return Ok(());
+	}
+	set(to, rating)?;
+	set(from, Rating::default())
+}
+
+#[test]
+fn test_passes() {
+	let filter = |min_stars, favorites_only| RatingFilter {
+		min_stars,
+		favorites_only,
+	};
+	let rating = |stars, favorite| Rating { stars, favorite };
+
+	assert!(Rating::default().passes(RatingFilter::default()));
+	assert!(rating(3, false).passes(filter(3, false)));
+	assert!(!rating(2, true).passes(filter(3, false)));
+	assert!(!rating(5, false).passes(filter(0, true)));
+	assert!(rating(0, true).passes(filter(0, true)));
+}
@@ -0,0 +1,199 @@
+//! Shuffling the images into a random order that shows every image once per cycle.
+//!
+//! Each cycle is a seeded permutation of the images other than the one being shown when it starts, so an image is never shown twice in a row.
+//! The images that were shown are remembered, so that going left walks back through them.
+
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom as _;
+use rand::SeedableRng as _;
+use rustc_hash::FxHashSet;
+
+use super::next_path::Direction;
+
+/// How many images are remembered for going back.
+const MAX_HISTORY: usize = 1000;
+
+#[derive(Debug)]
+pub struct Shuffle {
+	seed: u64,
+	/// How many cycles were started, which gives every cycle a different order.
+	cycles: u64,
+	/// The images that are yet to be shown in this cycle, with the next one last.
+	queue: Vec<Arc<Path>>,
+	/// The order of the images in this cycle, starting with the one that was shown when it started.
+	cycle: Vec<Arc<Path>>,
+	/// The images that were shown, oldest first.
+	history: Vec<Arc<Path>>,
+	/// The index of the current image in `history`.
+	position: usize,
+}
+
+impl Shuffle {
+	pub fn new(seed: u64) -> Self {
+		Self {
+			seed,
+			cycles: 0,
+			queue: Vec::new(),
+			cycle: Vec::new(),
+			history: Vec::new(),
+			position: 0,
+		}
+	}
+
+	/// The position in the history, to restore if moving is cancelled.
+	pub fn position(&self) -> usize {
+		self.position
+	}
+
+	pub fn restore(&mut self, position: usize) {
+		self.position = position.min(self.history.len().saturating_sub(1));
+	}
+
+	/// The place of `path` in the shuffled order of this cycle, and how many images the cycle has.
+	pub fn index_of(&self, path: &Path) -> Option<(usize, usize)> {
+		let idx = self.cycle.iter().position(|other| **other == *path)?;
+		Some((idx, self.cycle.len()))
+	}
+
+	/// Find the image to move to from `current`, given `all` the images that can be shown.
+	/// Moving left past the start of the history returns `None`, as does moving right at the end of a cycle if `wrap` is false.
+	pub fn next(
+		&mut self,
+		current: &Arc<Path>,
+		direction: Direction,
+		wrap: bool,
+		all: impl FnOnce() -> io::Result<Vec<Arc<Path>>>,
+	) -> io::Result<Option<Arc<Path>>> {
+		let all: FxHashSet<Arc<Path>> = all()?.into_iter().collect();
+		self.forget_missing(&all);
+
+		// the image was reached without shuffling, so the history continues from it
+		if self.history.get(self.position) != Some(current) {
+			self.history.truncate(self.position + 1);
+			self.push(Arc::clone(current));
+			self.queue.retain(|path| path != current);
+		}
+
+		match direction {
+			Direction::Left => {
+				if self.position == 0 {
+					return Ok(None);
+				}
+				self.position -= 1;
+			}
+			Direction::Right if self.position + 1 < self.history.len() => self.position += 1,
+			Direction::Right => {
+				if self.queue.is_empty() {
+					let first = self.cycles == 0;
+					self.start_cycle(current, &all);
+					// the next cycle is ready for when moving is resumed
+					if !wrap && !first {
+						return Ok(None);
+					}
+				}
+				let Some(next) = self.queue.pop() else {
+					return Ok(None);
+				};
+				self.push(next);
+			}
+		}
+
+		Ok(Some(Arc::clone(&self.history[self.position])))
+	}
+
+	/// Drop the images that can no longer be shown, except for the current one.
+	fn forget_missing(&mut self, all: &FxHashSet<Arc<Path>>) {
+		self.queue.retain(|path| all.contains(path));
+		self.cycle.retain(|path| all.contains(path));
+
+		let mut idx = 0;
+		let mut position = 0;
+		self.history.retain(|path| {
+			let keep = idx == self.position || all.contains(path);
+			if keep && idx < self.position {
+				position += 1;
+			}
+			idx += 1;
+			keep
+		});
+		self.position = position;
+	}
+
+	fn start_cycle(&mut self, current: &Arc<Path>, all: &FxHashSet<Arc<Path>>) {
+		self.queue = all
+			.iter()
+			.filter(|path| *path != current)
+			.cloned()
+			.collect();
+		// the set has no meaningful order, so sort it to make the permutation depend only on the seed
+		self.queue.sort_unstable();
+		let mut rng = StdRng::seed_from_u64(self.seed.wrapping_add(self.cycles));
+		self.queue.shuffle(&mut rng);
+		self.cycles += 1;
+		self.cycle = std::iter::once(Arc::clone(current))
+			.chain(self.queue.iter().rev().cloned())
+			.collect();
+	}
+
+	/// Add an image after the current one and make it current.
+	fn push(&mut self, path: Arc<Path>) {
+		self.history.truncate(self.position + 1);
+		self.history.push(path);
+		if self.history.len() > MAX_HISTORY {
+			self.history.remove(0);
+		}
+		self.position = self.history.len() - 1;
+	}
+}
+
+#[test]
+fn test_shuffle() {
+	const FILES: &[&str] = &["a", "b", "c", "d"];
+
+	let all = || {
+		Ok(
+			FILES
+				.iter()
+				.map(|&name| Arc::from(Path::new(name)))
+				.collect(),
+		)
+	};
+
+	for seed in 0..20 {
+		let mut shuffle = Shuffle::new(seed);
+		let mut current: Arc<Path> = Arc::from(Path::new(FILES[0]));
+		let mut shown = vec![Arc::clone(&current)];
+		for _ in 0..(FILES.len() - 1) * 5 {
+			current = shuffle
+				.next(&current, Direction::Right, true, all)
+				.unwrap()
+				.unwrap();
+			shown.push(Arc::clone(&current));
+			let (_idx, total) = shuffle.index_of(&current).unwrap();
+			assert_eq!(total, FILES.len(), "the index is within the cycle");
+		}
+
+		// each cycle starts from the last image of the previous one
+		for cycle in shown.windows(FILES.len()).step_by(FILES.len() - 1) {
+			let mut names: Vec<_> = cycle.iter().map(|path| path.to_str().unwrap()).collect();
+			names.sort_unstable();
+			assert_eq!(names, FILES, "every image is shown once per cycle");
+		}
+
+		for expected in shown.iter().rev().skip(1) {
+			current = shuffle
+				.next(&current, Direction::Left, true, all)
+				.unwrap()
+				.unwrap();
+			assert_eq!(current, *expected, "going left retraces the shuffle");
+		}
+		assert!(shuffle
+			.next(&current, Direction::Left, true, all)
+			.unwrap()
+			.is_none());
+	}
+}
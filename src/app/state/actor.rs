@@ -1,66 +1,297 @@
 // In this actor, rather than using the typical pattern of passing "response" channels in the commands, we have a single response channel.
 // This makes it easier to handle responses in the UI code, since we only need to poll one channel rather than a dynamic number of them.
+// Every command gets exactly one response. Side effects that the UI should hear about, such as a file being deleted, are sent on a separate notification channel, so that they don't count as the response.
 
-use std::hash::BuildHasherDefault;
 use std::num::NonZeroUsize;
-use std::path::Path;
-use std::sync::{mpsc, Arc};
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex, MutexGuard};
+use std::time::{Instant, SystemTime};
 use std::{io, thread};
 
-use clru::{CLruCache, CLruCacheConfig};
+use egui::Rect;
 use image::error::ImageResult;
-use rustc_hash::FxHasher;
+use rand::seq::SliceRandom as _;
 
-use crate::app::image::Image;
-use crate::app::next_path;
+use crate::app::clipboard::Clipboard;
+#[cfg(feature = "device-import")]
+use crate::app::device_import;
+use crate::app::image::white_balance::{self, WhiteBalance};
+use crate::app::image::{
+	animation_export, chunks, data_uri, save_as, sprite_sheet, Image, Pixels, Progress,
+};
+use crate::app::shuffle::Shuffle;
+use crate::app::state::cache::Cache;
+use crate::app::{batch, checksum, history, next_path, power, rating, workspace};
+use crate::error::diagnostic;
 
-#[derive(Debug)]
+/// How many unreadable images in a row are skipped when moving between images, before the error is shown instead.
+const MAX_SKIPPED: usize = 10;
+
+#[derive(Debug, Clone, Copy)]
 pub enum NextPathMode {
 	Simple,
 	Random,
 }
 
-impl NextPathMode {
-	fn with_random_seed(self, seed: u64) -> next_path::Mode {
-		match self {
-			Self::Simple => next_path::Mode::Simple,
-			Self::Random => next_path::Mode::Random { seed },
-		}
-	}
-}
-
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct NextPath {
 	pub direction: next_path::Direction,
 	pub mode: NextPathMode,
-}
-
-impl NextPath {
-	fn with_random_seed(self, seed: u64) -> next_path::NextPath {
-		next_path::NextPath {
-			direction: self.direction,
-			mode: self.mode.with_random_seed(seed),
-		}
-	}
+	pub sort: next_path::Order,
+	/// Which images in the directory to move between.
+	pub filter: next_path::Filter,
+	/// Whether to wrap around at the end of the images. Otherwise `Response::EndReached` is sent there.
+	pub wrap: bool,
 }
 
 #[derive(Debug)]
 enum Command {
 	NextPath(NextPath),
 	DeleteFile(Arc<Path>),
+	RenameFile {
+		from: Arc<Path>,
+		to: Arc<Path>,
+	},
+	Undo,
+	Redo,
+	CopyDataUri {
+		path: Arc<Path>,
+		max_size: usize,
+		markup: data_uri::Markup,
+	},
+	CopyPixels {
+		path: Arc<Path>,
+		frame_idx: usize,
+		region: Option<Rect>,
+	},
+	ExportSpriteSheet {
+		path: Arc<Path>,
+		options: sprite_sheet::Options,
+	},
+	/// Average the color around `pixel`, to pick a gray point for the white balance.
+	SampleColor {
+		path: Arc<Path>,
+		frame_idx: usize,
+		pixel: egui::Pos2,
+	},
+	ExportWhiteBalance {
+		path: Arc<Path>,
+		frame_idx: usize,
+		white_balance: WhiteBalance,
+		replace: bool,
+	},
+	SaveAs {
+		path: Arc<Path>,
+		options: save_as::Options,
+	},
+	ExportAnimation {
+		path: Arc<Path>,
+		options: animation_export::Options,
+	},
+	/// Save a bundle describing why the image failed to load with `error`.
+	SaveDiagnostics {
+		path: Arc<Path>,
+		error: String,
+	},
+	/// Decode the image again rather than using the cache, e.g., once a file that was still being written is complete.
+	Reload(Arc<Path>),
+	ClearCache,
+	SetCacheLimits(CacheLimits),
+	ReadChunks(Arc<Path>),
+	ExportChunk {
+		path: PathBuf,
+		data: Arc<[u8]>,
+	},
+	VerifyChecksum(Arc<Path>),
+	/// Generate checksums for the folder containing the file, then verify it.
+	GenerateChecksums(Arc<Path>),
+	ReadRating(Arc<Path>),
+	SetRating {
+		path: Arc<Path>,
+		rating: rating::Rating,
+	},
+	/// List the images in the order that the slideshow shows them.
+	SlideshowOrder {
+		sort: next_path::Order,
+		filter: next_path::Filter,
+		shuffle: bool,
+	},
+	#[cfg(feature = "device-import")]
+	ListDevices,
+	/// Copy the files into `target`, then open the copies.
+	#[cfg(feature = "device-import")]
+	ImportFromDevice {
+		files: Vec<PathBuf>,
+		target: PathBuf,
+	},
+	/// Move between `paths` instead, starting from the first.
+	OpenList(Vec<Arc<Path>>),
+	/// Do `action` to each of `paths`, reporting the progress through the batch progress.
+	Batch {
+		action: batch::Action,
+		paths: Vec<Arc<Path>>,
+	},
+	SaveWorkspace {
+		name: String,
+		view: workspace::View,
+	},
+	LoadWorkspace(String),
+	DeleteWorkspace(String),
+}
+
+impl Command {
+	/// Whether the command moves away from the current image or changes it, so that streaming in the current image should stop.
+	fn changes_image(&self) -> bool {
+		match self {
+			Self::NextPath(..)
+			| Self::DeleteFile(..)
+			| Self::RenameFile { .. }
+			| Self::Undo
+			| Self::Redo
+			| Self::Reload(..)
+			| Self::ClearCache
+			| Self::OpenList(..)
+			| Self::Batch { .. }
+			| Self::LoadWorkspace(..) => true,
+			#[cfg(feature = "device-import")]
+			Self::ImportFromDevice { .. } => true,
+			_ => false,
+		}
+	}
+
+	/// Run the command if it only reads files and doesn't need the state of the actor, so it can be run while an image is streaming in.
+	fn run_detached(self) -> Result<io::Result<Response>, Self> {
+		Ok(match self {
+			Self::ReadChunks(path) => {
+				// shown in place of the chunks
+				let chunks = chunks::read(&path).map_err(|error| error.to_string());
+				Ok(Response::Chunks(LoadedChunks { path, chunks }))
+			}
+			Self::VerifyChecksum(path) => {
+				checksum::verify(&path).map(|status| Response::Checksum(VerifiedChecksum { path, status }))
+			}
+			Self::ReadRating(path) => {
+				rating::get(&path).map(|rating| Response::Rating(RatedImage { path, rating }))
+			}
+			command => return Err(command),
+		})
+	}
 }
 
 pub struct LoadedImage {
 	pub path: Arc<Path>,
 	pub image: ImageResult<Arc<Image>>,
+	/// The index of the image among the images that can be moved between, and how many there are.
+	pub index: Option<(usize, usize)>,
+}
+
+pub struct SampledColor {
+	pub path: Arc<Path>,
+	pub color: egui::Color32,
+}
+
+/// An image that failed to load while moving between images, so the one after it was loaded instead.
+pub struct SkippedImage {
+	pub path: Arc<Path>,
+	pub error: String,
+}
+
+pub struct LoadedChunks {
+	pub path: Arc<Path>,
+	pub chunks: Result<Vec<chunks::Chunk>, String>,
+}
+
+pub struct VerifiedChecksum {
+	pub path: Arc<Path>,
+	pub status: checksum::Status,
+}
+
+pub struct SavedDiagnostics {
+	pub path: Arc<Path>,
+	/// Where the bundle was saved.
+	pub bundle: PathBuf,
+}
+
+pub struct RatedImage {
+	pub path: Arc<Path>,
+	pub rating: rating::Rating,
 }
 
 #[must_use = "responses must be handled"]
 pub enum Response {
 	LoadImage(LoadedImage),
+	/// Moving to the next image would have wrapped around to the start.
+	EndReached,
+	CopyText(String),
+	Chunks(LoadedChunks),
+	Checksum(VerifiedChecksum),
+	DiagnosticsSaved(SavedDiagnostics),
+	Rating(RatedImage),
+	SampledColor(SampledColor),
+	SlideshowOrder(Vec<Arc<Path>>),
 	NoOp,
 }
 
+/// Sent before the response to the command that caused it, on a separate channel so that any number of them can be sent.
+pub enum Notification {
+	/// Sent for each image that is skipped while moving between images.
+	Skipped(SkippedImage),
+	FileDeleted(Arc<Path>),
+	/// An image was written, possibly over an existing file.
+	FileWritten(Arc<Path>),
+	/// The history changed, e.g., an operation was done or undone.
+	History(history::Info),
+	/// Sent before the current image of the workspace is loaded.
+	WorkspaceLoaded(workspace::View),
+	/// A workspace was saved or deleted.
+	WorkspacesChanged,
+	/// Sent before the response to the batch, which moves on if the current image was moved or deleted.
+	Batch(batch::Outcome),
+	RatingChanged(RatedImage),
+	AnimationExported(animation_export::Outcome),
+	/// The checksums of the folder were generated, and the image was checked against them.
+	ChecksumsGenerated(VerifiedChecksum),
+	#[cfg(feature = "device-import")]
+	Devices(Vec<device_import::Device>),
+	/// Work that was done in the background failed.
+	Error(io::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheLimits {
+	/// The maximum estimated size of the cached images, in bytes.
+	pub size: NonZeroUsize,
+	/// The maximum estimated size of the textures kept on the GPU, in bytes.
+	pub gpu_budget: NonZeroUsize,
+	/// Whether to keep the pixels of images so their textures can be re-uploaded after being evicted, rather than decoding them again.
+	pub keep_pixels: bool,
+}
+
+#[derive(Debug)]
+pub struct CacheEntry {
+	pub path: Arc<Path>,
+	pub width: u32,
+	pub height: u32,
+	pub frames: usize,
+	pub weight: usize,
+	/// Whether the textures of the image are on the GPU.
+	pub resident: bool,
+}
+
+/// A snapshot of the image cache, updated by the actor whenever the cache is used.
+#[derive(Debug, Default)]
+pub struct CacheInfo {
+	/// Most recently used first.
+	pub entries: Vec<CacheEntry>,
+	pub weight: usize,
+	pub capacity: usize,
+	pub gpu_weight: usize,
+	pub gpu_capacity: usize,
+	pub hits: u64,
+	pub misses: u64,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum SendResult {
 	Sent,
@@ -79,11 +310,106 @@ pub enum NavigationMode {
 	Empty,
 }
 
+/// A snapshot of the current position of a `NavigationMode`, so that it can be restored.
+enum Position {
+	InDirectory(Arc<Path>),
+	Specified(usize),
+	Empty,
+}
+
 impl NavigationMode {
 	pub fn specified(paths: Vec<Arc<Path>>) -> Self {
 		Self::Specified { paths, current: 0 }
 	}
 
+	/// Start from the first image in `dir` that matches `filter` in the order of `sort`, or with nothing open if it has no such images.
+	pub fn directory(dir: Arc<Path>, sort: next_path::Order, filter: &next_path::Filter) -> Self {
+		match next_path::first_in_directory(&dir, sort, filter) {
+			Ok(Some(first)) => Self::InDirectory {
+				current: first.into(),
+			},
+			Ok(None) => Self::Empty,
+			// trying to load the directory shows that something is wrong with it
+			Err(_) => Self::InDirectory { current: dir },
+		}
+	}
+
+	fn position(&self) -> Position {
+		match self {
+			Self::InDirectory { current } => Position::InDirectory(Arc::clone(current)),
+			Self::Specified { current, .. } => Position::Specified(*current),
+			Self::Empty => Position::Empty,
+		}
+	}
+
+	fn restore(&mut self, position: Position) {
+		match (self, position) {
+			(Self::InDirectory { current }, Position::InDirectory(path)) => *current = path,
+			(Self::Specified { current, .. }, Position::Specified(idx)) => *current = idx,
+			_ => {}
+		}
+	}
+
+	/// Make `path` the current path, e.g., after the file was restored.
+	fn go_to(&mut self, path: Arc<Path>) {
+		match self {
+			Self::Specified { paths, current } => {
+				*current = paths
+					.iter()
+					.position(|other| *other == path)
+					.unwrap_or_else(|| {
+						paths.push(path);
+						paths.len() - 1
+					});
+			}
+			Self::InDirectory { .. } | Self::Empty => *self = Self::InDirectory { current: path },
+		}
+	}
+
+	/// Follow a file that was renamed (or moved back by undoing) from `from` to `to`, keeping its place in the images.
+	fn rename(&mut self, from: &Arc<Path>, to: &Arc<Path>) {
+		match self {
+			Self::InDirectory { current } => {
+				if current == from {
+					*current = Arc::clone(to);
+				}
+			}
+			Self::Specified { paths, .. } => {
+				for path in paths.iter_mut().filter(|path| *path == from) {
+					*path = Arc::clone(to);
+				}
+			}
+			Self::Empty => {}
+		}
+	}
+
+	fn to_workspace(&self) -> workspace::Navigation {
+		match self {
+			Self::InDirectory { current } => workspace::Navigation::InDirectory {
+				current: current.to_path_buf(),
+			},
+			Self::Specified { paths, current } => workspace::Navigation::Specified {
+				paths: paths.iter().map(|path| path.to_path_buf()).collect(),
+				current: *current,
+			},
+			Self::Empty => workspace::Navigation::Empty,
+		}
+	}
+
+	fn from_workspace(navigation: workspace::Navigation) -> Self {
+		match navigation {
+			workspace::Navigation::InDirectory { current } => Self::InDirectory {
+				current: current.into(),
+			},
+			workspace::Navigation::Specified { paths, .. } if paths.is_empty() => Self::Empty,
+			workspace::Navigation::Specified { paths, current } => Self::Specified {
+				current: current.min(paths.len() - 1),
+				paths: paths.into_iter().map(Arc::from).collect(),
+			},
+			workspace::Navigation::Empty => Self::Empty,
+		}
+	}
+
 	fn current_path(&self) -> Option<&Arc<Path>> {
 		match self {
 			Self::InDirectory { current } => Some(current),
@@ -92,20 +418,67 @@ impl NavigationMode {
 		}
 	}
 
-	fn next_path(&mut self, args: next_path::NextPath) -> io::Result<Option<&Arc<Path>>> {
+	/// What `all_paths` depends on, to tell whether a listing of them is out of date.
+	fn listing_source(&self) -> ListingSource {
+		match self {
+			Self::InDirectory { current } => {
+				let dir = current.parent().unwrap_or(Path::new(""));
+				let readable_dir = if dir.as_os_str().is_empty() {
+					Path::new(".")
+				} else {
+					dir
+				};
+				// adding, removing, or renaming files in the folder changes this
+				let modified = std::fs::metadata(readable_dir)
+					.and_then(|metadata| metadata.modified())
+					.ok();
+				ListingSource::Directory {
+					dir: dir.to_owned(),
+					modified,
+				}
+			}
+			Self::Specified { paths, .. } => ListingSource::Specified(paths.clone()),
+			Self::Empty => ListingSource::Empty,
+		}
+	}
+
+	/// All the paths that can be navigated to, in no particular order.
+	fn all_paths(&self, filter: &next_path::Filter) -> io::Result<Vec<Arc<Path>>> {
 		Ok(match self {
-			Self::InDirectory { current } => next_path::next_in_directory(current, args)?.map(|next| {
-				*current = next.into();
-				&*current
-			}),
-			Self::Specified { paths, current } => {
-				next_path::next_in_list(paths.iter().map(|path| &**path), &paths[*current], args).map(
-					|next| {
-						*current = next;
-						&paths[next]
-					},
-				)
+			Self::InDirectory { current } => next_path::images_in_directory(current, filter)?
+				.into_iter()
+				.map(Arc::from)
+				.collect(),
+			Self::Specified { paths, .. } => paths.clone(),
+			Self::Empty => Vec::new(),
+		})
+	}
+
+	fn next_path(
+		&mut self,
+		direction: next_path::Direction,
+		sort: next_path::Order,
+		filter: &next_path::Filter,
+		wrap: bool,
+	) -> io::Result<Option<&Arc<Path>>> {
+		Ok(match self {
+			Self::InDirectory { current } => {
+				next_path::next_in_directory(current, direction, sort, filter, wrap)?.map(|next| {
+					*current = next.into();
+					&*current
+				})
 			}
+			Self::Specified { paths, current } => next_path::next_in_list(
+				paths.iter().map(|path| &**path),
+				&paths[*current],
+				direction,
+				sort,
+				wrap,
+			)
+			.map(|next| {
+				*current = next;
+				&paths[next]
+			}),
 			Self::Empty => None,
 		})
 	}
@@ -114,46 +487,103 @@ impl NavigationMode {
 pub struct Handle {
 	command_sender: mpsc::SyncSender<Command>,
 	response_receiver: mpsc::Receiver<io::Result<Response>>,
+	notification_receiver: mpsc::Receiver<Notification>,
 	waiting: bool,
+	waiting_since: Instant,
+	progress: Arc<Progress>,
+	batch_progress: Arc<batch::Progress>,
+	export_progress: Arc<batch::Progress>,
+	cache_info: Arc<Mutex<CacheInfo>>,
 }
 
 impl Handle {
+	/// `sort` and `filter` are used until the first move, which replaces them.
 	pub fn spawn(
 		egui_ctx: egui::Context,
 		navigation_mode: NavigationMode,
-		cache_size: NonZeroUsize,
+		cache_limits: CacheLimits,
+		sort: next_path::Order,
+		filter: next_path::Filter,
+		workspace_dir: PathBuf,
 	) -> Self {
 		let (command_sender, command_receiver) = mpsc::sync_channel(1);
 		let (response_sender, response_receiver) = mpsc::sync_channel(1);
+		// unbounded, since the actor may send several before the UI gets to them
+		let (notification_sender, notification_receiver) = mpsc::channel();
+		let progress = Arc::new(Progress::default());
+		let actor_progress = Arc::clone(&progress);
+		let batch_progress = Arc::new(batch::Progress::default());
+		let actor_batch_progress = Arc::clone(&batch_progress);
+		let export_progress = Arc::new(batch::Progress::default());
+		let actor_export_progress = Arc::clone(&export_progress);
+		let cache_info = Arc::new(Mutex::new(CacheInfo {
+			capacity: cache_limits.size.get(),
+			gpu_capacity: cache_limits.gpu_budget.get(),
+			..CacheInfo::default()
+		}));
+		let actor_cache_info = Arc::clone(&cache_info);
 		thread::spawn(move || {
 			let actor = Actor {
 				bridge: Bridge {
 					egui_ctx,
 					command_receiver,
 					response_sender,
+					notification_sender,
+					progress: actor_progress,
+					batch_progress: actor_batch_progress,
+					export_progress: actor_export_progress,
 				},
 				state: State {
-					cache: CLruCache::with_config(
-						CLruCacheConfig::new(cache_size)
-							.with_hasher(BuildHasherDefault::default())
-							.with_scale(ImageSizeWeight),
-					),
+					cache: Cache::new(cache_limits),
 					navigation_mode,
-					random_seed: rand::random(),
+					shuffle: Shuffle::new(rand::random()),
+					clipboard: Clipboard::default(),
+					cache_info: actor_cache_info,
+					history: history::History::new(),
+					sort,
+					filter,
+					shuffled: false,
+					listing: None,
+					workspace_dir,
 				},
+				pending_command: None,
+				responded: false,
 			};
 			actor.run();
 		});
 		Self {
 			command_sender,
 			response_receiver,
+			notification_receiver,
 			// waiting for initial LoadImage
 			waiting: true,
+			waiting_since: Instant::now(),
+			progress,
+			batch_progress,
+			export_progress,
+			cache_info,
 		}
 	}
 
-	pub fn waiting(&self) -> bool {
-		self.waiting
+	/// How long we have been waiting for a response, if we are waiting.
+	pub fn waiting_for(&self) -> Option<std::time::Duration> {
+		self.waiting.then(|| self.waiting_since.elapsed())
+	}
+
+	pub fn progress(&self) -> &Progress {
+		&self.progress
+	}
+
+	pub fn batch_progress(&self) -> &batch::Progress {
+		&self.batch_progress
+	}
+
+	pub fn export_progress(&self) -> &batch::Progress {
+		&self.export_progress
+	}
+
+	pub fn cache_info(&self) -> MutexGuard<'_, CacheInfo> {
+		self.cache_info.lock().unwrap()
 	}
 
 	pub fn poll_response(&mut self) -> Option<io::Result<Response>> {
@@ -167,15 +597,29 @@ impl Handle {
 		}
 	}
 
+	/// Notifications don't end the wait for a response.
+	pub fn poll_notification(&self) -> Option<Notification> {
+		match self.notification_receiver.try_recv() {
+			Ok(notification) => Some(notification),
+			Err(mpsc::TryRecvError::Empty) => None,
+			Err(mpsc::TryRecvError::Disconnected) => panic!("actor disconnected"),
+		}
+	}
+
 	fn send(&mut self, command: Command) -> SendResult {
 		if self.waiting {
 			return SendResult::AlreadyWaiting;
 		}
+		// other commands run alongside the image that is streaming in
+		if command.changes_image() {
+			self.progress.reset();
+		}
 		self
 			.command_sender
 			.send(command)
 			.expect("actor disconnected");
 		self.waiting = true;
+		self.waiting_since = Instant::now();
 		SendResult::Sent
 	}
 
@@ -186,57 +630,375 @@ impl Handle {
 	pub fn delete_file(&mut self, file: Arc<Path>) -> SendResult {
 		self.send(Command::DeleteFile(file))
 	}
+
+	pub fn rename_file(&mut self, from: Arc<Path>, to: Arc<Path>) -> SendResult {
+		self.send(Command::RenameFile { from, to })
+	}
+
+	pub fn undo(&mut self) -> SendResult {
+		self.send(Command::Undo)
+	}
+
+	pub fn redo(&mut self) -> SendResult {
+		self.send(Command::Redo)
+	}
+
+	pub fn copy_data_uri(
+		&mut self,
+		path: Arc<Path>,
+		max_size: usize,
+		markup: data_uri::Markup,
+	) -> SendResult {
+		self.send(Command::CopyDataUri {
+			path,
+			max_size,
+			markup,
+		})
+	}
+
+	pub fn copy_pixels(
+		&mut self,
+		path: Arc<Path>,
+		frame_idx: usize,
+		region: Option<Rect>,
+	) -> SendResult {
+		self.send(Command::CopyPixels {
+			path,
+			frame_idx,
+			region,
+		})
+	}
+
+	pub fn export_sprite_sheet(
+		&mut self,
+		path: Arc<Path>,
+		options: sprite_sheet::Options,
+	) -> SendResult {
+		self.send(Command::ExportSpriteSheet { path, options })
+	}
+
+	pub fn sample_color(
+		&mut self,
+		path: Arc<Path>,
+		frame_idx: usize,
+		pixel: egui::Pos2,
+	) -> SendResult {
+		self.send(Command::SampleColor {
+			path,
+			frame_idx,
+			pixel,
+		})
+	}
+
+	pub fn export_white_balance(
+		&mut self,
+		path: Arc<Path>,
+		frame_idx: usize,
+		white_balance: WhiteBalance,
+		replace: bool,
+	) -> SendResult {
+		self.send(Command::ExportWhiteBalance {
+			path,
+			frame_idx,
+			white_balance,
+			replace,
+		})
+	}
+
+	pub fn save_as(&mut self, path: Arc<Path>, options: save_as::Options) -> SendResult {
+		self.send(Command::SaveAs { path, options })
+	}
+
+	pub fn export_animation(
+		&mut self,
+		path: Arc<Path>,
+		options: animation_export::Options,
+	) -> SendResult {
+		self.send(Command::ExportAnimation { path, options })
+	}
+
+	pub fn save_diagnostics(&mut self, path: Arc<Path>, error: String) -> SendResult {
+		self.send(Command::SaveDiagnostics { path, error })
+	}
+
+	pub fn reload(&mut self, path: Arc<Path>) -> SendResult {
+		self.send(Command::Reload(path))
+	}
+
+	pub fn clear_cache(&mut self) -> SendResult {
+		self.send(Command::ClearCache)
+	}
+
+	pub fn set_cache_limits(&mut self, limits: CacheLimits) -> SendResult {
+		self.send(Command::SetCacheLimits(limits))
+	}
+
+	pub fn read_chunks(&mut self, path: Arc<Path>) -> SendResult {
+		self.send(Command::ReadChunks(path))
+	}
+
+	pub fn export_chunk(&mut self, path: PathBuf, data: Arc<[u8]>) -> SendResult {
+		self.send(Command::ExportChunk { path, data })
+	}
+
+	pub fn verify_checksum(&mut self, path: Arc<Path>) -> SendResult {
+		self.send(Command::VerifyChecksum(path))
+	}
+
+	pub fn generate_checksums(&mut self, path: Arc<Path>) -> SendResult {
+		self.send(Command::GenerateChecksums(path))
+	}
+
+	pub fn read_rating(&mut self, path: Arc<Path>) -> SendResult {
+		self.send(Command::ReadRating(path))
+	}
+
+	pub fn set_rating(&mut self, path: Arc<Path>, rating: rating::Rating) -> SendResult {
+		self.send(Command::SetRating { path, rating })
+	}
+
+	pub fn slideshow_order(
+		&mut self,
+		sort: next_path::Order,
+		filter: next_path::Filter,
+		shuffle: bool,
+	) -> SendResult {
+		self.send(Command::SlideshowOrder {
+			sort,
+			filter,
+			shuffle,
+		})
+	}
+
+	#[cfg(feature = "device-import")]
+	pub fn list_devices(&mut self) -> SendResult {
+		self.send(Command::ListDevices)
+	}
+
+	#[cfg(feature = "device-import")]
+	pub fn import_from_device(&mut self, files: Vec<PathBuf>, target: PathBuf) -> SendResult {
+		self.send(Command::ImportFromDevice { files, target })
+	}
+
+	pub fn open_list(&mut self, paths: Vec<Arc<Path>>) -> SendResult {
+		self.send(Command::OpenList(paths))
+	}
+
+	pub fn run_batch(&mut self, action: batch::Action, paths: Vec<Arc<Path>>) -> SendResult {
+		self.send(Command::Batch { action, paths })
+	}
+
+	pub fn save_workspace(&mut self, name: String, view: workspace::View) -> SendResult {
+		self.send(Command::SaveWorkspace { name, view })
+	}
+
+	pub fn load_workspace(&mut self, name: String) -> SendResult {
+		self.send(Command::LoadWorkspace(name))
+	}
+
+	pub fn delete_workspace(&mut self, name: String) -> SendResult {
+		self.send(Command::DeleteWorkspace(name))
+	}
 }
 
 struct Bridge {
 	egui_ctx: egui::Context,
 	command_receiver: mpsc::Receiver<Command>,
 	response_sender: mpsc::SyncSender<io::Result<Response>>,
+	notification_sender: mpsc::Sender<Notification>,
+	progress: Arc<Progress>,
+	batch_progress: Arc<batch::Progress>,
+	/// The progress of exporting an animation.
+	export_progress: Arc<batch::Progress>,
 }
 
-struct ImageSizeWeight;
+impl Bridge {
+	fn send_response(&self, response: io::Result<Response>) {
+		self.response_sender.send(response).unwrap();
+		self.egui_ctx.request_repaint();
+	}
+
+	fn notify(&self, notification: Notification) {
+		self.notification_sender.send(notification).unwrap();
+		self.egui_ctx.request_repaint();
+	}
 
-impl clru::WeightScale<Arc<Path>, Arc<Image>> for ImageSizeWeight {
-	fn weight(&self, _path: &Arc<Path>, image: &Arc<Image>) -> usize {
-		image.size_in_memory()
+	/// Do slow work that doesn't need the state on another thread, so that the actor stays responsive, and send the result as a notification.
+	fn spawn_worker(&self, work: impl FnOnce() -> io::Result<Notification> + Send + 'static) {
+		let notification_sender = self.notification_sender.clone();
+		let egui_ctx = self.egui_ctx.clone();
+		thread::spawn(move || {
+			let notification = work().unwrap_or_else(Notification::Error);
+			// the app may have been closed in the meantime
+			_ = notification_sender.send(notification);
+			egui_ctx.request_repaint();
+		});
 	}
 }
 
+#[derive(PartialEq)]
+enum ListingSource {
+	Directory {
+		dir: PathBuf,
+		modified: Option<SystemTime>,
+	},
+	Specified(Vec<Arc<Path>>),
+	Empty,
+}
+
+/// The images in the order of the last move, kept so that the index of each image that is loaded is found without listing and sorting them again.
+struct Listing {
+	source: ListingSource,
+	sort: next_path::Order,
+	filter: next_path::Filter,
+	paths: Vec<Arc<Path>>,
+}
+
 struct State {
 	navigation_mode: NavigationMode,
-	cache: CLruCache<Arc<Path>, Arc<Image>, BuildHasherDefault<FxHasher>, ImageSizeWeight>,
-	random_seed: u64,
+	cache: Cache,
+	shuffle: Shuffle,
+	clipboard: Clipboard,
+	cache_info: Arc<Mutex<CacheInfo>>,
+	history: history::History,
+	/// The order of the images as of the last move, for moving on by itself.
+	sort: next_path::Order,
+	/// Likewise, which images were moved between.
+	filter: next_path::Filter,
+	/// Likewise, whether they were shuffled.
+	shuffled: bool,
+	listing: Option<Listing>,
+	/// Where workspaces are saved, next to the configuration file.
+	workspace_dir: PathBuf,
 }
 
 impl State {
+	/// The pixels of the image at `path`, from the cache if they were kept, so that it only has to be decoded again otherwise.
+	fn pixels(&self, path: &Path) -> ImageResult<Pixels> {
+		Pixels::get(path, self.cache.peek(path).map(Arc::as_ref))
+	}
+
+	/// Apply changed cache limits, evicting textures and pixels until they fit.
+	fn set_cache_limits(&mut self, limits: CacheLimits) {
+		self.cache.set_limits(limits);
+		self.update_cache_info(None);
+	}
+
+	fn update_cache_info(&self, hit: Option<bool>) {
+		let mut info = self.cache_info.lock().unwrap();
+		match hit {
+			Some(true) => info.hits += 1,
+			Some(false) => info.misses += 1,
+			None => {}
+		}
+		info.entries = self
+			.cache
+			.iter()
+			.map(|(path, image)| CacheEntry {
+				path: Arc::clone(path),
+				width: image.width,
+				height: image.height,
+				frames: image.frames.read().list.len(),
+				weight: image.size_in_memory(),
+				resident: image.is_resident(),
+			})
+			.collect();
+		let limits = self.cache.limits();
+		info.weight = self.cache.size_in_memory();
+		info.capacity = limits.size.get();
+		info.gpu_weight = self.cache.size_on_gpu();
+		info.gpu_capacity = limits.gpu_budget.get();
+	}
+
 	fn current_path(&self) -> Option<&Arc<Path>> {
 		self.navigation_mode.current_path()
 	}
 
+	/// The index of `path` among the images that can be moved between, in the order of the last move, and how many there are.
+	/// The images are only listed again once they or the order change.
+	fn index_of(&mut self, path: &Path) -> Option<(usize, usize)> {
+		if self.shuffled {
+			if let Some(index) = self.shuffle.index_of(path) {
+				return Some(index);
+			}
+		}
+
+		let source = self.navigation_mode.listing_source();
+		let up_to_date = self.listing.as_ref().is_some_and(|listing| {
+			listing.source == source && listing.sort == self.sort && listing.filter == self.filter
+		});
+		if !up_to_date {
+			let mut paths = self.navigation_mode.all_paths(&self.filter).ok()?;
+			next_path::sort_paths(&mut paths, self.sort);
+			self.listing = Some(Listing {
+				source,
+				sort: self.sort,
+				filter: self.filter.clone(),
+				paths,
+			});
+		}
+		let paths = &self.listing.as_ref()?.paths;
+		let idx = paths.iter().position(|other| **other == *path)?;
+		Some((idx, paths.len()))
+	}
+
 	fn next_path(&mut self, args: NextPath) -> io::Result<Option<&Arc<Path>>> {
-		self
-			.navigation_mode
-			.next_path(args.with_random_seed(self.random_seed))
+		self.sort = args.sort;
+		self.filter = args.filter;
+		self.shuffled = matches!(args.mode, NextPathMode::Random);
+		match args.mode {
+			NextPathMode::Simple => {
+				self
+					.navigation_mode
+					.next_path(args.direction, args.sort, &self.filter, args.wrap)
+			}
+			NextPathMode::Random => {
+				let Some(current) = self.navigation_mode.current_path() else {
+					return Ok(None);
+				};
+				let navigation_mode = &self.navigation_mode;
+				let filter = &self.filter;
+				let next = self.shuffle.next(current, args.direction, args.wrap, || {
+					navigation_mode.all_paths(filter)
+				})?;
+				Ok(next.map(|next| {
+					self.navigation_mode.go_to(next);
+					self.navigation_mode.current_path().unwrap()
+				}))
+			}
+		}
 	}
 }
 
 struct Actor {
 	bridge: Bridge,
 	state: State,
+	/// A command that was received while streaming in the frames of an image, to be run once it is done.
+	pending_command: Option<Command>,
+	/// Whether the response for the current command was already sent while streaming in an image.
+	responded: bool,
 }
 
 impl Actor {
-	fn send_response(&self, response: io::Result<Response>) {
-		self.bridge.response_sender.send(response).unwrap();
-		self.bridge.egui_ctx.request_repaint();
+	fn respond(&mut self, response: io::Result<Response>) {
+		if !std::mem::take(&mut self.responded) {
+			self.bridge.send_response(response);
+		}
+	}
+
+	fn next_command(&mut self) -> Option<Command> {
+		self
+			.pending_command
+			.take()
+			.or_else(|| self.bridge.command_receiver.recv().ok())
 	}
 
 	fn run(mut self) {
 		self.load_initial_image();
 
-		while let Ok(command) = self.bridge.command_receiver.recv() {
+		while let Some(command) = self.next_command() {
 			let response = self.run_command(command);
-			self.send_response(response);
+			self.respond(response);
 		}
 	}
 
@@ -245,50 +1007,465 @@ impl Actor {
 			Some(current_path) => self.load_image(Arc::clone(current_path)),
 			None => Response::NoOp,
 		};
-		self.send_response(Ok(response));
+		let response = if self.cancelled_before_responding() {
+			Response::NoOp
+		} else {
+			response
+		};
+		self.respond(Ok(response));
 	}
 
-	fn load_image_(&mut self, path: &Arc<Path>) -> ImageResult<Arc<Image>> {
-		Ok(if let Some(cached) = self.state.cache.get(path) {
-			Arc::clone(cached)
-		} else {
-			let image = Arc::new(Image::load(&self.bridge.egui_ctx, path)?);
-			_ = self
+	/// Whether a command that changes the image was received while loading the current one.
+	fn moved_on(&self) -> bool {
+		self
+			.pending_command
+			.as_ref()
+			.is_some_and(Command::changes_image)
+	}
+
+	/// Whether loading was cancelled before the image was shown, in which case the response should be discarded.
+	fn cancelled_before_responding(&self) -> bool {
+		!self.responded && self.bridge.progress.is_cancelled()
+	}
+
+	fn load_image_(
+		&mut self,
+		path: &Arc<Path>,
+		index: Option<(usize, usize)>,
+	) -> ImageResult<Arc<Image>> {
+		if let Some(cached) = self.state.cache.get(path, &self.bridge.egui_ctx) {
+			self.state.update_cache_info(Some(true));
+			return Ok(cached);
+		}
+
+		let Self {
+			bridge,
+			state,
+			pending_command,
+			responded,
+		} = self;
+		let keep_pixels = state.cache.limits().keep_pixels;
+		let image = Image::load(
+			&bridge.egui_ctx,
+			path,
+			&bridge.progress,
+			keep_pixels,
+			!power::saving(),
+			|image| {
+				// respond as soon as we know whether the image is animated, then keep streaming in the remaining frames.
+				if !*responded && (image.is_animated() || !image.frames.is_loading()) {
+					bridge.send_response(Ok(Response::LoadImage(LoadedImage {
+						path: Arc::clone(path),
+						image: Ok(Arc::clone(image)),
+						index,
+					})));
+					*responded = true;
+				}
+				bridge.egui_ctx.request_repaint();
+
+				if pending_command.is_none() {
+					if let Ok(command) = bridge.command_receiver.try_recv() {
+						match command.run_detached() {
+							Ok(response) => bridge.send_response(response),
+							Err(command) => *pending_command = Some(command),
+						}
+					}
+				}
+				// a command that changes the image means the user has moved on, so stop streaming to avoid blocking it. other commands wait until the image is complete.
+				if pending_command.as_ref().is_some_and(Command::changes_image) {
+					ControlFlow::Break(())
+				} else {
+					ControlFlow::Continue(())
+				}
+			},
+		)?;
+
+		// partially loaded images must not be cached, otherwise they would never finish loading.
+		if image.frames.is_complete() {
+			self
 				.state
 				.cache
-				.put_with_weight(Arc::clone(path), Arc::clone(&image));
-			image
-		})
+				.insert(Arc::clone(path), Arc::clone(&image));
+		}
+		self.state.update_cache_info(Some(false));
+		Ok(image)
 	}
 
 	fn load_image(&mut self, path: Arc<Path>) -> Response {
-		let image = self.load_image_(&path);
-		Response::LoadImage(LoadedImage { path, image })
+		let index = self.state.index_of(&path);
+		let image = self.load_image_(&path, index);
+		Response::LoadImage(LoadedImage { path, image, index })
+	}
+
+	fn next_path(&mut self, args: &NextPath) -> io::Result<Response> {
+		let previous_position = self.state.navigation_mode.position();
+		let previous_shuffle_position = self.state.shuffle.position();
+		let wrap = args.wrap;
+		let mut skipped = 0;
+		loop {
+			let Some(next_path) = self.state.next_path(args.clone())? else {
+				// the image before the skipped ones is still being shown
+				if skipped > 0 {
+					self.state.navigation_mode.restore(previous_position);
+					self.state.shuffle.restore(previous_shuffle_position);
+				}
+				return Ok(if wrap {
+					Response::NoOp
+				} else {
+					Response::EndReached
+				});
+			};
+			let next_path = Arc::clone(next_path);
+			let response = self.load_image(next_path);
+
+			// go back to the previous image, which is still being shown
+			if self.cancelled_before_responding() {
+				self.state.navigation_mode.restore(previous_position);
+				self.state.shuffle.restore(previous_shuffle_position);
+				return Ok(Response::NoOp);
+			}
+
+			// unreadable images are skipped, unless the user has already moved on or there are too many in a row
+			match response {
+				Response::LoadImage(LoadedImage {
+					path,
+					image: Err(error),
+					..
+				}) if !self.responded && !self.moved_on() && skipped < MAX_SKIPPED => {
+					skipped += 1;
+					self.bridge.notify(Notification::Skipped(SkippedImage {
+						path,
+						error: error.to_string(),
+					}));
+				}
+				response => return Ok(response),
+			}
+		}
 	}
 
-	fn next_path(&mut self, args: NextPath) -> io::Result<Response> {
-		let Some(next_path) = self.state.next_path(args)? else {
+	fn notify_ratings(&self, ratings: Vec<RatedImage>) {
+		for rated in ratings {
+			self.bridge.notify(Notification::RatingChanged(rated));
+		}
+	}
+
+	fn send_history(&self) {
+		let info = self.state.history.info();
+		self.bridge.notify(Notification::History(info));
+	}
+
+	/// Show `path` after an operation on it was undone or redone, or move on from it if it no longer exists.
+	fn show_changed(&mut self, path: Arc<Path>) -> io::Result<Response> {
+		if path.exists() {
+			self.state.navigation_mode.go_to(Arc::clone(&path));
+			return Ok(self.load_image(path));
+		}
+
+		let is_current = Some(&path) == self.state.current_path();
+		if is_current {
+			let args = NextPath {
+				direction: next_path::Direction::Right,
+				mode: NextPathMode::Simple,
+				sort: self.state.sort,
+				filter: self.state.filter.clone(),
+				wrap: true,
+			};
+			self.next_path(&args)
+		} else {
+			Ok(Response::NoOp)
+		}
+	}
+
+	/// Keep the place of files that were moved from the first path of each pair to the second, and show the first of them.
+	fn follow_renames(&mut self, renames: Vec<(Arc<Path>, Arc<Path>)>) -> io::Result<Response> {
+		for (from, to) in &renames {
+			self.state.navigation_mode.rename(from, to);
+		}
+		match renames.into_iter().next() {
+			Some((_from, to)) => self.show_changed(to),
+			None => Ok(Response::NoOp),
+		}
+	}
+
+	/// Do `action` to `paths`, then move on from the current image if it is gone.
+	fn run_batch(&mut self, action: batch::Action, paths: &[Arc<Path>]) -> io::Result<Response> {
+		let (outcome, operation) = batch::run(action, paths, &self.bridge.batch_progress);
+		if let Some(operation) = operation {
+			self.state.history.push(operation);
+			self.send_history();
+		}
+		let removed = outcome.action.removes();
+		// a list of images keeps following moved files, like renamed ones, but a folder moves on without them
+		if let (batch::Action::Move(dir), NavigationMode::Specified { .. }) =
+			(&outcome.action, &self.state.navigation_mode)
+		{
+			for path in &outcome.done {
+				let to = dir.join(path.file_name().unwrap_or_default());
+				self.state.navigation_mode.rename(path, &to.into());
+			}
+		}
+		let current = self.state.current_path().cloned();
+		let current_gone = removed
+			&& current
+				.as_ref()
+				.is_some_and(|current| outcome.done.contains(current));
+		self.bridge.notify(Notification::Batch(outcome));
+		match current {
+			Some(current) if current_gone => self.show_changed(current),
+			_ => Ok(Response::NoOp),
+		}
+	}
+
+	fn load_workspace(&mut self, name: &str) -> io::Result<Response> {
+		let workspace = workspace::load(&self.state.workspace_dir, name)?;
+		self.state.navigation_mode = NavigationMode::from_workspace(workspace.navigation);
+		self
+			.bridge
+			.notify(Notification::WorkspaceLoaded(workspace.view));
+		let Some(current) = self.state.current_path().cloned() else {
 			return Ok(Response::NoOp);
 		};
-		let next_path = Arc::clone(next_path);
-		Ok(self.load_image(next_path))
+		Ok(self.load_image(current))
 	}
 
+	#[allow(clippy::too_many_lines)] // one arm per command
 	fn run_command(&mut self, command: Command) -> io::Result<Response> {
+		let command = match command.run_detached() {
+			Ok(response) => return response,
+			Err(command) => command,
+		};
 		match command {
-			Command::NextPath(direction) => self.next_path(direction),
+			Command::ReadChunks(..) | Command::VerifyChecksum(..) | Command::ReadRating(..) => {
+				unreachable!("run detached")
+			}
+			Command::NextPath(direction) => self.next_path(&direction),
 			Command::DeleteFile(path) => {
-				std::fs::remove_file(&path)?;
-				let should_go_to_next = Some(&*path) == self.state.current_path().map(|path| &**path);
-				if should_go_to_next {
-					let args = NextPath {
-						direction: next_path::Direction::Right,
-						mode: NextPathMode::Simple,
+				let operation = history::Operation::delete(Arc::clone(&path), self.state.history.trash()?)?;
+				self.state.history.push(operation);
+				self.send_history();
+				self
+					.bridge
+					.notify(Notification::FileDeleted(Arc::clone(&path)));
+				self.show_changed(path)
+			}
+			Command::RenameFile { from, to } => {
+				let operation = history::Operation::rename(Arc::clone(&from), Arc::clone(&to))?;
+				self.state.history.push(operation);
+				self.send_history();
+				self.state.navigation_mode.rename(&from, &to);
+				self.show_changed(to)
+			}
+			Command::Undo => {
+				let parts = self
+					.state
+					.history
+					.undo()?
+					.map(history::Operation::parts)
+					.unwrap_or_default();
+				let undone: Vec<_> = parts
+					.iter()
+					.map(|part| (Arc::clone(part.path()), Arc::clone(part.undone_path())))
+					.collect();
+				let ratings: Vec<_> = parts
+					.iter()
+					.filter_map(|part| {
+						part
+							.undone_rating()
+							.map(|rating| (part.undone_path(), rating))
+					})
+					.map(|(path, rating)| RatedImage {
+						path: Arc::clone(path),
+						rating,
+					})
+					.collect();
+				self.send_history();
+				self.notify_ratings(ratings);
+				self.follow_renames(undone)
+			}
+			Command::Redo => {
+				let parts = self
+					.state
+					.history
+					.redo()?
+					.map(history::Operation::parts)
+					.unwrap_or_default();
+				let redone: Vec<_> = parts
+					.iter()
+					.map(|part| (Arc::clone(part.undone_path()), Arc::clone(part.path())))
+					.collect();
+				let ratings: Vec<_> = parts
+					.iter()
+					.filter_map(|part| part.rating().map(|rating| (part.path(), rating)))
+					.map(|(path, rating)| RatedImage {
+						path: Arc::clone(path),
+						rating,
+					})
+					.collect();
+				self.send_history();
+				self.notify_ratings(ratings);
+				self.follow_renames(redone)
+			}
+			Command::CopyDataUri {
+				path,
+				max_size,
+				markup,
+			} => data_uri::make(&path, max_size, markup)
+				.map(Response::CopyText)
+				.map_err(io::Error::other),
+			Command::CopyPixels {
+				path,
+				frame_idx,
+				region,
+			} => {
+				let pixels = self.state.pixels(&path).map_err(io::Error::other)?;
+				self
+					.state
+					.clipboard
+					.copy_pixels(&pixels, frame_idx, region)
+					.map_err(io::Error::other)?;
+				Ok(Response::NoOp)
+			}
+			Command::ExportSpriteSheet { path, options } => {
+				let pixels = self.state.pixels(&path).map_err(io::Error::other)?;
+				sprite_sheet::export(&path, &pixels, options).map_err(io::Error::other)?;
+				Ok(Response::NoOp)
+			}
+			Command::SampleColor {
+				path,
+				frame_idx,
+				pixel,
+			} => {
+				let pixels = self.state.pixels(&path).map_err(io::Error::other)?;
+				let color = white_balance::sample(&pixels, frame_idx, pixel);
+				Ok(Response::SampledColor(SampledColor { path, color }))
+			}
+			Command::ExportWhiteBalance {
+				path,
+				frame_idx,
+				white_balance,
+				replace,
+			} => {
+				let pixels = self.state.pixels(&path).map_err(io::Error::other)?;
+				white_balance::export(&path, &pixels, frame_idx, white_balance, replace)
+					.map_err(io::Error::other)?;
+				self.bridge.notify(Notification::FileWritten(
+					white_balance::export_path(&path).into(),
+				));
+				Ok(Response::NoOp)
+			}
+			Command::SaveAs { path, options } => {
+				save_as::save(&path, &options, || self.state.pixels(&path)).map_err(io::Error::other)?;
+				Ok(Response::NoOp)
+			}
+			Command::ExportAnimation { path, options } => {
+				let pixels = self.state.pixels(&path);
+				let progress = Arc::clone(&self.bridge.export_progress);
+				self.bridge.spawn_worker(move || {
+					let outcome = match pixels {
+						Ok(pixels) => animation_export::export(&path, &pixels, &options, &progress),
+						Err(error) => animation_export::Outcome::Failed(error.to_string()),
 					};
-					self.next_path(args)
+					Ok(Notification::AnimationExported(outcome))
+				});
+				Ok(Response::NoOp)
+			}
+			Command::SaveDiagnostics { path, error } => {
+				let bundle = diagnostic::save(&path, &error)?;
+				Ok(Response::DiagnosticsSaved(SavedDiagnostics {
+					path,
+					bundle,
+				}))
+			}
+			Command::Reload(path) => {
+				self.state.cache.remove(&path);
+				Ok(self.load_image(path))
+			}
+			Command::ClearCache => {
+				self.state.cache.clear();
+				self.state.update_cache_info(None);
+				Ok(Response::NoOp)
+			}
+			Command::SetCacheLimits(limits) => {
+				self.state.set_cache_limits(limits);
+				Ok(Response::NoOp)
+			}
+			Command::ExportChunk { path, data } => {
+				chunks::export(&path, &data)?;
+				Ok(Response::NoOp)
+			}
+			Command::GenerateChecksums(path) => {
+				self.bridge.spawn_worker(move || {
+					let dir = path.parent().unwrap_or(Path::new("."));
+					checksum::generate(dir)?;
+					let status = checksum::verify(&path)?;
+					Ok(Notification::ChecksumsGenerated(VerifiedChecksum {
+						path,
+						status,
+					}))
+				});
+				Ok(Response::NoOp)
+			}
+			Command::SetRating { path, rating } => {
+				let operation = history::Operation::rate(Arc::clone(&path), rating)?;
+				self.state.history.push(operation);
+				self.send_history();
+				self
+					.bridge
+					.notify(Notification::RatingChanged(RatedImage { path, rating }));
+				Ok(Response::NoOp)
+			}
+			Command::SlideshowOrder {
+				sort,
+				filter,
+				shuffle,
+			} => {
+				let mut paths = self.state.navigation_mode.all_paths(&filter)?;
+				if shuffle {
+					paths.shuffle(&mut rand::thread_rng());
 				} else {
-					Ok(Response::NoOp)
+					next_path::sort_paths(&mut paths, sort);
 				}
+				Ok(Response::SlideshowOrder(paths))
+			}
+			#[cfg(feature = "device-import")]
+			Command::ListDevices => {
+				// the devices can be slow to read
+				self
+					.bridge
+					.spawn_worker(|| device_import::devices().map(Notification::Devices));
+				Ok(Response::NoOp)
+			}
+			#[cfg(feature = "device-import")]
+			Command::ImportFromDevice { files, target } => {
+				let paths = device_import::import(&files, &target)?;
+				let Some(first) = paths.first().cloned() else {
+					return Ok(Response::NoOp);
+				};
+				self.state.navigation_mode = NavigationMode::specified(paths);
+				Ok(self.load_image(first))
+			}
+			Command::OpenList(paths) => {
+				let Some(first) = paths.first().cloned() else {
+					return Ok(Response::NoOp);
+				};
+				self.state.navigation_mode = NavigationMode::specified(paths);
+				Ok(self.load_image(first))
+			}
+			Command::Batch { action, paths } => self.run_batch(action, &paths),
+			Command::SaveWorkspace { name, view } => {
+				let workspace = workspace::Workspace {
+					navigation: self.state.navigation_mode.to_workspace(),
+					view,
+				};
+				workspace::save(&self.state.workspace_dir, &name, &workspace)?;
+				self.bridge.notify(Notification::WorkspacesChanged);
+				Ok(Response::NoOp)
+			}
+			Command::LoadWorkspace(name) => self.load_workspace(&name),
+			Command::DeleteWorkspace(name) => {
+				workspace::delete(&self.state.workspace_dir, &name)?;
+				self.bridge.notify(Notification::WorkspacesChanged);
+				Ok(Response::NoOp)
 			}
 		}
 	}
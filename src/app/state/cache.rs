@@ -0,0 +1,120 @@
+//! The cache of loaded images, in two tiers with separate budgets: the decoded pixels in memory, and their textures on the GPU.
+//!
+//! An image whose textures were evicted is shown again by uploading its pixels rather than decoding the file again, and an image whose pixels were dropped stays cached as long as its textures are on the GPU. Images are only dropped once they have neither.
+
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::Arc;
+
+use egui::Context;
+
+use super::actor::CacheLimits;
+use crate::app::image::Image;
+
+pub struct Cache {
+	/// Most recently used first.
+	images: VecDeque<(Arc<Path>, Arc<Image>)>,
+	limits: CacheLimits,
+}
+
+impl Cache {
+	pub fn new(limits: CacheLimits) -> Self {
+		Self {
+			images: VecDeque::new(),
+			limits,
+		}
+	}
+
+	pub fn limits(&self) -> CacheLimits {
+		self.limits
+	}
+
+	/// The cached image at `path`, marked as the most recently used, with its textures uploaded again if they were evicted.
+	/// `None` if it isn't cached or it can't be shown without decoding it again.
+	pub fn get(&mut self, path: &Path, ctx: &Context) -> Option<Arc<Image>> {
+		let idx = self
+			.images
+			.iter()
+			.position(|(cached, _)| **cached == *path)?;
+		let entry = self.images.remove(idx).unwrap();
+		if !entry.1.restore_textures(ctx) {
+			return None;
+		}
+		let image = Arc::clone(&entry.1);
+		self.images.push_front(entry);
+		self.shrink();
+		Some(image)
+	}
+
+	/// The cached image at `path` as it is, e.g., to read its kept pixels.
+	pub fn peek(&self, path: &Path) -> Option<&Arc<Image>> {
+		self
+			.images
+			.iter()
+			.find_map(|(cached, image)| (**cached == *path).then_some(image))
+	}
+
+	/// Add the image at `path` as the most recently used.
+	pub fn insert(&mut self, path: Arc<Path>, image: Arc<Image>) {
+		self.remove(&path);
+		self.images.push_front((path, image));
+		self.shrink();
+	}
+
+	pub fn remove(&mut self, path: &Path) {
+		self.images.retain(|(cached, _)| **cached != *path);
+	}
+
+	pub fn clear(&mut self) {
+		self.images.clear();
+	}
+
+	/// Apply changed limits, evicting textures and dropping pixels until they fit.
+	pub fn set_limits(&mut self, limits: CacheLimits) {
+		self.limits = limits;
+		if !limits.keep_pixels {
+			for (_path, image) in &self.images {
+				image.drop_pixels();
+			}
+		}
+		self.shrink();
+	}
+
+	/// Most recently used first.
+	pub fn iter(&self) -> impl Iterator<Item = (&Arc<Path>, &Arc<Image>)> {
+		self.images.iter().map(|(path, image)| (path, image))
+	}
+
+	/// The memory used by the pixels of the cached images, in bytes.
+	pub fn size_in_memory(&self) -> usize {
+		self
+			.iter()
+			.map(|(_path, image)| image.size_in_memory())
+			.sum()
+	}
+
+	/// The estimated GPU memory used by the textures of the cached images, in bytes.
+	pub fn size_on_gpu(&self) -> usize {
+		self.iter().map(|(_path, image)| image.size_on_gpu()).sum()
+	}
+
+	/// Evict the textures and drop the pixels of the least recently used images until each tier fits in its budget, then drop the images that have neither.
+	fn shrink(&mut self) {
+		let mut gpu = self.size_on_gpu();
+		let mut memory = self.size_in_memory();
+		// the most recently used image is the one being shown, so it is left alone
+		for (_path, image) in self.images.iter().skip(1).rev() {
+			if gpu > self.limits.gpu_budget.get() {
+				gpu -= image.size_on_gpu();
+				image.evict_textures();
+			}
+			if memory > self.limits.size.get() {
+				memory -= image.size_in_memory();
+				image.drop_pixels();
+			}
+		}
+		self
+			.images
+			.retain(|(_path, image)| image.is_resident() || image.has_pixels());
+	}
+}
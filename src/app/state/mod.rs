@@ -1,81 +1,185 @@
-use std::num::NonZeroUsize;
-use std::path::Path;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use egui::Context;
 use image::error::ImageResult;
 
-use self::actor::{LoadedImage, NavigationMode, NextPath, Response};
-use super::image::Image;
+use self::actor::{
+	CacheLimits, LoadedChunks, LoadedImage, NavigationMode, NextPath, Notification, RatedImage,
+	Response, SampledColor, SavedDiagnostics, SendResult, SkippedImage, VerifiedChecksum,
+};
+use super::events::{self, Event};
+use super::image::white_balance::WhiteBalance;
+use super::image::{
+	animation_export, chunks, corrected_path, data_uri, exif, save_as, sprite_sheet, Image, Progress,
+};
+use super::next_path::{Filter, Order};
+use super::{batch, checksum, history, rating, workspace};
 
 pub mod actor;
+mod cache;
 pub mod play;
 
 pub struct OpenImageInner {
 	pub play_state: play::State,
 	pub image: Arc<Image>,
 	pub zoom: crate::widgets::image::Zoom,
+	/// Where the image was last shown, which the zoom level is relative to.
+	pub view: Option<crate::widgets::image::View>,
+	/// Where the image was last painted, to find the pixel under the cursor.
+	pub mapping: Option<crate::widgets::selection::PixelMapping>,
+	pub selection: Option<crate::widgets::Selection>,
+	pub measurement: Option<crate::widgets::Measurement>,
+	/// Set if a static image is being viewed as a sprite sheet.
+	pub sprite_sheet: Option<play::SpriteSheet>,
+	/// The raw metadata chunks of the file or the error reading them, once they have been read.
+	pub chunks: Option<Result<Vec<chunks::Chunk>, String>>,
+	/// The GPS location from the EXIF chunk, once the chunks have been read.
+	pub gps: Option<exif::Gps>,
+	/// The result of checking the file against its sidecar checksums, once it has been verified.
+	pub checksum: Option<checksum::Status>,
+	/// The rating of the image, once it has been read.
+	pub rating: Option<rating::Rating>,
+	/// Shown by tinting the image, and only applied to the pixels when exporting.
+	pub white_balance: WhiteBalance,
+	/// Whether clicking the image sets the white balance from the clicked pixel.
+	pub picking_gray_point: bool,
+}
+
+impl OpenImageInner {
+	/// The index of the frame being shown.
+	pub fn frame_idx(&self) -> usize {
+		match self.play_state {
+			play::State::Animated { current_frame, .. } => current_frame.idx,
+			play::State::Single => 0,
+		}
+	}
 }
 
 pub struct OpenImage {
 	pub inner: ImageResult<OpenImageInner>,
 	pub path: Arc<Path>,
+	/// The index of the image among the images that can be moved between, and how many there are.
+	pub index: Option<(usize, usize)>,
 }
 
-static ERRORS_ID_COUNTER: AtomicU64 = AtomicU64::new(0);
-
 pub struct State {
 	pub current: Option<OpenImage>,
+	/// Carry the zoom and pan of the current image over to newly loaded images.
+	pub lock_view: bool,
+	/// The zoom of a workspace that was just loaded, for its current image.
+	workspace_zoom: Option<crate::widgets::image::Zoom>,
+	workspace_listing: Option<workspace::Listing>,
+	workspace_names: Option<Result<Vec<String>, String>>,
+	/// Where workspaces are saved, next to the configuration file.
+	workspace_dir: PathBuf,
 	actor: actor::Handle,
-	errors: Vec<(egui::Id, String)>,
+	/// The cache limits that the actor was last given.
+	cache_limits: CacheLimits,
+	egui_ctx: Context,
+	errors: crate::widgets::Toasts,
+	/// The path that metadata chunks were last requested for, to avoid requesting them repeatedly.
+	chunks_requested: Option<Arc<Path>>,
+	/// The path that the checksum was last requested for.
+	checksum_requested: Option<Arc<Path>>,
+	/// The path that the rating was last requested for.
+	rating_requested: Option<Arc<Path>>,
+	/// The operations that can be undone and redone.
+	pub history: history::Info,
+	/// The images that were marked to act on together, in the order they were marked.
+	pub marked: Vec<Arc<Path>>,
+	/// Whether a batch is being done to the marked images.
+	batch_running: bool,
+	/// The result of the last batch, until another one is started.
+	pub batch_outcome: Option<batch::Outcome>,
+	/// Whether an animation is being exported.
+	animation_export_running: bool,
+	/// The result of the last export of an animation, until another one is started.
+	pub animation_export_outcome: Option<animation_export::Outcome>,
+	/// The last diagnostic bundle that was saved, and the image that it is for.
+	pub diagnostic_bundle: Option<SavedDiagnostics>,
+	/// The images in the order that the slideshow shows them, once they have been listed.
+	pub slideshow_order: Option<Vec<Arc<Path>>>,
+	/// The devices that can be imported from, once they have been listed.
+	#[cfg(feature = "device-import")]
+	pub devices: Option<Vec<super::device_import::Device>>,
 }
 
-#[derive(Debug, Clone, Copy)]
-pub struct ErrorAcknowledged;
-
 impl State {
-	pub fn new(egui_ctx: Context, cache_size: NonZeroUsize, navigation_mode: NavigationMode) -> Self {
+	pub fn new(
+		egui_ctx: Context,
+		cache_limits: CacheLimits,
+		navigation_mode: NavigationMode,
+		sort: Order,
+		filter: Filter,
+		workspace_dir: PathBuf,
+	) -> Self {
 		Self {
 			current: None,
-			actor: actor::Handle::spawn(egui_ctx, navigation_mode, cache_size),
-			errors: Vec::new(),
+			lock_view: false,
+			workspace_zoom: None,
+			workspace_listing: None,
+			workspace_names: None,
+			actor: actor::Handle::spawn(
+				egui_ctx.clone(),
+				navigation_mode,
+				cache_limits,
+				sort,
+				filter,
+				workspace_dir.clone(),
+			),
+			workspace_dir,
+			cache_limits,
+			egui_ctx,
+			errors: crate::widgets::Toasts::default(),
+			chunks_requested: None,
+			checksum_requested: None,
+			rating_requested: None,
+			history: history::Info::default(),
+			marked: Vec::new(),
+			batch_running: false,
+			batch_outcome: None,
+			animation_export_running: false,
+			animation_export_outcome: None,
+			diagnostic_bundle: None,
+			slideshow_order: None,
+			#[cfg(feature = "device-import")]
+			devices: None,
 		}
 	}
 
-	pub fn waiting(&self) -> bool {
-		self.actor.waiting()
+	pub fn waiting_for(&self) -> Option<std::time::Duration> {
+		self.actor.waiting_for()
 	}
 
-	fn push_error(&mut self, error: String) {
-		let id =
-			egui::Id::new("image-state-error").with(ERRORS_ID_COUNTER.fetch_add(1, Ordering::Relaxed));
-		self.errors.push((id, error));
+	pub fn progress(&self) -> &Progress {
+		self.actor.progress()
 	}
 
-	fn show_errors_inner(
-		&mut self,
-		mut show: impl FnMut(egui::Id, &str) -> Option<ErrorAcknowledged>,
-	) {
-		self.errors.retain(|(id, error)| match show(*id, error) {
-			Some(ErrorAcknowledged) => false,
-			None => true,
-		});
+	pub fn cache_info(&self) -> std::sync::MutexGuard<'_, actor::CacheInfo> {
+		self.actor.cache_info()
+	}
+
+	pub fn clear_cache(&mut self) {
+		self.actor.clear_cache();
+	}
+
+	/// Apply the cache limits if they changed, e.g., in the settings.
+	pub fn set_cache_limits(&mut self, limits: CacheLimits) {
+		if limits != self.cache_limits
+			&& matches!(self.actor.set_cache_limits(limits), SendResult::Sent)
+		{
+			self.cache_limits = limits;
+		}
+	}
+
+	/// Show `error` as a toast.
+	pub fn push_error(&mut self, error: String) {
+		self.errors.push(error);
 	}
 
 	pub fn show_errors(&mut self, ctx: &Context) {
-		self.show_errors_inner(|id, error| {
-			let response = egui::Window::new("Error").id(id).show(ctx, |ui| {
-				ui.heading("An error occurred.");
-				ui.label(error);
-				ui.vertical_centered(|ui| ui.button("Ok").clicked()).inner
-			});
-
-			response
-				.and_then(|response| response.inner)
-				.unwrap_or(false)
-				.then_some(ErrorAcknowledged)
-		});
+		self.errors.show(ctx);
 	}
 
 	pub fn current_path(&self) -> Option<&Path> {
@@ -90,8 +194,474 @@ impl State {
 		self.actor.delete_file(file);
 	}
 
-	pub fn handle_actor_responses(&mut self) {
-		while let Some(response) = self.actor.poll_response() {
+	pub fn undo(&mut self) {
+		self.actor.undo();
+	}
+
+	pub fn redo(&mut self) {
+		self.actor.redo();
+	}
+
+	/// Save the navigation, the zoom of the current image, whether the view is locked, the marked images, and `listing`.
+	pub fn save_workspace(&mut self, name: String, listing: workspace::Listing) {
+		let zoom = match &self.current {
+			Some(OpenImage {
+				inner: Ok(inner), ..
+			}) => inner.zoom,
+			_ => crate::widgets::image::Zoom::default(),
+		};
+		let view = workspace::View {
+			lock_view: self.lock_view,
+			zoom,
+			marked: self.marked.iter().map(|path| path.to_path_buf()).collect(),
+			listing: Some(listing),
+		};
+		self.actor.save_workspace(name, view);
+	}
+
+	pub fn load_workspace(&mut self, name: String) {
+		self.actor.load_workspace(name);
+	}
+
+	pub fn delete_workspace(&mut self, name: String) {
+		self.actor.delete_workspace(name);
+	}
+
+	/// The names of the saved workspaces, which are only listed again once one is saved or deleted.
+	pub fn workspace_names(&mut self) -> &Result<Vec<String>, String> {
+		self.workspace_names.get_or_insert_with(|| {
+			workspace::list(&self.workspace_dir).map_err(|error| error.to_string())
+		})
+	}
+
+	/// The sort order and filter of the workspace that was just loaded, to be put into the configuration.
+	pub fn take_workspace_listing(&mut self) -> Option<workspace::Listing> {
+		self.workspace_listing.take()
+	}
+
+	pub fn is_marked(&self) -> bool {
+		self
+			.current
+			.as_ref()
+			.is_some_and(|current| self.marked.contains(&current.path))
+	}
+
+	/// Mark the current image, or unmark it if it is already marked.
+	pub fn toggle_mark(&mut self) {
+		let Some(current) = &self.current else {
+			return;
+		};
+		if let Some(idx) = self.marked.iter().position(|path| *path == current.path) {
+			self.marked.remove(idx);
+		} else {
+			self.marked.push(Arc::clone(&current.path));
+		}
+	}
+
+	/// Move between the marked images instead of the current ones.
+	pub fn open_marked(&mut self) {
+		self.actor.open_list(self.marked.clone());
+	}
+
+	/// Copy the paths of the marked images, one per line.
+	pub fn copy_marked_paths(&self) {
+		let mut text = String::new();
+		for path in &self.marked {
+			text.push_str(&path.to_string_lossy());
+			text.push('\n');
+		}
+		self.egui_ctx.output_mut(|output| output.copied_text = text);
+	}
+
+	/// Do `action` to the marked images.
+	pub fn run_batch(&mut self, action: batch::Action) {
+		if let SendResult::Sent = self.actor.run_batch(action, self.marked.clone()) {
+			self.batch_running = true;
+			self.batch_outcome = None;
+		}
+	}
+
+	/// The progress of the batch being done, if there is one.
+	pub fn batch_progress(&self) -> Option<&batch::Progress> {
+		self.batch_running.then(|| self.actor.batch_progress())
+	}
+
+	pub fn export_animation(&mut self, options: animation_export::Options) {
+		let Some(current) = &self.current else {
+			return;
+		};
+		if let SendResult::Sent = self
+			.actor
+			.export_animation(Arc::clone(&current.path), options)
+		{
+			self.animation_export_running = true;
+			self.animation_export_outcome = None;
+		}
+	}
+
+	/// The progress of the animation being exported, if there is one.
+	pub fn animation_export_progress(&self) -> Option<&batch::Progress> {
+		self
+			.animation_export_running
+			.then(|| self.actor.export_progress())
+	}
+
+	pub fn copy_data_uri(&mut self, max_size: usize, markup: data_uri::Markup) {
+		if let Some(current) = &self.current {
+			self
+				.actor
+				.copy_data_uri(Arc::clone(&current.path), max_size, markup);
+		}
+	}
+
+	pub fn export_sprite_sheet(&mut self, options: sprite_sheet::Options) {
+		if let Some(current) = &self.current {
+			self
+				.actor
+				.export_sprite_sheet(Arc::clone(&current.path), options);
+		}
+	}
+
+	pub fn save_as(&mut self, options: save_as::Options) {
+		if let Some(current) = &self.current {
+			self.actor.save_as(Arc::clone(&current.path), options);
+		}
+	}
+
+	/// Read the metadata chunks of the current image if they haven't been requested yet.
+	pub fn request_chunks(&mut self) {
+		let Some(current) = &self.current else {
+			return;
+		};
+		if self.chunks_requested.as_ref() == Some(&current.path) {
+			return;
+		}
+		if let SendResult::Sent = self.actor.read_chunks(Arc::clone(&current.path)) {
+			self.chunks_requested = Some(Arc::clone(&current.path));
+		}
+	}
+
+	/// Verify the current image against its sidecar checksums if that hasn't been requested yet.
+	pub fn request_checksum(&mut self) {
+		let Some(current) = &self.current else {
+			return;
+		};
+		if self.checksum_requested.as_ref() == Some(&current.path) {
+			return;
+		}
+		if let SendResult::Sent = self.actor.verify_checksum(Arc::clone(&current.path)) {
+			self.checksum_requested = Some(Arc::clone(&current.path));
+		}
+	}
+
+	/// Write a checksum manifest for the folder of the current image in the background, unless the folder already has one.
+	pub fn generate_checksums(&mut self) {
+		if let Some(current) = &self.current {
+			self.actor.generate_checksums(Arc::clone(&current.path));
+		}
+	}
+
+	/// Read the rating of the current image if that hasn't been requested yet.
+	pub fn request_rating(&mut self) {
+		let Some(OpenImage {
+			inner: Ok(_), path, ..
+		}) = &self.current
+		else {
+			return;
+		};
+		if self.rating_requested.as_ref() == Some(path) {
+			return;
+		}
+		if let SendResult::Sent = self.actor.read_rating(Arc::clone(path)) {
+			self.rating_requested = Some(Arc::clone(path));
+		}
+	}
+
+	/// Change the rating of the current image, once it is known.
+	pub fn update_rating(&mut self, update: impl FnOnce(&mut rating::Rating)) {
+		let Some(OpenImage {
+			inner: Ok(OpenImageInner {
+				rating: Some(rating),
+				..
+			}),
+			path,
+			..
+		}) = &self.current
+		else {
+			return;
+		};
+		let mut rating = *rating;
+		update(&mut rating);
+		self.actor.set_rating(Arc::clone(path), rating);
+	}
+
+	/// List the images in the order that the slideshow shows them.
+	/// Returns whether the order was requested, in which case it is set in `slideshow_order` once it arrives.
+	pub fn list_slideshow_order(&mut self, sort: Order, filter: Filter, shuffle: bool) -> bool {
+		let sent = matches!(
+			self.actor.slideshow_order(sort, filter, shuffle),
+			SendResult::Sent
+		);
+		if sent {
+			self.slideshow_order = None;
+		}
+		sent
+	}
+
+	/// List the attached devices, replacing the previous list once it is done.
+	#[cfg(feature = "device-import")]
+	pub fn list_devices(&mut self) {
+		if let SendResult::Sent = self.actor.list_devices() {
+			self.devices = None;
+		}
+	}
+
+	#[cfg(feature = "device-import")]
+	pub fn import_from_device(&mut self, files: Vec<PathBuf>, target: PathBuf) {
+		self.actor.import_from_device(files, target);
+	}
+
+	pub fn export_chunk(&mut self, idx: usize) {
+		let Some(OpenImage {
+			inner: Ok(OpenImageInner {
+				chunks: Some(Ok(chunks)),
+				..
+			}),
+			path,
+			..
+		}) = &self.current
+		else {
+			return;
+		};
+		let Some(chunk) = chunks.get(idx) else {
+			return;
+		};
+		let export_path = chunks::export_path(path, idx, chunk);
+		self
+			.actor
+			.export_chunk(export_path, Arc::clone(&chunk.data));
+	}
+
+	/// Rename the current image to have the extension of the format that it is actually in.
+	pub fn fix_extension(&mut self) {
+		let Some(OpenImage {
+			inner: Ok(inner),
+			path,
+			..
+		}) = &self.current
+		else {
+			return;
+		};
+		let Some(corrected) = corrected_path(path, inner.image.format) else {
+			return;
+		};
+		self.actor.rename_file(Arc::clone(path), corrected.into());
+	}
+
+	/// Load the current image again, e.g., if it failed to load because it was still being copied.
+	pub fn reload(&mut self) {
+		if let Some(current) = &self.current {
+			self.actor.reload(Arc::clone(&current.path));
+		}
+	}
+
+	/// Save a bundle describing why the current image failed to load, for bug reports.
+	pub fn save_diagnostics(&mut self) {
+		let Some(OpenImage {
+			inner: Err(error),
+			path,
+			..
+		}) = &self.current
+		else {
+			return;
+		};
+		self
+			.actor
+			.save_diagnostics(Arc::clone(path), error.to_string());
+	}
+
+	/// Copy the pixels of the current frame, limited to the selection if there is one.
+	pub fn copy_pixels(&mut self) {
+		let Some(OpenImage {
+			inner: Ok(inner),
+			path,
+			..
+		}) = &self.current
+		else {
+			return;
+		};
+
+		let frame_idx = inner.frame_idx();
+		let image_size = egui::vec2(az::cast(inner.image.width), az::cast(inner.image.height));
+		let region = inner
+			.selection
+			.and_then(|selection| selection.pixel_rect(image_size));
+		self.actor.copy_pixels(Arc::clone(path), frame_idx, region);
+	}
+
+	/// Set the white balance of the current image so that `pixel` of the current frame becomes neutral.
+	pub fn pick_gray_point(&mut self, pixel: egui::Pos2) {
+		let Some(OpenImage {
+			inner: Ok(inner),
+			path,
+			..
+		}) = &mut self.current
+		else {
+			return;
+		};
+		inner.picking_gray_point = false;
+		let frame_idx = inner.frame_idx();
+		self.actor.sample_color(Arc::clone(path), frame_idx, pixel);
+	}
+
+	/// Export the current frame with its white balance applied, next to the image, replacing an earlier export only if `replace` is set.
+	pub fn export_white_balance(&mut self, replace: bool) {
+		let Some(OpenImage {
+			inner: Ok(inner),
+			path,
+			..
+		}) = &self.current
+		else {
+			return;
+		};
+		let frame_idx = inner.frame_idx();
+		self
+			.actor
+			.export_white_balance(Arc::clone(path), frame_idx, inner.white_balance, replace);
+	}
+
+	/// Show the image that was loaded, keeping the zoom if the view is locked.
+	fn open_loaded(&mut self, loaded: LoadedImage, events: &mut events::Bus) {
+		let LoadedImage { path, image, index } = loaded;
+		let previous = self.current.as_ref().map(|open| Arc::clone(&open.path));
+		if previous.as_ref() != Some(&path) {
+			if let Some(previous) = previous {
+				events.emit(Event::ImageClosed(previous));
+			}
+			events.emit(Event::ImageOpened(Arc::clone(&path)));
+		}
+		self.chunks_requested = None;
+		self.checksum_requested = None;
+		self.rating_requested = None;
+		let zoom = match &self.current {
+			_ if self.workspace_zoom.is_some() => self.workspace_zoom.take().unwrap(),
+			Some(OpenImage {
+				inner: Ok(previous),
+				..
+			}) if self.lock_view => previous.zoom,
+			_ => crate::widgets::image::Zoom::default(),
+		};
+		let inner = image.map(|image| {
+			let play_state = image.make_play_state();
+			OpenImageInner {
+				play_state,
+				image,
+				zoom,
+				view: None,
+				mapping: None,
+				selection: None,
+				measurement: None,
+				sprite_sheet: None,
+				chunks: None,
+				gps: None,
+				checksum: None,
+				rating: None,
+				white_balance: WhiteBalance::default(),
+				picking_gray_point: false,
+			}
+		});
+		self.current = Some(OpenImage { inner, path, index });
+	}
+
+	fn set_checksum(&mut self, VerifiedChecksum { path, status }: VerifiedChecksum) {
+		if let Some(OpenImage {
+			inner: Ok(inner),
+			path: current_path,
+			..
+		}) = &mut self.current
+		{
+			if *current_path == path {
+				inner.checksum = Some(status);
+			}
+		}
+	}
+
+	fn push_skipped(&mut self, skipped: SkippedImage) {
+		let SkippedImage { path, error } = skipped;
+		let name = path
+			.file_name()
+			.unwrap_or(path.as_os_str())
+			.to_string_lossy();
+		self.push_error(format!(
+			"Skipped {name}, which could not be loaded: {error}"
+		));
+	}
+
+	/// Events for opened, closed, deleted, and written files, and for reaching the end of the images, are emitted to `events`.
+	fn handle_notification(&mut self, notification: Notification, events: &mut events::Bus) {
+		match notification {
+			Notification::Skipped(skipped) => self.push_skipped(skipped),
+			#[cfg(feature = "device-import")]
+			Notification::Devices(devices) => self.devices = Some(devices),
+			Notification::Error(error) => self.push_error(error.to_string()),
+			Notification::ChecksumsGenerated(checksum) => self.set_checksum(checksum),
+			Notification::AnimationExported(outcome) => {
+				self.animation_export_running = false;
+				self.animation_export_outcome = Some(outcome);
+			}
+			Notification::FileDeleted(path) => {
+				self.marked.retain(|marked| *marked != path);
+				events.emit(Event::FileDeleted(path));
+			}
+			Notification::FileWritten(path) => events.emit(Event::FileWritten(path)),
+			Notification::History(history) => {
+				self.history = history;
+			}
+			Notification::WorkspaceLoaded(view) => {
+				self.lock_view = view.lock_view;
+				self.workspace_zoom = Some(view.zoom);
+				self.marked = view.marked.into_iter().map(Arc::from).collect();
+				self.workspace_listing = view.listing;
+			}
+			Notification::WorkspacesChanged => self.workspace_names = None,
+			Notification::Batch(outcome) => {
+				self.batch_running = false;
+				if outcome.action.removes() {
+					self.marked.retain(|path| !outcome.done.contains(path));
+				}
+				if let batch::Action::Delete = outcome.action {
+					for path in &outcome.done {
+						events.emit(Event::FileDeleted(Arc::clone(path)));
+					}
+				}
+				self.batch_outcome = Some(outcome);
+			}
+			Notification::RatingChanged(RatedImage { path, rating }) => {
+				if let Some(OpenImage {
+					inner: Ok(inner),
+					path: current_path,
+					..
+				}) = &mut self.current
+				{
+					if *current_path == path {
+						inner.rating = Some(rating);
+					}
+				}
+				events.emit(Event::RatingChanged(path, rating));
+			}
+		}
+	}
+
+	pub fn handle_actor_responses(&mut self, events: &mut events::Bus) {
+		loop {
+			// notifications are sent before the response to the command that caused them, so they are handled first
+			let response = self.actor.poll_response();
+			while let Some(notification) = self.actor.poll_notification() {
+				self.handle_notification(notification, events);
+			}
+			let Some(response) = response else {
+				break;
+			};
 			let response = match response {
 				Ok(response) => response,
 				Err(error) => {
@@ -100,16 +670,53 @@ impl State {
 				}
 			};
 			match response {
-				Response::LoadImage(LoadedImage { path, image }) => {
-					let inner = image.map(|image| {
-						let play_state = image.make_play_state();
-						OpenImageInner {
-							play_state,
-							image,
-							zoom: crate::widgets::image::Zoom::default(),
+				Response::LoadImage(loaded) => self.open_loaded(loaded, events),
+				Response::Chunks(LoadedChunks { path, chunks }) => {
+					if let Some(OpenImage {
+						inner: Ok(inner),
+						path: current_path,
+						..
+					}) = &mut self.current
+					{
+						if *current_path == path {
+							inner.gps = chunks
+								.as_ref()
+								.ok()
+								.and_then(|chunks| exif::gps_from_chunks(chunks));
+							inner.chunks = Some(chunks);
+						}
+					}
+				}
+				Response::Checksum(checksum) => self.set_checksum(checksum),
+				Response::Rating(RatedImage { path, rating }) => {
+					if let Some(OpenImage {
+						inner: Ok(inner),
+						path: current_path,
+						..
+					}) = &mut self.current
+					{
+						if *current_path == path {
+							inner.rating = Some(rating);
+						}
+					}
+				}
+				Response::DiagnosticsSaved(saved) => self.diagnostic_bundle = Some(saved),
+				Response::SlideshowOrder(paths) => self.slideshow_order = Some(paths),
+				Response::SampledColor(SampledColor { path, color }) => {
+					if let Some(OpenImage {
+						inner: Ok(inner),
+						path: current_path,
+						..
+					}) = &mut self.current
+					{
+						if *current_path == path {
+							inner.white_balance = WhiteBalance::from_gray_point(color);
 						}
-					});
-					self.current = Some(OpenImage { inner, path });
+					}
+				}
+				Response::EndReached => events.emit(Event::EndReached),
+				Response::CopyText(text) => {
+					self.egui_ctx.output_mut(|output| output.copied_text = text);
 				}
 				Response::NoOp => (),
 			}
@@ -1,3 +1,5 @@
+use egui::{Rect, Vec2};
+
 use crate::app::image::Image;
 use crate::duration::Duration;
 
@@ -5,6 +7,8 @@ use crate::duration::Duration;
 pub struct CurrentFrame {
 	pub idx: usize,
 	pub remaining: Duration,
+	/// How many times playback wrapped around to the first frame.
+	pub loops: u32,
 }
 
 impl CurrentFrame {
@@ -16,22 +20,36 @@ impl CurrentFrame {
 		Self {
 			idx,
 			remaining: remaining.into(),
+			loops: 0,
 		}
 	}
 
+	/// Jumping to a frame keeps the count of loops.
 	pub fn move_to(&mut self, idx: usize, remaining: impl Into<Duration>) {
-		*self = Self::new_at(idx, remaining.into());
+		self.idx = idx;
+		self.remaining = remaining.into();
 	}
 
+	/// If `more_coming` is set, playback waits on the last frame rather than wrapping around.
 	pub fn advance(
 		&mut self,
 		elapsed: Duration,
 		num_frames: usize,
+		more_coming: bool,
 		mut get_frame_time: impl FnMut(usize) -> Duration,
 	) {
 		// note: this intentionally never advances more than one frame
 		if self.remaining.advance(elapsed) {
-			self.idx = (self.idx + 1) % num_frames;
+			let next_idx = self.idx + 1;
+			if next_idx < num_frames {
+				self.idx = next_idx;
+			} else if more_coming {
+				// `remaining` stays at zero so we advance as soon as the next frame arrives.
+				return;
+			} else {
+				self.idx = 0;
+				self.loops = self.loops.saturating_add(1);
+			}
 			self.remaining = get_frame_time(self.idx);
 		}
 	}
@@ -46,10 +64,23 @@ pub enum State {
 	Single,
 }
 
+impl State {
+	/// Whether an animation is playing and has not been played through once yet.
+	pub fn is_in_first_loop(self) -> bool {
+		match self {
+			Self::Animated {
+				current_frame,
+				playing,
+			} => playing && current_frame.loops == 0,
+			Self::Single => false,
+		}
+	}
+}
+
 impl Image {
 	pub fn make_play_state(&self) -> State {
 		if self.is_animated() {
-			let current_delay = self.frames[0].1;
+			let current_delay = self.frames.read().list[0].1;
 			State::Animated {
 				current_frame: CurrentFrame::new(current_delay),
 				playing: true,
@@ -59,3 +90,90 @@ impl Image {
 		}
 	}
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Grid {
+	Count { columns: u32, rows: u32 },
+	CellSize { width: u32, height: u32 },
+}
+
+/// Plays the cells of a static image as the frames of an animation, for previewing sprite sheets.
+#[derive(Debug, Clone, Copy)]
+pub struct SpriteSheet {
+	pub grid: Grid,
+	pub fps: f32,
+	pub current_frame: CurrentFrame,
+	pub playing: bool,
+}
+
+impl SpriteSheet {
+	pub fn new() -> Self {
+		let fps = 12.0;
+		Self {
+			grid: Grid::Count {
+				columns: 4,
+				rows: 1,
+			},
+			fps,
+			current_frame: CurrentFrame::new(Self::frame_time_for(fps)),
+			playing: true,
+		}
+	}
+
+	fn frame_time_for(fps: f32) -> Duration {
+		Duration::new_secs_f32_saturating(1.0 / fps)
+	}
+
+	pub fn frame_time(&self) -> Duration {
+		Self::frame_time_for(self.fps)
+	}
+
+	/// Returns the number of columns and rows, ignoring any leftover partial cells.
+	pub fn dimensions(&self, [width, height]: [u32; 2]) -> [u32; 2] {
+		match self.grid {
+			Grid::Count { columns, rows } => [columns.clamp(1, width), rows.clamp(1, height)],
+			Grid::CellSize {
+				width: cell_width,
+				height: cell_height,
+			} => [
+				(width / cell_width.max(1)).max(1),
+				(height / cell_height.max(1)).max(1),
+			],
+		}
+	}
+
+	pub fn num_cells(&self, image_size: [u32; 2]) -> usize {
+		let [columns, rows] = self.dimensions(image_size);
+		// there can be a cell for every pixel, which overflows `u32` for large images
+		az::cast::<_, usize>(columns).saturating_mul(az::cast(rows))
+	}
+
+	/// The rect of the cell at `idx`, in pixels.
+	pub fn cell_rect(&self, idx: usize, image_size @ [width, height]: [u32; 2]) -> Rect {
+		let [columns, rows] = self.dimensions(image_size);
+		let cell_size = match self.grid {
+			Grid::Count { .. } => [width / columns, height / rows],
+			Grid::CellSize {
+				width: cell_width,
+				height: cell_height,
+			} => [cell_width.min(width), cell_height.min(height)],
+		};
+		let columns: usize = az::cast(columns);
+		let column = idx % columns;
+		let row = idx / columns;
+		let cell_size = Vec2::new(az::cast(cell_size[0]), az::cast(cell_size[1]));
+		let min = Vec2::new(az::cast(column), az::cast(row)) * cell_size;
+		Rect::from_min_size(min.to_pos2(), cell_size)
+	}
+
+	pub fn advance(&mut self, elapsed: Duration, image_size: [u32; 2]) {
+		let num_cells = self.num_cells(image_size);
+		let frame_time = self.frame_time();
+		if self.current_frame.idx >= num_cells {
+			self.current_frame.move_to(0, frame_time);
+		}
+		self
+			.current_frame
+			.advance(elapsed, num_cells, false, |_| frame_time);
+	}
+}
@@ -0,0 +1,56 @@
+//! Viewing an image that is piped in, e.g., `curl … | eo2 -`.
+//!
+//! The image is read into memory and written to a temporary file named after its format, so that it is loaded like any other image. The file is removed when eo2 exits.
+
+use std::io::{self, Read as _};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// The path that stands for stdin on the command line.
+pub const ARG: &str = "-";
+
+#[derive(Debug)]
+pub struct Piped {
+	/// Removed along with the file when dropped.
+	_dir: tempfile::TempDir,
+	pub path: Arc<Path>,
+}
+
+impl Piped {
+	/// Read the image from stdin if it is the only path given.
+	pub fn read_if_requested(paths: &[Arc<Path>]) -> io::Result<Option<Self>> {
+		match paths {
+			[path] if path.as_os_str() == ARG => Self::read().map(Some),
+			_ => Ok(None),
+		}
+	}
+
+	fn read() -> io::Result<Self> {
+		let mut data = Vec::new();
+		io::stdin().lock().read_to_end(&mut data)?;
+		if data.is_empty() {
+			return Err(io::Error::new(
+				io::ErrorKind::UnexpectedEof,
+				"nothing was piped in",
+			));
+		}
+		// an unknown format is still written so that loading it shows why it can't be opened
+		let extension = image::guess_format(&data).map_or("bin", |format| format.extensions_str()[0]);
+
+		// created with a random name that only we can access, so other users can't swap the file out
+		let dir = tempfile::Builder::new().prefix("eo2-stdin-").tempdir()?;
+		let name = format!("stdin.{extension}");
+		let path = dir.path().join(&name);
+		std::fs::write(&path, data)?;
+		Ok(Self {
+			_dir: dir,
+			path: path.into(),
+		})
+	}
+
+	/// Where to save the image by default, in the working directory since the temporary file is removed on exit.
+	pub fn default_target(&self) -> PathBuf {
+		let name = self.path.file_name().unwrap_or_default();
+		std::env::current_dir().unwrap_or_default().join(name)
+	}
+}
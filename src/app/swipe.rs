@@ -0,0 +1,94 @@
+//! Swiping horizontally to move between images, with one finger on a touchscreen or two fingers on a trackpad.
+
+use egui::{Context, Response, Vec2};
+
+use super::next_path::Direction;
+
+/// How far a swipe has to move to change the image, in points.
+const SWIPE_DISTANCE: f32 = 100.0;
+/// Trackpads report swipes as a stream of horizontal scroll events, so a pause this long (in seconds) ends the gesture.
+const SCROLL_GESTURE_GAP: f64 = 0.3;
+
+/// Moving the content to the left brings in the next image from the right.
+fn direction(distance: f32) -> Option<Direction> {
+	if distance <= -SWIPE_DISTANCE {
+		Some(Direction::Right)
+	} else if distance >= SWIPE_DISTANCE {
+		Some(Direction::Left)
+	} else {
+		None
+	}
+}
+
+#[derive(Debug)]
+struct ScrollGesture {
+	distance: f32,
+	/// Whether the gesture already changed the image, so that one swipe only moves by one image.
+	done: bool,
+	last_event: f64,
+}
+
+#[derive(Debug, Default)]
+pub struct Swipe {
+	/// How far the finger has moved, if it is swiping.
+	drag: Option<Vec2>,
+	scroll: Option<ScrollGesture>,
+}
+
+impl Swipe {
+	/// Track a finger swiping across the image. Swipes only start while the image is not zoomed or panned, since then dragging pans it instead.
+	/// Returns whether a swipe is in progress, in which case the drag should not pan the image, and the direction to move in once the swipe ends.
+	pub fn drag(&mut self, response: &Response, zoomed: bool) -> (bool, Option<Direction>) {
+		let (touching, multi_touch) = response
+			.ctx
+			.input(|input| (input.any_touches(), input.multi_touch().is_some()));
+		// adding a finger turns the swipe into a pinch
+		if multi_touch {
+			self.drag = None;
+			return (false, None);
+		}
+		if response.drag_started() && touching && !zoomed {
+			self.drag = Some(Vec2::ZERO);
+		}
+		let Some(drag) = &mut self.drag else {
+			return (false, None);
+		};
+
+		*drag += response.drag_delta();
+		if response.dragged() {
+			return (true, None);
+		}
+		let drag = self.drag.take().unwrap();
+		let horizontal = drag.x.abs() > drag.y.abs();
+		(true, direction(drag.x).filter(|_| horizontal))
+	}
+
+	/// Track horizontal scrolling, which is how trackpads report two-finger swipes. Returns the direction to move in, at most once per gesture.
+	pub fn scroll(&mut self, ctx: &Context) -> Option<Direction> {
+		let (delta, time) = ctx.input(|input| (input.smooth_scroll_delta.x, input.time));
+		if self
+			.scroll
+			.as_ref()
+			.is_some_and(|gesture| time - gesture.last_event > SCROLL_GESTURE_GAP)
+		{
+			self.scroll = None;
+		}
+		if delta == 0.0 {
+			return None;
+		}
+
+		let gesture = self.scroll.get_or_insert(ScrollGesture {
+			distance: 0.0,
+			done: false,
+			last_event: time,
+		});
+		gesture.last_event = time;
+		if gesture.done {
+			return None;
+		}
+		gesture.distance += delta;
+		let direction = direction(gesture.distance);
+		gesture.done = direction.is_some();
+		direction
+	}
+}
@@ -0,0 +1,82 @@
+//! Switching between the light and dark themes while running, following the system or the sun.
+
+use std::f64::consts::TAU;
+
+use eframe::Theme;
+use egui::{Context, Visuals};
+use time::OffsetDateTime;
+
+use crate::config::{Config, Style, ThemeFollow};
+
+/// How often to check the sun, which moves slowly.
+const SUN_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// The elevation (in degrees) of the center of the sun at sunrise and sunset, accounting for refraction and the size of the sun.
+const HORIZON: f64 = -0.833;
+
+/// The elevation of the sun above the horizon in degrees, using the NOAA approximation of its position.
+fn solar_elevation(now: OffsetDateTime, latitude: f64, longitude: f64) -> f64 {
+	let minutes =
+		f64::from(now.hour()) * 60.0 + f64::from(now.minute()) + f64::from(now.second()) / 60.0;
+	// the fraction of the year, in radians
+	let year = TAU / 365.0 * (f64::from(now.ordinal()) - 1.0 + (minutes / 60.0 - 12.0) / 24.0);
+	let equation_of_time = 229.18
+		* (0.000_075 + 0.001_868 * year.cos()
+			- 0.032_077 * year.sin()
+			- 0.014_615 * (2.0 * year).cos()
+			- 0.040_849 * (2.0 * year).sin());
+	let declination = 0.006_918 - 0.399_912 * year.cos() + 0.070_257 * year.sin()
+		- 0.006_758 * (2.0 * year).cos()
+		+ 0.000_907 * (2.0 * year).sin()
+		- 0.002_697 * (3.0 * year).cos()
+		+ 0.001_48 * (3.0 * year).sin();
+
+	let solar_time = minutes + equation_of_time + 4.0 * longitude;
+	let hour_angle = (solar_time / 4.0 - 180.0).to_radians();
+	let latitude = latitude.to_radians();
+	let zenith = (latitude.sin() * declination.sin()
+		+ latitude.cos() * declination.cos() * hour_angle.cos())
+	.clamp(-1.0, 1.0)
+	.acos();
+	90.0 - zenith.to_degrees()
+}
+
+fn sun_is_up(latitude: f64, longitude: f64) -> bool {
+	solar_elevation(OffsetDateTime::now_utc(), latitude, longitude) > HORIZON
+}
+
+/// Apply the theme that the configuration asks for, with the changes to its style, if it differs from the one that was applied last.
+/// `system` is the theme of the system, if it is known.
+pub fn update(ctx: &Context, config: &Config, system: Option<Theme>) {
+	let auto = config.auto_theme;
+	let theme = match auto.follow {
+		ThemeFollow::Off => config.theme.or(system),
+		ThemeFollow::System => system,
+		ThemeFollow::Sun => {
+			ctx.request_repaint_after(SUN_CHECK_INTERVAL);
+			Some(if sun_is_up(auto.latitude, auto.longitude) {
+				Theme::Light
+			} else {
+				Theme::Dark
+			})
+		}
+	};
+	// without a theme, the current one is kept
+	let dark = match theme {
+		Some(theme) => theme == Theme::Dark,
+		None => ctx.style().visuals.dark_mode,
+	};
+	let wanted = (dark, config.style);
+	let id = egui::Id::new("theme-applied");
+	if ctx.data(|data| data.get_temp::<(bool, Style)>(id)) == Some(wanted) {
+		return;
+	}
+	let mut visuals = if dark {
+		Visuals::dark()
+	} else {
+		Visuals::light()
+	};
+	config.style.apply(&mut visuals);
+	ctx.set_visuals(visuals);
+	ctx.data_mut(|data| data.insert_temp(id, wanted));
+}
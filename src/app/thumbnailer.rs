@@ -0,0 +1,46 @@
+//! Making thumbnails for file managers without opening a window, for `--thumbnail`, following the freedesktop thumbnailer specification.
+//!
+//! File managers run `eo2 --thumbnail <input> <output> <size>` for the formats that they can't read themselves, as listed in a `.thumbnailer` file.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use image::imageops::{self, FilterType};
+use image::{ImageFormat, ImageResult};
+
+use super::image::save_as::Resize;
+use super::image::{to_rgba, Image};
+
+/// Write a PNG thumbnail of the image at `input`, no larger than `size` on either side, to `output`.
+/// Animations are represented by their first frame.
+fn make(input: &Path, output: &Path, size: u32) -> ImageResult<()> {
+	let image = Image::load_first_frame(input)?;
+	let frames = image.frames.read();
+	let source = to_rgba(image.width, image.height, &frames.list[0].0);
+
+	let (width, height) = Resize::Fit {
+		width: size,
+		height: size,
+	}
+	.size(image.width, image.height);
+	let thumbnail = if (width, height) == source.dimensions() {
+		source
+	} else {
+		imageops::resize(&source, width, height, FilterType::Triangle)
+	};
+	thumbnail.save_with_format(output, ImageFormat::Png)
+}
+
+/// Make a thumbnail from the input path, output path, and size given on the command line.
+/// Problems are reported on stderr, which file managers ignore, and make this return an error so that the file manager doesn't use the output.
+pub fn run(paths: &[Arc<Path>]) -> Result<(), ()> {
+	let [input, output, size] = paths else {
+		eprintln!("expected <input> <output> <size>");
+		return Err(());
+	};
+	let Some(size) = size.to_str().and_then(|size| size.parse().ok()) else {
+		eprintln!("invalid size {:?}", size.display().to_string());
+		return Err(());
+	};
+	make(input, output, size).map_err(|error| eprintln!("{}: {error}", input.display()))
+}
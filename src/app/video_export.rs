@@ -0,0 +1,265 @@
+//! Rendering the slideshow to a video by piping frames to `ffmpeg`, so that a curated folder can be shared as a video.
+//!
+//! Each image is scaled to fit the video on a black background and shown for the slideshow interval, and animated images play as they do in the slideshow.
+//! The Ken Burns effect is not rendered. `ffmpeg` must be installed; it picks the codec from the extension of the output, e.g., `.mp4` or `.webm`.
+
+use std::io::{self, Read as _, Write as _};
+use std::path::{Path, PathBuf};
+use std::process::{ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use egui::Color32;
+use image::imageops::{self, FilterType};
+use image::{Rgba, RgbaImage};
+
+use super::image::{to_rgba, Pixels};
+use crate::duration::Duration;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Settings {
+	pub width: u32,
+	pub height: u32,
+	pub frame_rate: u32,
+	pub interval: Duration,
+	/// Play animated images through at least once, even if that is longer than the interval.
+	pub wait_for_animations: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum Status {
+	Running {
+		done: usize,
+		total: usize,
+	},
+	Finished {
+		output: PathBuf,
+		/// How many images were left out because they could not be loaded.
+		skipped: usize,
+	},
+	Cancelled,
+	Failed(String),
+}
+
+/// An export running in the background, which is cancelled when this is dropped.
+pub struct Export {
+	status: Arc<Mutex<Status>>,
+	cancelled: Arc<AtomicBool>,
+}
+
+impl Export {
+	/// Start rendering `paths`, in order, to `output`.
+	pub fn start(
+		egui_ctx: egui::Context,
+		paths: Vec<Arc<Path>>,
+		output: PathBuf,
+		settings: Settings,
+	) -> Self {
+		let status = Arc::new(Mutex::new(Status::Running {
+			done: 0,
+			total: paths.len(),
+		}));
+		let cancelled = Arc::new(AtomicBool::new(false));
+		let worker = Worker {
+			egui_ctx,
+			status: Arc::clone(&status),
+			cancelled: Arc::clone(&cancelled),
+			settings,
+		};
+		thread::spawn(move || worker.run(&paths, output));
+		Self { status, cancelled }
+	}
+
+	pub fn status(&self) -> Status {
+		self.status.lock().unwrap().clone()
+	}
+
+	pub fn cancel(&self) {
+		self.cancelled.store(true, Ordering::Relaxed);
+	}
+}
+
+impl Drop for Export {
+	fn drop(&mut self) {
+		self.cancel();
+	}
+}
+
+struct Worker {
+	egui_ctx: egui::Context,
+	status: Arc<Mutex<Status>>,
+	cancelled: Arc<AtomicBool>,
+	settings: Settings,
+}
+
+impl Worker {
+	fn run(self, paths: &[Arc<Path>], output: PathBuf) {
+		let status = match self.render(paths, &output) {
+			Ok(Some(skipped)) => Status::Finished { output, skipped },
+			Ok(None) => {
+				// the video would end partway through
+				_ = std::fs::remove_file(&output);
+				Status::Cancelled
+			}
+			Err(error) => Status::Failed(error.to_string()),
+		};
+		self.set_status(status);
+	}
+
+	fn set_status(&self, status: Status) {
+		*self.status.lock().unwrap() = status;
+		self.egui_ctx.request_repaint();
+	}
+
+	fn is_cancelled(&self) -> bool {
+		self.cancelled.load(Ordering::Relaxed)
+	}
+
+	/// The size of the video, which must be even for the chroma subsampling of `yuv420p`.
+	fn size(&self) -> (u32, u32) {
+		let even = |side: u32| side.max(2) & !1;
+		(even(self.settings.width), even(self.settings.height))
+	}
+
+	/// Returns how many images were skipped, or `None` if the export was cancelled. An existing file at `output` is never replaced.
+	fn render(&self, paths: &[Arc<Path>], output: &Path) -> io::Result<Option<usize>> {
+		// checked here for a clear error; ffmpeg also refuses, in case the file is created in the meantime
+		if output.try_exists()? {
+			return Err(io::Error::new(
+				io::ErrorKind::AlreadyExists,
+				format!("{} already exists", output.display()),
+			));
+		}
+		let (width, height) = self.size();
+		let mut ffmpeg = Command::new("ffmpeg")
+			.args(["-n", "-loglevel", "error"])
+			.args(["-f", "rawvideo", "-pix_fmt", "rgba"])
+			.args(["-video_size", &format!("{width}x{height}")])
+			.args(["-framerate", &self.settings.frame_rate.max(1).to_string()])
+			.args(["-i", "-"])
+			// the most widely supported pixel format
+			.args(["-pix_fmt", "yuv420p"])
+			.arg(output)
+			.stdin(Stdio::piped())
+			.stdout(Stdio::null())
+			.stderr(Stdio::piped())
+			.spawn()
+			.map_err(|error| io::Error::new(error.kind(), format!("running ffmpeg: {error}")))?;
+
+		let written = self.write_images(paths, ffmpeg.stdin.take().unwrap());
+		if self.is_cancelled() {
+			_ = ffmpeg.kill();
+		}
+		let mut errors = String::new();
+		if let Some(mut stderr) = ffmpeg.stderr.take() {
+			_ = stderr.read_to_string(&mut errors);
+		}
+		let exit = ffmpeg.wait()?;
+
+		if self.is_cancelled() {
+			return Ok(None);
+		}
+		// a failed write is most likely because ffmpeg exited, which explains why
+		if !exit.success() {
+			return Err(io::Error::other(format!(
+				"ffmpeg failed: {}",
+				errors.trim()
+			)));
+		}
+		written.map(Some)
+	}
+
+	/// Returns how many images were skipped. Closes the input of ffmpeg when done, which finishes the video.
+	fn write_images(&self, paths: &[Arc<Path>], mut input: ChildStdin) -> io::Result<usize> {
+		let mut skipped = 0;
+		for (idx, path) in paths.iter().enumerate() {
+			if self.is_cancelled() {
+				break;
+			}
+			match Pixels::decode(path) {
+				Ok(image) => self.write_image(&image, &mut input)?,
+				Err(_) => skipped += 1,
+			}
+			self.set_status(Status::Running {
+				done: idx + 1,
+				total: paths.len(),
+			});
+		}
+		Ok(skipped)
+	}
+
+	fn write_image(&self, image: &Pixels, input: &mut ChildStdin) -> io::Result<()> {
+		let secs = |duration: Duration| std::time::Duration::from(duration).as_secs_f64();
+		let frame_rate = f64::from(self.settings.frame_rate.max(1));
+		let frames = &image.frames;
+
+		let loop_length: f64 = if frames.len() > 1 {
+			frames.iter().map(|(_frame, delay)| secs(*delay)).sum()
+		} else {
+			0.0
+		};
+		let mut length = secs(self.settings.interval);
+		if self.settings.wait_for_animations {
+			length = length.max(loop_length);
+		}
+		let count = az::saturating_cast::<f64, usize>((length * frame_rate).ceil()).max(1);
+
+		let mut shown = None;
+		let mut canvas = RgbaImage::new(0, 0);
+		for video_frame in 0..count {
+			if self.is_cancelled() {
+				break;
+			}
+			let idx = if loop_length > 0.0 {
+				let time = az::cast::<usize, f64>(video_frame) / frame_rate % loop_length;
+				frame_at(frames, time, secs)
+			} else {
+				0
+			};
+			if shown != Some(idx) {
+				canvas = self.fit(image.width, image.height, &frames[idx].0);
+				shown = Some(idx);
+			}
+			input.write_all(canvas.as_raw())?;
+		}
+		Ok(())
+	}
+
+	/// Scale the frame to fit the video, centered on a black background.
+	fn fit(&self, width: u32, height: u32, frame: &[Color32]) -> RgbaImage {
+		let (video_width, video_height) = self.size();
+		let source = to_rgba(width, height, frame);
+		let scale =
+			(f64::from(video_width) / f64::from(width)).min(f64::from(video_height) / f64::from(height));
+		let scaled =
+			|side: u32| az::saturating_cast::<f64, u32>((f64::from(side) * scale).round()).max(1);
+		let (fitted_width, fitted_height) = (scaled(width), scaled(height));
+		let fitted = imageops::resize(&source, fitted_width, fitted_height, FilterType::Triangle);
+
+		let mut canvas = RgbaImage::from_pixel(video_width, video_height, Rgba([0, 0, 0, u8::MAX]));
+		imageops::overlay(
+			&mut canvas,
+			&fitted,
+			i64::from(video_width.saturating_sub(fitted_width) / 2),
+			i64::from(video_height.saturating_sub(fitted_height) / 2),
+		);
+		canvas
+	}
+}
+
+/// The index of the frame that is shown `time` seconds into the animation.
+fn frame_at(
+	frames: &[(Arc<[Color32]>, Duration)],
+	time: f64,
+	secs: impl Fn(Duration) -> f64,
+) -> usize {
+	let mut end = 0.0;
+	for (idx, (_frame, delay)) in frames.iter().enumerate() {
+		end += secs(*delay);
+		if time < end {
+			return idx;
+		}
+	}
+	frames.len() - 1
+}
@@ -0,0 +1,137 @@
+//! Saving the viewing context as a named workspace, to return to the same comparison or triage setup later.
+//!
+//! Workspaces are stored as TOML files in the `workspaces` folder next to the configuration.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{self, Config};
+use crate::widgets::image::Zoom;
+
+/// The images being navigated, mirroring the actor's navigation mode.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum Navigation {
+	InDirectory { current: PathBuf },
+	Specified { paths: Vec<PathBuf>, current: usize },
+	Empty,
+}
+
+/// The parts of a workspace that belong to the UI rather than the actor.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[allow(clippy::struct_field_names)] // `lock_view` is named after the setting, not the struct
+pub struct View {
+	/// Whether the zoom and pan are kept when changing images.
+	#[serde(default)]
+	pub lock_view: bool,
+	/// The zoom and pan of the current image.
+	#[serde(default)]
+	pub zoom: Zoom,
+	/// The marked images, in the order they were marked.
+	#[serde(default)]
+	pub marked: Vec<PathBuf>,
+	/// Missing from workspaces that were saved before it was stored, which keep the current settings when loaded.
+	#[serde(default)]
+	pub listing: Option<Listing>,
+}
+
+/// The settings that choose which images are moved between and in what order, which are put back into the configuration when the workspace is loaded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Listing {
+	pub sort: config::Sort,
+	pub name_order: config::NameOrder,
+	pub filter: String,
+	pub rating_filter: config::RatingFilter,
+	pub hidden_files: config::HiddenFiles,
+	/// Whether the slideshow is shuffled.
+	pub shuffle: bool,
+}
+
+impl Listing {
+	pub fn from_config(config: &Config) -> Self {
+		Self {
+			sort: config.sort,
+			name_order: config.name_order,
+			filter: config.filter.clone(),
+			rating_filter: config.rating_filter,
+			hidden_files: config.hidden_files,
+			shuffle: config.slideshow.shuffle,
+		}
+	}
+
+	pub fn apply(self, config: &mut Config) {
+		config.sort = self.sort;
+		config.name_order = self.name_order;
+		config.filter = self.filter;
+		config.rating_filter = self.rating_filter;
+		config.hidden_files = self.hidden_files;
+		config.slideshow.shuffle = self.shuffle;
+	}
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Workspace {
+	pub navigation: Navigation,
+	#[serde(default)]
+	pub view: View,
+}
+
+/// The folder of the workspaces, next to the configuration file at `config_path`.
+pub fn dir(config_path: &Path) -> PathBuf {
+	config_path
+		.parent()
+		.unwrap_or(Path::new("."))
+		.join("workspaces")
+}
+
+fn path(dir: &Path, name: &str) -> io::Result<PathBuf> {
+	// the name becomes a file name, so it must not escape the folder
+	if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+		return Err(io::Error::new(
+			io::ErrorKind::InvalidInput,
+			format!("{name:?} is not a valid workspace name"),
+		));
+	}
+	Ok(dir.join(format!("{name}.toml")))
+}
+
+pub fn save(dir: &Path, name: &str, workspace: &Workspace) -> io::Result<()> {
+	let path = path(dir, name)?;
+	let raw = toml::to_string(workspace)
+		.map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+	std::fs::create_dir_all(dir)?;
+	std::fs::write(path, raw)
+}
+
+pub fn load(dir: &Path, name: &str) -> io::Result<Workspace> {
+	let raw = std::fs::read_to_string(path(dir, name)?)?;
+	toml::from_str(&raw).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+pub fn delete(dir: &Path, name: &str) -> io::Result<()> {
+	std::fs::remove_file(path(dir, name)?)
+}
+
+/// The names of the saved workspaces, in natural order.
+pub fn list(dir: &Path) -> io::Result<Vec<String>> {
+	let entries = match std::fs::read_dir(dir) {
+		Ok(entries) => entries,
+		Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+		Err(error) => return Err(error),
+	};
+	let mut names = Vec::new();
+	for entry in entries {
+		let path = entry?.path();
+		if path
+			.extension()
+			.is_some_and(|extension| extension == "toml")
+		{
+			if let Some(name) = path.file_stem() {
+				names.push(name.to_string_lossy().into_owned());
+			}
+		}
+	}
+	names.sort_unstable_by(|left, right| natord::compare(left, right));
+	Ok(names)
+}
@@ -1,14 +1,47 @@
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+use crate::config::{Config, Sort};
+
 /// View images
 #[derive(argh::FromArgs)]
+#[allow(clippy::struct_excessive_bools)] // each is a command-line switch
 pub struct Args {
-	/// the image(s) to open
+	/// the image(s) to open, a directory to open its first image, or - to read an image from stdin
 	///
 	/// if multiple images are specified, only these images will be used when moving left and right, rather than all the images in the directory of the initial image.
 	#[argh(positional, from_str_fn(via_pathbuf))]
 	pub paths: Vec<Arc<Path>>,
+	/// the configuration file to use instead of the default one
+	#[argh(option)]
+	pub config: Option<PathBuf>,
+	/// start in fullscreen
+	#[argh(switch)]
+	pub fullscreen: bool,
+	/// start a slideshow
+	#[argh(switch)]
+	pub slideshow: bool,
+	/// hide the title bar and borders of the window, overriding the configuration
+	#[argh(switch)]
+	pub borderless: bool,
+	/// shuffle the slideshow, overriding the configuration
+	#[argh(switch)]
+	pub shuffle: bool,
+	/// the order of the images (name, modified, or size), overriding the configuration
+	#[argh(option, from_str_fn(parse_sort))]
+	pub sort: Option<Sort>,
+	/// only move between the images in a directory that match these comma-separated globs or extensions (e.g., "*.png,*.webp" or "png,webp"), overriding the configuration
+	#[argh(option)]
+	pub filter: Option<String>,
+	/// print the format, dimensions, frames, and file information of the images instead of opening them
+	#[argh(switch)]
+	pub info: bool,
+	/// with --info, print each image as a line of JSON
+	#[argh(switch)]
+	pub json: bool,
+	/// write a PNG thumbnail of the image, given as <input> <output> <size>, for file managers
+	#[argh(switch)]
+	pub thumbnail: bool,
 }
 
 #[allow(clippy::unnecessary_wraps)] // required for `argh` interface
@@ -16,6 +49,106 @@ fn via_pathbuf(s: &str) -> Result<Arc<Path>, String> {
 	Ok(PathBuf::from(s).into())
 }
 
+fn parse_sort(s: &str) -> Result<Sort, String> {
+	Sort::VARIANTS
+		.iter()
+		.copied()
+		.find(|sort| sort.name() == s)
+		.ok_or_else(|| {
+			let names: Vec<_> = Sort::VARIANTS.iter().map(|sort| sort.name()).collect();
+			format!("expected one of {}", names.join(", "))
+		})
+}
+
+/// A setting that a flag replaced, with the value from the configuration and the value of the flag.
+#[derive(Debug, Clone)]
+struct Replaced<T> {
+	original: T,
+	flag: T,
+}
+
+impl<T: Clone + PartialEq> Replaced<T> {
+	fn new(setting: &mut T, flag: T) -> Self {
+		Self {
+			original: std::mem::replace(setting, flag.clone()),
+			flag,
+		}
+	}
+
+	/// Keep the flag in the reloaded `new` setting if it was still in effect in `old`, taking the value to restore from `new` instead.
+	/// If the setting was changed since, the flag is forgotten and the reloaded value is kept.
+	fn carry_over(this: &mut Option<Self>, old: &T, new: &mut T) {
+		let Some(replaced) = this else {
+			return;
+		};
+		if *old == replaced.flag {
+			replaced.original = std::mem::replace(new, replaced.flag.clone());
+		} else {
+			*this = None;
+		}
+	}
+
+	/// Put back the value from the configuration, unless the setting was changed since, in which case the change is kept.
+	fn restore(this: Option<&Self>, setting: &mut T) {
+		if let Some(replaced) = this.filter(|replaced| *setting == replaced.flag) {
+			setting.clone_from(&replaced.original);
+		}
+	}
+}
+
+/// The configuration that was replaced by flags, which is put back before saving so that the flags only last for one run.
+#[derive(Debug, Clone)]
+pub struct Overridden {
+	shuffle: Option<Replaced<bool>>,
+	sort: Option<Replaced<Sort>>,
+	filter: Option<Replaced<String>>,
+	borderless: Option<Replaced<bool>>,
+}
+
+impl Args {
+	pub fn override_config(&self, config: &mut Config) -> Overridden {
+		Overridden {
+			shuffle: self
+				.shuffle
+				.then(|| Replaced::new(&mut config.slideshow.shuffle, true)),
+			sort: self.sort.map(|sort| Replaced::new(&mut config.sort, sort)),
+			filter: self
+				.filter
+				.clone()
+				.map(|filter| Replaced::new(&mut config.filter, filter)),
+			borderless: self
+				.borderless
+				.then(|| Replaced::new(&mut config.window.borderless, true)),
+		}
+	}
+}
+
+impl Overridden {
+	/// Keep the overrides in the configuration that replaces `old` when it is reloaded, taking the values to restore from the reloaded configuration instead.
+	pub fn carry_over(&mut self, old: &Config, new: &mut Config) {
+		Replaced::carry_over(
+			&mut self.shuffle,
+			&old.slideshow.shuffle,
+			&mut new.slideshow.shuffle,
+		);
+		Replaced::carry_over(&mut self.sort, &old.sort, &mut new.sort);
+		Replaced::carry_over(&mut self.filter, &old.filter, &mut new.filter);
+		Replaced::carry_over(
+			&mut self.borderless,
+			&old.window.borderless,
+			&mut new.window.borderless,
+		);
+	}
+
+	/// Put back the settings that the flags replaced, except for those that were changed in the meantime.
+	pub fn restore(&self, config: &mut Config) {
+		Replaced::restore(self.shuffle.as_ref(), &mut config.slideshow.shuffle);
+		Replaced::restore(self.sort.as_ref(), &mut config.sort);
+		Replaced::restore(self.filter.as_ref(), &mut config.filter);
+		Replaced::restore(self.borderless.as_ref(), &mut config.window.borderless);
+	}
+}
+
 pub fn load() -> Args {
 	argh::from_env()
 }
@@ -0,0 +1,538 @@
+//! The keyboard shortcuts of the actions of the app, which can be rebound in the settings.
+//!
+//! Shortcuts are written like `Ctrl+Shift+Z`, using the names of the keys from egui, e.g., `Right` or `Plus`.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+
+use egui::{Event, EventFilter, Key, KeyboardShortcut, Modifiers, RichText};
+use serde::{Deserialize, Serialize};
+
+use crate::widgets;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+	Next,
+	Previous,
+	/// Go to the next image in order even if a shuffled slideshow is active.
+	NextIgnoringSlideshow,
+	PreviousIgnoringSlideshow,
+	ZoomFit,
+	ZoomFill,
+	ZoomActualSize,
+	ZoomContent,
+	ToggleSlideshow,
+	ExtendSlideshow,
+	ToggleFullscreen,
+	ToggleSidebar,
+	ToggleSettings,
+	ToggleInternal,
+	ToggleLockView,
+	ToggleLoupe,
+	ToggleMeasure,
+	ToggleMark,
+	ToggleFavorite,
+	Rate1,
+	Rate2,
+	Rate3,
+	Rate4,
+	Rate5,
+	ClearRating,
+	CopyPixels,
+	CopyDataUri,
+	CopyHtml,
+	ClearSelection,
+	Undo,
+	Redo,
+	Quit,
+}
+
+impl Action {
+	pub fn repr(self) -> &'static str {
+		match self {
+			Self::Next => "Go to next",
+			Self::Previous => "Go to previous",
+			Self::NextIgnoringSlideshow => "Go to next, ignoring the slideshow",
+			Self::PreviousIgnoringSlideshow => "Go to previous, ignoring the slideshow",
+			Self::ZoomFit => "Fit to the window",
+			Self::ZoomFill => "Fill the window",
+			Self::ZoomActualSize => "Zoom to actual size",
+			Self::ZoomContent => "Fit to the content of the image",
+			Self::ToggleSlideshow => "Toggle slideshow",
+			Self::ExtendSlideshow => "Give the current image extra time",
+			Self::ToggleFullscreen => "Toggle fullscreen",
+			Self::ToggleSidebar => "Toggle info panel",
+			Self::ToggleSettings => "Toggle settings",
+			Self::ToggleInternal => "Toggle internal state window",
+			Self::ToggleLockView => "Toggle keeping the zoom and pan",
+			Self::ToggleLoupe => "Toggle loupe",
+			Self::ToggleMeasure => "Toggle measuring",
+			Self::ToggleMark => "Mark or unmark the image",
+			Self::ToggleFavorite => "Toggle favorite",
+			Self::Rate1 => "Rate one star",
+			Self::Rate2 => "Rate two stars",
+			Self::Rate3 => "Rate three stars",
+			Self::Rate4 => "Rate four stars",
+			Self::Rate5 => "Rate five stars",
+			Self::ClearRating => "Clear the rating",
+			Self::CopyPixels => "Copy the pixels of the selection",
+			Self::CopyDataUri => "Copy image as a data: URI",
+			Self::CopyHtml => "Copy image as an HTML <img> element",
+			Self::ClearSelection => "Clear the selection and measurement",
+			Self::Undo => "Undo",
+			Self::Redo => "Redo",
+			Self::Quit => "Quit",
+		}
+	}
+
+	const VARIANTS: &'static [Self] = &[
+		Self::Next,
+		Self::Previous,
+		Self::NextIgnoringSlideshow,
+		Self::PreviousIgnoringSlideshow,
+		Self::ZoomFit,
+		Self::ZoomFill,
+		Self::ZoomActualSize,
+		Self::ZoomContent,
+		Self::ToggleSlideshow,
+		Self::ExtendSlideshow,
+		Self::ToggleFullscreen,
+		Self::ToggleSidebar,
+		Self::ToggleSettings,
+		Self::ToggleInternal,
+		Self::ToggleLockView,
+		Self::ToggleLoupe,
+		Self::ToggleMeasure,
+		Self::ToggleMark,
+		Self::ToggleFavorite,
+		Self::Rate1,
+		Self::Rate2,
+		Self::Rate3,
+		Self::Rate4,
+		Self::Rate5,
+		Self::ClearRating,
+		Self::CopyPixels,
+		Self::CopyDataUri,
+		Self::CopyHtml,
+		Self::ClearSelection,
+		Self::Undo,
+		Self::Redo,
+		Self::Quit,
+	];
+
+	/// The number of stars that this action rates the image with, where zero clears the rating.
+	pub fn stars(self) -> Option<u8> {
+		Some(match self {
+			Self::ClearRating => 0,
+			Self::Rate1 => 1,
+			Self::Rate2 => 2,
+			Self::Rate3 => 3,
+			Self::Rate4 => 4,
+			Self::Rate5 => 5,
+			_ => return None,
+		})
+	}
+
+	fn default_shortcuts(self) -> Vec<KeyboardShortcut> {
+		const fn key(key: Key) -> KeyboardShortcut {
+			KeyboardShortcut::new(Modifiers::NONE, key)
+		}
+		const fn ctrl(key: Key) -> KeyboardShortcut {
+			KeyboardShortcut::new(Modifiers::CTRL, key)
+		}
+		const fn shift(key: Key) -> KeyboardShortcut {
+			KeyboardShortcut::new(Modifiers::SHIFT, key)
+		}
+		const fn alt(key: Key) -> KeyboardShortcut {
+			KeyboardShortcut::new(Modifiers::ALT, key)
+		}
+		const fn with(modifiers: Modifiers, key: Key) -> KeyboardShortcut {
+			KeyboardShortcut::new(modifiers, key)
+		}
+		const CTRL_SHIFT: Modifiers = Modifiers {
+			ctrl: true,
+			shift: true,
+			..Modifiers::NONE
+		};
+		const ALT_SHIFT: Modifiers = Modifiers {
+			alt: true,
+			shift: true,
+			..Modifiers::NONE
+		};
+
+		match self {
+			Self::Next => vec![key(Key::ArrowRight), key(Key::N)],
+			Self::Previous => vec![key(Key::ArrowLeft), key(Key::P), shift(Key::N)],
+			Self::NextIgnoringSlideshow => vec![alt(Key::ArrowRight), alt(Key::N)],
+			Self::PreviousIgnoringSlideshow => {
+				vec![alt(Key::ArrowLeft), alt(Key::P), with(ALT_SHIFT, Key::N)]
+			}
+			Self::ZoomFit => vec![key(Key::Num0)],
+			Self::ZoomFill => vec![key(Key::W)],
+			Self::ZoomActualSize => vec![key(Key::Num1)],
+			Self::ZoomContent => vec![key(Key::B)],
+			Self::ToggleSlideshow => vec![key(Key::S)],
+			// `+` is usually typed with Shift, so `=` on the same key works too
+			Self::ExtendSlideshow => vec![key(Key::Plus), key(Key::Equals)],
+			Self::ToggleFullscreen => vec![key(Key::F)],
+			Self::ToggleSidebar => vec![key(Key::I)],
+			Self::ToggleSettings => vec![key(Key::C)],
+			Self::ToggleInternal => vec![with(CTRL_SHIFT, Key::I)],
+			Self::ToggleLockView => vec![key(Key::L)],
+			Self::ToggleLoupe => vec![key(Key::O)],
+			Self::ToggleMeasure => vec![key(Key::R)],
+			Self::ToggleMark => vec![key(Key::M)],
+			Self::ToggleFavorite => vec![key(Key::H)],
+			Self::Rate1 => vec![ctrl(Key::Num1)],
+			Self::Rate2 => vec![ctrl(Key::Num2)],
+			Self::Rate3 => vec![ctrl(Key::Num3)],
+			Self::Rate4 => vec![ctrl(Key::Num4)],
+			Self::Rate5 => vec![ctrl(Key::Num5)],
+			Self::ClearRating => vec![ctrl(Key::Num0)],
+			Self::CopyPixels => vec![with(CTRL_SHIFT, Key::C)],
+			Self::CopyDataUri => vec![key(Key::U)],
+			Self::CopyHtml => vec![shift(Key::U)],
+			Self::ClearSelection => vec![key(Key::Escape)],
+			Self::Undo => vec![ctrl(Key::Z)],
+			Self::Redo => vec![with(CTRL_SHIFT, Key::Z)],
+			Self::Quit => vec![key(Key::Q)],
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Shortcut(KeyboardShortcut);
+
+impl Shortcut {
+	/// The shortcut for `key` pressed with `modifiers`, with the platform-independent `command` modifier resolved to the key that was actually held.
+	fn pressed(key: Key, modifiers: Modifiers) -> Self {
+		Self(KeyboardShortcut::new(
+			Modifiers {
+				command: false,
+				..modifiers
+			},
+			key,
+		))
+	}
+
+	fn num_modifiers(self) -> usize {
+		let Modifiers {
+			alt,
+			ctrl,
+			shift,
+			mac_cmd,
+			command,
+		} = self.0.modifiers;
+		[alt, ctrl || command, shift, mac_cmd]
+			.into_iter()
+			.filter(|&held| held)
+			.count()
+	}
+}
+
+impl Display for Shortcut {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		let Modifiers {
+			alt,
+			ctrl,
+			shift,
+			mac_cmd,
+			command,
+		} = self.0.modifiers;
+		for (held, name) in [
+			(ctrl || command, "Ctrl"),
+			(mac_cmd, "Cmd"),
+			(alt, "Alt"),
+			(shift, "Shift"),
+		] {
+			if held {
+				write!(f, "{name}+")?;
+			}
+		}
+		f.write_str(self.0.logical_key.name())
+	}
+}
+
+impl TryFrom<String> for Shortcut {
+	type Error = String;
+
+	fn try_from(raw: String) -> Result<Self, Self::Error> {
+		let mut parts = raw.split('+');
+		let key = parts
+			.next_back()
+			.and_then(Key::from_name)
+			.ok_or_else(|| format!("unknown key in shortcut {raw:?}"))?;
+		let mut modifiers = Modifiers::NONE;
+		for part in parts {
+			let modifier = match part {
+				"Ctrl" => &mut modifiers.ctrl,
+				"Cmd" => &mut modifiers.mac_cmd,
+				"Alt" => &mut modifiers.alt,
+				"Shift" => &mut modifiers.shift,
+				_ => return Err(format!("unknown modifier {part:?} in shortcut {raw:?}")),
+			};
+			*modifier = true;
+		}
+		Ok(Self(KeyboardShortcut::new(modifiers, key)))
+	}
+}
+
+impl From<Shortcut> for String {
+	fn from(shortcut: Shortcut) -> Self {
+		shortcut.to_string()
+	}
+}
+
+/// The shortcuts of each action. Actions that are missing from the configuration keep their default shortcuts, so that new actions are bound when upgrading.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+	from = "BTreeMap<Action, Vec<Shortcut>>",
+	into = "BTreeMap<Action, Vec<Shortcut>>"
+)]
+pub struct Bindings(BTreeMap<Action, Vec<Shortcut>>);
+
+impl Default for Bindings {
+	fn default() -> Self {
+		Self(
+			Action::VARIANTS
+				.iter()
+				.map(|&action| {
+					let shortcuts = action.default_shortcuts().into_iter().map(Shortcut);
+					(action, shortcuts.collect())
+				})
+				.collect(),
+		)
+	}
+}
+
+impl From<BTreeMap<Action, Vec<Shortcut>>> for Bindings {
+	fn from(configured: BTreeMap<Action, Vec<Shortcut>>) -> Self {
+		let mut bindings = Self::default();
+		bindings.0.extend(configured);
+		bindings
+	}
+}
+
+impl From<Bindings> for BTreeMap<Action, Vec<Shortcut>> {
+	fn from(bindings: Bindings) -> Self {
+		bindings.0
+	}
+}
+
+/// The shortcut that is being recorded in the settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Recording {
+	action: Action,
+	/// The index of the shortcut that is being changed, or `None` if one is being added.
+	idx: Option<usize>,
+	/// Whether the button that takes the key press has been focused yet.
+	focused: bool,
+}
+
+impl Bindings {
+	/// Consume the presses of bound shortcuts, returning the actions to do.
+	///
+	/// egui ignores extra Shift and Alt modifiers when matching shortcuts, so the shortcuts with the most modifiers are checked first, e.g., so that Ctrl-Shift-Z is not taken as Ctrl-Z.
+	pub fn take_pressed(&self, input: &mut egui::InputState) -> Vec<Action> {
+		let mut shortcuts: Vec<(Shortcut, Action)> = self
+			.0
+			.iter()
+			.flat_map(|(&action, shortcuts)| shortcuts.iter().map(move |&shortcut| (shortcut, action)))
+			.collect();
+		shortcuts.sort_by_key(|(shortcut, _)| std::cmp::Reverse(shortcut.num_modifiers()));
+		shortcuts
+			.into_iter()
+			.filter(|(shortcut, _)| input.consume_shortcut(&shortcut.0))
+			.map(|(_, action)| action)
+			.collect()
+	}
+
+	/// The actions other than `action` that `shortcut` is also bound to.
+	fn conflicts(&self, action: Action, shortcut: Shortcut) -> Vec<Action> {
+		self
+			.0
+			.iter()
+			.filter(|&(&other, shortcuts)| other != action && shortcuts.contains(&shortcut))
+			.map(|(&other, _)| other)
+			.collect()
+	}
+
+	pub fn ui(&mut self, ui: &mut egui::Ui) {
+		let recording_id = egui::Id::new("config-bindings-recording");
+		let mut recording: Option<Recording> = ui.data(|data| data.get_temp(recording_id)).flatten();
+
+		ui.label("Click a shortcut to change it, or right-click it to remove it.");
+		egui::ScrollArea::vertical()
+			.max_height(400.0)
+			.show(ui, |ui| {
+				widgets::KeyValue::new("config-bindings-kv").show(ui, |mut rows| {
+					for &action in Action::VARIANTS {
+						rows.row(action.repr(), |ui| {
+							ui.horizontal_wrapped(|ui| self.action_ui(ui, action, &mut recording));
+						});
+					}
+				});
+			});
+
+		if ui.button("Reset to Defaults").clicked() {
+			*self = Self::default();
+			recording = None;
+		}
+
+		ui.data_mut(|data| data.insert_temp(recording_id, recording));
+	}
+
+	fn action_ui(&mut self, ui: &mut egui::Ui, action: Action, recording: &mut Option<Recording>) {
+		let is_recording = |recording: Option<Recording>, idx| {
+			recording.is_some_and(|recording| recording.action == action && recording.idx == idx)
+		};
+
+		let shortcuts = self.0.get(&action).cloned().unwrap_or_default();
+		let mut remove = None;
+		for (idx, shortcut) in shortcuts.into_iter().enumerate() {
+			if is_recording(*recording, Some(idx)) {
+				self.record(ui, recording);
+				continue;
+			}
+
+			let conflicts = self.conflicts(action, shortcut);
+			let mut text = RichText::new(shortcut.to_string());
+			let mut hover = "Click to change, right-click to remove".to_owned();
+			if !conflicts.is_empty() {
+				text = text.color(ui.visuals().error_fg_color);
+				let names: Vec<_> = conflicts.iter().map(|other| other.repr()).collect();
+				hover = format!("Also bound to: {}\n{hover}", names.join(", "));
+			}
+			let response = ui.button(text).on_hover_text(hover);
+			if response.clicked() {
+				*recording = Some(Recording {
+					action,
+					idx: Some(idx),
+					focused: false,
+				});
+			}
+			if response.secondary_clicked() {
+				remove = Some(idx);
+			}
+		}
+
+		if is_recording(*recording, None) {
+			self.record(ui, recording);
+		} else if ui
+			.small_button("+")
+			.on_hover_text("Add a shortcut")
+			.clicked()
+		{
+			*recording = Some(Recording {
+				action,
+				idx: None,
+				focused: false,
+			});
+		}
+
+		if let Some(idx) = remove {
+			self.0.entry(action).or_default().remove(idx);
+			// the indices of the following shortcuts have changed
+			*recording = None;
+		}
+	}
+
+	/// The button of the shortcut that is being recorded, which takes the next key that is pressed while it is focused.
+	/// Focusing it keeps the key presses from the rest of the app, which only handles them while nothing is focused.
+	fn record(&mut self, ui: &mut egui::Ui, recording: &mut Option<Recording>) {
+		let Some(current) = recording else {
+			return;
+		};
+		let response = ui
+			.add(egui::Button::new("Press a key…").selected(true))
+			.on_hover_text("Press the new shortcut, or Escape to cancel");
+
+		if !current.focused {
+			response.request_focus();
+			current.focused = true;
+			return;
+		}
+		// cancelled with Escape or by clicking elsewhere
+		if !response.has_focus() {
+			*recording = None;
+			return;
+		}
+
+		ui.memory_mut(|memory| {
+			memory.set_focus_lock_filter(
+				response.id,
+				EventFilter {
+					tab: true,
+					horizontal_arrows: true,
+					vertical_arrows: true,
+					escape: false,
+				},
+			);
+		});
+
+		let pressed = ui.input_mut(|input| {
+			let idx = input.events.iter().position(|event| {
+				matches!(
+					event,
+					Event::Key {
+						pressed: true,
+						repeat: false,
+						..
+					}
+				)
+			})?;
+			match input.events.remove(idx) {
+				Event::Key { key, modifiers, .. } => Some(Shortcut::pressed(key, modifiers)),
+				_ => None,
+			}
+		});
+		if let Some(shortcut) = pressed {
+			let shortcuts = self.0.entry(current.action).or_default();
+			match current.idx {
+				Some(idx) => shortcuts[idx] = shortcut,
+				None if !shortcuts.contains(&shortcut) => shortcuts.push(shortcut),
+				None => {}
+			}
+			*recording = None;
+			response.surrender_focus();
+		}
+	}
+}
+
+#[test]
+fn test_shortcut_round_trip() {
+	for raw in ["Right", "Ctrl+Shift+Z", "Alt+Shift+N", "Plus", "Ctrl+0"] {
+		let shortcut = Shortcut::try_from(raw.to_owned()).unwrap();
+		assert_eq!(shortcut.to_string(), raw);
+	}
+	assert!(Shortcut::try_from("Hyper+A".to_owned()).is_err());
+	assert!(Shortcut::try_from("Ctrl+".to_owned()).is_err());
+}
+
+#[test]
+fn test_extra_modifiers_checked_first() {
+	let press = |key, modifiers| {
+		let input = egui::RawInput {
+			events: vec![Event::Key {
+				key,
+				physical_key: None,
+				pressed: true,
+				repeat: false,
+				modifiers,
+			}],
+			..Default::default()
+		};
+		egui::InputState::default().begin_frame(input, false, 1.0)
+	};
+	let bindings = Bindings::default();
+	assert_eq!(
+		bindings.take_pressed(&mut press(Key::U, Modifiers::SHIFT)),
+		[Action::CopyHtml]
+	);
+	assert_eq!(
+		bindings.take_pressed(&mut press(Key::U, Modifiers::NONE)),
+		[Action::CopyDataUri]
+	);
+}
@@ -1,10 +1,11 @@
-use std::num::NonZeroUsize;
+use std::num::{NonZeroU32, NonZeroUsize};
 use std::path::PathBuf;
 
 use eframe::Theme;
-use egui::ComboBox;
+use egui::{Color32, ComboBox, Stroke, Visuals};
 use serde::{Deserialize, Serialize};
 
+use crate::bindings::Bindings;
 use crate::duration::Duration;
 use crate::widgets;
 
@@ -12,27 +13,194 @@ use crate::widgets;
 pub struct Config {
 	pub theme: Option<Theme>,
 	#[serde(default)]
+	pub auto_theme: AutoTheme,
+	#[serde(default)]
+	pub style: Style,
+	/// The scale of the interface (not the image), on top of the scale of the display.
+	#[serde(default = "default_ui_scale")]
+	pub ui_scale: f32,
+	#[serde(default)]
 	pub show_sidebar: bool,
 	#[serde(default)]
 	pub show_frames: bool,
+	#[serde(default)]
+	pub status_bar: StatusBar,
+	#[serde(default)]
+	pub window: Window,
+	/// The memory for the decoded pixels of cached images.
 	#[serde(default = "default_cache_size")]
 	pub cache_size: NonZeroUsize,
+	/// The GPU memory for the textures of cached images, which is used separately from `cache_size`.
+	#[serde(default = "default_gpu_budget")]
+	pub gpu_budget: NonZeroUsize,
+	/// Keep the pixels of cached images so that their textures can be re-uploaded without decoding them again.
+	/// Otherwise, images are dropped from the cache as soon as their textures are evicted.
+	#[serde(default = "default_keep_evicted_pixels")]
+	pub keep_evicted_pixels: bool,
+	#[serde(default = "default_data_uri_max_size")]
+	pub data_uri_max_size: NonZeroUsize,
 	#[serde(default)]
 	pub background: Background,
 	#[serde(default)]
+	pub sort: Sort,
+	#[serde(default)]
+	pub name_order: NameOrder,
+	/// Comma-separated globs or extensions of the images to move between in a directory, e.g., `*.png,*.webp`. Empty for all images.
+	#[serde(default)]
+	pub filter: String,
+	#[serde(default)]
+	pub rating_filter: RatingFilter,
+	#[serde(default)]
+	pub hidden_files: HiddenFiles,
+	#[serde(default)]
 	pub slideshow: Slideshow,
+	#[cfg(feature = "audio")]
+	#[serde(default)]
+	pub slideshow_audio: SlideshowAudio,
+	#[serde(default)]
+	pub sprite_sheet: SpriteSheet,
+	#[serde(default)]
+	pub video_export: VideoExport,
+	#[serde(default)]
+	pub animation_export: AnimationExport,
+	#[serde(default)]
+	pub pixel_grid: PixelGrid,
+	#[serde(default)]
+	pub loupe: Loupe,
+	#[serde(default)]
+	pub scroll: Scroll,
+	#[serde(default)]
+	pub auto_hide: AutoHide,
+	#[serde(default)]
+	pub accessibility: Accessibility,
+	#[serde(default)]
+	pub power_saving: PowerSaving,
+	#[serde(default)]
+	pub bindings: Bindings,
+	#[serde(skip)]
+	source: Source,
 }
 
+fn default_ui_scale() -> f32 {
+	1.0
+}
+
+/// The range of `Config::ui_scale`.
+pub const UI_SCALE_RANGE: std::ops::RangeInclusive<f32> = 0.5..=3.0;
+
 fn default_cache_size() -> NonZeroUsize {
 	NonZeroUsize::new(1024 * 1024 * 1024).unwrap()
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default)]
+fn default_gpu_budget() -> NonZeroUsize {
+	NonZeroUsize::new(512 * 1024 * 1024).unwrap()
+}
+
+fn default_keep_evicted_pixels() -> bool {
+	true
+}
+
+fn default_data_uri_max_size() -> NonZeroUsize {
+	NonZeroUsize::new(1024 * 1024).unwrap()
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
 pub struct Background {
 	#[serde(default)]
 	pub checkered: bool,
 	#[serde(default)]
 	pub color: BackgroundColor,
+	/// The color of the background if `color` is `Custom`.
+	#[serde(default = "default_custom_color")]
+	pub custom_color: Color32,
+	#[serde(default)]
+	pub checker: Checker,
+	/// Values below 1 make the window transparent so the desktop shows through.
+	#[serde(default = "default_opacity")]
+	pub opacity: f32,
+}
+
+impl Default for Background {
+	fn default() -> Self {
+		Self {
+			checkered: false,
+			color: BackgroundColor::default(),
+			custom_color: default_custom_color(),
+			checker: Checker::default(),
+			opacity: default_opacity(),
+		}
+	}
+}
+
+fn default_custom_color() -> Color32 {
+	Color32::from_gray(128)
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+pub struct Checker {
+	/// The side of each square, in points.
+	#[serde(default = "default_checker_size")]
+	pub size: f32,
+	/// The colors of the squares, or `None` for shades of the background color.
+	#[serde(default)]
+	pub colors: Option<[Color32; 2]>,
+}
+
+impl Default for Checker {
+	fn default() -> Self {
+		Self {
+			size: default_checker_size(),
+			colors: None,
+		}
+	}
+}
+
+fn default_checker_size() -> f32 {
+	20.0
+}
+
+impl Checker {
+	/// Smaller squares would take too many shapes to draw.
+	pub const MIN_SIZE: f32 = 4.0;
+}
+
+fn default_opacity() -> f32 {
+	1.0
+}
+
+impl Background {
+	/// Whether the window needs to be created with transparency support.
+	pub fn needs_transparency(self) -> bool {
+		self.opacity < 1.0
+	}
+}
+
+/// Changes to the look of the interface on top of the light or dark theme. Whatever is not set is left as the theme has it.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq)]
+pub struct Style {
+	/// The color of selections and links.
+	#[serde(default)]
+	pub accent: Option<Color32>,
+	#[serde(default)]
+	pub window_fill: Option<Color32>,
+	/// The lines around and between panels.
+	#[serde(default)]
+	pub panel_stroke: Option<Stroke>,
+}
+
+impl Style {
+	pub fn apply(self, visuals: &mut Visuals) {
+		if let Some(accent) = self.accent {
+			visuals.selection.bg_fill = accent;
+			visuals.hyperlink_color = accent;
+		}
+		if let Some(window_fill) = self.window_fill {
+			visuals.window_fill = window_fill;
+		}
+		if let Some(panel_stroke) = self.panel_stroke {
+			visuals.widgets.noninteractive.bg_stroke = panel_stroke;
+		}
+	}
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
@@ -42,6 +210,8 @@ pub enum BackgroundColor {
 	Default,
 	Dark,
 	Light,
+	/// The `custom_color` of the background.
+	Custom,
 }
 
 impl BackgroundColor {
@@ -50,10 +220,11 @@ impl BackgroundColor {
 			Self::Default => "Default",
 			Self::Dark => "Dark",
 			Self::Light => "Light",
+			Self::Custom => "Custom",
 		}
 	}
 
-	const VARIANTS: &'static [Self] = &[Self::Default, Self::Dark, Self::Light];
+	const VARIANTS: &'static [Self] = &[Self::Default, Self::Dark, Self::Light, Self::Custom];
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Copy)]
@@ -62,6 +233,18 @@ pub struct Slideshow {
 	pub interval: Duration,
 	#[serde(default = "default_shuffle")]
 	pub shuffle: bool,
+	#[serde(default)]
+	pub end: SlideshowEnd,
+	/// The time added to the current image by pressing `+`.
+	#[serde(default = "default_extra_time")]
+	pub extra_time: Duration,
+	#[serde(default = "default_progress_bar")]
+	pub progress_bar: bool,
+	/// Keep showing animated images until they have played through once, even if that is longer than the interval.
+	#[serde(default = "default_wait_for_animations")]
+	pub wait_for_animations: bool,
+	#[serde(default)]
+	pub ken_burns: KenBurns,
 }
 
 impl Default for Slideshow {
@@ -69,6 +252,11 @@ impl Default for Slideshow {
 		Self {
 			interval: default_interval(),
 			shuffle: default_shuffle(),
+			end: SlideshowEnd::default(),
+			extra_time: default_extra_time(),
+			progress_bar: default_progress_bar(),
+			wait_for_animations: default_wait_for_animations(),
+			ken_burns: KenBurns::default(),
 		}
 	}
 }
@@ -81,6 +269,18 @@ fn default_shuffle() -> bool {
 	false
 }
 
+fn default_extra_time() -> Duration {
+	Duration::new_secs(5).unwrap()
+}
+
+fn default_progress_bar() -> bool {
+	true
+}
+
+fn default_wait_for_animations() -> bool {
+	true
+}
+
 impl Slideshow {
 	fn ui(&mut self, ui: &mut egui::Ui) {
 		widgets::KeyValue::new("config-slideshow-kv").show(ui, |mut rows| {
@@ -95,80 +295,1420 @@ impl Slideshow {
 				ui.add(widgets::UnitInput::duration(&mut self.interval));
 			});
 			rows.row("Shuffle", |ui| ui.checkbox(&mut self.shuffle, ""));
+			rows.row("At the End", |ui| {
+				ComboBox::from_id_source("config-slideshow-end-combo")
+					.selected_text(self.end.repr())
+					.show_ui(ui, |ui| {
+						for &variant in SlideshowEnd::VARIANTS {
+							ui.selectable_value(&mut self.end, variant, variant.repr());
+						}
+					})
+					.response
+					.on_hover_text(
+						"What to do after the last image, or once every image was shown when shuffling",
+					)
+			});
+			rows.row("Extra Time", |ui| {
+				ui.add(widgets::UnitInput::duration(&mut self.extra_time))
+					.on_hover_text("How much time pressing + gives the current image");
+			});
+			rows.row("Progress Bar", |ui| ui.checkbox(&mut self.progress_bar, ""));
+			rows.row("Wait for Animations", |ui| {
+				ui.checkbox(&mut self.wait_for_animations, "")
+					.on_hover_text("Play animated images through at least once before moving on")
+			});
 		});
+		ui.collapsing("Ken Burns Effect", |ui| self.ken_burns.ui(ui));
 	}
 }
 
-fn config_path() -> PathBuf {
-	directories_next::ProjectDirs::from("nz", "felle", "eo2")
-		.expect("getting configuration path")
-		.config_dir()
-		.to_owned()
-		.join("config.toml")
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SlideshowEnd {
+	/// Start again from the first image.
+	#[default]
+	Wrap,
+	/// Stop on the last image and say that the slideshow finished.
+	Stop,
+	/// Stop on the last image and leave fullscreen.
+	ExitFullscreen,
 }
 
-impl Background {
+impl SlideshowEnd {
+	fn repr(self) -> &'static str {
+		match self {
+			Self::Wrap => "Start Over",
+			Self::Stop => "Stop",
+			Self::ExitFullscreen => "Exit Fullscreen",
+		}
+	}
+
+	const VARIANTS: &'static [Self] = &[Self::Wrap, Self::Stop, Self::ExitFullscreen];
+}
+
+/// The order of the images when moving between them.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Sort {
+	/// Natural ordering of the file names.
+	#[default]
+	Name,
+	/// Oldest first.
+	Modified,
+	/// Smallest first.
+	Size,
+}
+
+impl Sort {
+	fn repr(self) -> &'static str {
+		match self {
+			Self::Name => "Name",
+			Self::Modified => "Date Modified",
+			Self::Size => "Size",
+		}
+	}
+
+	/// The name used in the configuration file and on the command line.
+	pub fn name(self) -> &'static str {
+		match self {
+			Self::Name => "name",
+			Self::Modified => "modified",
+			Self::Size => "size",
+		}
+	}
+
+	pub const VARIANTS: &'static [Self] = &[Self::Name, Self::Modified, Self::Size];
+}
+
+/// How file names are compared when sorting by name, or between files that are otherwise equal.
+/// Numbers in names are always compared by their value.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NameOrder {
+	/// Uppercase letters come before all lowercase letters.
+	#[default]
+	CaseSensitive,
+	IgnoreCase,
+	/// Also ignores accents in any script, e.g., `é` is sorted with `e`, as file managers do in most languages.
+	IgnoreCaseAndAccents,
+}
+
+impl NameOrder {
+	fn repr(self) -> &'static str {
+		match self {
+			Self::CaseSensitive => "Case-Sensitive",
+			Self::IgnoreCase => "Ignoring Case",
+			Self::IgnoreCaseAndAccents => "Ignoring Case and Accents",
+		}
+	}
+
+	const VARIANTS: &'static [Self] = &[
+		Self::CaseSensitive,
+		Self::IgnoreCase,
+		Self::IgnoreCaseAndAccents,
+	];
+
 	fn ui(&mut self, ui: &mut egui::Ui) {
-		widgets::KeyValue::new("config-background-kv").show(ui, |mut rows| {
-			rows.row("Color", |ui| {
-				ComboBox::from_id_source("config-background-color-combo")
-					.selected_text(self.color.repr())
-					.show_ui(ui, |ui| {
-						for &variant in BackgroundColor::VARIANTS {
-							ui.selectable_value(&mut self.color, variant, variant.repr());
-						}
-					})
+		ComboBox::from_id_source("config-name-order-combo")
+			.selected_text(self.repr())
+			.show_ui(ui, |ui| {
+				for &variant in Self::VARIANTS {
+					ui.selectable_value(self, variant, variant.repr());
+				}
+			})
+			.response
+			.on_hover_text("How names are compared");
+	}
+}
+
+/// Whether hidden files (whose names start with a dot, e.g., `.thumbnail.png`) are included when moving between the images in a directory.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HiddenFiles {
+	#[default]
+	Skip,
+	Include,
+}
+
+impl HiddenFiles {
+	fn repr(self) -> &'static str {
+		match self {
+			Self::Skip => "Skip",
+			Self::Include => "Include",
+		}
+	}
+
+	const VARIANTS: &'static [Self] = &[Self::Skip, Self::Include];
+
+	fn ui(&mut self, ui: &mut egui::Ui) {
+		ComboBox::from_id_source("config-hidden-files-combo")
+			.selected_text(self.repr())
+			.show_ui(ui, |ui| {
+				for &variant in Self::VARIANTS {
+					ui.selectable_value(self, variant, variant.repr());
+				}
+			})
+			.response
+			.on_hover_text("Whether to move to files whose names start with a dot");
+	}
+}
+
+/// When to save power by repainting animations less often and making fewer thumbnails.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerSaving {
+	/// While running on battery or in a power saver mode.
+	#[default]
+	Auto,
+	Always,
+	Never,
+}
+
+impl PowerSaving {
+	fn repr(self) -> &'static str {
+		match self {
+			Self::Auto => "On Battery",
+			Self::Always => "Always",
+			Self::Never => "Never",
+		}
+	}
+
+	const VARIANTS: &'static [Self] = &[Self::Auto, Self::Always, Self::Never];
+}
+
+/// Background audio that plays while the slideshow is running.
+#[cfg(feature = "audio")]
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct SlideshowAudio {
+	#[serde(default)]
+	pub enabled: bool,
+	/// A folder of audio files, an M3U playlist, or a single audio file.
+	#[serde(default)]
+	pub source: Option<PathBuf>,
+	#[serde(default = "default_volume")]
+	pub volume: f32,
+}
+
+#[cfg(feature = "audio")]
+impl Default for SlideshowAudio {
+	fn default() -> Self {
+		Self {
+			enabled: false,
+			source: None,
+			volume: default_volume(),
+		}
+	}
+}
+
+#[cfg(feature = "audio")]
+fn default_volume() -> f32 {
+	1.0
+}
+
+#[cfg(feature = "audio")]
+impl SlideshowAudio {
+	/// The source to play, if audio is enabled.
+	pub fn active_source(&self) -> Option<&std::path::Path> {
+		self.source.as_deref().filter(|_| self.enabled)
+	}
+
+	fn ui(&mut self, ui: &mut egui::Ui) {
+		widgets::KeyValue::new("config-slideshow-audio-kv").show(ui, |mut rows| {
+			rows.row("Enabled", |ui| ui.checkbox(&mut self.enabled, ""));
+			rows.row("Source", |ui| {
+				let mut source = self
+					.source
+					.as_ref()
+					.map(|source| source.display().to_string())
+					.unwrap_or_default();
+				let response = ui
+					.text_edit_singleline(&mut source)
+					.on_hover_text("A folder of audio files, an M3U playlist, or a single audio file");
+				if response.changed() {
+					self.source = (!source.is_empty()).then(|| source.into());
+				}
+			});
+			rows.row("Volume", |ui| {
+				ui.add(egui::Slider::new(&mut self.volume, 0.0..=1.0))
 			});
-			rows.row("Checkered", |ui| ui.checkbox(&mut self.checkered, ""));
 		});
 	}
 }
 
-impl Config {
-	pub fn load() -> Result<Self, crate::error::Stringed> {
-		let raw = std::fs::read_to_string(config_path())?;
-		Ok(toml::from_str(&raw)?)
-	}
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemeFollow {
+	/// Use `Config::theme`, or the system theme if it is not set.
+	#[default]
+	Off,
+	/// Follow the system theme as it changes.
+	System,
+	/// Light while the sun is up at the configured location, and dark otherwise.
+	Sun,
+}
 
-	pub fn save(&self) -> std::io::Result<()> {
-		let raw = toml::to_string(self).expect("serializing configuration");
-		std::fs::write(config_path(), raw)
+impl ThemeFollow {
+	fn repr(self) -> &'static str {
+		match self {
+			Self::Off => "Off",
+			Self::System => "Follow System",
+			Self::Sun => "Sunrise and Sunset",
+		}
 	}
 
-	pub fn ui(&mut self, ui: &mut egui::Ui) {
-		widgets::KeyValue::new("config-kv").show(ui, |mut rows| {
-			rows.row("Background", |ui| {
-				self.background.ui(ui);
+	const VARIANTS: &'static [Self] = &[Self::Off, Self::System, Self::Sun];
+}
+
+/// Switching between the light and dark themes while running.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default)]
+pub struct AutoTheme {
+	#[serde(default)]
+	pub follow: ThemeFollow,
+	/// The location whose sunrise and sunset are followed, in degrees.
+	#[serde(default)]
+	pub latitude: f64,
+	#[serde(default)]
+	pub longitude: f64,
+}
+
+impl AutoTheme {
+	fn ui(&mut self, ui: &mut egui::Ui) {
+		ComboBox::from_id_source("config-auto-theme-combo")
+			.selected_text(self.follow.repr())
+			.show_ui(ui, |ui| {
+				for &variant in ThemeFollow::VARIANTS {
+					ui.selectable_value(&mut self.follow, variant, variant.repr());
+				}
 			});
-			rows.row("Color Scheme", |ui| {
-				self.light_dark_toggle_button(ui);
+		if self.follow == ThemeFollow::Sun {
+			widgets::KeyValue::new("config-auto-theme-kv").show(ui, |mut rows| {
+				rows.row("Latitude", |ui| {
+					ui.add(
+						egui::DragValue::new(&mut self.latitude)
+							.clamp_range(-90.0..=90.0)
+							.speed(0.1)
+							.suffix("°"),
+					)
+					.on_hover_text("Positive is north")
+				});
+				rows.row("Longitude", |ui| {
+					ui.add(
+						egui::DragValue::new(&mut self.longitude)
+							.clamp_range(-180.0..=180.0)
+							.speed(0.1)
+							.suffix("°"),
+					)
+					.on_hover_text("Positive is east")
+				});
 			});
-			rows.row("Slideshow", |ui| {
-				self.slideshow.ui(ui);
+		}
+	}
+}
+
+/// A slow pan and zoom over static images during slideshows.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+pub struct KenBurns {
+	#[serde(default)]
+	pub enabled: bool,
+	/// How much the image is zoomed over the course of the interval, e.g., 0.2 zooms from 100% to 120% of the fitted size.
+	#[serde(default = "default_ken_burns_intensity")]
+	pub intensity: f32,
+	/// The fraction of the image's width and height that always stays in view.
+	#[serde(default = "default_ken_burns_safe_area")]
+	pub safe_area: f32,
+}
+
+impl Default for KenBurns {
+	fn default() -> Self {
+		Self {
+			enabled: false,
+			intensity: default_ken_burns_intensity(),
+			safe_area: default_ken_burns_safe_area(),
+		}
+	}
+}
+
+fn default_ken_burns_intensity() -> f32 {
+	0.15
+}
+
+fn default_ken_burns_safe_area() -> f32 {
+	0.8
+}
+
+impl KenBurns {
+	const INTENSITY_RANGE: std::ops::RangeInclusive<f32> = 0.0..=1.0;
+	const SAFE_AREA_RANGE: std::ops::RangeInclusive<f32> = 0.5..=1.0;
+
+	/// The zoom factor at the most zoomed-in point, relative to the fitted size.
+	/// The settings are clamped to the ranges of their sliders, since the file and the environment can set any value.
+	pub fn max_zoom(self) -> f32 {
+		// unlike `clamp`, this turns NaN into the lower bound
+		let clamp = |value: f32, range: std::ops::RangeInclusive<f32>| {
+			value.max(*range.start()).min(*range.end())
+		};
+		let intensity = clamp(self.intensity, Self::INTENSITY_RANGE);
+		let safe_area = clamp(self.safe_area, Self::SAFE_AREA_RANGE);
+		(1.0 + intensity).min(1.0 / safe_area)
+	}
+
+	fn ui(&mut self, ui: &mut egui::Ui) {
+		widgets::KeyValue::new("config-ken-burns-kv").show(ui, |mut rows| {
+			rows.row("Enabled", |ui| ui.checkbox(&mut self.enabled, ""));
+			rows.row("Intensity", |ui| {
+				ui.add(egui::Slider::new(
+					&mut self.intensity,
+					Self::INTENSITY_RANGE,
+				))
 			});
-			rows.row("Cache Size", |ui| {
-				let mut size = self.cache_size.get();
-				if ui.add(widgets::UnitInput::size(&mut size)).changed() {
-					if let Some(nz) = NonZeroUsize::new(size) {
-						self.cache_size = nz;
-					}
-				}
+			rows.row("Safe Area", |ui| {
+				ui.add(egui::Slider::new(
+					&mut self.safe_area,
+					Self::SAFE_AREA_RANGE,
+				))
+				.on_hover_text("The fraction of the image that always stays in view")
 			});
 		});
 	}
+}
 
-	pub fn light_dark_toggle_button(&mut self, ui: &mut egui::Ui) {
-		if let Some(new_visuals) = ui.ctx().style().visuals.light_dark_small_toggle_button(ui) {
-			self.theme = Some(if new_visuals.dark_mode {
-				Theme::Dark
-			} else {
-				Theme::Light
-			});
-			ui.ctx().set_visuals(new_visuals);
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+pub struct SpriteSheet {
+	#[serde(default = "default_sprite_sheet_columns")]
+	pub columns: NonZeroU32,
+	/// Space around each frame, in pixels.
+	#[serde(default)]
+	pub padding: u32,
+}
+
+impl Default for SpriteSheet {
+	fn default() -> Self {
+		Self {
+			columns: default_sprite_sheet_columns(),
+			padding: 0,
 		}
 	}
 }
 
-pub fn load() -> Result<Config, crate::error::Stringed> {
-	Config::load()
+fn default_sprite_sheet_columns() -> NonZeroU32 {
+	NonZeroU32::new(8).unwrap()
+}
+
+/// Rendering the slideshow to a video.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+pub struct VideoExport {
+	/// The size of the video in pixels, which the images are scaled to fit.
+	#[serde(default = "default_video_width")]
+	pub width: u32,
+	#[serde(default = "default_video_height")]
+	pub height: u32,
+	#[serde(default = "default_frame_rate")]
+	pub frame_rate: u32,
+}
+
+impl Default for VideoExport {
+	fn default() -> Self {
+		Self {
+			width: default_video_width(),
+			height: default_video_height(),
+			frame_rate: default_frame_rate(),
+		}
+	}
+}
+
+fn default_video_width() -> u32 {
+	1920
+}
+
+fn default_video_height() -> u32 {
+	1080
+}
+
+fn default_frame_rate() -> u32 {
+	30
+}
+
+impl VideoExport {
+	pub fn ui(&mut self, ui: &mut egui::Ui) {
+		widgets::KeyValue::new("config-video-export-kv").show(ui, |mut rows| {
+			rows.row("Width", |ui| {
+				ui.add(
+					egui::DragValue::new(&mut self.width)
+						.clamp_range(2..=8192)
+						.speed(2)
+						.suffix(" px"),
+				)
+			});
+			rows.row("Height", |ui| {
+				ui.add(
+					egui::DragValue::new(&mut self.height)
+						.clamp_range(2..=8192)
+						.speed(2)
+						.suffix(" px"),
+				)
+			});
+			rows.row("Frame Rate", |ui| {
+				ui.add(
+					egui::DragValue::new(&mut self.frame_rate)
+						.clamp_range(1..=120)
+						.suffix(" fps"),
+				)
+			});
+		});
+	}
+}
+
+/// Exporting animations to GIF or animated WebP.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+pub struct AnimationExport {
+	#[serde(default)]
+	pub format: AnimationFormat,
+	/// For GIF, from 1, which is the slowest but has the most accurate colors, to 30.
+	#[serde(default = "default_gif_speed")]
+	pub speed: u8,
+	/// How many times the animation plays, where 0 is forever. If not set, the loop count of the animation is kept.
+	#[serde(default)]
+	pub loops: Option<u32>,
+}
+
+impl Default for AnimationExport {
+	fn default() -> Self {
+		Self {
+			format: AnimationFormat::default(),
+			speed: default_gif_speed(),
+			loops: None,
+		}
+	}
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AnimationFormat {
+	#[default]
+	Gif,
+	/// Every frame is lossless, so the colors are exact but photos make large files.
+	#[serde(rename = "webp")]
+	WebP,
+}
+
+impl AnimationFormat {
+	pub fn repr(self) -> &'static str {
+		match self {
+			Self::Gif => "GIF",
+			Self::WebP => "Animated WebP",
+		}
+	}
+
+	pub fn extension(self) -> &'static str {
+		match self {
+			Self::Gif => "gif",
+			Self::WebP => "webp",
+		}
+	}
+
+	const VARIANTS: &'static [Self] = &[Self::Gif, Self::WebP];
+}
+
+fn default_gif_speed() -> u8 {
+	10
+}
+
+impl AnimationExport {
+	pub fn ui(&mut self, ui: &mut egui::Ui) {
+		let loops_repr = |loops: Option<u32>| match loops {
+			None => "Same as the Animation",
+			Some(0) => "Forever",
+			Some(_) => "Times",
+		};
+		widgets::KeyValue::new("config-animation-export-kv").show(ui, |mut rows| {
+			rows.row("Format", |ui| {
+				ComboBox::from_id_source("config-animation-export-format-combo")
+					.selected_text(self.format.repr())
+					.show_ui(ui, |ui| {
+						for &variant in AnimationFormat::VARIANTS {
+							ui.selectable_value(&mut self.format, variant, variant.repr());
+						}
+					})
+					.response
+					.on_hover_text("Animated WebP keeps the exact colors, but makes larger files of photos");
+			});
+			if self.format == AnimationFormat::Gif {
+				rows.row("Encoding Speed", |ui| {
+					ui.add(egui::Slider::new(&mut self.speed, 1..=30))
+						.on_hover_text("Slower encoding finds more accurate colors");
+				});
+			}
+			rows.row("Loops", |ui| {
+				ui.horizontal(|ui| {
+					ComboBox::from_id_source("config-animation-export-loops-combo")
+						.selected_text(loops_repr(self.loops))
+						.show_ui(ui, |ui| {
+							for loops in [None, Some(0), Some(1)] {
+								let selected = loops_repr(self.loops) == loops_repr(loops);
+								if ui.selectable_label(selected, loops_repr(loops)).clicked() && !selected {
+									self.loops = loops;
+								}
+							}
+						});
+					if let Some(times) = &mut self.loops {
+						if *times > 0 {
+							ui.add(egui::DragValue::new(times).clamp_range(1..=u16::MAX));
+						}
+					}
+				});
+			});
+		});
+	}
+}
+
+impl SpriteSheet {
+	pub fn ui(&mut self, ui: &mut egui::Ui) {
+		widgets::KeyValue::new("config-sprite-sheet-kv").show(ui, |mut rows| {
+			rows.row("Columns", |ui| {
+				let mut columns = self.columns.get();
+				if ui
+					.add(egui::DragValue::new(&mut columns).clamp_range(1..=u32::MAX))
+					.changed()
+				{
+					if let Some(nz) = NonZeroU32::new(columns) {
+						self.columns = nz;
+					}
+				}
+			});
+			rows.row("Padding", |ui| {
+				ui.add(egui::DragValue::new(&mut self.padding).suffix(" px"))
+			});
+		});
+	}
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+pub struct PixelGrid {
+	#[serde(default = "default_pixel_grid_enabled")]
+	pub enabled: bool,
+	/// The zoom level, as a percentage of the image's actual size, above which the grid is shown.
+	#[serde(default = "default_pixel_grid_threshold")]
+	pub threshold: f32,
+	#[serde(default)]
+	pub show_coordinates: bool,
+}
+
+impl Default for PixelGrid {
+	fn default() -> Self {
+		Self {
+			enabled: default_pixel_grid_enabled(),
+			threshold: default_pixel_grid_threshold(),
+			show_coordinates: false,
+		}
+	}
+}
+
+fn default_pixel_grid_enabled() -> bool {
+	true
+}
+
+fn default_pixel_grid_threshold() -> f32 {
+	800.0
+}
+
+impl PixelGrid {
+	/// Whether the grid should be shown when each image pixel covers `pixel_size` physical pixels.
+	pub fn visible_at(self, pixel_size: f32) -> bool {
+		self.enabled && pixel_size * 100.0 >= self.threshold
+	}
+
+	fn ui(&mut self, ui: &mut egui::Ui) {
+		widgets::KeyValue::new("config-pixel-grid-kv").show(ui, |mut rows| {
+			rows.row("Enabled", |ui| ui.checkbox(&mut self.enabled, ""));
+			rows.row("Minimum Zoom", |ui| {
+				ui.add(
+					egui::DragValue::new(&mut self.threshold)
+						.clamp_range(100.0..=10000.0)
+						.speed(10.0)
+						.suffix("%"),
+				)
+			});
+			rows.row("Coordinates", |ui| {
+				ui.checkbox(&mut self.show_coordinates, "")
+					.on_hover_text("Label each pixel with its coordinates when there is room")
+			});
+		});
+	}
+}
+
+/// A magnified inset that follows the cursor over the image.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+pub struct Loupe {
+	#[serde(default)]
+	pub enabled: bool,
+	#[serde(default)]
+	pub shape: LoupeShape,
+	/// The width and height of the loupe, in points.
+	#[serde(default = "default_loupe_size")]
+	pub size: f32,
+	/// How large the pixels are in the loupe, as a multiple of the image's actual size, regardless of the zoom.
+	#[serde(default = "default_loupe_magnification")]
+	pub magnification: f32,
+}
+
+impl Default for Loupe {
+	fn default() -> Self {
+		Self {
+			enabled: false,
+			shape: LoupeShape::default(),
+			size: default_loupe_size(),
+			magnification: default_loupe_magnification(),
+		}
+	}
+}
+
+fn default_loupe_size() -> f32 {
+	200.0
+}
+
+fn default_loupe_magnification() -> f32 {
+	4.0
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LoupeShape {
+	#[default]
+	Circle,
+	Square,
+}
+
+impl LoupeShape {
+	fn repr(self) -> &'static str {
+		match self {
+			Self::Circle => "Circle",
+			Self::Square => "Square",
+		}
+	}
+
+	const VARIANTS: &'static [Self] = &[Self::Circle, Self::Square];
+}
+
+impl Loupe {
+	fn ui(&mut self, ui: &mut egui::Ui) {
+		widgets::KeyValue::new("config-loupe-kv").show(ui, |mut rows| {
+			rows.row("Enabled", |ui| ui.checkbox(&mut self.enabled, ""));
+			rows.row("Shape", |ui| {
+				ComboBox::from_id_source("config-loupe-shape-combo")
+					.selected_text(self.shape.repr())
+					.show_ui(ui, |ui| {
+						for &shape in LoupeShape::VARIANTS {
+							ui.selectable_value(&mut self.shape, shape, shape.repr());
+						}
+					});
+			});
+			rows.row("Size", |ui| {
+				ui.add(
+					egui::DragValue::new(&mut self.size)
+						.clamp_range(50.0..=1000.0)
+						.suffix(" pt"),
+				)
+			});
+			rows.row("Magnification", |ui| {
+				ui.add(
+					egui::DragValue::new(&mut self.magnification)
+						.clamp_range(1.0..=64.0)
+						.speed(0.1)
+						.suffix("×"),
+				)
+				.on_hover_text(
+					"The size of the pixels in the loupe, as a multiple of the actual size of the image",
+				)
+			});
+		});
+	}
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WheelAction {
+	#[default]
+	Zoom,
+	/// Move between images, zooming with Ctrl held.
+	Navigate,
+}
+
+impl WheelAction {
+	fn repr(self) -> &'static str {
+		match self {
+			Self::Zoom => "Zoom",
+			Self::Navigate => "Navigate",
+		}
+	}
+
+	const VARIANTS: &'static [Self] = &[Self::Zoom, Self::Navigate];
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+pub struct Scroll {
+	#[serde(default)]
+	pub wheel: WheelAction,
+	/// How much the zoom level (a power of 2) changes per point scrolled.
+	#[serde(default = "default_zoom_sensitivity")]
+	pub zoom_sensitivity: f32,
+}
+
+impl Default for Scroll {
+	fn default() -> Self {
+		Self {
+			wheel: WheelAction::default(),
+			zoom_sensitivity: default_zoom_sensitivity(),
+		}
+	}
+}
+
+fn default_zoom_sensitivity() -> f32 {
+	0.01
+}
+
+impl Scroll {
+	pub fn zoom(self, reduce_motion: bool) -> widgets::image::ScrollZoom {
+		widgets::image::ScrollZoom {
+			require_ctrl: self.wheel == WheelAction::Navigate,
+			sensitivity: self.zoom_sensitivity,
+			smooth: !reduce_motion,
+		}
+	}
+
+	fn ui(&mut self, ui: &mut egui::Ui) {
+		widgets::KeyValue::new("config-scroll-kv").show(ui, |mut rows| {
+			rows.row("Wheel", |ui| {
+				ComboBox::from_id_source("config-scroll-wheel-combo")
+					.selected_text(self.wheel.repr())
+					.show_ui(ui, |ui| {
+						for &variant in WheelAction::VARIANTS {
+							ui.selectable_value(&mut self.wheel, variant, variant.repr());
+						}
+					})
+					.response
+					.on_hover_text("When navigating, hold Ctrl to zoom")
+			});
+			rows.row("Zoom Sensitivity", |ui| {
+				ui.add(
+					egui::DragValue::new(&mut self.zoom_sensitivity)
+						.clamp_range(0.001..=0.1)
+						.speed(0.001),
+				)
+			});
+		});
+	}
+}
+
+fn config_path() -> PathBuf {
+	directories_next::ProjectDirs::from("nz", "felle", "eo2")
+		.expect("getting configuration path")
+		.config_dir()
+		.to_owned()
+		.join("config.toml")
+}
+
+impl Default for Config {
+	fn default() -> Self {
+		toml::Table::new()
+			.try_into()
+			.expect("every key of the configuration has a default")
+	}
+}
+
+/// Where the configuration came from, so that saving it writes back only what came from the file.
+#[derive(Debug, Default)]
+struct Source {
+	/// `None` for the default path.
+	path: Option<PathBuf>,
+	/// The keys that were set by environment variables.
+	overridden: Vec<EnvOverride>,
+	/// Why the file couldn't be loaded, if the defaults are used instead.
+	load_error: Option<String>,
+}
+
+/// A key that was set by an environment variable.
+#[derive(Debug, Clone)]
+struct EnvOverride {
+	key: Vec<String>,
+	/// The value from the file that was replaced.
+	original: Option<toml::Value>,
+	/// The value that the configuration had because of the variable, as it is serialized, to tell whether it was changed since.
+	applied: Option<toml::Value>,
+}
+
+/// Environment variables starting with this set configuration values.
+const ENV_PREFIX: &str = "EO2_";
+
+/// Set the configuration values given by environment variables like `EO2_SLIDESHOW__INTERVAL=3`, where `__` separates nested keys.
+/// Values are parsed as TOML, or taken as strings if that fails.
+/// Returns the keys that were set, with their previous values.
+fn apply_env(
+	table: &mut toml::Table,
+	vars: impl Iterator<Item = (std::ffi::OsString, std::ffi::OsString)>,
+) -> Vec<EnvOverride> {
+	let mut overridden = Vec::new();
+	for (name, raw) in vars {
+		let (Some(name), Some(raw)) = (name.to_str(), raw.to_str()) else {
+			continue;
+		};
+		let Some(key) = name.strip_prefix(ENV_PREFIX) else {
+			continue;
+		};
+		let key: Vec<String> = key.split("__").map(str::to_ascii_lowercase).collect();
+		let value = toml::from_str::<toml::Table>(&format!("value = {raw}"))
+			.ok()
+			.and_then(|mut parsed| parsed.remove("value"))
+			.unwrap_or_else(|| toml::Value::String(raw.to_owned()));
+		let original = set_key(table, &key, Some(value));
+		overridden.push(EnvOverride {
+			key,
+			original,
+			applied: None,
+		});
+	}
+	overridden
+}
+
+fn get_key<'a>(table: &'a toml::Table, key: &[String]) -> Option<&'a toml::Value> {
+	let (first, rest) = key.split_first()?;
+	let value = table.get(first)?;
+	if rest.is_empty() {
+		Some(value)
+	} else {
+		get_key(value.as_table()?, rest)
+	}
+}
+
+/// Set the value at the nested `key`, or remove it if `value` is `None`, and return the value that was there.
+fn set_key(
+	table: &mut toml::Table,
+	key: &[String],
+	value: Option<toml::Value>,
+) -> Option<toml::Value> {
+	let (last, parents) = key.split_last()?;
+	let mut table = table;
+	for parent in parents {
+		let entry = table
+			.entry(parent.clone())
+			.or_insert_with(|| toml::Value::Table(toml::Table::new()));
+		if !entry.is_table() {
+			*entry = toml::Value::Table(toml::Table::new());
+		}
+		table = entry.as_table_mut().unwrap();
+	}
+	match value {
+		Some(value) => table.insert(last.clone(), value),
+		None => table.remove(last),
+	}
+}
+
+/// Hiding the panels and the mouse cursor when they aren't being used.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+pub struct AutoHide {
+	/// Hide the panels in fullscreen. They are shown again by moving the mouse to the edge of the screen.
+	#[serde(default = "default_auto_hide_panels")]
+	pub panels: bool,
+	#[serde(default = "default_auto_hide_panels_after")]
+	pub panels_after: Duration,
+	/// Hide the mouse cursor over the image in fullscreen and during slideshows.
+	#[serde(default = "default_auto_hide_cursor")]
+	pub cursor: bool,
+	#[serde(default = "default_auto_hide_cursor_after")]
+	pub cursor_after: Duration,
+}
+
+impl Default for AutoHide {
+	fn default() -> Self {
+		Self {
+			panels: default_auto_hide_panels(),
+			panels_after: default_auto_hide_panels_after(),
+			cursor: default_auto_hide_cursor(),
+			cursor_after: default_auto_hide_cursor_after(),
+		}
+	}
+}
+
+fn default_auto_hide_panels() -> bool {
+	true
+}
+
+fn default_auto_hide_panels_after() -> Duration {
+	Duration::new_secs(3).unwrap()
+}
+
+fn default_auto_hide_cursor() -> bool {
+	true
+}
+
+fn default_auto_hide_cursor_after() -> Duration {
+	Duration::new_secs(2).unwrap()
+}
+
+impl AutoHide {
+	fn ui(&mut self, ui: &mut egui::Ui) {
+		widgets::KeyValue::new("config-auto-hide-kv").show(ui, |mut rows| {
+			rows.row("Panels", |ui| {
+				ui.checkbox(&mut self.panels, "").on_hover_text(
+					"In fullscreen; move the mouse to the edge of the screen to show them again",
+				)
+			});
+			rows.row("Panels After", |ui| {
+				ui.add(widgets::UnitInput::duration(&mut self.panels_after))
+			});
+			rows.row("Cursor", |ui| {
+				ui.checkbox(&mut self.cursor, "")
+					.on_hover_text("In fullscreen and during slideshows")
+			});
+			rows.row("Cursor After", |ui| {
+				ui.add(widgets::UnitInput::duration(&mut self.cursor_after))
+			});
+		});
+	}
+}
+
+impl Checker {
+	fn ui(&mut self, ui: &mut egui::Ui) {
+		widgets::KeyValue::new("config-checker-kv").show(ui, |mut rows| {
+			rows.row("Size", |ui| {
+				ui.add(egui::Slider::new(&mut self.size, Self::MIN_SIZE..=100.0).suffix(" pt"))
+			});
+			rows.row("Colors", |ui| {
+				let default = [Color32::from_gray(204), Color32::from_gray(153)];
+				optional_ui(ui, &mut self.colors, default, |ui, colors| {
+					for color in colors {
+						ui.color_edit_button_srgba(color);
+					}
+				})
+				.on_hover_text("Otherwise, the squares are shades of the background color");
+			});
+		});
+	}
+}
+
+/// A checkbox for whether `value` is set, followed by `edit` if it is. Returns the response of the checkbox.
+fn optional_ui<T>(
+	ui: &mut egui::Ui,
+	value: &mut Option<T>,
+	default: T,
+	edit: impl FnOnce(&mut egui::Ui, &mut T),
+) -> egui::Response {
+	let mut set = value.is_some();
+	let response = ui.checkbox(&mut set, "Custom");
+	match (set, value) {
+		(true, Some(value)) => edit(ui, value),
+		(true, value @ None) => *value = Some(default),
+		(false, value) => *value = None,
+	}
+	response
+}
+
+impl Style {
+	fn ui(&mut self, ui: &mut egui::Ui) {
+		// what the theme has, which is what is shown while the value is not set
+		let theme = ui.visuals().clone();
+		widgets::KeyValue::new("config-style-kv").show(ui, |mut rows| {
+			rows.row("Accent", |ui| {
+				optional_ui(
+					ui,
+					&mut self.accent,
+					theme.selection.bg_fill,
+					|ui, color| {
+						ui.color_edit_button_srgba(color);
+					},
+				)
+			});
+			rows.row("Window Fill", |ui| {
+				optional_ui(ui, &mut self.window_fill, theme.window_fill, |ui, color| {
+					ui.color_edit_button_srgba(color);
+				})
+			});
+			rows.row("Panel Stroke", |ui| {
+				let stroke = theme.widgets.noninteractive.bg_stroke;
+				optional_ui(ui, &mut self.panel_stroke, stroke, |ui, stroke| {
+					egui::stroke_ui(ui, stroke, "");
+				})
+			});
+		});
+	}
+}
+
+impl Background {
+	fn ui(&mut self, ui: &mut egui::Ui) {
+		widgets::KeyValue::new("config-background-kv").show(ui, |mut rows| {
+			rows.row("Color", |ui| {
+				ComboBox::from_id_source("config-background-color-combo")
+					.selected_text(self.color.repr())
+					.show_ui(ui, |ui| {
+						for &variant in BackgroundColor::VARIANTS {
+							ui.selectable_value(&mut self.color, variant, variant.repr());
+						}
+					});
+				if self.color == BackgroundColor::Custom {
+					ui.color_edit_button_srgba(&mut self.custom_color);
+				}
+			});
+			rows.row("Checkered", |ui| ui.checkbox(&mut self.checkered, ""));
+			if self.checkered {
+				rows.row("Checker", |ui| {
+					self.checker.ui(ui);
+				});
+			}
+			rows.row("Opacity", |ui| {
+				ui.add(egui::Slider::new(&mut self.opacity, 0.0..=1.0))
+					.on_hover_text(
+						"Transparency only takes effect if it was enabled when the window was opened",
+					)
+			});
+		});
+	}
+}
+
+/// A bar along the bottom of the window with the zoom, the size of the image, its position among the images, and the pixel under the cursor.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default)]
+pub struct StatusBar {
+	#[serde(default)]
+	pub enabled: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default)]
+pub struct Window {
+	/// Hide the title bar and borders of the window. The window can still be moved by dragging the empty part of the toolbar.
+	#[serde(default)]
+	pub borderless: bool,
+}
+
+/// Which images to move between by their rating, in addition to the filter by name.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RatingFilter {
+	/// Zero for every image, including unrated ones.
+	#[serde(default)]
+	pub min_stars: u8,
+	#[serde(default)]
+	pub favorites_only: bool,
+}
+
+impl RatingFilter {
+	/// Whether any images are left out, which means that the ratings have to be read.
+	pub fn is_active(self) -> bool {
+		self.min_stars > 0 || self.favorites_only
+	}
+
+	fn ui(&mut self, ui: &mut egui::Ui) {
+		widgets::KeyValue::new("config-rating-filter-kv").show(ui, |mut rows| {
+			rows.row("Minimum Stars", |ui| {
+				ui.add(egui::Slider::new(&mut self.min_stars, 0..=5))
+					.on_hover_text(
+						"Only move between images rated at least this many stars, or all images at zero",
+					)
+			});
+			rows.row("Favorites Only", |ui| {
+				ui.checkbox(&mut self.favorites_only, "")
+			});
+		});
+	}
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+pub struct Accessibility {
+	/// Turn off animations, such as the Ken Burns effect and eased zooming, for people who are sensitive to motion.
+	#[serde(default)]
+	pub reduce_motion: bool,
+	/// Have screen readers read out the name, position, and size of each image as it is shown.
+	#[serde(default = "default_announce_images")]
+	pub announce_images: bool,
+}
+
+impl Default for Accessibility {
+	fn default() -> Self {
+		Self {
+			reduce_motion: false,
+			announce_images: default_announce_images(),
+		}
+	}
+}
+
+fn default_announce_images() -> bool {
+	true
+}
+
+impl Accessibility {
+	fn ui(&mut self, ui: &mut egui::Ui) {
+		widgets::KeyValue::new("config-accessibility-kv").show(ui, |mut rows| {
+			rows.row("Reduce Motion", |ui| {
+				ui.checkbox(&mut self.reduce_motion, "").on_hover_text(
+					"Turn off animations, including the Ken Burns effect, eased zooming, and sliding panels",
+				)
+			});
+			rows.row("Announce Images", |ui| {
+				ui.checkbox(&mut self.announce_images, "").on_hover_text(
+					"Have screen readers read out the name, position, and size of each image as it is shown",
+				)
+			});
+		});
+	}
+}
+
+impl Config {
+	/// Load the configuration from `path`, or the default path if it is `None`, then apply the `EO2_*` environment variables on top.
+	pub fn load(path: Option<PathBuf>) -> Result<Self, crate::error::Stringed> {
+		let raw = match std::fs::read_to_string(path.clone().unwrap_or_else(config_path)) {
+			Ok(raw) => raw,
+			// the file is created when saving
+			Err(error) if error.kind() == std::io::ErrorKind::NotFound => String::new(),
+			Err(error) => return Err(error.into()),
+		};
+		Self::from_table(toml::from_str(&raw)?, path)
+	}
+
+	/// Apply the environment to the table and deserialize it.
+	fn from_table(
+		mut table: toml::Table,
+		path: Option<PathBuf>,
+	) -> Result<Self, crate::error::Stringed> {
+		let mut overridden = apply_env(&mut table, std::env::vars_os());
+		let mut config: Self = table.try_into()?;
+		// compared as the configuration serializes them, since e.g. a duration may be written in another form than it is saved in
+		let applied = toml::Table::try_from(&config).expect("serializing configuration");
+		for env_override in &mut overridden {
+			env_override.applied = get_key(&applied, &env_override.key).cloned();
+		}
+		config.source = Source {
+			path,
+			overridden,
+			load_error: None,
+		};
+		Ok(config)
+	}
+
+	/// Load the configuration, or use the defaults if the file can't be read or parsed.
+	/// In that case, the error is kept to be shown, and the file is not saved over unless `confirm_overwrite` is called, so that it can be fixed instead.
+	/// The environment still applies to the defaults, unless it is broken too.
+	pub fn load_or_default(path: Option<PathBuf>) -> Self {
+		Self::load(path.clone()).unwrap_or_else(|error| {
+			let mut config =
+				Self::from_table(toml::Table::new(), path.clone()).unwrap_or_else(|_| Self {
+					source: Source {
+						path,
+						overridden: Vec::new(),
+						load_error: None,
+					},
+					..Self::default()
+				});
+			config.source.load_error = Some(error.0);
+			config
+		})
+	}
+
+	/// Why the file couldn't be loaded, if the defaults are used instead.
+	pub fn load_error(&self) -> Option<&str> {
+		self.source.load_error.as_deref()
+	}
+
+	/// Save over the file that couldn't be loaded.
+	pub fn confirm_overwrite(&mut self) {
+		self.source.load_error = None;
+	}
+
+	pub fn save(&self) -> std::io::Result<()> {
+		if self.source.load_error.is_some() {
+			return Ok(());
+		}
+		let mut table = toml::Table::try_from(self).expect("serializing configuration");
+		// the environment only applies while it is set, so the values from the file are kept unless they were changed in the settings
+		for env_override in &self.source.overridden {
+			if get_key(&table, &env_override.key) == env_override.applied.as_ref() {
+				set_key(&mut table, &env_override.key, env_override.original.clone());
+			}
+		}
+		let raw = toml::to_string(&table).expect("serializing configuration");
+		let path = self.path();
+		if let Some(dir) = path.parent() {
+			std::fs::create_dir_all(dir)?;
+		}
+		std::fs::write(path, raw)
+	}
+
+	/// The path of the file that the configuration is loaded from and saved to.
+	pub fn path(&self) -> PathBuf {
+		self.source.path.clone().unwrap_or_else(config_path)
+	}
+
+	/// Load the configuration again from the same file, e.g., after it was edited by hand.
+	pub fn reload(&self) -> Result<Self, crate::error::Stringed> {
+		Self::load(self.source.path.clone())
+	}
+
+	pub fn ui(&mut self, ui: &mut egui::Ui, tab: &mut SettingsTab) {
+		ui.horizontal(|ui| {
+			ui.selectable_value(tab, SettingsTab::General, "General");
+			ui.selectable_value(tab, SettingsTab::Bindings, "Key Bindings");
+		});
+		ui.separator();
+		match tab {
+			SettingsTab::General => self.general_ui(ui),
+			SettingsTab::Bindings => self.bindings.ui(ui),
+		}
+	}
+
+	fn general_ui(&mut self, ui: &mut egui::Ui) {
+		widgets::KeyValue::new("config-kv").show(ui, |mut rows| {
+			rows.row("Background", |ui| {
+				self.background.ui(ui);
+			});
+			rows.row("Color Scheme", |ui| {
+				self.light_dark_toggle_button(ui);
+			});
+			rows.row("Automatic Color Scheme", |ui| {
+				self.auto_theme.ui(ui);
+			});
+			rows.row("Style", |ui| {
+				self.style.ui(ui);
+			});
+			rows.row("UI Scale", |ui| ui_scale_input(ui, &mut self.ui_scale));
+			rows.row("Sort By", |ui| {
+				self.sort_ui(ui);
+			});
+			rows.row("Filter", |ui| {
+				ui.add(egui::TextEdit::singleline(&mut self.filter).hint_text("All images"))
+					.on_hover_text(
+						"Only move between the images in a folder that match these comma-separated globs or extensions, e.g., *.png,*.webp or png,webp",
+					)
+			});
+			rows.row("Rating Filter", |ui| {
+				self.rating_filter.ui(ui);
+			});
+			rows.row("Hidden Files", |ui| {
+				self.hidden_files.ui(ui);
+			});
+			rows.row("Slideshow", |ui| {
+				self.slideshow.ui(ui);
+				#[cfg(feature = "audio")]
+				ui.collapsing("Background Audio", |ui| self.slideshow_audio.ui(ui));
+			});
+			rows.row("Sprite Sheet", |ui| {
+				self.sprite_sheet.ui(ui);
+			});
+			rows.row("Video Export", |ui| {
+				self.video_export.ui(ui);
+			});
+			rows.row("Animation Export", |ui| {
+				self.animation_export.ui(ui);
+			});
+			rows.row("Pixel Grid", |ui| {
+				self.pixel_grid.ui(ui);
+			});
+			rows.row("Loupe", |ui| {
+				self.loupe.ui(ui);
+			});
+			rows.row("Scroll", |ui| {
+				self.scroll.ui(ui);
+			});
+			rows.row("Status Bar", |ui| {
+				ui.checkbox(&mut self.status_bar.enabled, "").on_hover_text(
+					"Show the zoom, the size of the image, its position among the images, and the pixel under the cursor",
+				)
+			});
+			rows.row("Borderless", |ui| {
+				ui.checkbox(&mut self.window.borderless, "").on_hover_text(
+					"Hide the title bar and borders of the window. Drag the empty part of the toolbar to move it",
+				)
+			});
+			rows.row("Auto-Hide", |ui| {
+				self.auto_hide.ui(ui);
+			});
+			rows.row("Accessibility", |ui| {
+				self.accessibility.ui(ui);
+			});
+			rows.row("Save Power", |ui| {
+				ComboBox::from_id_source("config-power-saving-combo")
+					.selected_text(self.power_saving.repr())
+					.show_ui(ui, |ui| {
+						for &variant in PowerSaving::VARIANTS {
+							ui.selectable_value(&mut self.power_saving, variant, variant.repr());
+						}
+					})
+					.response
+					.on_hover_text("Repaint animations less often and make fewer thumbnails. Being on battery is only detected on Linux")
+			});
+			rows.row("Cache Size", |ui| {
+				size_input(ui, &mut self.cache_size)
+					.on_hover_text("The memory for the decoded pixels of cached images");
+			});
+			rows.row("GPU Budget", |ui| {
+				size_input(ui, &mut self.gpu_budget)
+					.on_hover_text("The GPU memory for the textures of cached images");
+			});
+			rows.row("Keep Evicted Pixels", |ui| {
+				ui.checkbox(&mut self.keep_evicted_pixels, "")
+					.on_hover_text(
+					"Keep the pixels of cached images in memory, up to the cache size, so they can be shown again after their textures are evicted without decoding them",
+				)
+			});
+			rows.row("Data URI Size Limit", |ui| {
+				size_input(ui, &mut self.data_uri_max_size);
+			});
+		});
+	}
+
+	fn sort_ui(&mut self, ui: &mut egui::Ui) {
+		ComboBox::from_id_source("config-sort-combo")
+			.selected_text(self.sort.repr())
+			.show_ui(ui, |ui| {
+				for &variant in Sort::VARIANTS {
+					ui.selectable_value(&mut self.sort, variant, variant.repr());
+				}
+			});
+		self.name_order.ui(ui);
+	}
+
+	pub fn light_dark_toggle_button(&mut self, ui: &mut egui::Ui) {
+		if let Some(new_visuals) = ui.ctx().style().visuals.light_dark_small_toggle_button(ui) {
+			// choosing a theme by hand turns off switching automatically
+			self.auto_theme.follow = ThemeFollow::Off;
+			self.theme = Some(if new_visuals.dark_mode {
+				Theme::Dark
+			} else {
+				Theme::Light
+			});
+			ui.ctx().set_visuals(new_visuals);
+		}
+	}
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SettingsTab {
+	#[default]
+	General,
+	Bindings,
+}
+
+/// An input for a size in bytes, which ignores zero.
+fn size_input(ui: &mut egui::Ui, size: &mut NonZeroUsize) -> egui::Response {
+	let mut raw = size.get();
+	let response = ui.add(widgets::UnitInput::size(&mut raw));
+	if response.changed() {
+		if let Some(nz) = NonZeroUsize::new(raw) {
+			*size = nz;
+		}
+	}
+	response
+}
+
+fn ui_scale_input(ui: &mut egui::Ui, scale: &mut f32) -> egui::Response {
+	// dragging rather than a slider, which would move under the pointer as the interface is scaled
+	ui.add(
+		egui::DragValue::new(scale)
+			.clamp_range(UI_SCALE_RANGE)
+			.speed(0.01)
+			.custom_formatter(|scale, _| format!("{:.0}%", scale * 100.0))
+			.custom_parser(|raw| {
+				let raw = raw.trim().trim_end_matches('%').trim_end();
+				raw.parse::<f64>().ok().map(|percent| percent / 100.0)
+			}),
+	)
+	.on_hover_text("The size of the toolbar, panels, and text, on top of the scale of the display")
+}
+
+pub fn load(path: Option<PathBuf>) -> Config {
+	Config::load_or_default(path)
 }
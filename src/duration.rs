@@ -99,6 +99,10 @@ impl Duration {
 		az::cast::<_, f32>(self.micros) / SECS_MICROS_F
 	}
 
+	pub fn as_millis_f32(self) -> f32 {
+		az::cast::<_, f32>(self.micros) / MILLIS_MICROS_F
+	}
+
 	pub fn whole_secs(self) -> u32 {
 		self.micros / SECS_MICROS
 	}
@@ -138,6 +142,10 @@ impl Duration {
 		self.is_over()
 	}
 
+	pub fn saturating_add(self, other: Duration) -> Self {
+		Self::new_micros(self.micros.saturating_add(other.micros))
+	}
+
 	/// Whether this duration has elapsed.
 	pub fn is_over(self) -> bool {
 		self.micros == 0
@@ -0,0 +1,184 @@
+//! Bundling the details of an image that failed to load into a zip file, to attach to bug reports.
+
+use std::fmt::Write as _;
+use std::io::{self, Read as _, Write};
+use std::path::{Path, PathBuf};
+
+use image::ImageFormat;
+
+/// How much of the start of the file is included, which is where the headers that identify its format are.
+const HEADER_BYTES: u64 = 512;
+
+/// Where the bundles are saved, so that they don't clutter the folders of the images.
+pub fn bundle_dir() -> PathBuf {
+	directories_next::ProjectDirs::from("nz", "felle", "eo2")
+		.expect("getting data path")
+		.data_dir()
+		.join("diagnostics")
+}
+
+/// Create a new file for the bundle of the file at `path`, numbering its name if there is already a bundle for a file with the same name.
+fn create_bundle(path: &Path) -> io::Result<(std::fs::File, PathBuf)> {
+	let dir = bundle_dir();
+	std::fs::create_dir_all(&dir)?;
+	let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+	for number in 1.. {
+		let name = if number == 1 {
+			format!("{file_name}.diagnostic.zip")
+		} else {
+			format!("{file_name} ({number}).diagnostic.zip")
+		};
+		let bundle_path = dir.join(name);
+		match std::fs::File::create_new(&bundle_path) {
+			Ok(file) => return Ok((file, bundle_path)),
+			Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {}
+			Err(error) => return Err(error),
+		}
+	}
+	unreachable!()
+}
+
+/// Save a bundle describing why the file at `path` failed to load with `error`, returning where it was saved.
+pub fn save(path: &Path, error: &str) -> io::Result<PathBuf> {
+	let mut header = Vec::new();
+	std::fs::File::open(path)?
+		.take(HEADER_BYTES)
+		.read_to_end(&mut header)?;
+	let size = std::fs::metadata(path)?.len();
+
+	let report = report(path, error, size, &header);
+	let dump = hex_dump(&header);
+	let (file, bundle_path) = create_bundle(path)?;
+	write_zip(
+		io::BufWriter::new(file),
+		&[
+			("report.txt", report.as_bytes()),
+			("header.hex", dump.as_bytes()),
+		],
+	)?;
+	Ok(bundle_path)
+}
+
+fn report(path: &Path, error: &str, size: u64, header: &[u8]) -> String {
+	let format_name = |format: Option<ImageFormat>| {
+		format.map_or_else(|| "unknown".to_owned(), |format| format!("{format:?}"))
+	};
+
+	let mut report = String::new();
+	writeln!(report, "error: {error}").unwrap();
+	writeln!(report, "file: {}", path.display()).unwrap();
+	writeln!(report, "size: {size} bytes").unwrap();
+	writeln!(
+		report,
+		"format from contents: {}",
+		format_name(image::guess_format(header).ok()),
+	)
+	.unwrap();
+	writeln!(
+		report,
+		"format from extension: {}",
+		format_name(ImageFormat::from_path(path).ok()),
+	)
+	.unwrap();
+	writeln!(report, "eo2 version: {}", env!("CARGO_PKG_VERSION")).unwrap();
+	writeln!(
+		report,
+		"platform: {} {}",
+		std::env::consts::OS,
+		std::env::consts::ARCH,
+	)
+	.unwrap();
+	report
+}
+
+/// 16 bytes per line, with the offset first and the printable characters last.
+fn hex_dump(data: &[u8]) -> String {
+	const LINE: usize = 16;
+
+	let mut dump = String::new();
+	for (idx, line) in data.chunks(LINE).enumerate() {
+		write!(dump, "{:08x} ", idx * LINE).unwrap();
+		for byte in line {
+			write!(dump, " {byte:02x}").unwrap();
+		}
+		// pad the last line so that its characters line up with the others
+		dump.push_str(&"   ".repeat(LINE - line.len()));
+		dump.push_str("  ");
+		dump.extend(line.iter().map(|&byte| {
+			if byte.is_ascii_graphic() || byte == b' ' {
+				char::from(byte)
+			} else {
+				'.'
+			}
+		}));
+		dump.push('\n');
+	}
+	dump
+}
+
+/// Write `files` to a zip archive without compressing them, since they are small.
+fn write_zip(mut writer: impl Write, files: &[(&str, &[u8])]) -> io::Result<()> {
+	const VERSION: u16 = 20;
+	// 1980-01-01, the earliest date that zip files can represent
+	const DATE: u16 = (1 << 5) | 1;
+
+	let too_large = || io::Error::new(io::ErrorKind::InvalidInput, "too large for a zip file");
+
+	let mut central_directory = Vec::new();
+	let mut offset = 0u32;
+	for &(name, data) in files {
+		let size = u32::try_from(data.len()).map_err(|_| too_large())?;
+		let name_len = u16::try_from(name.len()).map_err(|_| too_large())?;
+
+		// the fields that the local header and the central directory have in common:
+		// the version needed, flags, method (stored), time, date, checksum, sizes, name length, and extra field length
+		let mut common = Vec::new();
+		for field in [VERSION, 0, 0, 0, DATE] {
+			common.extend(field.to_le_bytes());
+		}
+		for field in [crc32fast::hash(data), size, size] {
+			common.extend(field.to_le_bytes());
+		}
+		for field in [name_len, 0] {
+			common.extend(field.to_le_bytes());
+		}
+
+		let mut local = 0x0403_4b50u32.to_le_bytes().to_vec();
+		local.extend(&common);
+		local.extend(name.as_bytes());
+		writer.write_all(&local)?;
+		writer.write_all(data)?;
+
+		central_directory.extend(0x0201_4b50u32.to_le_bytes());
+		// the version that made the file
+		central_directory.extend(VERSION.to_le_bytes());
+		central_directory.extend(&common);
+		// the comment length, disk number, and internal attributes
+		for field in [0u16, 0, 0] {
+			central_directory.extend(field.to_le_bytes());
+		}
+		// the external attributes
+		central_directory.extend(0u32.to_le_bytes());
+		central_directory.extend(offset.to_le_bytes());
+		central_directory.extend(name.as_bytes());
+
+		let entry_len = u32::try_from(local.len() + data.len()).map_err(|_| too_large())?;
+		offset = offset.checked_add(entry_len).ok_or_else(too_large)?;
+	}
+
+	let count = u16::try_from(files.len()).map_err(|_| too_large())?;
+	let central_directory_len = u32::try_from(central_directory.len()).map_err(|_| too_large())?;
+	writer.write_all(&central_directory)?;
+
+	let mut end = 0x0605_4b50u32.to_le_bytes().to_vec();
+	// the disk numbers, then the number of entries on this disk and in total
+	for field in [0u16, 0, count, count] {
+		end.extend(field.to_le_bytes());
+	}
+	end.extend(central_directory_len.to_le_bytes());
+	end.extend(offset.to_le_bytes());
+	// the comment length
+	end.extend(0u16.to_le_bytes());
+	writer.write_all(&end)?;
+	writer.flush()
+}
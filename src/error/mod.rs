@@ -1,6 +1,8 @@
 use eframe::NativeOptions;
 use egui::{Context, ViewportCommand};
 
+pub mod diagnostic;
+
 pub struct Stringed(pub String);
 
 impl<E: std::error::Error> From<E> for Stringed {
@@ -18,39 +18,61 @@
 
 mod app;
 mod args;
+mod bindings;
 mod config;
 mod duration;
 mod error;
 mod widgets;
 
 fn main() -> Result<(), ()> {
-	match main_() {
-		Ok(()) => Ok(()),
+	app::init_timezone();
+
+	let args = args::load();
+	if args.info {
+		return app::print_info(&args.paths, args.json);
+	}
+	if args.thumbnail {
+		return app::make_thumbnail(&args.paths);
+	}
+
+	// read before the window opens, since the image is needed to open it
+	let piped = match app::Piped::read_if_requested(&args.paths) {
+		Ok(piped) => piped,
 		Err(error) => {
-			error::show(error.0);
-			Err(())
+			eprintln!("stdin: {error}");
+			return Err(());
 		}
-	}
-}
+	};
 
-fn main_() -> Result<(), error::Stringed> {
-	app::init_timezone();
+	// the window could not be opened, e.g. without a display, so there is nowhere else to show the error
+	main_(args, piped).map_err(|error| eprintln!("{error}"))
+}
 
-	let args = args::load();
-	let config = config::load()?;
+fn main_(args: args::Args, piped: Option<app::Piped>) -> Result<(), eframe::Error> {
+	let config = config::load(args.config.clone());
 
-	let mut native_options = eframe::NativeOptions::default();
+	let mut native_options = eframe::NativeOptions {
+		// the system theme is always tracked so that following it can be turned on while running; the app overrides it otherwise
+		follow_system_theme: true,
+		..eframe::NativeOptions::default()
+	};
 	if let Some(theme) = config.theme {
-		native_options.follow_system_theme = false;
 		native_options.default_theme = theme;
 	}
+	if config.background.needs_transparency() {
+		native_options.viewport = native_options.viewport.with_transparent(true);
+	}
+	// the configuration is only overridden by the flags once the app starts
+	if config.window.borderless || args.borderless {
+		native_options.viewport = native_options.viewport.with_decorations(false);
+	}
+	if args.fullscreen {
+		native_options.viewport = native_options.viewport.with_fullscreen(true);
+	}
 
 	eframe::run_native(
 		"Image Viewer",
 		native_options,
-		Box::new(move |cc| Box::new(app::App::new(args, config, cc))),
+		Box::new(move |cc| Box::new(app::App::new(args, config, piped, cc))),
 	)
-	.unwrap();
-
-	Ok(())
 }
@@ -0,0 +1,38 @@
+//! Describing widgets to screen readers through AccessKit, for widgets whose contents don't say what they are, such as buttons that only show an icon.
+
+use egui::Response;
+
+pub trait AccessibleExt {
+	/// Give `label` to screen readers as the name of the widget.
+	fn accessible_label(self, label: impl Into<String>) -> Self;
+
+	/// Show `text` on hover, and give it to screen readers as the name of the widget.
+	fn on_hover_label(self, text: impl Into<String>) -> Self;
+}
+
+impl AccessibleExt for Response {
+	fn accessible_label(self, label: impl Into<String>) -> Self {
+		let label = label.into();
+		self
+			.ctx
+			.accesskit_node_builder(self.id, |builder| builder.set_name(label));
+		self
+	}
+
+	fn on_hover_label(self, text: impl Into<String>) -> Self {
+		let text = text.into();
+		self.accessible_label(text.clone()).on_hover_text(text)
+	}
+}
+
+/// Describe the image being viewed to screen readers.
+/// If `announce` is set, the description is read out whenever it changes, i.e., when another image is shown.
+pub fn describe_image(response: &Response, description: String, announce: bool) {
+	response.ctx.accesskit_node_builder(response.id, |builder| {
+		builder.set_role(accesskit::Role::Image);
+		builder.set_name(description);
+		if announce {
+			builder.set_live(accesskit::Live::Polite);
+		}
+	});
+}
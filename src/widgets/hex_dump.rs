@@ -0,0 +1,44 @@
+use egui::{RichText, ScrollArea, Ui};
+
+const BYTES_PER_ROW: usize = 16;
+
+fn format_row(offset: usize, bytes: &[u8]) -> String {
+	use std::fmt::Write as _;
+
+	let mut row = format!("{offset:08x} ");
+	for idx in 0..BYTES_PER_ROW {
+		// an extra space between the two halves of the row
+		if idx == BYTES_PER_ROW / 2 {
+			row.push(' ');
+		}
+		match bytes.get(idx) {
+			Some(byte) => write!(row, " {byte:02x}").unwrap(),
+			None => row.push_str("   "),
+		}
+	}
+	row.push_str("  ");
+	row.extend(bytes.iter().map(|&byte| {
+		if byte.is_ascii_graphic() || byte == b' ' {
+			char::from(byte)
+		} else {
+			'.'
+		}
+	}));
+	row
+}
+
+/// Show `data` as rows of offsets, hexadecimal bytes, and ASCII. Only the visible rows are laid out.
+pub fn show(ui: &mut Ui, id_source: impl std::hash::Hash, data: &[u8]) {
+	let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+	let num_rows = data.len().div_ceil(BYTES_PER_ROW);
+	ScrollArea::both()
+		.id_source(id_source)
+		.auto_shrink([false, true])
+		.show_rows(ui, row_height, num_rows, |ui, rows| {
+			for row in rows {
+				let offset = row * BYTES_PER_ROW;
+				let bytes = &data[offset..data.len().min(offset + BYTES_PER_ROW)];
+				ui.label(RichText::new(format_row(offset, bytes)).monospace());
+			}
+		});
+}
@@ -1,14 +1,71 @@
 use egui::load::SizedTexture;
-use egui::{Rect, Response, Sense, TextureHandle, TextureId, Ui, Vec2, Widget};
+use egui::{Color32, Pos2, Rect, Response, Sense, Ui, Vec2, Widget};
+use serde::{Deserialize, Serialize};
 
-use super::image_size;
+use super::{image_size, TiledTexture};
 
-#[derive(Clone, Copy, PartialEq)]
+/// A zoom level that depends on the size of the view, so it is resolved lazily.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+enum Preset {
+	/// Fit this region of the image, in pixels.
+	Region(Rect),
+	/// Fill the view, cropping the image.
+	Fill,
+	/// Show each pixel of the image as one physical pixel.
+	ActualSize,
+	/// Zoom in by `factor` relative to the fitted size, panning toward `direction` only as far as the fitted image extends.
+	KenBurns { factor: f32, direction: Vec2 },
+}
+
+/// The geometry that zoom levels are relative to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct View {
+	/// The size of the space available to the image, in points.
+	pub available: Vec2,
+	/// The size of the shown image, in pixels.
+	pub image_size: Vec2,
+	pub pixels_per_point: f32,
+}
+
+impl View {
+	pub fn from_response(response: &Response, image_size: Vec2) -> Self {
+		Self {
+			available: response.rect.size(),
+			image_size,
+			pixels_per_point: response.ctx.pixels_per_point(),
+		}
+	}
+
+	/// Points per image pixel without any zoom.
+	fn base_scale(self) -> f32 {
+		image_size(self.image_size, self.available).x / self.image_size.x
+	}
+}
+
+/// How scrolling zooms the image.
+#[derive(Debug, Clone, Copy)]
+pub struct ScrollZoom {
+	/// Only zoom while Ctrl is held, so the scroll wheel can be used for something else.
+	pub require_ctrl: bool,
+	/// How much the zoom level (a power of 2) changes per point scrolled.
+	pub sensitivity: f32,
+	/// Ease into the zoom over a few frames rather than jumping to it.
+	pub smooth: bool,
+}
+
+/// egui turns Ctrl-scrolling into a zoom factor of `exp(delta / CTRL_SCROLL_POINTS)`.
+const CTRL_SCROLL_POINTS: f32 = 200.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[allow(clippy::struct_field_names)] // `zoom` is the clearest name
 pub struct Zoom {
 	/// 0, 0 = center
 	pub center: Vec2,
 	/// 0 = no zoom
 	pub zoom: f32,
+	/// If set, `center` and `zoom` are ignored and the view is determined by the preset.
+	#[serde(default)]
+	preset: Option<Preset>,
 }
 
 impl Default for Zoom {
@@ -16,32 +73,161 @@ impl Default for Zoom {
 		Self {
 			center: Vec2 { x: 0.0, y: 0.0 },
 			zoom: 0.0,
+			preset: None,
 		}
 	}
 }
 
 impl Zoom {
+	fn preset(preset: Preset) -> Self {
+		Self {
+			preset: Some(preset),
+			..Self::default()
+		}
+	}
+
+	/// Fit the whole image in the view, without upscaling it. This is the default.
+	pub fn fit() -> Self {
+		Self::default()
+	}
+
+	/// Fill the view, cropping the image if its aspect ratio differs.
+	pub fn fill() -> Self {
+		Self::preset(Preset::Fill)
+	}
+
+	/// Show the image at its native size, one image pixel per physical pixel.
+	pub fn actual_size() -> Self {
+		Self::preset(Preset::ActualSize)
+	}
+
+	/// One frame of a slow pan and zoom: `factor` times the fitted size, panned toward `direction` (a unit vector).
+	pub fn ken_burns(factor: f32, direction: Vec2) -> Self {
+		Self::preset(Preset::KenBurns { factor, direction })
+	}
+
+	/// Fit the view to `region` of the image, in pixels.
+	pub fn fit_to(region: Rect) -> Self {
+		Self::preset(Preset::Region(region))
+	}
+
+	/// Convert `preset` into an equivalent `center` and `zoom` for `view`.
+	fn resolve(self, view: View) -> Self {
+		let Some(preset) = self.preset else {
+			return self;
+		};
+		// the image is centered, so the center of the unzoomed image is the center of the view
+		let View {
+			available,
+			image_size,
+			pixels_per_point,
+		} = view;
+		let base_scale = view.base_scale();
+		let (factor, offset) = match preset {
+			Preset::Region(region) => (
+				(available / (region.size() * base_scale)).min_elem(),
+				(region.center() - image_size / 2.0).to_vec2() * base_scale,
+			),
+			Preset::Fill => (
+				(available / (image_size * base_scale)).max_elem(),
+				Vec2::ZERO,
+			),
+			Preset::ActualSize => (1.0 / (base_scale * pixels_per_point), Vec2::ZERO),
+			// panning by half of the growth keeps the edges of the image outside of where they were when fitted
+			Preset::KenBurns { factor, direction } => (
+				factor,
+				-direction * (factor - 1.0) / factor * image_size * base_scale / 2.0,
+			),
+		};
+		Self {
+			center: -offset * factor,
+			zoom: factor.log2(),
+			preset: None,
+		}
+	}
+
 	fn zoom_factor(self) -> f32 {
 		2f32.powf(self.zoom)
 	}
 
+	/// The number of physical pixels per image pixel, so 1 is the actual size.
+	pub fn scale(self, view: View) -> f32 {
+		let resolved = self.resolve(view);
+		view.base_scale() * view.pixels_per_point * resolved.zoom_factor()
+	}
+
+	/// Zoom around the center of the view so that `scale` would return `scale`.
+	pub fn set_scale(&mut self, view: View, scale: f32) {
+		*self = self.resolve(view);
+		let old_zoom = self.zoom_factor();
+		self.zoom = (scale / (view.base_scale() * view.pixels_per_point)).log2();
+		self.center *= self.zoom_factor() / old_zoom;
+	}
+
 	fn apply(self, rect: Rect) -> Rect {
 		let center = rect.center() + self.center;
 		let size = rect.size() * self.zoom_factor();
 		Rect::from_center_size(center, size)
 	}
 
-	pub fn update_from_response(&mut self, response: &Response) {
+	/// `image_size` is the size of the shown image in pixels, which is needed to snap to integer zoom levels while Alt is held.
+	pub fn update_from_response(
+		&mut self,
+		response: &Response,
+		image_size: Vec2,
+		scroll_zoom: ScrollZoom,
+	) {
 		if response.middle_clicked() {
 			*self = Self::default();
 			return;
 		}
 
-		self.center += response.drag_delta();
+		let (snap, scroll, touch) = response.ctx.input(|input| {
+			let touch = input.multi_touch();
+			if input.modifiers.alt {
+				return (true, input.raw_scroll_delta.y, touch);
+			}
+			// pinching on a trackpad is reported the same way as Ctrl-scrolling
+			let ctrl_scroll = if touch.is_none() {
+				input.zoom_delta().ln() * CTRL_SCROLL_POINTS
+			} else {
+				0.0
+			};
+			let wheel = if scroll_zoom.require_ctrl {
+				0.0
+			} else if scroll_zoom.smooth {
+				input.smooth_scroll_delta.y
+			} else {
+				input.raw_scroll_delta.y
+			};
+			(false, wheel + ctrl_scroll, touch)
+		});
+		// with several fingers on a touchscreen, they pan and pinch to zoom rather than dragging with the first finger
+		let (drag, pinch) = match touch {
+			Some(touch) if response.hovered() => (touch.translation_delta, touch.zoom_delta.log2()),
+			_ => (response.drag_delta(), 0.0),
+		};
+		if drag == Vec2::ZERO && ((scroll == 0.0 && pinch == 0.0) || !response.hovered()) {
+			return;
+		}
+		let view = View::from_response(response, image_size);
+		*self = self.resolve(view);
+
+		self.center += drag;
 		if let Some(pointer) = response.hover_pos() {
 			let pointer = pointer - response.rect.center();
 			let old_zoom = self.zoom_factor();
-			self.zoom += response.ctx.input(|input| input.smooth_scroll_delta.y) * 0.01;
+			if snap {
+				if scroll != 0.0 {
+					// physical pixels per image pixel without any zoom
+					let base_scale = view.base_scale() * view.pixels_per_point;
+					self.step_integer_zoom(base_scale, scroll > 0.0);
+				}
+			} else {
+				self.zoom += scroll * scroll_zoom.sensitivity;
+			}
+			// pinching follows the fingers exactly
+			self.zoom += pinch;
 			let zoom_delta = self.zoom_factor() / old_zoom;
 			self.center -= pointer;
 			self.center *= zoom_delta;
@@ -49,31 +235,70 @@ impl Zoom {
 		}
 	}
 
+	/// Move to the next integer number of physical pixels per image pixel, never going below 1.
+	fn step_integer_zoom(&mut self, base_scale: f32, zoom_in: bool) {
+		const EPSILON: f32 = 1e-3;
+
+		let current = base_scale * self.zoom_factor();
+		let target = if zoom_in {
+			(current + EPSILON).floor() + 1.0
+		} else if current > 1.0 + EPSILON {
+			((current - EPSILON).ceil() - 1.0).max(1.0)
+		} else {
+			return;
+		};
+		self.zoom = (target / base_scale).log2();
+	}
+
 	pub fn modified(self) -> bool {
 		self != Self::default()
 	}
 }
 
+/// At integer zoom levels, align the image to physical pixels so that every image pixel covers the same number of physical pixels.
+/// Otherwise nearest-neighbor sampling shimmers and shows uneven pixel sizes.
+fn snap_to_pixels(ui: &Ui, image_rect: Rect, actual_size: Vec2) -> Rect {
+	const EPSILON: f32 = 1e-3;
+
+	let pixels_per_point = ui.ctx().pixels_per_point();
+	let scale = image_rect.width() / actual_size.x * pixels_per_point;
+	let rounded = scale.round();
+	if rounded < 1.0 || (scale - rounded).abs() > EPSILON {
+		return image_rect;
+	}
+
+	let min = ui.painter().round_pos_to_pixels(image_rect.min);
+	Rect::from_min_size(min, actual_size * rounded / pixels_per_point)
+}
+
 /// Similar to `egui::widgets::Image` but preserves the aspect ratio of the texture.
-pub struct Image {
-	texture: TextureId,
-	actual_size: Vec2,
+pub struct Image<'a> {
+	texture: &'a TiledTexture,
+	/// The part of the texture to show, in pixels.
+	region: Option<Rect>,
 	zoom: Zoom,
 	clickable: bool,
+	/// Multiplied with the colors of the texture.
+	tint: Color32,
 }
 
-impl Image {
-	pub fn new(texture: TextureId, size: Vec2) -> Self {
+impl<'a> Image<'a> {
+	pub fn for_texture(texture: &'a TiledTexture) -> Self {
 		Self {
 			texture,
-			actual_size: size,
+			region: None,
 			zoom: Zoom::default(),
 			clickable: false,
+			tint: Color32::WHITE,
 		}
 	}
 
-	pub fn for_texture(texture: &TextureHandle) -> Self {
-		Self::new(texture.id(), texture.size_vec2())
+	/// Only show `region` of the texture, in pixels.
+	pub fn region(self, region: Rect) -> Self {
+		Self {
+			region: Some(region),
+			..self
+		}
 	}
 
 	pub fn zoom(self, zoom: Zoom) -> Self {
@@ -84,25 +309,59 @@ impl Image {
 		Self { clickable, ..self }
 	}
 
+	pub fn tint(self, tint: Color32) -> Self {
+		Self { tint, ..self }
+	}
+
 	/// Returns the actual rect that the image filled
 	pub fn paint_at(self, ui: &mut Ui, available_rect: Rect) -> Rect {
 		// Create a child UI so we can set the clip of the painter
 		let mut ui = ui.child_ui(available_rect, *ui.layout());
 		ui.set_clip_rect(available_rect.intersect(ui.clip_rect()));
 
+		let region = self
+			.region
+			.unwrap_or_else(|| Rect::from_min_size(Pos2::ZERO, self.texture.size_vec2()));
+		let actual_size = region.size();
 		let available_size = available_rect.size();
-		let scaled_size = image_size(self.actual_size, available_size);
+		let scaled_size = image_size(actual_size, available_size);
 		let mut image_rect = ui
 			.layout()
 			.align_size_within_rect(scaled_size, available_rect);
 
-		image_rect = self.zoom.apply(image_rect);
+		image_rect = self
+			.zoom
+			.resolve(View {
+				available: available_size,
+				image_size: actual_size,
+				pixels_per_point: ui.ctx().pixels_per_point(),
+			})
+			.apply(image_rect);
+		image_rect = snap_to_pixels(&ui, image_rect, actual_size);
 
-		let texture = SizedTexture {
-			id: self.texture,
-			size: scaled_size,
-		};
-		egui::widgets::Image::from_texture(texture).paint_at(&ui, image_rect);
+		if self.region.is_some() {
+			ui.set_clip_rect(image_rect.intersect(ui.clip_rect()));
+		}
+
+		let scale = image_rect.size() / actual_size;
+		// where the top left of the whole texture ends up
+		let origin = image_rect.min - region.min.to_vec2() * scale;
+		for tile in self.texture.tiles() {
+			let tile_rect = Rect::from_min_max(
+				origin + tile.rect.min.to_vec2() * scale,
+				origin + tile.rect.max.to_vec2() * scale,
+			);
+			if !ui.is_rect_visible(tile_rect) {
+				continue;
+			}
+			let texture = SizedTexture {
+				id: tile.texture.id(),
+				size: tile_rect.size(),
+			};
+			egui::widgets::Image::from_texture(texture)
+				.tint(self.tint)
+				.paint_at(&ui, tile_rect);
+		}
 
 		image_rect
 	}
@@ -116,13 +375,20 @@ impl Image {
 	}
 }
 
-impl Widget for Image {
-	fn ui(self, ui: &mut Ui) -> Response {
+impl Image<'_> {
+	/// Like `Widget::ui`, but also returns the rect that the image filled.
+	pub fn show(self, ui: &mut Ui) -> (Response, Rect) {
 		let (id, space) = ui.allocate_space(ui.available_size());
 		let sense = self.sense();
-		self.paint_at(ui, space);
+		let image_rect = self.paint_at(ui, space);
 		// passing `space` for the interaction rect rather than the rect returned by `paint_at` so that the image can be zoomed/paused without the cursor necessarily being inside the actual image.
 		// this makes zoom behavior more friendly, as the user can continue zooming even if the image has become small enough that the cursor is now outside of it.
-		ui.interact(space, id, sense)
+		(ui.interact(space, id, sense), image_rect)
+	}
+}
+
+impl Widget for Image<'_> {
+	fn ui(self, ui: &mut Ui) -> Response {
+		self.show(ui).0
 	}
 }
@@ -1,18 +1,19 @@
-use egui::{Response, Sense, TextureHandle, TextureId, Ui, Vec2, Widget, WidgetInfo, WidgetType};
+use egui::{Response, Sense, Ui, Vec2, Widget, WidgetInfo, WidgetType};
+
+use super::TiledTexture;
 
 /// Similar to `egui::widgets::ImageButton` but preserves the aspect ratio of the texture.
-pub struct ImageButton {
-	texture: TextureId,
-	image_size_actual: Vec2,
+/// Shows the thumbnail of the texture if it has one.
+pub struct ImageButton<'a> {
+	texture: &'a TiledTexture,
 	button_size: Vec2,
 	selected: bool,
 }
 
-impl ImageButton {
-	pub fn new(texture: &TextureHandle, button_size: Vec2) -> Self {
+impl<'a> ImageButton<'a> {
+	pub fn new(texture: &'a TiledTexture, button_size: Vec2) -> Self {
 		Self {
-			texture: texture.id(),
-			image_size_actual: texture.size_vec2(),
+			texture,
 			button_size,
 			selected: false,
 		}
@@ -23,12 +24,11 @@ impl ImageButton {
 	}
 }
 
-impl Widget for ImageButton {
+impl Widget for ImageButton<'_> {
 	fn ui(self, ui: &mut Ui) -> Response {
 		let Self {
 			texture,
 			button_size,
-			image_size_actual,
 			selected,
 		} = self;
 
@@ -49,7 +49,7 @@ impl Widget for ImageButton {
 			ui.painter().rect_filled(rect, rounding, fill);
 
 			let available_rect = rect.shrink2(padding);
-			super::Image::new(texture, image_size_actual).paint_at(ui, available_rect);
+			super::Image::for_texture(texture.thumbnail()).paint_at(ui, available_rect);
 
 			// Draw frame outline:
 			ui.painter().rect_stroke(rect, rounding, stroke);
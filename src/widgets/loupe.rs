@@ -0,0 +1,92 @@
+//! A magnified inset of the pixels under the cursor, to check sharpness without zooming the whole image.
+
+use std::f32::consts::TAU;
+
+use egui::epaint::Vertex;
+use egui::{Color32, Mesh, Painter, Pos2, Rect, Shape, Stroke, Vec2};
+
+use super::selection::PixelMapping;
+use super::TiledTexture;
+
+/// The number of sides of the polygon that approximates a circular loupe.
+const CIRCLE_SEGMENTS: u16 = 64;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Options {
+	/// The width and height of the loupe, in points.
+	pub size: f32,
+	/// The size of one image pixel in the loupe, in points.
+	pub scale: f32,
+	pub circle: bool,
+	/// Multiplied with the colors of the texture.
+	pub tint: Color32,
+}
+
+/// Paint the loupe centered on the `cursor`, showing the pixels of `texture` around it.
+pub fn paint(
+	painter: &Painter,
+	texture: &TiledTexture,
+	mapping: PixelMapping,
+	cursor: Pos2,
+	options: Options,
+) {
+	let radius = options.size / 2.0;
+	let bounds = Rect::from_center_size(cursor, Vec2::splat(options.size));
+	let outline: Vec<Pos2> = if options.circle {
+		(0..CIRCLE_SEGMENTS)
+			.map(|idx| cursor + Vec2::angled(TAU * f32::from(idx) / f32::from(CIRCLE_SEGMENTS)) * radius)
+			.collect()
+	} else {
+		vec![
+			bounds.left_top(),
+			bounds.right_top(),
+			bounds.right_bottom(),
+			bounds.left_bottom(),
+		]
+	};
+
+	let center_pixel = mapping.to_pixel(cursor);
+	let to_loupe = |pixel: Pos2| cursor + (pixel - center_pixel) * options.scale;
+	let to_pixel = |pos: Pos2| center_pixel + (pos - cursor) / options.scale;
+
+	let visuals = &painter.ctx().style().visuals;
+	painter.add(Shape::convex_polygon(
+		outline.clone(),
+		visuals.extreme_bg_color,
+		Stroke::NONE,
+	));
+	// each tile gets the whole outline, clipped to where the tile is, since the texture coordinates only make sense within it
+	for tile in texture.tiles() {
+		let tile_rect = Rect::from_min_max(to_loupe(tile.rect.min), to_loupe(tile.rect.max));
+		let clip = tile_rect.intersect(bounds).intersect(painter.clip_rect());
+		if !clip.is_positive() {
+			continue;
+		}
+		let mut mesh = Mesh::with_texture(tile.texture.id());
+		mesh.vertices.extend(outline.iter().map(|&pos| Vertex {
+			pos,
+			uv: ((to_pixel(pos) - tile.rect.min) / tile.rect.size()).to_pos2(),
+			color: options.tint,
+		}));
+		let len: u32 = az::cast(outline.len());
+		for idx in 1..len - 1 {
+			mesh.add_triangle(0, idx, idx + 1);
+		}
+		painter.with_clip_rect(clip).add(mesh);
+	}
+
+	// the pixel under the cursor
+	let pixel = Rect::from_min_size(center_pixel.floor(), Vec2::splat(1.0));
+	if Rect::from_min_size(Pos2::ZERO, mapping.image_size).contains_rect(pixel) {
+		let pixel = Rect::from_min_max(to_loupe(pixel.min), to_loupe(pixel.max));
+		painter.with_clip_rect(bounds).rect_stroke(
+			pixel,
+			0.0,
+			Stroke::new(1.0, Color32::from_gray(128)),
+		);
+	}
+	painter.add(Shape::closed_line(
+		outline,
+		Stroke::new(2.0, visuals.widgets.active.bg_stroke.color),
+	));
+}
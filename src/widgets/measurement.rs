@@ -0,0 +1,108 @@
+//! Measuring distances over an image in image pixels, e.g., to check the size of sprites and UI assets.
+
+use egui::{Align, Align2, Color32, FontId, Painter, Pos2, Rect, Response, Shape, Stroke, Vec2};
+
+use super::selection::PixelMapping;
+
+/// A line between two corners of pixels, in image pixel coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Measurement {
+	start: Pos2,
+	end: Pos2,
+}
+
+impl Measurement {
+	/// The width and height of the rect spanned by the line, in pixels.
+	pub fn size(self) -> Vec2 {
+		(self.end - self.start).abs()
+	}
+
+	/// The length of the line, in pixels.
+	pub fn length(self) -> f32 {
+		(self.end - self.start).length()
+	}
+
+	/// E.g., `32 × 16 px, 35.8 px long`.
+	pub fn repr(self) -> String {
+		let size = self.size();
+		format!("{} × {} px, {:.1} px long", size.x, size.y, self.length())
+	}
+
+	/// Start or extend the measurement based on a drag in `response`.
+	///
+	/// The ends snap to the nearest corner between pixels so that whole pixels are measured, whatever the zoom.
+	pub fn update_from_response(
+		measurement: &mut Option<Self>,
+		response: &Response,
+		mapping: PixelMapping,
+	) {
+		let to_corner = |pos: Pos2| {
+			mapping
+				.to_pixel(pos)
+				.round()
+				.clamp(Pos2::ZERO, mapping.image_size.to_pos2())
+		};
+		if response.drag_started() {
+			*measurement = response.interact_pointer_pos().map(|pos| {
+				let pos = to_corner(pos);
+				Self {
+					start: pos,
+					end: pos,
+				}
+			});
+		} else if response.dragged() {
+			if let (Some(measurement), Some(pos)) = (measurement, response.interact_pointer_pos()) {
+				measurement.end = to_corner(pos);
+			}
+		}
+	}
+
+	pub fn paint(self, painter: &Painter, mapping: PixelMapping) {
+		let start = mapping.to_screen(self.start);
+		let end = mapping.to_screen(self.end);
+		let color = painter.ctx().style().visuals.selection.stroke.color;
+
+		painter.rect_stroke(
+			Rect::from_two_pos(start, end),
+			0.0,
+			Stroke::new(1.0, color.gamma_multiply(0.5)),
+		);
+		painter.add(Shape::dashed_line(
+			&[start, end],
+			Stroke::new(2.0, color),
+			6.0,
+			4.0,
+		));
+		for pos in [start, end] {
+			painter.circle_filled(pos, 3.0, color);
+		}
+
+		let galley = painter.layout_no_wrap(self.repr(), FontId::monospace(12.0), Color32::WHITE);
+		// beside the end being dragged, on the side away from the start so that it doesn't cover the line
+		let side = |end: f32, start: f32| {
+			if end < start {
+				(Align::Max, -8.0)
+			} else {
+				(Align::Min, 8.0)
+			}
+		};
+		let (align_x, offset_x) = side(end.x, start.x);
+		let (align_y, offset_y) = side(end.y, start.y);
+		let anchor = Align2([align_x, align_y]);
+		let offset = Vec2::new(offset_x, offset_y);
+		let rect = anchor.anchor_size(end + offset, galley.size());
+		painter.rect_filled(rect.expand(3.0), 3.0, Color32::from_black_alpha(192));
+		painter.galley(rect.min, galley, Color32::WHITE);
+	}
+}
+
+#[test]
+fn test_measurement() {
+	let measurement = Measurement {
+		start: Pos2::new(4.0, 20.0),
+		end: Pos2::new(7.0, 16.0),
+	};
+	assert_eq!(measurement.size(), Vec2::new(3.0, 4.0));
+	assert!((measurement.length() - 5.0).abs() < f32::EPSILON);
+	assert_eq!(measurement.repr(), "3 × 4 px, 5.0 px long");
+}
@@ -1,15 +1,28 @@
 use egui::Vec2;
 
+pub use self::accessibility::AccessibleExt;
 pub use self::columns::ShowColumnsExt;
 pub use self::image::Image;
 pub use self::image_button::ImageButton;
 pub use self::key_value::KeyValue;
+pub use self::measurement::Measurement;
+pub use self::selection::Selection;
+pub use self::tiled::TiledTexture;
+pub use self::toasts::Toasts;
 pub use self::unit_input::UnitInput;
 
+pub mod accessibility;
 pub mod columns;
+pub mod hex_dump;
 pub mod image;
 pub mod image_button;
 pub mod key_value;
+pub mod loupe;
+pub mod measurement;
+pub mod pixel_grid;
+pub mod selection;
+pub mod tiled;
+pub mod toasts;
 pub mod unit_input;
 
 fn image_size(actual: Vec2, max: Vec2) -> Vec2 {
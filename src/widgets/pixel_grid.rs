@@ -0,0 +1,54 @@
+use egui::{Align2, Color32, FontId, Painter, Pos2, Rect, Stroke};
+
+use super::selection::PixelMapping;
+
+/// Coordinate labels are only drawn once pixels are at least this large, in points, so that they fit.
+const MIN_LABEL_SCALE: f32 = 40.0;
+
+/// Draw lines between the pixels of the image, optionally labelling each pixel with its coordinates.
+pub fn paint(painter: &Painter, mapping: PixelMapping, show_coordinates: bool) {
+	let visible = painter.clip_rect().intersect(mapping.image_rect);
+	if !visible.is_positive() {
+		return;
+	}
+	let painter = painter.with_clip_rect(visible);
+
+	// the range of pixels that are at least partially visible
+	let bounds = Rect::from_min_size(Pos2::ZERO, mapping.image_size);
+	let pixels = Rect::from_min_max(
+		mapping.to_pixel(visible.min).floor(),
+		mapping.to_pixel(visible.max).ceil(),
+	)
+	.intersect(bounds);
+	let xs = az::cast::<f32, u32>(pixels.min.x)..=az::cast::<f32, u32>(pixels.max.x);
+	let ys = az::cast::<f32, u32>(pixels.min.y)..=az::cast::<f32, u32>(pixels.max.y);
+
+	let stroke = Stroke::new(1.0, Color32::from_gray(128).gamma_multiply(0.5));
+	for x in xs.clone() {
+		let x = mapping.to_screen(Pos2::new(az::cast(x), 0.0)).x;
+		painter.vline(x, visible.y_range(), stroke);
+	}
+	for y in ys.clone() {
+		let y = mapping.to_screen(Pos2::new(0.0, az::cast(y))).y;
+		painter.hline(visible.x_range(), y, stroke);
+	}
+
+	let scale = mapping.scale().min_elem();
+	if !show_coordinates || scale < MIN_LABEL_SCALE {
+		return;
+	}
+	let font = FontId::monospace((scale / 5.0).min(12.0));
+	// the ranges include the far edge, which is not a pixel
+	for y in *ys.start()..*ys.end() {
+		for x in *xs.start()..*xs.end() {
+			let pixel = Pos2::new(az::cast(x), az::cast(y));
+			painter.text(
+				mapping.to_screen(pixel) + egui::vec2(2.0, 2.0),
+				Align2::LEFT_TOP,
+				format!("{x},{y}"),
+				font.clone(),
+				Color32::from_gray(128),
+			);
+		}
+	}
+}
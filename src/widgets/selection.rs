@@ -0,0 +1,78 @@
+use egui::{Color32, Painter, Pos2, Rect, Response, Rounding, Stroke, Vec2};
+
+/// Maps between screen coordinates and image pixel coordinates.
+#[derive(Clone, Copy)]
+pub struct PixelMapping {
+	/// The rect that the image was painted in.
+	pub image_rect: Rect,
+	/// The size of the image in pixels.
+	pub image_size: Vec2,
+}
+
+impl PixelMapping {
+	/// The size of one image pixel on screen, in points.
+	pub fn scale(self) -> Vec2 {
+		self.image_rect.size() / self.image_size
+	}
+
+	pub fn to_pixel(self, pos: Pos2) -> Pos2 {
+		((pos - self.image_rect.min) / self.scale()).to_pos2()
+	}
+
+	pub fn to_screen(self, pixel: Pos2) -> Pos2 {
+		self.image_rect.min + pixel.to_vec2() * self.scale()
+	}
+}
+
+/// A rectangular selection over an image, in image pixel coordinates.
+#[derive(Clone, Copy, Debug)]
+pub struct Selection {
+	start: Pos2,
+	end: Pos2,
+}
+
+impl Selection {
+	/// The selected rect snapped to whole pixels and clamped to the image, or `None` if it is empty.
+	pub fn pixel_rect(self, image_size: Vec2) -> Option<Rect> {
+		let bounds = Rect::from_min_size(Pos2::ZERO, image_size);
+		let rect = Rect::from_two_pos(self.start, self.end);
+		let rect = Rect::from_min_max(rect.min.floor(), rect.max.ceil()).intersect(bounds);
+		(rect.width() >= 1.0 && rect.height() >= 1.0).then_some(rect)
+	}
+
+	/// Start, extend, or clear the selection based on a drag in `response`.
+	pub fn update_from_response(
+		selection: &mut Option<Self>,
+		response: &Response,
+		mapping: PixelMapping,
+	) {
+		if response.drag_started() {
+			*selection = response.interact_pointer_pos().map(|pos| {
+				let pos = mapping.to_pixel(pos);
+				Self {
+					start: pos,
+					end: pos,
+				}
+			});
+		} else if response.dragged() {
+			if let (Some(selection), Some(pos)) = (selection, response.interact_pointer_pos()) {
+				selection.end = mapping.to_pixel(pos);
+			}
+		}
+	}
+
+	pub fn paint(self, painter: &Painter, mapping: PixelMapping) {
+		let Some(rect) = self.pixel_rect(mapping.image_size) else {
+			return;
+		};
+		let rect = Rect::from_min_max(mapping.to_screen(rect.min), mapping.to_screen(rect.max));
+
+		let painter = painter.with_clip_rect(painter.clip_rect().intersect(mapping.image_rect));
+		painter.rect(
+			rect,
+			Rounding::ZERO,
+			Color32::from_white_alpha(24),
+			Stroke::new(1.0, painter.ctx().style().visuals.selection.stroke.color),
+		);
+	}
+}
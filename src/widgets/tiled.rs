@@ -0,0 +1,288 @@
+use std::sync::Arc;
+
+use egui::{Color32, ColorImage, Context, Pos2, Rect, TextureHandle, TextureOptions, Vec2};
+
+pub struct Tile {
+	pub texture: TextureHandle,
+	/// The area of the whole image covered by this tile, in pixels.
+	pub rect: Rect,
+}
+
+/// A texture that is split into tiles if it is larger than the GPU's maximum texture size.
+pub struct TiledTexture {
+	size: [usize; 2],
+	/// Empty if the texture has been evicted from the GPU.
+	tiles: Vec<Tile>,
+	/// A CPU-side copy of the pixels, kept so that the texture can be re-uploaded after being evicted.
+	/// It is shared so that it can be read without copying it, e.g., for exporting.
+	pixels: Option<Arc<[Color32]>>,
+	options: TextureOptions,
+	thumbnail: Option<Thumbnail>,
+}
+
+struct Thumbnail {
+	/// `None` if the texture is already small enough to be used as its own thumbnail.
+	/// It keeps its pixels and is only uploaded while it is shown, so that there can be thousands of them.
+	texture: Option<Box<TiledTexture>>,
+	/// The length in pixels of the longer side that the thumbnail was made for.
+	max_side: usize,
+}
+
+impl std::fmt::Debug for TiledTexture {
+	fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		formatter
+			.debug_struct("TiledTexture")
+			.field("size", &self.size)
+			.field("tiles", &self.tiles.len())
+			.field("kept_pixels", &self.pixels.is_some())
+			.field("options", &self.options)
+			.field(
+				"thumbnail_side",
+				&self.thumbnail.as_ref().map(|thumbnail| thumbnail.max_side),
+			)
+			.finish()
+	}
+}
+
+fn size_vec2([width, height]: [usize; 2]) -> Vec2 {
+	Vec2::new(az::cast(width), az::cast(height))
+}
+
+fn upload(
+	ctx: &Context,
+	size: [usize; 2],
+	pixels: Box<[Color32]>,
+	options: TextureOptions,
+) -> Vec<Tile> {
+	let load = |size, pixels| {
+		ctx.load_texture(
+			"", // has no importance
+			ColorImage { size, pixels },
+			options,
+		)
+	};
+
+	let max_side = ctx.input(|input| input.max_texture_side);
+	let [width, height] = size;
+
+	// fast path, which avoids copying the pixels
+	if width <= max_side && height <= max_side {
+		let tile = Tile {
+			texture: load(size, pixels.into()),
+			rect: Rect::from_min_size(Pos2::ZERO, size_vec2(size)),
+		};
+		return vec![tile];
+	}
+
+	let mut tiles = Vec::new();
+	for y in (0..height).step_by(max_side) {
+		let tile_height = max_side.min(height - y);
+		for x in (0..width).step_by(max_side) {
+			let tile_width = max_side.min(width - x);
+			let tile_pixels = pixels
+				.chunks_exact(width)
+				.skip(y)
+				.take(tile_height)
+				.flat_map(|row| &row[x..x + tile_width])
+				.copied()
+				.collect();
+			let tile_size = [tile_width, tile_height];
+			tiles.push(Tile {
+				texture: load(tile_size, tile_pixels),
+				rect: Rect::from_min_size(size_vec2([x, y]).to_pos2(), size_vec2(tile_size)),
+			});
+		}
+	}
+	tiles
+}
+
+/// Downscale the pixels with a box filter so that the longer side is at most `max_side`.
+/// Returns `None` if the image is already small enough.
+fn downscale(
+	[width, height]: [usize; 2],
+	pixels: &[Color32],
+	max_side: usize,
+) -> Option<([usize; 2], Box<[Color32]>)> {
+	if width.max(height) <= max_side || max_side == 0 {
+		return None;
+	}
+	let scaled = |side: usize| (side * max_side / width.max(height)).max(1);
+	let [new_width, new_height] = [scaled(width), scaled(height)];
+
+	let mut downscaled = Vec::with_capacity(new_width * new_height);
+	let mut sums = vec![[0u32; 4]; new_width];
+	for y in 0..new_height {
+		sums.fill([0; 4]);
+		let rows = (y * height / new_height)..((y + 1) * height / new_height);
+		let row_count = rows.len();
+		for row in pixels.chunks_exact(width).skip(rows.start).take(row_count) {
+			for (x, sum) in sums.iter_mut().enumerate() {
+				let columns = (x * width / new_width)..((x + 1) * width / new_width);
+				for pixel in &row[columns] {
+					for (channel, value) in sum.iter_mut().zip(pixel.to_array()) {
+						*channel += u32::from(value);
+					}
+				}
+			}
+		}
+		for (x, sum) in sums.iter().enumerate() {
+			let column_count = (x + 1) * width / new_width - x * width / new_width;
+			let count: u32 = az::cast(row_count * column_count);
+			// the channels are premultiplied, so they can be averaged independently
+			let [r, g, b, a] = sum.map(|channel| az::cast::<_, u8>(channel / count));
+			downscaled.push(Color32::from_rgba_premultiplied(r, g, b, a));
+		}
+	}
+	Some(([new_width, new_height], downscaled.into()))
+}
+
+fn make_thumbnail(size: [usize; 2], pixels: &[Color32], max_side: usize) -> Thumbnail {
+	let texture = downscale(size, pixels, max_side).map(|(size, pixels)| {
+		Box::new(TiledTexture {
+			size,
+			tiles: Vec::new(),
+			pixels: Some(pixels.into()),
+			options: TextureOptions::LINEAR,
+			thumbnail: None,
+		})
+	});
+	Thumbnail { texture, max_side }
+}
+
+impl TiledTexture {
+	/// If `keep_pixels` is set, a copy of the pixels is kept so that the texture can be restored after it is evicted.
+	/// If `thumbnail_side` is set, a downscaled copy is made whose longer side is at most that many pixels. It isn't uploaded until `load_thumbnail` is called.
+	pub fn load(
+		ctx: &Context,
+		size: [usize; 2],
+		pixels: Box<[Color32]>,
+		options: TextureOptions,
+		keep_pixels: bool,
+		thumbnail_side: Option<usize>,
+	) -> Self {
+		let thumbnail = thumbnail_side.map(|max_side| make_thumbnail(size, &pixels, max_side));
+		let kept = keep_pixels.then(|| Arc::from(&*pixels));
+		Self {
+			size,
+			tiles: upload(ctx, size, pixels, options),
+			pixels: kept,
+			options,
+			thumbnail,
+		}
+	}
+
+	/// The size of the pixels, wherever they are, in bytes.
+	fn size_in_bytes(&self) -> usize {
+		let [width, height] = self.size;
+		width
+			.saturating_mul(height)
+			.saturating_mul(std::mem::size_of::<Color32>())
+	}
+
+	pub fn size_vec2(&self) -> Vec2 {
+		size_vec2(self.size)
+	}
+
+	pub fn tiles(&self) -> &[Tile] {
+		&self.tiles
+	}
+
+	pub fn is_resident(&self) -> bool {
+		!self.tiles.is_empty()
+	}
+
+	/// The estimated amount of GPU memory used by the texture, in bytes.
+	pub fn size_on_gpu(&self) -> usize {
+		if self.is_resident() {
+			self.size_in_bytes()
+		} else {
+			0
+		}
+	}
+
+	/// The memory used by the kept pixels, in bytes.
+	pub fn size_in_memory(&self) -> usize {
+		if self.has_pixels() {
+			self.size_in_bytes()
+		} else {
+			0
+		}
+	}
+
+	pub fn has_pixels(&self) -> bool {
+		self.pixels.is_some()
+	}
+
+	/// The kept pixels, if they were kept.
+	pub fn pixels(&self) -> Option<&Arc<[Color32]>> {
+		self.pixels.as_ref()
+	}
+
+	/// Free the kept pixels. The texture can't be restored after it is evicted anymore, and the thumbnail can't be remade.
+	pub fn drop_pixels(&mut self) {
+		self.pixels = None;
+	}
+
+	/// Free the texture on the GPU. It can only be restored if the pixels were kept.
+	pub fn evict(&mut self) {
+		self.tiles = Vec::new();
+	}
+
+	/// Re-upload the texture if it was evicted. Returns whether the texture is now resident.
+	pub fn restore(&mut self, ctx: &Context) -> bool {
+		if self.is_resident() {
+			return true;
+		}
+		let Some(pixels) = &self.pixels else {
+			return false;
+		};
+		self.tiles = upload(ctx, self.size, pixels.to_vec().into(), self.options);
+		true
+	}
+	/// The thumbnail of the texture, or the texture itself if it has no thumbnail.
+	/// The thumbnail is only resident after `load_thumbnail`.
+	pub fn thumbnail(&self) -> &Self {
+		self
+			.thumbnail
+			.as_ref()
+			.and_then(|thumbnail| thumbnail.texture.as_deref())
+			.unwrap_or(self)
+	}
+
+	/// Whether the thumbnail was made for a different size and can be remade at `max_side`, which is only possible if the pixels were kept.
+	pub fn thumbnail_outdated(&self, max_side: usize) -> bool {
+		self.pixels.is_some()
+			&& !matches!(&self.thumbnail, Some(thumbnail) if thumbnail.max_side == max_side)
+	}
+
+	/// Remake the thumbnail at `max_side` if it is outdated.
+	pub fn update_thumbnail(&mut self, max_side: usize) {
+		if !self.thumbnail_outdated(max_side) {
+			return;
+		}
+		if let Some(pixels) = &self.pixels {
+			self.thumbnail = Some(make_thumbnail(self.size, pixels, max_side));
+		}
+	}
+
+	fn thumbnail_mut(&mut self) -> Option<&mut Self> {
+		self
+			.thumbnail
+			.as_mut()
+			.and_then(|thumbnail| thumbnail.texture.as_deref_mut())
+	}
+
+	/// Upload the thumbnail so that it can be shown.
+	pub fn load_thumbnail(&mut self, ctx: &Context) {
+		if let Some(thumbnail) = self.thumbnail_mut() {
+			thumbnail.restore(ctx);
+		}
+	}
+
+	/// Free the thumbnail on the GPU once it is no longer shown. It can be loaded again later.
+	pub fn unload_thumbnail(&mut self) {
+		if let Some(thumbnail) = self.thumbnail_mut() {
+			thumbnail.evict();
+		}
+	}
+}
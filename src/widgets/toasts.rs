@@ -0,0 +1,104 @@
+//! Notifications stacked in the corner of the window that go away by themselves, for errors that shouldn't interrupt what is being done.
+//!
+//! A toast stays while the pointer is over it, and clicking it shows the whole message, which keeps it until it is closed.
+
+use std::time::Duration;
+
+use egui::{Align2, Context, Id, Label, Order, RichText, Sense};
+
+/// How long a toast is shown, counted from when the pointer last left it or it was collapsed.
+const LIFETIME: f64 = 6.0;
+/// Older toasts are dropped beyond this many, so that a burst of errors doesn't cover the image.
+const MAX_TOASTS: usize = 5;
+const WIDTH: f32 = 320.0;
+
+#[derive(Debug)]
+struct Toast {
+	message: String,
+	/// When the toast was first shown, or last hovered or collapsed, by the clock of egui.
+	since: Option<f64>,
+	expanded: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct Toasts {
+	toasts: Vec<Toast>,
+}
+
+impl Toasts {
+	pub fn push(&mut self, message: String) {
+		if self.toasts.len() == MAX_TOASTS {
+			self.toasts.remove(0);
+		}
+		self.toasts.push(Toast {
+			message,
+			since: None,
+			expanded: false,
+		});
+	}
+
+	pub fn show(&mut self, ctx: &Context) {
+		if self.toasts.is_empty() {
+			return;
+		}
+
+		let now = ctx.input(|input| input.time);
+		egui::Area::new(Id::new("toasts"))
+			.anchor(Align2::RIGHT_BOTTOM, [-8.0, -8.0])
+			.order(Order::Foreground)
+			.show(ctx, |ui| {
+				ui.set_width(WIDTH);
+				let mut idx = 0;
+				self.toasts.retain_mut(|toast| {
+					idx += 1;
+					ui.push_id(idx, |ui| toast.show(ui, now)).inner
+				});
+			});
+
+		// wake up to remove the next toast that expires
+		let next_expiry = self
+			.toasts
+			.iter()
+			.filter(|toast| !toast.expanded)
+			.filter_map(|toast| toast.since)
+			.map(|since| since + LIFETIME - now)
+			.reduce(f64::min);
+		if let Some(next_expiry) = next_expiry {
+			ctx.request_repaint_after(Duration::from_secs_f64(next_expiry.max(0.0)));
+		}
+	}
+}
+
+impl Toast {
+	/// Returns whether to keep the toast.
+	fn show(&mut self, ui: &mut egui::Ui, now: f64) -> bool {
+		let since = *self.since.get_or_insert(now);
+		if !self.expanded && now - since > LIFETIME {
+			return false;
+		}
+
+		let mut keep = true;
+		let frame = egui::Frame::popup(ui.style()).show(ui, |ui| {
+			ui.horizontal(|ui| {
+				keep = !ui.small_button("✖").on_hover_text("Dismiss").clicked();
+				ui.label(RichText::new("⚠").color(ui.visuals().warn_fg_color));
+				let label = Label::new(&self.message).sense(Sense::click());
+				let (label, hover) = if self.expanded {
+					(label.wrap(true), "Click to collapse")
+				} else {
+					(label.truncate(true), "Click to show the whole message")
+				};
+				if ui.add(label).on_hover_text(hover).clicked() {
+					self.expanded ^= true;
+					// a collapsed toast gets its full lifetime again, rather than vanishing if it was expanded for a while
+					self.since = Some(now);
+				}
+			});
+		});
+		if ui.rect_contains_pointer(frame.response.rect) {
+			self.since = Some(now);
+		}
+
+		keep
+	}
+}